@@ -0,0 +1,88 @@
+use lifec::plugins::ThunkContext;
+use lifec::Entity;
+
+/// Operation on the shell itself, requested by a runmd block or a plugin
+///
+/// i.e. an `install` block that connects to an address and opens a log channel at startup,
+///
+/// ~~~text
+/// ``` install
+/// add enable_char_device .enable
+/// add shell_grammer      .text log
+/// add shell_actions      .enable
+/// add shell_connect      .text 127.0.0.1:7070
+/// ```
+/// ~~~
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellAction {
+    /// Clears the buffer of a channel
+    ClearChannel(u32),
+    /// Connects to an address in the background
+    Connect(String),
+    /// Switches the grammer a channel is highlighted with
+    SetGrammer { channel: u32, grammer: String },
+    /// Loads a file into the editor
+    LoadFile(String),
+    /// Sets the color of a theme token, to a color name, i.e. `keyword blue`
+    SetColor { token: String, color: String },
+}
+
+impl ShellAction {
+    /// Takes the actions requested by the entity's thunk context
+    ///
+    /// Actions are requested by enabling `shell_actions` along w/ any of `shell_clear`, `shell_connect`,
+    /// `shell_set_grammer`, `shell_load_file`, or `shell_set_color`. Channel actions apply to the entity's own channel.
+    /// The attribute is disabled once the actions are taken, so that each request is handled once
+    pub fn take_from_context(entity: Entity, tc: &mut ThunkContext) -> Vec<Self> {
+        if !tc.as_ref().is_enabled("shell_actions").unwrap_or_default() {
+            return vec![];
+        }
+
+        tc.as_mut().with_bool("shell_actions", false);
+
+        let channel = entity.id();
+        let mut actions = vec![];
+        if tc.as_ref().is_enabled("shell_clear").unwrap_or_default() {
+            actions.push(ShellAction::ClearChannel(channel));
+        }
+
+        if let Some(address) = tc.as_ref().find_text("shell_connect") {
+            actions.push(ShellAction::Connect(address));
+        }
+
+        if let Some(grammer) = tc.as_ref().find_text("shell_set_grammer") {
+            actions.push(ShellAction::SetGrammer { channel, grammer });
+        }
+
+        if let Some(path) = tc.as_ref().find_text("shell_load_file") {
+            actions.push(ShellAction::LoadFile(path));
+        }
+
+        if let Some(action) = tc.as_ref().find_text("shell_set_color").and_then(|c| Self::parse_set_color(&c)) {
+            actions.push(action);
+        }
+
+        actions
+    }
+
+    /// Parses a `token color` pair into a SetColor action
+    fn parse_set_color(value: &str) -> Option<Self> {
+        let (token, color) = value.trim().split_once(char::is_whitespace)?;
+        Some(ShellAction::SetColor {
+            token: token.to_string(),
+            color: color.trim().to_string(),
+        })
+    }
+}
+
+#[test]
+fn test_parse_set_color() {
+    assert_eq!(
+        ShellAction::parse_set_color("keyword  blue"),
+        Some(ShellAction::SetColor {
+            token: "keyword".to_string(),
+            color: "blue".to_string()
+        })
+    );
+    assert_eq!(ShellAction::parse_set_color("keyword"), None);
+}
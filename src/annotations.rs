@@ -0,0 +1,76 @@
+/// Result of evaluating a line of a runmd block, rendered in a dimmed color after the line in the editor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// Line of the source the annotation belongs to
+    pub line: usize,
+    /// Describes the result, i.e. `= Int(5)`
+    pub text: String,
+    /// If true, the annotation describes an error
+    pub error: bool,
+}
+
+impl Annotation {
+    /// Returns an annotation for the result of line
+    pub fn new(line: usize, text: impl Into<String>) -> Self {
+        Self {
+            line,
+            text: text.into(),
+            error: false,
+        }
+    }
+
+    /// Returns an annotation for an error on line
+    pub fn error(line: usize, text: impl Into<String>) -> Self {
+        Self {
+            error: true,
+            ..Self::new(line, text)
+        }
+    }
+
+    /// Returns the annotation moved down by lines, i.e. from a line of a block to the line of the buffer it's in
+    pub fn offset(self, lines: usize) -> Self {
+        Self {
+            line: self.line + lines,
+            ..self
+        }
+    }
+}
+
+/// Returns the name of the attribute a runmd line adds or defines, i.e. `a` for `add a .text hello`
+pub fn attribute_name(line: &str) -> Option<&str> {
+    let mut words = line.split_whitespace();
+    match words.next() {
+        Some("add") | Some("define") => words.next(),
+        _ => None,
+    }
+}
+
+/// Returns the row and column each line of source ends at, once lines longer than columns wrap onto more rows
+///
+/// Lines are expected to be separated by `\r`, wrapping matches the rows of the editor's gutter
+pub fn line_ends(source: &str, columns: usize) -> Vec<(usize, usize)> {
+    let columns = columns.max(1);
+    let mut row = 0;
+    let mut ends = vec![];
+    for line in source.split('\r') {
+        let len = line.chars().count();
+        let wrapped = len.saturating_sub(1) / columns;
+        row += wrapped;
+        ends.push((row, len - wrapped * columns));
+        row += 1;
+    }
+    ends
+}
+
+#[test]
+fn test_annotations() {
+    assert_eq!(attribute_name("  add name .text hello"), Some("name"));
+    assert_eq!(attribute_name("define a b .int 5"), Some("a"));
+    assert_eq!(attribute_name("``` demo process"), None);
+
+    assert_eq!(line_ends("abcdef\rab\r\rabcd", 4), vec![(1, 2), (2, 2), (3, 0), (4, 4)]);
+
+    let annotation = Annotation::error(1, "unexpected input").offset(3);
+    assert_eq!(annotation.line, 4);
+    assert!(annotation.error);
+}
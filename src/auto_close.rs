@@ -0,0 +1,83 @@
+/// Edit made in place of typing a char, when auto-closing
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutoCloseEdit {
+    /// Inserts text in place of the typed char, and moves the cursor back by back bytes from the end of text
+    Insert { text: String, back: usize },
+    /// Moves the cursor over the closing char that was typed, instead of inserting another one
+    Skip,
+}
+
+/// Editing filter that closes brackets, quotes, and runmd fences as they're typed
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoClose {
+    /// If true, typing ``` at the start of a line inserts the closing fence on the next line
+    pub fences: bool,
+    /// Pairs of open and close chars, a close char is inserted after its open char w/ the cursor between them
+    pub pairs: Vec<(char, char)>,
+}
+
+impl Default for AutoClose {
+    fn default() -> Self {
+        Self {
+            fences: true,
+            pairs: vec![('{', '}'), ('(', ')'), ('"', '"')],
+        }
+    }
+}
+
+impl AutoClose {
+    /// Returns the edit to make in place of typing typed, between before and after on the cursor's line
+    ///
+    /// Returns None if typed should be written as-is
+    pub fn filter(&self, before: &str, after: &str, typed: char) -> Option<AutoCloseEdit> {
+        if self.fences && typed == '`' && before.trim_start() == "``" && after.is_empty() {
+            let indent = &before[..before.len() - 2];
+            return Some(AutoCloseEdit::Insert {
+                text: format!("`\r{indent}```"),
+                back: indent.len() + 4,
+            });
+        }
+
+        if after.starts_with(typed) && self.pairs.iter().any(|(_, close)| *close == typed) {
+            return Some(AutoCloseEdit::Skip);
+        }
+
+        // Only closes at the end of a word, so that i.e. quoting an existing word isn't doubled
+        let at_boundary = after.chars().next().map_or(true, |next| next.is_whitespace() || ")]}".contains(next));
+        // A quote typed after an unclosed quote closes it
+        let closes_quote = |open: char, close: char| open == close && before.matches(open).count() % 2 == 1;
+        self.pairs
+            .iter()
+            .find(|(open, close)| *open == typed && at_boundary && !closes_quote(*open, *close))
+            .map(|(open, close)| AutoCloseEdit::Insert {
+                text: format!("{open}{close}"),
+                back: close.len_utf8(),
+            })
+    }
+}
+
+#[test]
+fn test_auto_close() {
+    let auto_close = AutoClose::default();
+    assert_eq!(
+        auto_close.filter("add a ", "", '('),
+        Some(AutoCloseEdit::Insert {
+            text: "()".to_string(),
+            back: 1
+        })
+    );
+    assert_eq!(auto_close.filter("add a \"b", "\"", '"'), Some(AutoCloseEdit::Skip));
+    assert_eq!(auto_close.filter("add a ", "b", '"'), None);
+    assert_eq!(auto_close.filter("add a .text \"b", "", '"'), None);
+    assert_eq!(auto_close.filter("add a ", "", 'x'), None);
+    assert_eq!(
+        auto_close.filter("  ``", "", '`'),
+        Some(AutoCloseEdit::Insert {
+            text: "`\r  ```".to_string(),
+            back: 6
+        })
+    );
+    assert_eq!(auto_close.filter("a ``", "", '`'), None);
+}
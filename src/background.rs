@@ -0,0 +1,351 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use tracing::{event, Level};
+use wgpu::util::DeviceExt;
+
+use crate::Rect;
+
+/// Shader for drawing textured quads, the texture is multiplied by the vertex color
+const BACKGROUND_SHADER: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = uv;
+    out.color = color;
+    return out;
+}
+
+@group(0) @binding(0)
+var background_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var background_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(background_texture, background_sampler, in.uv) * in.color;
+}
+"#;
+
+/// Background drawn behind the text of a pane
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaneBackground {
+    /// Vertical gradient, colors are linear sRGB
+    Gradient {
+        top: [f32; 4],
+        bottom: [f32; 4],
+        opacity: f32,
+    },
+    /// Image stretched over the pane
+    Image { path: String, opacity: f32 },
+}
+
+/// Draws pane backgrounds before text is drawn
+pub struct BackgroundRenderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    /// 1x1 white texture, used for gradients
+    white: wgpu::BindGroup,
+    /// Loaded images by path, None if the image could not be loaded
+    images: HashMap<String, Option<wgpu::BindGroup>>,
+}
+
+impl BackgroundRenderer {
+    /// Returns a new renderer for the surface format
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("lifec_shell background shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(BACKGROUND_SHADER)),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("lifec_shell background bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("lifec_shell background pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("lifec_shell background pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: (8 * std::mem::size_of::<f32>()) as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("lifec_shell background sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let white = Self::create_bind_group(
+            device,
+            queue,
+            &bind_group_layout,
+            &sampler,
+            &[255, 255, 255, 255],
+            (1, 1),
+        );
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            white,
+            images: HashMap::default(),
+        }
+    }
+
+    /// Draws backgrounds for each pane, surface_size is used to convert pane areas to clip space
+    pub fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        surface_size: (f32, f32),
+        backgrounds: &[(Rect, PaneBackground)],
+    ) {
+        if backgrounds.is_empty() {
+            return;
+        }
+
+        // Failures of paths that aren't used anymore are forgotten, so that going back to a path tries it again
+        self.images.retain(|path, image| {
+            image.is_some()
+                || backgrounds
+                    .iter()
+                    .any(|(_, b)| matches!(b, PaneBackground::Image { path: p, .. } if p == path))
+        });
+        for (_, background) in backgrounds {
+            if let PaneBackground::Image { path, .. } = background {
+                self.load_image(device, queue, path);
+            }
+        }
+
+        let mut vertices: Vec<f32> = vec![];
+        for (rect, background) in backgrounds {
+            let (top, bottom) = match background {
+                PaneBackground::Gradient {
+                    top,
+                    bottom,
+                    opacity,
+                } => {
+                    let [r, g, b, a] = *top;
+                    let top = [r, g, b, a * opacity];
+                    let [r, g, b, a] = *bottom;
+                    (top, [r, g, b, a * opacity])
+                }
+                PaneBackground::Image { opacity, .. } => {
+                    ([1.0, 1.0, 1.0, *opacity], [1.0, 1.0, 1.0, *opacity])
+                }
+            };
+            vertices.extend(quad(*rect, surface_size, top, bottom));
+        }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("lifec_shell background vertices"),
+            contents: &vertices
+                .iter()
+                .flat_map(|f| f.to_ne_bytes())
+                .collect::<Vec<u8>>(),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("lifec_shell background pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        pass.set_pipeline(&self.pipeline);
+        pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        for (idx, (_, background)) in backgrounds.iter().enumerate() {
+            let bind_group = match background {
+                PaneBackground::Gradient { .. } => Some(&self.white),
+                PaneBackground::Image { path, .. } => {
+                    self.images.get(path).and_then(|i| i.as_ref())
+                }
+            };
+
+            if let Some(bind_group) = bind_group {
+                let start = (idx * 6) as u32;
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.draw(start..start + 6, 0..1);
+            }
+        }
+    }
+
+    /// Forgets that the image at path couldn't be loaded, so that loading it is tried again on the next draw
+    pub fn retry(&mut self, path: &str) {
+        if let Some(None) = self.images.get(path) {
+            self.images.remove(path);
+        }
+    }
+
+    /// Loads the image at path into a texture, if it hasn't been loaded already
+    fn load_image(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str) {
+        if self.images.contains_key(path) {
+            return;
+        }
+
+        let bind_group = match image::open(path) {
+            Ok(image) => {
+                let image = image.to_rgba8();
+                Some(Self::create_bind_group(
+                    device,
+                    queue,
+                    &self.bind_group_layout,
+                    &self.sampler,
+                    image.as_raw(),
+                    image.dimensions(),
+                ))
+            }
+            Err(err) => {
+                event!(Level::WARN, "Could not load background image {path}, {err}");
+                None
+            }
+        };
+
+        self.images.insert(path.to_string(), bind_group);
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        rgba: &[u8],
+        (width, height): (u32, u32),
+    ) -> wgpu::BindGroup {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("lifec_shell background texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(4 * width),
+                rows_per_image: NonZeroU32::new(height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("lifec_shell background bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+}
+
+/// Returns the vertices for two triangles covering rect, in clip space
+///
+/// Each vertex is position (2), uv (2), color (4)
+fn quad(rect: Rect, (width, height): (f32, f32), top: [f32; 4], bottom: [f32; 4]) -> Vec<f32> {
+    let left = rect.x / width * 2.0 - 1.0;
+    let right = (rect.x + rect.width) / width * 2.0 - 1.0;
+    let upper = 1.0 - rect.y / height * 2.0;
+    let lower = 1.0 - (rect.y + rect.height) / height * 2.0;
+
+    let vertex = |x: f32, y: f32, u: f32, v: f32, [r, g, b, a]: [f32; 4]| vec![x, y, u, v, r, g, b, a];
+    [
+        vertex(left, upper, 0.0, 0.0, top),
+        vertex(left, lower, 0.0, 1.0, bottom),
+        vertex(right, lower, 1.0, 1.0, bottom),
+        vertex(left, upper, 0.0, 0.0, top),
+        vertex(right, lower, 1.0, 1.0, bottom),
+        vertex(right, upper, 1.0, 0.0, top),
+    ]
+    .concat()
+}
@@ -0,0 +1,136 @@
+use lifec::{editor::RuntimeEditor, App, System};
+use lifec_shell::{PaneLayout, Shell};
+
+const USAGE: &str = "\
+Usage: lifec-shell [options] [file]
+
+Options:
+    --connect <address>     Connects to a tcp stream, i.e. 127.0.0.1:7070
+    --theme <path>          Loads a theme saved from the shell's theme menu
+    --layout <preset>       Layout of panes: split (default), stacked, editor, or triple
+    -h, --help              Prints this message
+";
+
+/// Settings parsed from the command line
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    address: Option<String>,
+    file: Option<String>,
+    theme: Option<String>,
+    layout: Option<String>,
+}
+
+impl Args {
+    /// Parses args, not including the program name, returns an error message if they're invalid
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("Missing value for {name}"));
+            match arg.as_str() {
+                "--connect" => parsed.address = Some(value("--connect")?),
+                "--theme" => parsed.theme = Some(value("--theme")?),
+                "--layout" => parsed.layout = Some(value("--layout")?),
+                "-h" | "--help" => return Err(String::default()),
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+                _ if parsed.file.is_none() => parsed.file = Some(arg),
+                _ => return Err(format!("Unexpected argument {arg}")),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn main() {
+    tracing_subscriber::fmt::Subscriber::builder()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .compact()
+        .init();
+
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            if !err.is_empty() {
+                eprintln!("{err}\n");
+            }
+            eprint!("{USAGE}");
+            std::process::exit(if err.is_empty() { 0 } else { 2 });
+        }
+    };
+
+    let mut shell = Shell::default();
+    if let Some(theme) = args.theme.as_ref() {
+        shell = shell.with_theme_file(theme);
+    }
+
+    if let Some(preset) = args.layout.as_ref() {
+        match PaneLayout::preset(preset) {
+            Some(layout) => *shell.layout_mut() = layout,
+            None => {
+                eprint!("Unknown layout {preset}\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // The runtime is kept alive for the connection, which is driven by its worker threads
+    let runtime = tokio::runtime::Runtime::new().expect("should be able to create a tokio runtime");
+    runtime.block_on(async {
+        if let Some(file) = args.file.as_ref() {
+            if let Err(err) = shell.load_file(file).await {
+                eprintln!("Could not open {file}, {err}");
+            }
+        }
+
+        if let Some(address) = args.address.as_ref() {
+            shell.connect_to(address).await;
+        }
+    });
+
+    lifec::open(
+        "lifec shell",
+        Standalone {},
+        lifec::combine(RuntimeEditor::default(), shell),
+    )
+}
+
+/// App that only hosts the shell
+struct Standalone;
+
+impl App for Standalone {
+    fn name() -> &'static str {
+        "lifec shell"
+    }
+
+    fn enable_depth_stencil<'a>(&self) -> bool {
+        true
+    }
+
+    fn edit_ui(&mut self, _ui: &imgui::Ui) {}
+
+    fn display_ui(&self, _ui: &imgui::Ui) {}
+}
+
+impl<'a> System<'a> for Standalone {
+    type SystemData = ();
+
+    fn run(&mut self, _: Self::SystemData) {}
+}
+
+#[test]
+fn test_args() {
+    let args = |args: &[&str]| Args::parse(args.iter().map(|a| a.to_string()));
+
+    assert_eq!(
+        args(&["--connect", "127.0.0.1:7070", "notes.runmd", "--layout", "stacked"]),
+        Ok(Args {
+            address: Some("127.0.0.1:7070".to_string()),
+            file: Some("notes.runmd".to_string()),
+            theme: None,
+            layout: Some("stacked".to_string()),
+        })
+    );
+    assert_eq!(args(&["--help"]), Err(String::default()));
+    assert!(args(&["--theme"]).is_err());
+    assert!(args(&["a", "b"]).is_err());
+}
@@ -0,0 +1,106 @@
+use std::ops::{Range, RangeInclusive};
+
+/// Rectangular selection of a buffer, in lines and char columns, i.e. from Alt+drag
+///
+/// Lines in the buffer are expected to be separated by `\r`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockSelection {
+    /// Line and column the selection started at
+    pub anchor: (usize, usize),
+    /// Line and column the selection was extended to
+    pub head: (usize, usize),
+}
+
+impl BlockSelection {
+    /// Returns an empty selection at a line and column
+    pub fn new(at: (usize, usize)) -> Self {
+        Self { anchor: at, head: at }
+    }
+
+    /// Extends the selection to a line and column
+    pub fn extend_to(&mut self, head: (usize, usize)) {
+        self.head = head;
+    }
+
+    /// Returns the lines covered by the selection
+    pub fn lines(&self) -> RangeInclusive<usize> {
+        self.anchor.0.min(self.head.0)..=self.anchor.0.max(self.head.0)
+    }
+
+    /// Returns the columns covered by the selection
+    pub fn columns(&self) -> Range<usize> {
+        self.anchor.1.min(self.head.1)..self.anchor.1.max(self.head.1)
+    }
+
+    /// Returns the byte span selected on each line of buffer, lines shorter than the selection have shorter spans
+    pub fn spans(&self, buffer: &str) -> Vec<Range<usize>> {
+        let columns = self.columns();
+        let mut spans = vec![];
+        let mut start = 0;
+        for (line_no, line) in buffer.split('\r').enumerate() {
+            if self.lines().contains(&line_no) {
+                let span = column_span(line, columns.clone());
+                spans.push(start + span.start..start + span.end);
+            }
+            start += line.len() + 1;
+        }
+        spans
+    }
+
+    /// Returns the text selected on each line of buffer
+    pub fn copy(&self, buffer: &str) -> Vec<String> {
+        self.spans(buffer)
+            .into_iter()
+            .map(|span| buffer[span].to_string())
+            .collect()
+    }
+}
+
+/// Returns the byte span of columns in line, clamped to the end of the line
+fn column_span(line: &str, columns: Range<usize>) -> Range<usize> {
+    let offset = |col: usize| line.char_indices().nth(col).map(|(idx, _)| idx).unwrap_or(line.len());
+    offset(columns.start)..offset(columns.end)
+}
+
+/// Returns the line and column of offset in buffer
+pub fn position(buffer: &str, offset: usize) -> (usize, usize) {
+    let before = buffer.get(..offset).unwrap_or(buffer);
+    let line = before.matches('\r').count();
+    let column = before.rsplit('\r').next().map(|l| l.chars().count()).unwrap_or_default();
+    (line, column)
+}
+
+/// Returns buffer w/ each row of block inserted at column, on consecutive lines starting at line
+///
+/// Lines shorter than column are padded w/ spaces, and lines are added to the end of the buffer if the block doesn't fit
+pub fn paste_block(buffer: &str, (line, column): (usize, usize), block: &[String]) -> String {
+    let mut lines = buffer.split('\r').map(str::to_string).collect::<Vec<_>>();
+    while lines.len() < line + block.len() {
+        lines.push(String::new());
+    }
+
+    for (row, text) in lines.iter_mut().skip(line).zip(block) {
+        let len = row.chars().count();
+        if len < column {
+            row.push_str(&" ".repeat(column - len));
+        }
+
+        let at = column_span(row, column..column).start;
+        row.insert_str(at, text);
+    }
+
+    lines.join("\r")
+}
+
+#[test]
+fn test_block_selection() {
+    let buffer = "add a .text 1\radd bb .text 2\radd c\radd dddd .text 4";
+    let mut selection = BlockSelection::new((0, 4));
+    selection.extend_to((3, 8));
+
+    assert_eq!(selection.copy(buffer), vec!["a .t", "bb .", "c", "dddd"]);
+    assert_eq!(position(buffer, 18), (1, 4));
+
+    let pasted = paste_block("ab\rc", (0, 3), &["x".to_string(), "y".to_string(), "z".to_string()]);
+    assert_eq!(pasted, "ab x\rc  y\r   z");
+}
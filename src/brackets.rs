@@ -0,0 +1,123 @@
+use std::ops::Range;
+
+use crate::Token;
+
+/// Returns the pair of brackets adjacent to the cursor, i.e. the cursor is directly before or after either bracket
+pub fn matching_pair(
+    pairs: &[(Range<usize>, Range<usize>)],
+    cursor: usize,
+) -> Option<(Range<usize>, Range<usize>)> {
+    let adjacent = |span: &Range<usize>| span.contains(&cursor) || (cursor > 0 && span.contains(&(cursor - 1)));
+
+    pairs
+        .iter()
+        .find(|(open, close)| adjacent(open) || adjacent(close))
+        .cloned()
+}
+
+/// Returns a copy of source where every character outside of spans is replaced with a space
+///
+/// Since the font is monospace, the overlay can be rendered on top of the source to highlight the spans
+pub fn highlight_overlay(source: &str, spans: &[Range<usize>]) -> String {
+    source
+        .char_indices()
+        .map(|(idx, c)| match c {
+            '\r' | '\n' => c,
+            _ if spans.iter().any(|s| s.contains(&idx)) => c,
+            _ => ' ',
+        })
+        .collect()
+}
+
+/// Returns every pair of brackets in source
+///
+/// Tokens are the theming tokens of the active grammer, ``` block delimitters are paired from its Token::Bracket spans,
+/// and `{}`, `()`, and quotes are paired from the source, skipping anything inside of a Token::Comment span
+pub fn pairs(source: &str, tokens: &[(Token, Range<usize>)]) -> Vec<(Range<usize>, Range<usize>)> {
+    let delimitters = tokens
+        .iter()
+        .filter(|(token, span)| *token == Token::Bracket && source.get(span.clone()) == Some("```"))
+        .map(|(_, span)| span.clone())
+        .collect::<Vec<_>>();
+
+    // A delimitter followed by a header always opens a block, so an unclosed block doesn't shift every pair after it
+    let mut pairs = vec![];
+    let mut open: Option<Range<usize>> = None;
+    for delimitter in delimitters.iter() {
+        let header = source[delimitter.end..]
+            .split(['\r', '\n'])
+            .next()
+            .map(|header| !header.trim().is_empty())
+            .unwrap_or_default();
+
+        match open.take() {
+            Some(start) if !header => pairs.push((start, delimitter.clone())),
+            _ => open = Some(delimitter.clone()),
+        }
+    }
+
+    let skipped = tokens
+        .iter()
+        .filter(|(token, _)| *token == Token::Comment)
+        .map(|(_, span)| span.clone())
+        .chain(delimitters.iter().cloned())
+        .collect::<Vec<_>>();
+
+    let mut stack: Vec<(char, usize)> = vec![];
+    let mut quote: Option<(char, usize)> = None;
+    for (idx, c) in source.char_indices() {
+        if skipped.iter().any(|s| s.contains(&idx)) {
+            continue;
+        }
+
+        match (quote, c) {
+            (Some((q, start)), _) if c == q => {
+                pairs.push((start..start + 1, idx..idx + 1));
+                quote = None;
+            }
+            // Quotes don't span lines
+            (Some(_), '\r' | '\n') => quote = None,
+            (Some(_), _) => {}
+            // An apostrophe inside of a word isn't a quote, i.e. it's
+            (None, '\'') if source[..idx].chars().next_back().map(char::is_alphanumeric).unwrap_or_default() => {}
+            (None, '"' | '\'') => quote = Some((c, idx)),
+            (None, '{' | '(') => stack.push((c, idx)),
+            (None, '}' | ')') => {
+                let open = if c == '}' { '{' } else { '(' };
+                if let Some(pos) = stack.iter().rposition(|(o, _)| *o == open) {
+                    let (_, start) = stack[pos];
+                    stack.truncate(pos);
+                    pairs.push((start..start + 1, idx..idx + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pairs
+}
+
+#[test]
+fn test_matching_pair() {
+    let source = "``` a\r.text (hello {world})\r```";
+    let tokens = vec![(Token::Bracket, 0..3), (Token::Bracket, 28..31)];
+    let found = pairs(source, &tokens);
+
+    assert_eq!(matching_pair(&found, 3), Some((0..3, 28..31)));
+    assert_eq!(matching_pair(&found, 12), Some((12..13, 26..27)));
+    assert_eq!(matching_pair(&found, 20), Some((19..20, 25..26)));
+    assert_eq!(matching_pair(&found, 15), None);
+    assert_eq!(
+        highlight_overlay(source, &[12..13, 26..27]),
+        "     \r      (             )\r   "
+    );
+
+    // An unclosed block doesn't pair its delimitter w/ the next block's header
+    let source = "``` a\r```b c\r.text\r```";
+    let tokens = vec![(Token::Bracket, 0..3), (Token::Bracket, 6..9), (Token::Bracket, 19..22)];
+    assert_eq!(pairs(source, &tokens), vec![(6..9, 19..22)]);
+
+    // Apostrophes inside of words aren't quotes
+    let source = "it's 'quoted'";
+    assert_eq!(pairs(source, &[]), vec![(5..6, 12..13)]);
+}
@@ -0,0 +1,79 @@
+/// Id of the first channel reserved for the shell's own output, entity ids are expected to stay below it
+const FIRST_RESERVED: u32 = SESSION - MAX_PROCESSES - 4;
+
+/// Id of the session channel, the other built-in channels count down from it
+const SESSION: u32 = i32::MAX as u32;
+
+/// Max number of processes that can have a channel at once
+const MAX_PROCESSES: u32 = 1 << 16;
+
+/// Channels the shell prints its own output to, i.e. output that doesn't belong to an entity
+///
+/// Each is mapped to an id reserved at the top of the channel ids, so that they can be kept alongside entity channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinChannel {
+    /// Output received from a tcp connection, and session summaries
+    Session,
+    /// Lines from a relay peer
+    Relay,
+    /// The cheat sheet
+    Help,
+    /// Results of searching every channel
+    Search,
+    /// Output of the nth process spawned by the shell
+    Process(u32),
+}
+
+impl BuiltinChannel {
+    /// Returns the channel id reserved for this channel
+    pub fn id(self) -> u32 {
+        match self {
+            BuiltinChannel::Session => SESSION,
+            BuiltinChannel::Relay => SESSION - 1,
+            BuiltinChannel::Help => SESSION - 2,
+            BuiltinChannel::Search => SESSION - 3,
+            BuiltinChannel::Process(n) => SESSION - 4 - n.min(MAX_PROCESSES - 1),
+        }
+    }
+
+    /// Returns the built-in channel w/ id, or None if id belongs to an entity
+    pub fn from_id(id: u32) -> Option<Self> {
+        match id {
+            SESSION => Some(BuiltinChannel::Session),
+            id if id == SESSION - 1 => Some(BuiltinChannel::Relay),
+            id if id == SESSION - 2 => Some(BuiltinChannel::Help),
+            id if id == SESSION - 3 => Some(BuiltinChannel::Search),
+            id if (FIRST_RESERVED + 1..SESSION - 3).contains(&id) => Some(BuiltinChannel::Process(SESSION - 4 - id)),
+            _ => None,
+        }
+    }
+
+    /// Returns the locale key of this channel's title, processes are titled by their command line instead
+    pub fn title_key(self) -> Option<&'static str> {
+        match self {
+            BuiltinChannel::Session => Some("session_channel"),
+            BuiltinChannel::Relay => Some("relay_channel"),
+            BuiltinChannel::Help => Some("help_channel"),
+            BuiltinChannel::Search => Some("search_channel"),
+            BuiltinChannel::Process(_) => None,
+        }
+    }
+}
+
+#[test]
+fn test_builtin_channel() {
+    for channel in [
+        BuiltinChannel::Session,
+        BuiltinChannel::Relay,
+        BuiltinChannel::Help,
+        BuiltinChannel::Search,
+        BuiltinChannel::Process(0),
+        BuiltinChannel::Process(7),
+    ] {
+        assert_eq!(BuiltinChannel::from_id(channel.id()), Some(channel));
+    }
+
+    assert_eq!(BuiltinChannel::Session.id(), i32::MAX as u32);
+    assert_eq!(BuiltinChannel::from_id(0), None);
+    assert_eq!(BuiltinChannel::from_id(FIRST_RESERVED), None);
+}
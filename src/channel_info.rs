@@ -0,0 +1,41 @@
+use lifec::plugins::ThunkContext;
+use lifec::Entity;
+use std::time::Instant;
+
+/// Metadata for a channel, stored next to its char device
+///
+#[derive(Debug, Clone)]
+pub struct ChannelInfo {
+    /// Title to display in tabs and the status line
+    pub title: String,
+    /// Entity that owns this channel
+    pub entity: Entity,
+    /// When the channel was added
+    pub created_at: Instant,
+    /// If true, the channel only displays output
+    pub read_only: bool,
+    /// Name of the grammer to use for this channel, instead of the shell's default
+    pub grammer: Option<String>,
+}
+
+impl ChannelInfo {
+    /// Returns channel info populated from the entity's thunk context
+    ///
+    /// Reads `shell_title` (falling back to `node_title`, then the block name), `shell_read_only`, and `shell_grammer`
+    pub fn from_context(entity: Entity, tc: &ThunkContext, created_at: Instant) -> Self {
+        let title = tc
+            .as_ref()
+            .find_text("shell_title")
+            .or_else(|| tc.as_ref().find_text("node_title"))
+            .or_else(|| Some(tc.block.block_name.clone()).filter(|n| !n.is_empty()))
+            .unwrap_or(format!("channel {}", entity.id()));
+
+        Self {
+            title,
+            entity,
+            created_at,
+            read_only: tc.as_ref().is_enabled("shell_read_only").unwrap_or_default(),
+            grammer: tc.as_ref().find_text("shell_grammer"),
+        }
+    }
+}
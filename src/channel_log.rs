@@ -0,0 +1,97 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::time::SystemTime;
+use tracing::{event, Level};
+
+use crate::civil_time::{date, time_of_day};
+
+/// Appends everything written to a channel to a timestamped file, i.e. `channel-3-2022-08-01-142530.log`
+///
+/// Writes happen on a background thread, so that logging a flood of output doesn't stall rendering. Line breaks are
+/// written as `\n`, and the file is closed once the log is dropped
+#[derive(Debug)]
+pub struct ChannelLog {
+    /// File being appended to
+    path: PathBuf,
+    /// Sends bytes to the writer thread
+    tx: Sender<Vec<u8>>,
+}
+
+impl ChannelLog {
+    /// Starts logging channel to a new file in dir, dir is created if it doesn't exist
+    pub fn start(dir: impl AsRef<Path>, channel_id: u32) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+
+        let path = dir
+            .as_ref()
+            .join(format!("channel-{channel_id}-{}.log", timestamp(SystemTime::now())));
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        let (tx, rx) = channel::<Vec<u8>>();
+        let log_path = path.clone();
+        std::thread::spawn(move || {
+            for mut bytes in rx {
+                for b in bytes.iter_mut().filter(|b| **b == b'\r') {
+                    *b = b'\n';
+                }
+
+                if let Err(err) = file.write_all(&bytes) {
+                    event!(Level::ERROR, "Could not write channel log {:?}, {err}", log_path);
+                    break;
+                }
+            }
+        });
+
+        Ok(Self { path, tx })
+    }
+
+    /// Returns the file being appended to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Queues bytes to be appended to the file
+    pub fn append(&self, bytes: &[u8]) {
+        if self.tx.send(bytes.to_vec()).is_err() {
+            event!(Level::WARN, "Channel log {:?} was closed, dropping {} bytes", self.path, bytes.len());
+        }
+    }
+}
+
+/// Returns the UTC date and time of time, formatted as `YYYY-MM-DD-HHMMSS`
+fn timestamp(time: SystemTime) -> String {
+    let (hours, minutes, seconds) = time_of_day(time);
+    format!("{}-{hours:02}{minutes:02}{seconds:02}", date(time))
+}
+
+#[test]
+fn test_channel_log() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    assert_eq!(
+        timestamp(UNIX_EPOCH + Duration::from_secs(1_659_312_000 + 14 * 3600 + 25 * 60 + 30)),
+        "2022-08-01-142530"
+    );
+
+    let dir = std::env::temp_dir().join(format!("lifec_shell_channel_logs_{}", std::process::id()));
+    let log = ChannelLog::start(&dir, 3).expect("should start");
+    log.append(b"hello\rworld");
+
+    let path = log.path().to_path_buf();
+    drop(log);
+
+    // The writer thread finishes once the log is dropped
+    let mut written = String::new();
+    for _ in 0..100 {
+        written = std::fs::read_to_string(&path).unwrap();
+        if !written.is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(written, "hello\nworld");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,175 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::AsyncWrite;
+use tokio::sync::mpsc::{error::SendError, OwnedPermit, Sender};
+
+use crate::ShellChannel;
+
+type PendingPermit =
+    Pin<Box<dyn Future<Output = Result<OwnedPermit<(u32, u8)>, SendError<()>>> + Send>>;
+
+impl ShellChannel {
+    /// Returns the channel this component writes to
+    pub fn channel(&self) -> u32 {
+        self.1
+    }
+
+    /// Sends text to the shell channel, returns false if the shell is no longer receiving
+    ///
+    /// Line endings are converted to `\r`, the line separator of char devices
+    pub async fn send_str(&self, text: impl AsRef<str>) -> bool {
+        let sender = match self.0.as_ref() {
+            Some(sender) => sender,
+            None => return false,
+        };
+
+        let mut line_endings = LineEndings::default();
+        for next in text.as_ref().bytes() {
+            if let Some(next) = line_endings.convert(next) {
+                if sender.send((self.1, next)).await.is_err() {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Sends text followed by a line break, returns false if the shell is no longer receiving
+    pub async fn send_line(&self, text: impl AsRef<str>) -> bool {
+        self.send_str(format!("{}\r", text.as_ref())).await
+    }
+
+    /// Returns a writer to the shell channel, i.e. to pipe the stdout of a process w/ tokio::io::copy
+    ///
+    /// Returns None if this component doesn't have a sender
+    pub fn writer(&self) -> Option<ChannelWriter> {
+        self.0.clone().map(|sender| ChannelWriter {
+            sender,
+            channel: self.1,
+            pending: None,
+            line_endings: LineEndings::default(),
+        })
+    }
+}
+
+/// AsyncWrite adapter for a shell channel, line endings are converted to `\r`
+pub struct ChannelWriter {
+    sender: Sender<(u32, u8)>,
+    channel: u32,
+    /// Waits for space in the byte channel when it's full
+    pending: Option<PendingPermit>,
+    line_endings: LineEndings,
+}
+
+impl AsyncWrite for ChannelWriter {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.as_mut().get_mut();
+        let mut written = 0;
+        for next in buf {
+            if let Some(pending) = this.pending.as_mut() {
+                match pending.as_mut().poll(cx) {
+                    Poll::Ready(Ok(permit)) => {
+                        this.pending = None;
+                        if let Some(next) = this.line_endings.convert(*next) {
+                            permit.send((this.channel, next));
+                        }
+                        written += 1;
+                        continue;
+                    }
+                    Poll::Ready(Err(_)) => return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+                    Poll::Pending if written > 0 => return Poll::Ready(Ok(written)),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+
+            match this.sender.try_reserve() {
+                Ok(permit) => {
+                    if let Some(next) = this.line_endings.convert(*next) {
+                        permit.send((this.channel, next));
+                    }
+                    written += 1;
+                }
+                Err(tokio::sync::mpsc::error::TrySendError::Full(_)) if written > 0 => {
+                    return Poll::Ready(Ok(written));
+                }
+                Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
+                    // Waits for space, the byte is sent on the next poll once the permit is ready
+                    let mut pending: PendingPermit = Box::pin(this.sender.clone().reserve_owned());
+                    match pending.as_mut().poll(cx) {
+                        Poll::Ready(Ok(permit)) => {
+                            if let Some(next) = this.line_endings.convert(*next) {
+                                permit.send((this.channel, next));
+                            }
+                            written += 1;
+                        }
+                        Poll::Ready(Err(_)) => return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+                        Poll::Pending => {
+                            this.pending = Some(pending);
+                            return Poll::Pending;
+                        }
+                    }
+                }
+                Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                    return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+                }
+            }
+        }
+
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Converts `\n` and `\r\n` line endings to `\r`
+#[derive(Default)]
+struct LineEndings {
+    /// True if the last byte was `\r`
+    after_cr: bool,
+}
+
+impl LineEndings {
+    /// Returns the byte to send for next, None if it should be skipped
+    fn convert(&mut self, next: u8) -> Option<u8> {
+        let after_cr = std::mem::replace(&mut self.after_cr, next == b'\r');
+        match next {
+            b'\n' if after_cr => None,
+            b'\n' => Some(b'\r'),
+            _ => Some(next),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_channel_writer() {
+    use tokio::io::AsyncWriteExt;
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+    let shell_channel = ShellChannel(Some(tx), 7);
+
+    let reader = tokio::spawn(async move {
+        let mut received = vec![];
+        while let Some((channel, next)) = rx.recv().await {
+            assert_eq!(channel, 7);
+            received.push(next);
+        }
+        received
+    });
+
+    assert!(shell_channel.send_line("hello\r\nworld").await);
+    let mut writer = shell_channel.writer().expect("should have a sender");
+    writer.write_all(b"piped\noutput\n").await.unwrap();
+    drop(writer);
+    drop(shell_channel);
+
+    let received = reader.await.unwrap();
+    assert_eq!(received, b"hello\rworld\rpiped\routput\r");
+}
@@ -2,8 +2,18 @@ use lifec::Component;
 use lifec::HashMapStorage;
 use terminal_keycode::{Decoder, KeyCode};
 use std::io::Cursor;
+use std::ops::Range;
 use tokio::io::AsyncRead;
 
+use crate::block_selection::{paste_block, position};
+use crate::history::Edit;
+use crate::history::History;
+use crate::BlockSelection;
+use crate::TerminalGrid;
+
+/// Default max number of lines kept by output channels, before the oldest lines are trimmed
+pub const DEFAULT_MAX_LINES: usize = 10_000;
+
 /// Component that can be used to decode a sequence of terminal characters
 /// 
 #[derive(Component, Default)]
@@ -19,8 +29,30 @@ pub struct CharDevice {
     cursor: usize,
     /// line number
     line: usize,
-    /// col number
-    _col: usize,
+    /// column, in chars, that vertical movement tries to keep the cursor at
+    preferred_col: Option<usize>,
+    /// first line that is visible when rendering output
+    scroll: usize,
+    /// edits that can be undone/redone
+    history: History,
+    /// if true, edits aren't recorded for undo, i.e. output written by plugins and remotes
+    untracked: bool,
+    /// incremented whenever the buffer or scroll position changes
+    revision: u64,
+    /// if true, the device only displays output and keyboard input is rejected
+    read_only: bool,
+    /// edits that haven't been written to the journal yet, None if the device isn't journaled
+    unjournaled: Option<Vec<Edit>>,
+    /// selected span of the buffer, cleared when the buffer changes or the cursor is moved
+    selection: Option<Range<usize>>,
+    /// rectangular selection of the buffer, cleared the same as selection
+    block_selection: Option<BlockSelection>,
+    /// max number of lines kept in the buffer, the oldest lines are trimmed once it's exceeded
+    max_lines: Option<usize>,
+    /// number of lines trimmed from the start of the buffer since it was last taken
+    trimmed: usize,
+    /// fixed grid of cells that bytes are written to instead of the buffer, if grid mode is enabled
+    grid: Option<TerminalGrid>,
 }
 
 impl CharDevice {
@@ -31,6 +63,21 @@ impl CharDevice {
         Cursor::new(self.buffer.as_bytes().to_vec())
     }
 
+    /// Returns the current revision, which changes whenever the buffer or scroll position changes
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns true if this device only displays output
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Sets whether this device only displays output, i.e. for streamed plugin output
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
     /// Returns the number of lines in the buffer
     pub fn line_count(&self) -> usize {
         self.line_info.len()
@@ -38,25 +85,172 @@ impl CharDevice {
 
     /// Moves the cursor position up a line
     /// 
+    /// The cursor keeps its preferred column, or moves to the end of the line if it's shorter
     pub fn cursor_up(&mut self) {
         if self.line > 0 {
-            self.line -= 1;
-            self.goto_line(self.line);
+            self.move_to_line(self.line - 1);
         }
     }
 
     /// Moves the cursor down a line
     /// 
+    /// The cursor keeps its preferred column, or moves to the end of the line if it's shorter
     pub fn cursor_down(&mut self) {
-        if self.line < self.line_info.len() - 1 {
-            self.line += 1;
-            self.goto_line(self.line);
+        if self.line + 1 < self.line_info.len() {
+            self.move_to_line(self.line + 1);
+        }
+    }
+
+    /// Returns the column of the cursor, in chars from the start of its line
+    pub fn column(&self) -> usize {
+        let start = self.line_start(self.line).min(self.cursor);
+        self.buffer[start..self.cursor].chars().count()
+    }
+
+    /// Returns the offset of the start of line_no
+    fn line_start(&self, line_no: usize) -> usize {
+        let start = self.line_info.iter().take(line_no).sum::<usize>() + line_no;
+        start.min(self.buffer.len())
+    }
+
+    /// Moves the cursor to line_no, at the preferred column clamped to the length of the line
+    fn move_to_line(&mut self, line_no: usize) {
+        let col = match self.preferred_col {
+            Some(col) => col,
+            None => self.column(),
+        };
+        self.preferred_col = Some(col);
+        let start = self.line_start(line_no);
+        let len = self.line_info.get(line_no).cloned().unwrap_or_default();
+
+        self.line = line_no;
+        self.cursor = self.buffer[start..start + len]
+            .char_indices()
+            .nth(col)
+            .map(|(offset, _)| start + offset)
+            .unwrap_or(start + len);
+    }
+
+    /// Returns the offsets of the start and end of line_no, the end excludes the line break
+    fn line_range(&self, line_no: usize) -> (usize, usize) {
+        let start = self.line_start(line_no);
+        let len = self.line_info.get(line_no).cloned().unwrap_or_default();
+        (start, (start + len).min(self.buffer.len()))
+    }
+
+    /// Records and applies an edit to the buffer
+    fn record_edit(&mut self, edit: Edit) {
+        edit.apply(&mut self.buffer);
+        self.record(edit);
+    }
+
+    /// Records an edit that was applied to the buffer, for undo and the journal
+    fn record(&mut self, edit: Edit) {
+        if let Some(unjournaled) = self.unjournaled.as_mut() {
+            unjournaled.extend(edit.clone().flatten());
+        }
+        if !self.untracked {
+            self.history.record(edit);
+        }
+    }
+
+    /// Sets whether edits are recorded for undo, output channels only display what's written to them
+    pub fn set_undoable(&mut self, undoable: bool) {
+        self.untracked = !undoable;
+        if self.untracked {
+            self.history.clear();
         }
     }
 
+    /// Sets whether edits to this device are kept for the journal, see take_unjournaled
+    pub fn set_journaled(&mut self, journaled: bool) {
+        self.unjournaled = if journaled { Some(vec![]) } else { None };
+    }
+
+    /// Returns true if edits to this device are kept for the journal
+    pub fn journaled(&self) -> bool {
+        self.unjournaled.is_some()
+    }
+
+    /// Takes the edits made since the last call, in the order they were applied
+    pub fn take_unjournaled(&mut self) -> Vec<Edit> {
+        self.unjournaled.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Deletes from the cursor to the end of the line, returns the deleted text
+    ///
+    /// If the cursor is already at the end of the line, the line break is deleted, joining the next line
+    pub fn kill_line(&mut self) -> String {
+        let (_, end) = self.line_range(self.line);
+        let end = if self.cursor >= end && end < self.buffer.len() {
+            end + 1
+        } else {
+            end
+        };
+
+        if self.cursor >= end {
+            return String::new();
+        }
+
+        let text = self.buffer[self.cursor..end].to_string();
+        self.record_edit(Edit::Delete {
+            at: self.cursor,
+            text: text.clone(),
+        });
+        self.update_line_info();
+        text
+    }
+
+    /// Inserts a copy of the current line below it, and moves the cursor to the copy
+    pub fn duplicate_line(&mut self) {
+        let col = self.column();
+        let (start, end) = self.line_range(self.line);
+        // Grouped, so the copy isn't merged into the typing before it
+        let text = format!("\r{}", &self.buffer[start..end]);
+        self.record_edit(Edit::Group(vec![Edit::Insert { at: end, text }]));
+        self.update_line_info();
+
+        self.preferred_col = Some(col);
+        self.move_to_line(self.line + 1);
+    }
+
+    /// Swaps the current line w/ the line above it, the cursor moves w/ the line
+    pub fn move_line_up(&mut self) {
+        if self.line > 0 {
+            self.swap_lines(self.line - 1);
+            self.move_to_line(self.line - 1);
+        }
+    }
+
+    /// Swaps the current line w/ the line below it, the cursor moves w/ the line
+    pub fn move_line_down(&mut self) {
+        if self.line + 1 < self.line_info.len() {
+            self.swap_lines(self.line);
+            self.move_to_line(self.line + 1);
+        }
+    }
+
+    /// Swaps line_no w/ the line after it, keeping the cursor's column as the preferred column
+    fn swap_lines(&mut self, line_no: usize) {
+        let col = self.column();
+        let (start, first_end) = self.line_range(line_no);
+        let (_, end) = self.line_range(line_no + 1);
+
+        let removed = self.buffer[start..end].to_string();
+        let swapped = format!("{}\r{}", &self.buffer[first_end + 1..end], &self.buffer[start..first_end]);
+        self.record_edit(Edit::Group(vec![
+            Edit::Delete { at: start, text: removed },
+            Edit::Insert { at: start, text: swapped },
+        ]));
+        self.update_line_info();
+
+        self.preferred_col = Some(col);
+    }
+
     /// Moves the cursor left one character
     /// 
     pub fn cursor_left(&mut self) {
+        self.preferred_col = None;
         if self.cursor > 1 && !self.buffer.is_empty() {
             self.cursor -= 1;
 
@@ -72,6 +266,7 @@ impl CharDevice {
     /// Moves the cursor right one character
     /// 
     pub fn cursor_right(&mut self) {
+        self.preferred_col = None;
         if self.cursor < self.buffer.len() {
             self.cursor += 1;
 
@@ -82,6 +277,115 @@ impl CharDevice {
         }
     }
 
+    /// Moves the cursor to offset, i.e. from a mouse click
+    ///
+    /// Offset is clamped to the buffer and moved back to the nearest char boundary
+    pub fn set_cursor(&mut self, offset: usize) {
+        let mut offset = offset.min(self.buffer.len());
+        while !self.buffer.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        self.cursor = offset;
+        self.line = self.buffer[..offset].matches('\r').count();
+        self.preferred_col = None;
+        self.selection = None;
+        self.block_selection = None;
+    }
+
+    /// Selects span of the buffer, moving the cursor to its end
+    pub fn set_selection(&mut self, span: Range<usize>) {
+        if self.buffer.get(span.clone()).is_some() {
+            self.set_cursor(span.end);
+            self.selection = Some(span);
+        }
+    }
+
+    /// Returns the selected span of the buffer
+    pub fn selection(&self) -> Option<Range<usize>> {
+        self.selection.clone()
+    }
+
+    /// Returns the selected text
+    pub fn selected_text(&self) -> Option<&str> {
+        self.buffer.get(self.selection.clone()?)
+    }
+
+    /// Returns the line and column of offset in the buffer
+    pub fn position_of(&self, offset: usize) -> (usize, usize) {
+        position(&self.buffer, offset)
+    }
+
+    /// Sets the rectangular selection of the buffer
+    pub fn set_block_selection(&mut self, selection: Option<BlockSelection>) {
+        self.block_selection = selection;
+        self.revision += 1;
+    }
+
+    /// Returns the rectangular selection of the buffer
+    pub fn block_selection(&self) -> Option<BlockSelection> {
+        self.block_selection
+    }
+
+    /// Returns the text of each line in the rectangular selection
+    pub fn copy_block(&self) -> Vec<String> {
+        self.block_selection
+            .map(|selection| selection.copy(&self.buffer))
+            .unwrap_or_default()
+    }
+
+    /// Inserts each row of block at the cursor's column, on the cursor's line and the lines after it
+    pub fn paste_block(&mut self, block: &[String]) {
+        if block.is_empty() {
+            return;
+        }
+
+        let cursor = self.cursor;
+        let pasted = paste_block(&self.buffer, (self.line, self.column()), block);
+        // Replacing the buffer is recorded as a single edit, so that one undo restores it
+        let removed = self.buffer.clone();
+        self.record_edit(Edit::Group(vec![
+            Edit::Delete { at: 0, text: removed },
+            Edit::Insert { at: 0, text: pasted },
+        ]));
+        self.update_line_info();
+        self.set_cursor(cursor);
+    }
+
+    /// Scrolls the output by delta lines, clamped to the lines in the buffer
+    pub fn scroll_by(&mut self, delta: i32) {
+        let max = self.line_count().saturating_sub(1) as i32;
+        self.scroll = (self.scroll as i32 + delta).clamp(0, max.max(0)) as usize;
+        self.revision += 1;
+    }
+
+    /// Scrolls the output to line, clamped to the lines in the buffer
+    pub fn set_scroll(&mut self, line: usize) {
+        let line = line.min(self.line_count().saturating_sub(1));
+        if line != self.scroll {
+            self.scroll = line;
+            self.revision += 1;
+        }
+    }
+
+    /// Returns the first line visible when rendering output
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Returns the output starting from the scrolled line
+    pub fn visible_output(&self) -> impl AsRef<str> + '_ {
+        let start = self
+            .line_info
+            .iter()
+            .take(self.scroll)
+            .map(|l| l + 1)
+            .sum::<usize>()
+            .min(self.buffer.len());
+
+        &self.buffer[start..]
+    }
+
     /// Moves the character to line_no
     /// 
     pub fn goto_line(&mut self, line_no: usize) {
@@ -97,16 +401,117 @@ impl CharDevice {
     ///
     /// Updates internal counters
     pub fn write_char(&mut self, next: u8) {
+        self.decode(next);
+        self.update_line_info();
+    }
+
+    /// Writes a batch of characters to the decoder, and internal buffer
+    ///
+    /// Internal counters are updated once, after the entire batch is written
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        if let Some(grid) = self.grid.as_mut() {
+            grid.write_bytes(bytes);
+            self.revision += 1;
+            return;
+        }
+
+        for next in bytes {
+            self.decode(*next);
+        }
+        self.update_line_info();
+        self.trim();
+    }
+
+    /// Enables grid mode w/ a grid of rows x cols cells, or resizes the grid if it's already enabled
+    ///
+    /// In grid mode, written bytes are interpreted like a terminal would and update the grid instead of the buffer
+    pub fn enable_grid(&mut self, rows: usize, cols: usize) {
+        match self.grid.as_mut() {
+            Some(grid) if grid.size() == (rows.max(1), cols.max(1)) => return,
+            Some(grid) => grid.resize(rows, cols),
+            None => self.grid = Some(TerminalGrid::new(rows, cols)),
+        }
+        self.revision += 1;
+    }
+
+    /// Disables grid mode, bytes written afterwards are appended to the buffer again
+    pub fn disable_grid(&mut self) {
+        if self.grid.take().is_some() {
+            self.revision += 1;
+        }
+    }
+
+    /// Returns the grid bytes are written to, if grid mode is enabled
+    pub fn grid(&self) -> Option<&TerminalGrid> {
+        self.grid.as_ref()
+    }
+
+    /// Sets the max number of lines kept in the buffer, None lets the buffer grow without bound
+    ///
+    /// Once the buffer has more lines, the oldest lines are trimmed from the start of the buffer
+    pub fn set_max_lines(&mut self, max_lines: Option<usize>) {
+        self.max_lines = max_lines.map(|max| max.max(1));
+        self.trim();
+    }
+
+    /// Returns the max number of lines kept in the buffer
+    pub fn max_lines(&self) -> Option<usize> {
+        self.max_lines
+    }
+
+    /// Returns the number of lines trimmed from the start of the buffer, since it was last taken
+    pub fn trimmed_lines(&self) -> usize {
+        self.trimmed
+    }
+
+    /// Removes the oldest lines past max_lines, the cursor and scroll position are moved up w/ the remaining lines
+    ///
+    /// Offsets of recorded edits no longer line up w/ the buffer, so the undo history is cleared
+    fn trim(&mut self) {
+        let excess = match self.max_lines {
+            Some(max) if self.line_info.len() > max => self.line_info.len() - max,
+            _ => return,
+        };
+
+        let end = match self.buffer.match_indices('\r').nth(excess - 1) {
+            Some((idx, _)) => idx + 1,
+            None => return,
+        };
+
+        let removed = self.buffer.drain(..end).collect::<String>();
+        if let Some(unjournaled) = self.unjournaled.as_mut() {
+            unjournaled.push(Edit::Delete { at: 0, text: removed });
+        }
+
+        self.cursor = self.cursor.saturating_sub(end);
+        self.line = self.line.saturating_sub(excess);
+        self.scroll = self.scroll.saturating_sub(excess);
+        self.trimmed += excess;
+        self.history.clear();
+        self.update_line_info();
+    }
+
+    /// Decodes the next character into the internal buffer
+    fn decode(&mut self, next: u8) {
         for keycode in self.decoder.write(next) {
             if let Some(printable) = keycode.printable() {
                 self.buffer.insert(self.cursor, printable);
+                self.record(Edit::Insert {
+                    at: self.cursor,
+                    text: printable.to_string(),
+                });
                 self.cursor += 1 as usize;
             } else {
                 match keycode {
                     KeyCode::Backspace => {
                         if self.cursor > 0 && !self.buffer.is_empty() {
                             self.cursor -= 1;
-                            match self.buffer.remove(self.cursor) {
+                            let removed = self.buffer.remove(self.cursor);
+                            self.record(Edit::Delete {
+                                at: self.cursor,
+                                text: removed.to_string(),
+                            });
+                            match removed {
                                 '\r' | '\n' => {
                                     if self.line > 0 {
                                         self.line -= 1;
@@ -124,10 +529,119 @@ impl CharDevice {
                 self.line += 1;
             }
         }
+    }
+
+    /// Returns the edits that can be undone, most recent last
+    pub fn history(&self) -> &[Edit] {
+        self.history.edits()
+    }
+
+    /// Replaces the undo history, edits are expected to have been made to the current buffer
+    pub fn restore_history(&mut self, edits: Vec<Edit>) {
+        self.history.restore(edits);
+    }
+
+    /// Undoes the most recent edit, returns false if there was nothing to undo
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo() {
+            Some(edit) => {
+                self.apply_edit(edit);
+                true
+            }
+            None => false,
+        }
+    }
 
+    /// Redoes the most recently undone edit, returns false if there was nothing to redo
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo() {
+            Some(edit) => {
+                self.apply_edit(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Applies an edit from the history, moving the cursor to where the edit ends
+    fn apply_edit(&mut self, edit: Edit) {
+        if let Some(unjournaled) = self.unjournaled.as_mut() {
+            unjournaled.extend(edit.clone().flatten());
+        }
+        self.cursor = edit.apply(&mut self.buffer);
+        self.line = self.buffer[..self.cursor].matches('\r').count();
+        self.update_line_info();
+    }
+
+    /// Recounts the characters per line
+    fn update_line_info(&mut self) {
+        self.revision += 1;
+        self.preferred_col = None;
+        self.selection = None;
+        self.block_selection = None;
         self.line_info = self.buffer.split('\r').map(|l| l.len()).collect();
     }
 
+    /// Replaces the buffer with text, moving the cursor to the end
+    ///
+    /// Lines in text are expected to be separated by `\r`
+    pub fn set_buffer(&mut self, text: impl AsRef<str>) {
+        let scroll = self.scroll;
+        let history = std::mem::take(&mut self.history);
+        let removed = self.take_buffer();
+        self.history = history;
+        self.buffer = text.as_ref().to_string();
+
+        // Replacing the buffer can be undone, i.e. after loading a file or applying a fix
+        if !self.untracked && removed != self.buffer {
+            let mut edits = vec![];
+            if !removed.is_empty() {
+                edits.push(Edit::Delete { at: 0, text: removed });
+            }
+            if !self.buffer.is_empty() {
+                edits.push(Edit::Insert {
+                    at: 0,
+                    text: self.buffer.clone(),
+                });
+            }
+            self.history.record(Edit::Group(edits));
+        }
+
+        if let Some(unjournaled) = self.unjournaled.as_mut().filter(|_| !self.buffer.is_empty()) {
+            unjournaled.push(Edit::Insert {
+                at: 0,
+                text: self.buffer.clone(),
+            });
+        }
+        self.cursor = self.buffer.len();
+        self.update_line_info();
+        self.line = self.line_info.len().saturating_sub(1);
+
+        // Keep the scroll position, i.e. when a watched file is reloaded
+        self.scroll = scroll;
+        self.scroll_by(0);
+        self.trim();
+    }
+
+    /// Appends text to the end of the buffer
+    ///
+    /// Lines in text are expected to be separated by `\r`
+    pub fn append(&mut self, text: impl AsRef<str>) {
+        self.set_buffer(format!("{}{}", self.buffer, text.as_ref()));
+    }
+
+    /// Replaces the buffer with text from outside the shell, i.e. a file
+    ///
+    /// Line endings are converted to `\r`
+    pub fn set_text(&mut self, text: impl AsRef<str>) {
+        self.set_buffer(text.as_ref().replace("\r\n", "\r").replace('\n', "\r"));
+    }
+
+    /// Returns the cursor's offset in the buffer
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
     /// Returns the cursor's tail
     pub fn cursor_tail(&self) -> usize {
         if self.cursor > 1 {
@@ -185,11 +699,236 @@ impl CharDevice {
     /// Takes the current buffer, resetting the state and clearing the decoder for this device
     pub fn take_buffer(&mut self) -> String {
         let output = self.buffer.clone();
+        if let Some(unjournaled) = self.unjournaled.as_mut().filter(|_| !output.is_empty()) {
+            unjournaled.push(Edit::Delete {
+                at: 0,
+                text: output.clone(),
+            });
+        }
         self.buffer.clear();
         self.cursor = 0;
         self.line = 0;
+        self.scroll = 0;
         self.line_info.clear();
+        self.history.clear();
+        self.trimmed = 0;
         self.decoder = Decoder::default();
+        self.revision += 1;
         output
     }
 }
+
+#[test]
+fn test_write_bytes() {
+    let mut by_char = CharDevice::default();
+    for next in b"hello\rworld" {
+        by_char.write_char(*next);
+    }
+
+    let mut by_batch = CharDevice::default();
+    by_batch.write_bytes(b"hello\rworld");
+
+    assert_eq!(by_batch.output().as_ref(), by_char.output().as_ref());
+    assert_eq!(by_batch.line_count(), 2);
+}
+
+#[test]
+fn test_preferred_column() {
+    let mut device = CharDevice::default();
+    device.set_buffer("hello world\rhi\rgoodbye world");
+    device.set_cursor(8);
+    assert_eq!(device.column(), 8);
+
+    // Clamped to the end of the shorter line
+    device.cursor_down();
+    assert_eq!(device.cursor(), 14);
+    assert_eq!(device.column(), 2);
+
+    // The preferred column is restored on a longer line
+    device.cursor_down();
+    assert_eq!(device.column(), 8);
+    device.cursor_up();
+    device.cursor_up();
+    assert_eq!(device.cursor(), 8);
+
+    // Horizontal movement resets the preferred column
+    device.cursor_right();
+    device.cursor_down();
+    device.cursor_down();
+    assert_eq!(device.column(), 9);
+}
+
+#[test]
+fn test_line_operations() {
+    let mut device = CharDevice::default();
+    device.set_buffer("one\rtwo\rthree");
+    device.set_cursor(5);
+
+    device.duplicate_line();
+    assert_eq!(device.output().as_ref(), "one\rtwo\rtwo\rthree");
+    assert_eq!(device.cursor(), 9);
+
+    device.move_line_up();
+    device.move_line_up();
+    assert_eq!(device.output().as_ref(), "two\rone\rtwo\rthree");
+    assert_eq!(device.column(), 1);
+
+    device.move_line_down();
+    device.move_line_down();
+    device.move_line_down();
+    assert_eq!(device.output().as_ref(), "one\rtwo\rthree\rtwo");
+    assert_eq!(device.column(), 1);
+
+    device.set_cursor(1);
+    assert_eq!(device.kill_line(), "ne");
+    assert_eq!(device.kill_line(), "\r");
+    assert_eq!(device.output().as_ref(), "otwo\rthree\rtwo");
+
+    assert!(device.undo());
+    assert_eq!(device.output().as_ref(), "o\rtwo\rthree\rtwo");
+}
+
+#[test]
+fn test_line_operations_undo() {
+    let mut device = CharDevice::default();
+    device.set_buffer("one\rtwo");
+    device.set_cursor(5);
+    device.write_bytes(b"x");
+    device.duplicate_line();
+    assert_eq!(device.output().as_ref(), "one\rtxwo\rtxwo");
+
+    // Duplicating isn't merged w/ the typing before it
+    assert!(device.undo());
+    assert_eq!(device.output().as_ref(), "one\rtxwo");
+    assert!(device.undo());
+    assert_eq!(device.output().as_ref(), "one\rtwo");
+
+    // Swapping lines is undone in one step
+    device.set_cursor(5);
+    device.move_line_up();
+    assert_eq!(device.output().as_ref(), "two\rone");
+    assert!(device.undo());
+    assert_eq!(device.output().as_ref(), "one\rtwo");
+    assert!(device.redo());
+    assert_eq!(device.output().as_ref(), "two\rone");
+}
+
+#[test]
+fn test_paste_block_undo() {
+    let mut device = CharDevice::default();
+    device.set_buffer("ab\rc");
+    device.set_cursor(2);
+    device.paste_block(&["x".to_string(), "y".to_string()]);
+    assert_eq!(device.output().as_ref(), "abx\rc y");
+
+    assert!(device.undo());
+    assert_eq!(device.output().as_ref(), "ab\rc");
+    assert!(device.redo());
+    assert_eq!(device.output().as_ref(), "abx\rc y");
+}
+
+#[test]
+fn test_set_text_undo() {
+    let mut device = CharDevice::default();
+    device.set_text("add a .text one");
+    device.set_text("add a .text two\nadd b .int 2");
+    assert!(device.undo());
+    assert_eq!(device.output().as_ref(), "add a .text one");
+
+    // Output channels don't keep history
+    device.set_undoable(false);
+    device.append("\rmore");
+    assert!(!device.undo());
+    assert_eq!(device.output().as_ref(), "add a .text one\rmore");
+}
+
+#[test]
+fn test_journaled_edits() {
+    let mut device = CharDevice::default();
+    device.set_buffer("hello");
+    device.set_journaled(true);
+
+    device.set_cursor(5);
+    device.duplicate_line();
+    device.kill_line();
+    device.set_buffer("replaced");
+
+    let mut replayed = "hello".to_string();
+    for edit in device.take_unjournaled() {
+        edit.apply(&mut replayed);
+    }
+    assert_eq!(replayed, device.output().as_ref());
+    assert!(device.take_unjournaled().is_empty());
+}
+
+#[test]
+fn test_selection() {
+    let mut device = CharDevice::default();
+    device.set_buffer("add name .text héllo");
+
+    device.set_selection(15..21);
+    assert_eq!(device.selected_text(), Some("héllo"));
+    assert_eq!(device.cursor(), 21);
+
+    // Spans that split a char aren't selected
+    device.set_selection(15..17);
+    assert_eq!(device.selection(), Some(15..21));
+
+    device.append(" world");
+    assert_eq!(device.selection(), None);
+}
+
+#[test]
+fn test_undo_redo() {
+    let mut device = CharDevice::default();
+    device.write_bytes(b"hello");
+    assert_eq!(device.output().as_ref(), "hello");
+
+    assert!(device.undo());
+    assert_eq!(device.output().as_ref(), "");
+    assert!(!device.undo());
+
+    assert!(device.redo());
+    assert_eq!(device.output().as_ref(), "hello");
+    assert!(!device.redo());
+}
+
+#[test]
+fn test_max_lines() {
+    let mut device = CharDevice::default();
+    device.set_max_lines(Some(3));
+    device.write_bytes(b"one
+two
+three
+four
+five");
+
+    assert_eq!(device.output().as_ref(), "three
+four
+five");
+    assert_eq!(device.line_count(), 3);
+    assert_eq!(device.trimmed_lines(), 2);
+    assert_eq!(device.cursor(), device.output().as_ref().len());
+
+    device.set_max_lines(Some(1));
+    assert_eq!(device.output().as_ref(), "five");
+    assert_eq!(device.trimmed_lines(), 4);
+
+    device.take_buffer();
+    assert_eq!(device.trimmed_lines(), 0);
+}
+
+#[test]
+fn test_grid_mode() {
+    let mut device = CharDevice::default();
+    device.enable_grid(2, 4);
+    device.write_bytes(b"ab\r\ncd\x1b[1;4HX");
+
+    assert_eq!(device.grid().map(|g| g.text()), Some("ab X\rcd".to_string()));
+    assert_eq!(device.output().as_ref(), "");
+
+    device.disable_grid();
+    device.write_bytes(b"ef");
+    assert!(device.grid().is_none());
+    assert_eq!(device.output().as_ref(), "ef");
+}
@@ -0,0 +1,42 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns the UTC date of time, formatted as `YYYY-MM-DD`
+pub fn date(time: SystemTime) -> String {
+    let days = (seconds(time) / 86400) as i64;
+
+    // Converts days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Returns the UTC time of day of time as hours, minutes, and seconds
+pub fn time_of_day(time: SystemTime) -> (u64, u64, u64) {
+    let secs = seconds(time) % 86400;
+    (secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Returns the seconds since the epoch, times before it are the epoch
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_civil_time() {
+    use std::time::Duration;
+
+    let time = UNIX_EPOCH + Duration::from_secs(1_659_312_000 + 14 * 3600 + 25 * 60 + 30);
+    assert_eq!(date(time), "2022-08-01");
+    assert_eq!(time_of_day(time), (14, 25, 30));
+    assert_eq!(date(UNIX_EPOCH), "1970-01-01");
+}
@@ -0,0 +1,18 @@
+use std::time::Instant;
+
+/// Trait for reading the current time, so that timers can be driven deterministically
+///
+pub trait Clock: Send + Sync {
+    /// Returns the current instant
+    fn now(&self) -> Instant;
+}
+
+/// Clock that reads the system's monotonic clock
+#[derive(Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
@@ -1,50 +1,194 @@
 use wgpu_glyph::Text;
 
 /// Trait to edit parts of the shell
-pub trait ColorTheme {
+///
+/// Themes are held as trait objects, so they can be installed or swapped at runtime
+pub trait ColorTheme: Send + Sync {
     /// Theme to use for the prompt
-    fn prompt() -> Text<'static>;
+    fn prompt(&self) -> Text<'static>;
 
     /// Theme to use for the cursor
-    fn cursor() -> Text<'static>;
+    fn cursor(&self) -> Text<'static>;
 
     /// Background color,
     /// 
     /// caveat: expecting linear srgb 
-    fn background() -> [f32; 4];
+    fn background(&self) -> [f32; 4];
 
     /// Foreground color
     /// 
     /// caveat: expecting linear srgb
-    fn foreground() -> [f32; 4];
+    fn foreground(&self) -> [f32; 4];
 
     /// Red color 
     /// 
     /// caveat: expecting linear srgb
-    fn red() -> [f32; 4];
+    fn red(&self) -> [f32; 4];
 
     /// Blue color 
     /// 
     /// caveat: expecting linear srgb
-    fn blue() -> [f32; 4];
+    fn blue(&self) -> [f32; 4];
 
     /// Purple color
     /// 
     /// caveat: expecting linear srgb
-    fn purple() -> [f32; 4];
+    fn purple(&self) -> [f32; 4];
 
     /// Green color
     /// 
     /// caveat: expecting linear srgb
-    fn green() -> [f32; 4];
+    fn green(&self) -> [f32; 4];
 
     /// Yellow color
     /// 
     /// caveat: expecting linear srgb
-    fn yellow() -> [f32; 4];
+    fn yellow(&self) -> [f32; 4];
 
     /// Orange color
     /// 
     /// caveat: expecting linear srgb
-    fn orange() -> [f32; 4];
+    fn orange(&self) -> [f32; 4];
+
+    /// Color for critical text, i.e. diagnostics
+    /// 
+    /// caveat: expecting linear srgb
+    fn critical(&self) -> [f32; 4] {
+        self.red()
+    }
+
+    /// Cyan color
+    ///
+    /// caveat: expecting linear srgb
+    fn cyan(&self) -> [f32; 4] {
+        self.blue()
+    }
+
+    /// Color at index of the 256-color palette
+    ///
+    /// 0-15 are the ansi colors of this theme, 16-231 are the 6x6x6 color cube, and 232-255 are the grayscale ramp
+    ///
+    /// caveat: expecting linear srgb
+    fn color(&self, index: u8) -> [f32; 4] {
+        match index {
+            0 | 8 => self.background(),
+            1 | 9 => self.red(),
+            2 | 10 => self.green(),
+            3 | 11 => self.yellow(),
+            4 | 12 => self.blue(),
+            5 | 13 => self.purple(),
+            6 | 14 => self.cyan(),
+            7 | 15 => self.foreground(),
+            16..=231 => {
+                let cube = index - 16;
+                let level = |c: u8| if c == 0 { 0 } else { 55 + c * 40 };
+                truecolor(level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+            }
+            _ => {
+                let gray = 8 + (index - 232) * 10;
+                truecolor(gray, gray, gray)
+            }
+        }
+    }
+
+    /// Returns the color for a name, i.e. from a theme block, or a remote connection
+    ///
+    /// Names are the ansi color names, `orange`, `foreground`, `background`, or `color0`-`color255` for the 256-color palette
+    fn named_color(&self, name: &str) -> Option<[f32; 4]> {
+        match name.trim() {
+            "black" | "background" => Some(self.background()),
+            "red" => Some(self.red()),
+            "green" => Some(self.green()),
+            "yellow" => Some(self.yellow()),
+            "blue" => Some(self.blue()),
+            "purple" | "magenta" => Some(self.purple()),
+            "cyan" => Some(self.cyan()),
+            "white" | "foreground" => Some(self.foreground()),
+            "orange" => Some(self.orange()),
+            "critical" => Some(self.critical()),
+            other => other
+                .strip_prefix("color")
+                .and_then(|index| index.parse::<u8>().ok())
+                .map(|index| self.color(index)),
+        }
+    }
+}
+
+/// Converts an sRGB component to linear sRGB
+pub fn srgb_to_linear(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear sRGB component to sRGB
+pub fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Converts an sRGB color, i.e. as authored in a theme block or picked in a color editor, to linear sRGB
+///
+/// Alpha is already linear, so it's left as is
+pub fn color_to_linear([r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a]
+}
+
+/// Converts a linear sRGB color to sRGB, the reverse of color_to_linear
+pub fn color_to_srgb([r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    [linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a]
+}
+
+/// Converts a 24-bit sRGB color, i.e. from a truecolor escape sequence, to linear sRGB
+pub fn truecolor(r: u8, g: u8, b: u8) -> [f32; 4] {
+    let linear = |c: u8| srgb_to_linear(c as f32 / 255.0);
+    [linear(r), linear(g), linear(b), 1.0]
+}
+
+/// Returns the foreground color set by the parameters of an SGR escape sequence, i.e. `ESC[38;5;208m`
+///
+/// Supports 30-37 and 90-97, 256-color (`38;5;n`), and truecolor (`38;2;r;g;b`), returns None for any other parameters
+pub fn sgr_foreground(theme: &dyn ColorTheme, params: &[u16]) -> Option<[f32; 4]> {
+    match params {
+        [code @ 30..=37] => Some(theme.color((code - 30) as u8)),
+        [code @ 90..=97] => Some(theme.color((code - 90 + 8) as u8)),
+        [38, 5, index] => u8::try_from(*index).ok().map(|index| theme.color(index)),
+        [38, 2, r, g, b] => Some(truecolor(
+            u8::try_from(*r).ok()?,
+            u8::try_from(*g).ok()?,
+            u8::try_from(*b).ok()?,
+        )),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_srgb_conversion() {
+    let color = [0.5, 0.25, 1.0, 0.5];
+    let linear = color_to_linear(color);
+    assert!((linear[0] - 0.21404).abs() < 0.0001);
+    assert_eq!(linear[3], 0.5);
+
+    for (a, b) in color_to_srgb(linear).iter().zip(color.iter()) {
+        assert!((a - b).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn test_palette() {
+    let theme = crate::DefaultTheme;
+    assert_eq!(theme.color(1), theme.red());
+    assert_eq!(theme.color(16), [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(theme.color(231), [1.0, 1.0, 1.0, 1.0]);
+    assert_eq!(theme.named_color("color9"), Some(theme.red()));
+    assert_eq!(sgr_foreground(&theme, &[38, 5, 196]), Some(truecolor(255, 0, 0)));
+    assert_eq!(sgr_foreground(&theme, &[38, 2, 255, 255, 255]), Some([1.0, 1.0, 1.0, 1.0]));
+    assert_eq!(sgr_foreground(&theme, &[38, 2, 256, 0, 0]), None);
 }
\ No newline at end of file
@@ -0,0 +1,62 @@
+use lifec::plugins::ThunkContext;
+use logos::Logos;
+
+use crate::{theme::ThemeToken, Grammer, Token};
+
+/// Grammer for the shell's own commands, i.e. `:open --force "file.runmd"`
+///
+/// Lines starting with the theme's command prefix are highlighted with this grammer instead of the channel's grammer
+#[derive(Logos, PartialEq, Eq, Debug, Clone)]
+#[logos(extras = ThunkContext)]
+pub enum CommandGrammer {
+    /// Name of the command, including the prefix
+    #[regex(r":[a-zA-Z_][a-zA-Z0-9_\-]*")]
+    Command,
+    /// Flags, i.e. `--force` or `-f`
+    #[regex(r"--?[a-zA-Z][a-zA-Z0-9_\-]*")]
+    Flag,
+    /// Quoted arguments
+    #[regex(r#""([^"\\]|\\.)*""#)]
+    Quoted,
+    /// Arguments
+    #[regex(r#"[^ \t\r\n\f":\-][^ \t\r\n\f"]*"#)]
+    Argument,
+    #[regex(r"[ \t\r\n\f]+", logos::skip)]
+    #[error]
+    Error,
+}
+
+impl Grammer for CommandGrammer {
+    fn name() -> &'static str {
+        "command"
+    }
+}
+
+impl Into<Vec<ThemeToken>> for CommandGrammer {
+    fn into(self) -> Vec<ThemeToken> {
+        match self {
+            CommandGrammer::Command => vec![(Token::Keyword, None)],
+            CommandGrammer::Flag => vec![(Token::Modifier, None)],
+            CommandGrammer::Quoted => vec![(Token::Literal, None)],
+            CommandGrammer::Argument => vec![(Token::Identifier, None)],
+            CommandGrammer::Error => vec![],
+        }
+    }
+}
+
+#[test]
+fn test_command_grammer() {
+    let mut lexer = CommandGrammer::lexer_with_extras(
+        r#":open --force -v "my file.runmd" test"#,
+        ThunkContext::default(),
+    );
+
+    assert_eq!(lexer.next(), Some(CommandGrammer::Command));
+    assert_eq!(lexer.slice(), ":open");
+    assert_eq!(lexer.next(), Some(CommandGrammer::Flag));
+    assert_eq!(lexer.next(), Some(CommandGrammer::Flag));
+    assert_eq!(lexer.next(), Some(CommandGrammer::Quoted));
+    assert_eq!(lexer.slice(), r#""my file.runmd""#);
+    assert_eq!(lexer.next(), Some(CommandGrammer::Argument));
+    assert_eq!(lexer.next(), None);
+}
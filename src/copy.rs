@@ -0,0 +1,30 @@
+/// Returns a buffer w/ its `\r` line breaks converted to `\n`, for the clipboard
+pub fn to_clipboard_text(buffer: &str) -> String {
+    buffer.replace('\r', "\n")
+}
+
+/// Returns a buffer wrapped in a fenced markdown code block, w/ language as the fence's info string
+///
+/// The fence is longer than any run of backticks in the buffer, so that the block can't be closed early
+pub fn to_code_block(buffer: &str, language: &str) -> String {
+    let longest_run = buffer
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    let text = to_clipboard_text(buffer);
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    format!("{fence}{language}\n{text}\n{fence}\n")
+}
+
+#[test]
+fn test_to_code_block() {
+    assert_eq!(to_clipboard_text("ls\rcargo build"), "ls\ncargo build");
+    assert_eq!(to_code_block("ls\rcargo build\r", "sh"), "```sh\nls\ncargo build\n```\n");
+    assert_eq!(
+        to_code_block("``` runmd\radd a .text b\r```", "markdown"),
+        "````markdown\n``` runmd\nadd a .text b\n```\n````\n"
+    );
+}
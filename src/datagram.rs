@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tracing::{event, Level};
+
+use crate::{ShellChannel, Transport};
+
+/// Max size of a received datagram
+const MAX_DATAGRAM_LEN: usize = 65507;
+
+/// Transport that sends each line as a udp datagram to a peer, i.e. for poking at discovery or telemetry protocols
+///
+/// Every datagram the socket receives is written to a shell channel as a line, prefixed w/ its source address,
+/// including datagrams from sources other than the peer.
+pub struct Datagram {
+    socket: Arc<UdpSocket>,
+    peer: SocketAddr,
+    /// Task receiving datagrams, stopped when the transport is dropped
+    receiver: JoinHandle<()>,
+}
+
+impl Datagram {
+    /// Returns the peer of a `udp://host:port` address
+    pub fn parse(address: &str) -> Option<&str> {
+        address.strip_prefix("udp://").map(|peer| peer.trim_end_matches('/'))
+    }
+
+    /// Binds a socket to local, and sends lines to peer, datagrams received are written to output
+    ///
+    /// If local is None, the socket is bound to an ephemeral port of the peer's address family
+    pub async fn bind(local: Option<&str>, peer: &str, output: ShellChannel) -> std::io::Result<Self> {
+        let peer = tokio::net::lookup_host(peer).await?.next().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, format!("{peer} did not resolve to any addresses"))
+        })?;
+
+        let local = match local {
+            Some(local) => local.to_string(),
+            None if peer.is_ipv6() => "[::]:0".to_string(),
+            None => "0.0.0.0:0".to_string(),
+        };
+        let socket = Arc::new(UdpSocket::bind(&local).await?);
+        event!(Level::DEBUG, "Bound {:?}, sending datagrams to {peer}", socket.local_addr());
+
+        let receiver = tokio::spawn(receive(socket.clone(), output));
+        Ok(Self {
+            socket,
+            peer,
+            receiver,
+        })
+    }
+}
+
+impl Drop for Datagram {
+    fn drop(&mut self) {
+        self.receiver.abort();
+    }
+}
+
+#[async_trait]
+impl Transport for Datagram {
+    async fn write(&mut self, message: &[u8]) -> std::io::Result<usize> {
+        // Each line is a single datagram, w/o the line ending added by the shell
+        let datagram = message.strip_suffix(b"\r\n").unwrap_or(message);
+        self.socket.send_to(datagram, self.peer).await?;
+        Ok(message.len())
+    }
+
+    fn peer(&self) -> String {
+        format!("udp {}", self.peer)
+    }
+}
+
+/// Writes each datagram received on socket to the shell channel, until the shell stops receiving
+async fn receive(socket: Arc<UdpSocket>, output: ShellChannel) {
+    let mut buf = vec![0; MAX_DATAGRAM_LEN];
+    loop {
+        match socket.recv_from(&mut buf).await {
+            Ok((len, source)) => {
+                if !output.send_line(format_datagram(source, &buf[..len])).await {
+                    break;
+                }
+            }
+            // i.e. an ICMP port unreachable from a previous send, the socket is still usable
+            Err(err) => event!(Level::DEBUG, "Could not receive datagram, {err}"),
+        }
+    }
+}
+
+/// Returns a received datagram as a line, prefixed w/ its source address
+fn format_datagram(source: SocketAddr, datagram: &[u8]) -> String {
+    let text = String::from_utf8_lossy(datagram);
+    format!("[{source}] {}", text.trim_end_matches(['\r', '\n']))
+}
+
+#[test]
+fn test_format_datagram() {
+    let source = "10.0.0.1:5353".parse().unwrap();
+    assert_eq!(format_datagram(source, b"hello\n"), "[10.0.0.1:5353] hello");
+    assert_eq!(format_datagram(source, &[0x68, 0xff]), "[10.0.0.1:5353] h\u{fffd}");
+    assert_eq!(Datagram::parse("udp://localhost:5353/"), Some("localhost:5353"));
+    assert_eq!(Datagram::parse("localhost:5353"), None);
+}
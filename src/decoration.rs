@@ -0,0 +1,113 @@
+use crate::{PaneBackground, Rect};
+
+/// Height of the title bar at the top of each pane, in pixels
+pub const PANE_TITLE_HEIGHT: f32 = 24.0;
+
+/// Width of the border around a pane, in pixels
+pub const PANE_BORDER_WIDTH: f32 = 1.0;
+
+/// Width of the border around the focused pane, in pixels
+pub const FOCUSED_BORDER_WIDTH: f32 = 2.0;
+
+/// Border and title bar drawn around a pane
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaneDecoration {
+    /// Area of the pane, including its title bar
+    pub rect: Rect,
+    /// Text of the title bar
+    pub title: String,
+    /// True if this is the pane the user last clicked
+    pub focused: bool,
+    /// Height of the title bar, PANE_TITLE_HEIGHT scaled w/ the window
+    pub title_height: f32,
+}
+
+impl PaneDecoration {
+    /// Returns the area of the title bar
+    pub fn title_bar(&self) -> Rect {
+        Rect {
+            height: self.title_height.min(self.rect.height),
+            ..self.rect
+        }
+    }
+
+    /// Returns the area below the title bar, where the pane's content is drawn
+    pub fn content(&self) -> Rect {
+        pane_content(self.rect, self.title_height)
+    }
+
+    /// Returns the quads to draw for the title bar and border, colors are linear sRGB
+    pub fn quads(&self, title_bar: [f32; 4], border: [f32; 4]) -> Vec<(Rect, PaneBackground)> {
+        let solid = |rect: Rect, color: [f32; 4]| {
+            (
+                rect,
+                PaneBackground::Gradient {
+                    top: color,
+                    bottom: color,
+                    opacity: 1.0,
+                },
+            )
+        };
+
+        let Rect { x, y, width, height } = self.rect;
+        let w = if self.focused {
+            FOCUSED_BORDER_WIDTH
+        } else {
+            PANE_BORDER_WIDTH
+        }
+        .min(width / 2.0)
+        .min(height / 2.0);
+
+        vec![
+            solid(self.title_bar(), title_bar),
+            solid(Rect { x, y, width, height: w }, border),
+            solid(Rect { x, y: y + height - w, width, height: w }, border),
+            solid(Rect { x, y, width: w, height }, border),
+            solid(Rect { x: x + width - w, y, width: w, height }, border),
+        ]
+    }
+}
+
+/// Returns the area of a pane below its title bar, w/ a title bar of title_height
+pub fn pane_content(rect: Rect, title_height: f32) -> Rect {
+    let title = title_height.min(rect.height);
+    Rect {
+        y: rect.y + title,
+        height: rect.height - title,
+        ..rect
+    }
+}
+
+/// Mixes color a into b by t, used to derive decoration colors from a theme's background and foreground
+pub fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    let mut mixed = [0.0; 4];
+    for (idx, c) in mixed.iter_mut().enumerate() {
+        *c = a[idx] + (b[idx] - a[idx]) * t;
+    }
+    mixed
+}
+
+#[test]
+fn test_pane_decoration() {
+    let decoration = PaneDecoration {
+        rect: Rect { x: 10.0, y: 20.0, width: 200.0, height: 100.0 },
+        title: "Editor".to_string(),
+        focused: true,
+        title_height: PANE_TITLE_HEIGHT,
+    };
+
+    assert_eq!(decoration.content(), Rect { x: 10.0, y: 44.0, width: 200.0, height: 76.0 });
+
+    let quads = decoration.quads([0.0; 4], [1.0; 4]);
+    assert_eq!(quads.len(), 5);
+    assert_eq!(quads[0].0, decoration.title_bar());
+    assert_eq!(quads[4].0, Rect { x: 208.0, y: 20.0, width: 2.0, height: 100.0 });
+
+    let scaled = PaneDecoration {
+        title_height: PANE_TITLE_HEIGHT * 2.0,
+        ..decoration
+    };
+    assert_eq!(scaled.content(), Rect { x: 10.0, y: 68.0, width: 200.0, height: 52.0 });
+
+    assert_eq!(mix([0.0; 4], [1.0; 4], 0.25), [0.25; 4]);
+}
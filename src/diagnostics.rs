@@ -0,0 +1,73 @@
+use logos::Span;
+
+/// Diagnostic found while lexing a source, i.e. a malformed block or attribute
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Span of the source this diagnostic applies to
+    pub span: Span,
+    /// Line number the span starts on
+    pub line: usize,
+    /// Describes what is wrong with the span
+    pub message: String,
+    /// Replacements for the span that fix this diagnostic
+    pub suggestions: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Returns a new diagnostic for span, computing the line number from the source
+    ///
+    pub fn new(source: &str, span: Span, message: impl AsRef<str>) -> Self {
+        // A `\r\n` is a single line break, so the `\r` of each pair isn't counted
+        let before = &source[..span.start];
+        let line = before
+            .chars()
+            .filter(|c| *c == '\r' || *c == '\n')
+            .count()
+            - before.matches("\r\n").count();
+
+        Self {
+            span,
+            line,
+            message: message.as_ref().to_string(),
+            suggestions: vec![],
+        }
+    }
+
+    /// Returns this diagnostic with suggested replacements for its span
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+
+    /// Returns a copy of source with the span of this diagnostic replaced by the suggestion
+    ///
+    pub fn apply(&self, source: &str, suggestion: &str) -> String {
+        let mut fixed = source.to_string();
+        fixed.replace_range(self.span.clone(), suggestion);
+        fixed
+    }
+
+    /// Returns a copy of source where every character covered by a diagnostic is replaced with a `~`
+    ///
+    /// Since the font is monospace, the overlay can be rendered on top of the source to draw squiggles
+    pub fn squiggles(source: &str, diagnostics: &[Diagnostic]) -> String {
+        source
+            .char_indices()
+            .map(|(idx, c)| match c {
+                '\r' | '\n' => c,
+                _ if diagnostics.iter().any(|d| d.span.contains(&idx)) => '~',
+                _ => ' ',
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_diagnostic() {
+    let source = "one\r\ntwo\rthree\nfour";
+    let diagnostic = Diagnostic::new(source, 15..19, "bad line");
+    assert_eq!(&source[diagnostic.span.clone()], "four");
+    assert_eq!(diagnostic.line, 3);
+    assert_eq!(Diagnostic::new(source, 5..8, "bad line").line, 1);
+}
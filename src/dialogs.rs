@@ -0,0 +1,73 @@
+use crate::SchemeFormat;
+
+/// File operation that needs a path from the user
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    /// Opens a file into the editor
+    Open,
+    /// Saves the editor to a file
+    Save,
+    /// Imports a color scheme into the theme
+    ImportTheme,
+    /// Exports the theme as a color scheme
+    ExportTheme(SchemeFormat),
+    /// Loads the theme from a runmd theme file
+    LoadThemeFile,
+    /// Saves the theme to a runmd theme file
+    SaveThemeFile,
+}
+
+/// Result of asking the user for a path
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogResult {
+    /// The user picked a path
+    Picked(String),
+    /// The user closed the dialog without picking a path
+    Cancelled,
+    /// Native dialogs aren't available, the path should be prompted for instead
+    Unavailable,
+}
+
+impl FileAction {
+    /// Asks the user for a path w/ a native file dialog
+    ///
+    /// Native dialogs require the `native-dialogs` feature, otherwise this always returns DialogResult::Unavailable
+    #[cfg(feature = "native-dialogs")]
+    pub fn pick_path(&self) -> DialogResult {
+        let dialog = rfd::FileDialog::new();
+        let picked = match self {
+            FileAction::Open | FileAction::LoadThemeFile => dialog.add_filter("runmd", &["runmd", "md"]).pick_file(),
+            FileAction::Save | FileAction::SaveThemeFile => dialog.add_filter("runmd", &["runmd", "md"]).save_file(),
+            // Xresources files usually don't have an extension, so import doesn't filter
+            FileAction::ImportTheme => dialog.pick_file(),
+            FileAction::ExportTheme(format) => dialog.add_filter(format.name(), &[format.extension()]).save_file(),
+        };
+
+        match picked {
+            Some(path) => DialogResult::Picked(path.to_string_lossy().to_string()),
+            None => DialogResult::Cancelled,
+        }
+    }
+
+    /// Asks the user for a path w/ a native file dialog
+    ///
+    /// Native dialogs require the `native-dialogs` feature, otherwise this always returns DialogResult::Unavailable
+    #[cfg(not(feature = "native-dialogs"))]
+    pub fn pick_path(&self) -> DialogResult {
+        DialogResult::Unavailable
+    }
+
+    /// Returns the locale key for the label of this action
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            FileAction::Open => "open_file",
+            FileAction::Save => "save_file",
+            FileAction::ImportTheme => "import_theme",
+            FileAction::ExportTheme(_) => "export_theme",
+            FileAction::LoadThemeFile => "load_theme_file",
+            FileAction::SaveThemeFile => "save_theme_file",
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt::Display;
+
+/// Address of a runmd block, from its opening delimitter, i.e. `demo` and `process` for `` ``` demo process ``
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockAddress {
+    /// Name of the block, matched against the block name of thunk contexts in the app world
+    pub name: String,
+    /// Symbol of the block, sent to the block's entities as a `shell_event`
+    pub symbol: String,
+}
+
+impl BlockAddress {
+    /// Returns the address of block, or None if block doesn't start w/ a delimitter that has a name and a symbol
+    pub fn parse(block: &str) -> Option<Self> {
+        let first = block.split(|c| c == '\r' || c == '\n').next()?.trim();
+        let mut words = first.strip_prefix("```")?.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some(name), Some(symbol)) => Some(Self {
+                name: name.to_string(),
+                symbol: symbol.to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Display for BlockAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.name, self.symbol)
+    }
+}
+
+#[test]
+fn test_block_address() {
+    let address = BlockAddress::parse("``` demo process\radd a .text hello\r```").expect("should parse");
+    assert_eq!(address.name, "demo");
+    assert_eq!(address.symbol, "process");
+    assert_eq!(address.to_string(), "demo process");
+
+    assert_eq!(BlockAddress::parse("  ```  demo   println  \n```"), BlockAddress::parse("``` demo println"));
+    assert_eq!(BlockAddress::parse("``` println\radd b .text world"), None);
+    assert_eq!(BlockAddress::parse("add a .text hello"), None);
+}
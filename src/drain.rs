@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, VecDeque};
+
+/// Strategy for choosing which channel's bytes are applied first, when more bytes are received than fit in a frame
+///
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DrainPolicy {
+    /// Bytes are applied in the order they were received
+    Fifo,
+    /// Focused channels are applied first, then channels w/ the smallest backlog
+    ///
+    /// The channel being watched stays responsive while a background channel floods, the flooding channel gets what's left of the budget
+    #[default]
+    Priority,
+}
+
+/// Bytes received from the byte channel, waiting to be applied to char devices
+///
+#[derive(Default)]
+pub struct DrainQueue {
+    /// Strategy for taking bytes
+    policy: DrainPolicy,
+    /// Bytes in the order they were received
+    received: VecDeque<(u32, u8)>,
+}
+
+impl DrainQueue {
+    /// Returns the current policy
+    pub fn policy(&self) -> DrainPolicy {
+        self.policy
+    }
+
+    /// Sets the current policy
+    pub fn set_policy(&mut self, policy: DrainPolicy) {
+        self.policy = policy;
+    }
+
+    /// Queues a byte received for channel
+    pub fn push(&mut self, channel: u32, byte: u8) {
+        self.received.push_back((channel, byte));
+    }
+
+    /// Returns the number of queued bytes
+    pub fn len(&self) -> usize {
+        self.received.len()
+    }
+
+    /// Returns true if no bytes are queued
+    pub fn is_empty(&self) -> bool {
+        self.received.is_empty()
+    }
+
+    /// Takes up to budget bytes, grouped into batches of consecutive bytes for the same channel, in the order they should be applied
+    ///
+    /// Bytes for the same channel are always taken in the order they were received
+    pub fn take(&mut self, budget: usize, focused: &[u32]) -> Vec<(u32, Vec<u8>)> {
+        let mut batches: Vec<(u32, Vec<u8>)> = vec![];
+        match self.policy {
+            DrainPolicy::Fifo => {
+                let taken = budget.min(self.received.len());
+                for (channel, next) in self.received.drain(..taken) {
+                    match batches.last_mut() {
+                        Some((last, bytes)) if *last == channel => bytes.push(next),
+                        _ => batches.push((channel, vec![next])),
+                    }
+                }
+            }
+            DrainPolicy::Priority => {
+                let mut backlogs = BTreeMap::<u32, usize>::new();
+                let mut order = vec![];
+                for (channel, _) in self.received.iter() {
+                    let backlog = backlogs.entry(*channel).or_default();
+                    if *backlog == 0 {
+                        order.push(*channel);
+                    }
+                    *backlog += 1;
+                }
+                order.sort_by_key(|c| (!focused.contains(c), backlogs[c]));
+
+                // Each channel is allotted its backlog, until the budget runs out
+                let mut remaining = budget;
+                let mut allotted = BTreeMap::<u32, usize>::new();
+                for channel in order.iter() {
+                    let allot = backlogs[channel].min(remaining);
+                    allotted.insert(*channel, allot);
+                    remaining -= allot;
+                }
+
+                let mut taken = BTreeMap::<u32, Vec<u8>>::new();
+                let mut kept = VecDeque::with_capacity(self.received.len());
+                for (channel, next) in self.received.drain(..) {
+                    match allotted.get_mut(&channel) {
+                        Some(allot) if *allot > 0 => {
+                            *allot -= 1;
+                            taken.entry(channel).or_default().push(next);
+                        }
+                        _ => kept.push_back((channel, next)),
+                    }
+                }
+                self.received = kept;
+
+                batches.extend(
+                    order
+                        .into_iter()
+                        .filter_map(|channel| taken.remove(&channel).map(|bytes| (channel, bytes))),
+                );
+            }
+        }
+        batches
+    }
+}
+
+#[test]
+fn test_drain_queue() {
+    let fill = |queue: &mut DrainQueue| {
+        for byte in b"flood" {
+            queue.push(5, *byte);
+        }
+        queue.push(2, b'a');
+        for byte in b"ok" {
+            queue.push(1, *byte);
+        }
+        queue.push(2, b'b');
+    };
+
+    let mut queue = DrainQueue::default();
+    queue.set_policy(DrainPolicy::Fifo);
+    fill(&mut queue);
+    assert_eq!(
+        queue.take(6, &[1]),
+        vec![(5, b"flood".to_vec()), (2, b"a".to_vec())]
+    );
+    assert_eq!(queue.len(), 3);
+
+    let mut queue = DrainQueue::default();
+    fill(&mut queue);
+    assert_eq!(
+        queue.take(6, &[1]),
+        vec![(1, b"ok".to_vec()), (2, b"ab".to_vec()), (5, b"fl".to_vec())]
+    );
+    assert_eq!(queue.take(100, &[1]), vec![(5, b"ood".to_vec())]);
+    assert!(queue.is_empty());
+}
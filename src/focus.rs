@@ -0,0 +1,49 @@
+use lifec::plugins::ThunkContext;
+use lifec::Entity;
+
+/// Setting for how the shell responds when a plugin requests focus for its channel
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusPolicy {
+    /// The requesting channel becomes the active channel
+    Follow,
+    /// A notification is shown, but the active channel doesn't change
+    Notify,
+    /// Requests are ignored
+    Ignore,
+}
+
+impl Default for FocusPolicy {
+    fn default() -> Self {
+        FocusPolicy::Notify
+    }
+}
+
+/// Request from a plugin to surface its channel, i.e. when a prompt is awaiting input
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusRequest {
+    /// Channel requesting focus
+    pub channel: u32,
+    /// Why focus was requested, shown in the notification
+    pub reason: Option<String>,
+}
+
+impl FocusRequest {
+    /// Takes a focus request from the entity's thunk context
+    ///
+    /// Plugins request focus by enabling `shell_request_focus`, and can describe why w/ `shell_focus_reason`.
+    /// The attribute is disabled once the request is taken, so that each request is handled once
+    pub fn take_from_context(entity: Entity, tc: &mut ThunkContext) -> Option<Self> {
+        if !tc.as_ref().is_enabled("shell_request_focus").unwrap_or_default() {
+            return None;
+        }
+
+        tc.as_mut().with_bool("shell_request_focus", false);
+
+        Some(Self {
+            channel: entity.id(),
+            reason: tc.as_ref().find_text("shell_focus_reason"),
+        })
+    }
+}
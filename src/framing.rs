@@ -0,0 +1,102 @@
+/// Line ending appended to each line sent to the connection
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+    /// Lines are sent w/o a line ending, i.e. when each line is length-prefixed
+    None,
+}
+
+impl LineEnding {
+    /// Returns the bytes of the line ending
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+            LineEnding::None => b"",
+        }
+    }
+}
+
+/// Length prefix written before each line sent to the connection
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPrefix {
+    /// Big-endian u16 length of the line
+    U16,
+    /// Big-endian u32 length of the line
+    U32,
+}
+
+/// Framing of outbound lines for a connection
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framing {
+    /// Line ending appended to each line
+    pub line_ending: LineEnding,
+    /// Length prefix written before each line, including its line ending
+    pub length_prefix: Option<LengthPrefix>,
+    /// If true, every keystroke is sent to the connection as it's typed, instead of when a line is completed
+    pub raw: bool,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self {
+            line_ending: LineEnding::CrLf,
+            length_prefix: None,
+            raw: false,
+        }
+    }
+}
+
+impl Framing {
+    /// Returns the message to write to the connection for a line taken from the editor
+    ///
+    /// The editor terminates lines w/ `\r`, which is replaced w/ the line ending. Returns None if the line is
+    /// too long for the length prefix.
+    pub fn frame(&self, line: &str) -> Option<Vec<u8>> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let mut payload = line.as_bytes().to_vec();
+        payload.extend_from_slice(self.line_ending.as_bytes());
+
+        let mut message = match self.length_prefix {
+            Some(LengthPrefix::U16) => u16::try_from(payload.len()).ok()?.to_be_bytes().to_vec(),
+            Some(LengthPrefix::U32) => u32::try_from(payload.len()).ok()?.to_be_bytes().to_vec(),
+            None => vec![],
+        };
+        message.append(&mut payload);
+        Some(message)
+    }
+}
+
+#[test]
+fn test_framing() {
+    let framing = Framing::default();
+    assert_eq!(framing.frame("ls\r"), Some(b"ls\r\n".to_vec()));
+    assert_eq!(framing.frame("ls"), Some(b"ls\r\n".to_vec()));
+
+    let framing = Framing {
+        line_ending: LineEnding::Lf,
+        ..Default::default()
+    };
+    assert_eq!(framing.frame("ls\r"), Some(b"ls\n".to_vec()));
+
+    let framing = Framing {
+        line_ending: LineEnding::None,
+        length_prefix: Some(LengthPrefix::U16),
+        raw: false,
+    };
+    assert_eq!(framing.frame("ls\r"), Some(vec![0, 2, b'l', b's']));
+    assert_eq!(framing.frame(&"a".repeat(u16::MAX as usize + 1)), None);
+
+    let framing = Framing {
+        line_ending: LineEnding::Lf,
+        length_prefix: Some(LengthPrefix::U32),
+        raw: false,
+    };
+    assert_eq!(framing.frame("ls"), Some(vec![0, 0, 0, 3, b'l', b's', b'\n']));
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+/// Weight of a use is halved after this many newer uses
+const HALF_LIFE: f32 = 50.0;
+
+/// Usage of a word, counted in uses rather than time so ranking doesn't depend on a clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Usage {
+    /// Number of times the word was used
+    uses: u32,
+    /// Tick of the most recent use
+    last: u64,
+}
+
+/// Ranks words by frecency, how frequently and how recently they were used
+///
+/// Uses are recorded from accepted completions and submitted commands
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Frecency {
+    usage: BTreeMap<String, Usage>,
+    /// Incremented w/ each use
+    tick: u64,
+}
+
+impl Frecency {
+    /// Records a use of word
+    pub fn record(&mut self, word: impl AsRef<str>) {
+        let word = word.as_ref().trim();
+        if word.is_empty() || word.contains(char::is_whitespace) {
+            return;
+        }
+
+        self.tick += 1;
+        let usage = self.usage.entry(word.to_string()).or_insert(Usage { uses: 0, last: 0 });
+        usage.uses += 1;
+        usage.last = self.tick;
+    }
+
+    /// Returns the score of word, 0.0 if it was never used
+    pub fn score(&self, word: &str) -> f32 {
+        match self.usage.get(word) {
+            Some(Usage { uses, last }) => {
+                let age = self.tick.saturating_sub(*last) as f32;
+                *uses as f32 * 0.5f32.powf(age / HALF_LIFE)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Sorts items by the score of their word, highest first, items w/ the same score keep their order
+    pub fn rank<T>(&self, items: &mut [T], word: impl Fn(&T) -> &str) {
+        items.sort_by(|a, b| self.score(word(b)).total_cmp(&self.score(word(a))));
+    }
+
+    /// Parses frecency saved w/ Frecency::to_text, entries that can't be parsed are skipped
+    pub fn from_text(text: &str) -> Self {
+        let mut frecency = Frecency::default();
+        for entry in text.split_whitespace() {
+            let mut parts = entry.rsplitn(3, ':');
+            if let (Some(last), Some(uses), Some(word)) = (parts.next(), parts.next(), parts.next()) {
+                if let (Ok(last), Ok(uses)) = (last.parse(), uses.parse()) {
+                    frecency.tick = frecency.tick.max(last);
+                    frecency.usage.insert(word.to_string(), Usage { uses, last });
+                }
+            }
+        }
+        frecency
+    }
+
+    /// Returns entries as `word:uses:last`, separated by spaces
+    pub fn to_text(&self) -> String {
+        self.usage
+            .iter()
+            .map(|(word, Usage { uses, last })| format!("{word}:{uses}:{last}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Returns true if nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        self.usage.is_empty()
+    }
+}
+
+#[test]
+fn test_frecency() {
+    let mut frecency = Frecency::default();
+    frecency.record("define");
+    frecency.record("define");
+    frecency.record("add");
+
+    let mut words = vec!["add", "block", "define"];
+    frecency.rank(&mut words, |w| w);
+    assert_eq!(words, vec!["define", "add", "block"]);
+
+    // Recent uses outweigh old ones
+    for _ in 0..200 {
+        frecency.record("add");
+    }
+    frecency.rank(&mut words, |w| w);
+    assert_eq!(words, vec!["add", "define", "block"]);
+
+    assert_eq!(Frecency::from_text(&frecency.to_text()), frecency);
+}
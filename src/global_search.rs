@@ -0,0 +1,66 @@
+/// Max number of chars of a matching line kept in its snippet
+pub const SNIPPET_LEN: usize = 80;
+
+/// Line that matched a search across every channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// Channel the match is in
+    pub channel: u32,
+    /// Line of the channel's buffer the match is on
+    pub line: usize,
+    /// Matching line, trimmed and cut off at SNIPPET_LEN chars
+    pub snippet: String,
+}
+
+/// Returns a result for each line of buffers that contains query, in the order of buffers
+///
+/// If the query is all lowercase, matching ignores case. Lines in buffers are expected to be separated by `\r`
+pub fn search_buffers<B: AsRef<str>>(buffers: impl IntoIterator<Item = (u32, B)>, query: &str) -> Vec<SearchResult> {
+    if query.is_empty() {
+        return vec![];
+    }
+
+    let ignore_case = !query.chars().any(|c| c.is_uppercase());
+    let matches = |line: &str| {
+        if ignore_case {
+            line.to_lowercase().contains(query)
+        } else {
+            line.contains(query)
+        }
+    };
+
+    let mut results = vec![];
+    for (channel, buffer) in buffers {
+        for (line_no, line) in buffer.as_ref().split('\r').enumerate().filter(|(_, l)| matches(l)) {
+            results.push(SearchResult {
+                channel,
+                line: line_no,
+                snippet: line.trim().chars().take(SNIPPET_LEN).collect(),
+            });
+        }
+    }
+    results
+}
+
+#[test]
+fn test_search_buffers() {
+    let results = search_buffers([(1, "Compiling shell\rFinished"), (3, "error: could not compile")], "compil");
+    assert_eq!(
+        results,
+        vec![
+            SearchResult {
+                channel: 1,
+                line: 0,
+                snippet: "Compiling shell".to_string()
+            },
+            SearchResult {
+                channel: 3,
+                line: 0,
+                snippet: "error: could not compile".to_string()
+            },
+        ]
+    );
+
+    assert_eq!(search_buffers([(1, "Compiling shell")], "Shell"), vec![]);
+    assert!(search_buffers([(1, "a".to_string())], "").is_empty());
+}
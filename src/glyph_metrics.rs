@@ -0,0 +1,96 @@
+use wgpu_glyph::ab_glyph::{Font, FontArc, PxScale, ScaleFont};
+
+/// Metrics of the monospace font the shell renders with, used to map screen positions to the buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphMetrics {
+    /// Horizontal advance of a single character
+    pub advance: f32,
+    /// Height of a single line
+    pub line_height: f32,
+}
+
+impl Default for GlyphMetrics {
+    /// Approximate metrics of Inconsolata at a scale of 40.0
+    fn default() -> Self {
+        Self {
+            advance: 19.0,
+            line_height: 40.0,
+        }
+    }
+}
+
+impl GlyphMetrics {
+    /// Returns metrics measured from the font at scale
+    pub fn from_font(font: &FontArc, scale: f32) -> Self {
+        let scaled = font.as_scaled(PxScale::from(scale));
+        Self {
+            advance: scaled.h_advance(font.glyph_id('m')),
+            line_height: scaled.height() + scaled.line_gap(),
+        }
+    }
+
+    /// Returns the number of columns that fit in width before a line wraps
+    pub fn columns(&self, width: f32) -> usize {
+        ((width / self.advance).floor() as usize).max(1)
+    }
+
+    /// Returns the byte offset in source under position, position is relative to where source is rendered
+    ///
+    /// Lines that are wider than width are wrapped, the same way the glyph brush lays them out
+    pub fn hit_test(&self, source: &str, width: f32, position: (f32, f32)) -> usize {
+        let (x, y) = position;
+        if y < 0.0 {
+            return 0;
+        }
+
+        let target_row = (y / self.line_height).floor() as usize;
+        let target_col = (x.max(0.0) / self.advance).round() as usize;
+        let columns = self.columns(width);
+
+        let mut row = 0;
+        let mut col = 0;
+        for (offset, c) in source.char_indices() {
+            let newline = c == '\r' || c == '\n';
+            if !newline && col >= columns {
+                row += 1;
+                col = 0;
+            }
+
+            if row > target_row {
+                return offset;
+            }
+
+            if row == target_row && (col >= target_col || newline) {
+                return offset;
+            }
+
+            if newline {
+                row += 1;
+                col = 0;
+            } else {
+                col += 1;
+            }
+        }
+
+        source.len()
+    }
+}
+
+#[test]
+fn test_hit_test() {
+    let metrics = GlyphMetrics {
+        advance: 10.0,
+        line_height: 20.0,
+    };
+
+    let source = "abc\rdefgh\rij";
+    assert_eq!(metrics.hit_test(source, 1000.0, (0.0, 0.0)), 0);
+    assert_eq!(metrics.hit_test(source, 1000.0, (20.0, 5.0)), 2);
+    assert_eq!(metrics.hit_test(source, 1000.0, (200.0, 5.0)), 3);
+    assert_eq!(metrics.hit_test(source, 1000.0, (10.0, 25.0)), 5);
+    assert_eq!(metrics.hit_test(source, 1000.0, (10.0, 45.0)), 11);
+    assert_eq!(metrics.hit_test(source, 1000.0, (10.0, 500.0)), source.len());
+
+    // Wraps after 3 columns, so "def" is on row 1, and "gh" is on row 2
+    assert_eq!(metrics.hit_test(source, 30.0, (10.0, 45.0)), 8);
+}
@@ -0,0 +1,43 @@
+use lifec::plugins::ThunkContext;
+use logos::{Lexer, Logos};
+
+use crate::indent::leading_whitespace;
+use crate::theme::ThemeToken;
+
+/// Trait for a language that can be lexed into theming tokens
+///
+/// The lexer's extras are the theme's thunk context, so grammers can read, or build the attribute graph while lexing
+pub trait Grammer:
+    for<'a> Logos<'a, Source = str, Extras = ThunkContext> + Into<Vec<ThemeToken>> + 'static
+{
+    /// Name used to select this grammer, i.e. from a channel's `shell_grammer` attribute
+    fn name() -> &'static str;
+
+    /// Returns the indentation of the line after line, when a line break is typed at the end of it
+    ///
+    /// By default, the indentation of line is continued
+    fn indent_after(line: &str) -> String {
+        leading_whitespace(line).to_string()
+    }
+
+    /// Returns true if lines starting w/ the theme's command prefix are highlighted as shell commands
+    ///
+    /// Only grammers the editor is lexed with parse commands, so output that happens to start w/ the prefix isn't
+    fn parses_commands() -> bool {
+        false
+    }
+}
+
+/// Returns the source before the lexer's current token, on the same line
+pub fn preceding<'a, G>(lexer: &Lexer<'a, G>) -> &'a str
+where
+    G: Logos<'a, Source = str>,
+{
+    let start = lexer.span().start;
+    let line_start = lexer.source()[..start]
+        .rfind(|c| c == '\r' || c == '\n')
+        .map(|i| i + 1)
+        .unwrap_or_default();
+
+    &lexer.source()[line_start..start]
+}
@@ -0,0 +1,40 @@
+/// Returns the label of each row in the editor's gutter, and whether the row belongs to the current line
+///
+/// Lines of source longer than columns wrap onto more rows, the number of a line is only shown on its first row so
+/// that numbers stay aligned w/ their lines. If relative is true, lines other than the current line are numbered by
+/// their distance from it
+pub fn gutter_rows(source: &str, columns: usize, current_line: usize, relative: bool) -> Vec<(String, bool)> {
+    let columns = columns.max(1);
+    let mut rows = vec![];
+    for (line_no, line) in source.split('\r').enumerate() {
+        let current = line_no == current_line;
+        let label = if relative && !current {
+            line_no.abs_diff(current_line).to_string()
+        } else {
+            line_no.to_string()
+        };
+        rows.push((label, current));
+
+        let wrapped = line.chars().count().saturating_sub(1) / columns;
+        rows.extend((0..wrapped).map(|_| (String::new(), current)));
+    }
+    rows
+}
+
+#[test]
+fn test_gutter_rows() {
+    let rows = gutter_rows("abcdef\rab\rabcd", 4, 1, false);
+    assert_eq!(
+        rows,
+        vec![
+            ("0".to_string(), false),
+            (String::new(), false),
+            ("1".to_string(), true),
+            ("2".to_string(), false),
+        ]
+    );
+
+    let rows = gutter_rows("a\rb\rc\rd", 4, 2, true);
+    let labels = rows.iter().map(|(l, _)| l.as_str()).collect::<Vec<_>>();
+    assert_eq!(labels, vec!["2", "1", "2", "1"]);
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Description and examples shown wherever a command, snippet, or completion is listed
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Help {
+    /// One line description
+    pub description: String,
+    /// Example usages
+    pub examples: Vec<String>,
+}
+
+impl Help {
+    /// Returns help w/ a description
+    pub fn new(description: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            examples: vec![],
+        }
+    }
+
+    /// Adds an example usage
+    pub fn with_example(mut self, example: impl Into<String>) -> Self {
+        self.examples.push(example.into());
+        self
+    }
+}
+
+/// Kind of item registered w/ the shell
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HelpKind {
+    /// Command, typed after the command prefix
+    Command,
+    /// Text inserted into the editor as-is
+    Snippet,
+    /// Text offered while typing a word
+    Completion,
+}
+
+impl HelpKind {
+    /// Returns the locale key for the heading of this kind
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            HelpKind::Command => "commands",
+            HelpKind::Snippet => "snippets",
+            HelpKind::Completion => "completions",
+        }
+    }
+}
+
+/// Item registered w/ the shell, and its help
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HelpEntry {
+    /// Kind of item
+    pub kind: HelpKind,
+    /// Name of the command, snippet, or completion
+    pub name: String,
+    /// Text inserted into the editor when this item is picked, commands insert their name
+    pub insert_text: String,
+    /// Description and examples
+    pub help: Help,
+}
+
+/// Registry of commands, snippets, and completions, each w/ help metadata
+///
+/// Extensions register items here so they show up in the palette, and the cheat sheet
+#[derive(Debug, Default, Clone)]
+pub struct HelpRegistry {
+    entries: BTreeMap<(HelpKind, String), HelpEntry>,
+}
+
+impl HelpRegistry {
+    /// Registers a command, replacing any command w/ the same name
+    pub fn register_command(&mut self, name: impl Into<String>, help: Help) {
+        let name = name.into();
+        self.register(HelpKind::Command, name.clone(), name, help);
+    }
+
+    /// Registers a snippet, body is inserted into the editor when the snippet is picked
+    pub fn register_snippet(&mut self, name: impl Into<String>, body: impl Into<String>, help: Help) {
+        self.register(HelpKind::Snippet, name.into(), body.into(), help);
+    }
+
+    /// Registers a completion, offered when the word being typed is a prefix of label
+    pub fn register_completion(&mut self, label: impl Into<String>, insert_text: impl Into<String>, help: Help) {
+        self.register(HelpKind::Completion, label.into(), insert_text.into(), help);
+    }
+
+    fn register(&mut self, kind: HelpKind, name: String, insert_text: String, help: Help) {
+        self.entries.insert(
+            (kind, name.clone()),
+            HelpEntry {
+                kind,
+                name,
+                insert_text,
+                help,
+            },
+        );
+    }
+
+    /// Returns every entry, grouped by kind, and sorted by name
+    pub fn entries(&self) -> impl Iterator<Item = &HelpEntry> {
+        self.entries.values()
+    }
+
+    /// Returns entries whose name or description contains query, ignoring case
+    pub fn search<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a HelpEntry> + 'a {
+        let query = query.to_lowercase();
+        self.entries().filter(move |e| {
+            e.name.to_lowercase().contains(&query) || e.help.description.to_lowercase().contains(&query)
+        })
+    }
+
+    /// Returns completions for the word being typed
+    pub fn completions<'a>(&'a self, word: &'a str) -> impl Iterator<Item = &'a HelpEntry> + 'a {
+        self.entries()
+            .filter(move |e| e.kind == HelpKind::Completion && !word.is_empty() && e.name.starts_with(word))
+    }
+
+    /// Returns true if nothing has been registered
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Generates a markdown cheat sheet of every entry, headings are looked up w/ label
+    pub fn cheat_sheet(&self, label: impl Fn(&str) -> String) -> String {
+        let mut sheet = String::new();
+        let mut kind = None;
+        for entry in self.entries() {
+            if kind != Some(entry.kind) {
+                kind = Some(entry.kind);
+                let _ = writeln!(sheet, "\n## {}\n", label(entry.kind.label_key()));
+            }
+
+            let _ = writeln!(sheet, "- `{}` {}", entry.name, entry.help.description);
+            for example in entry.help.examples.iter() {
+                let _ = writeln!(sheet, "  - `{example}`");
+            }
+        }
+
+        sheet.trim_start().to_string()
+    }
+}
+
+#[test]
+fn test_help_registry() {
+    let mut registry = HelpRegistry::default();
+    registry.register_command(
+        ":open",
+        Help::new("Opens a file into the editor").with_example(":open demo.runmd"),
+    );
+    registry.register_snippet("block", "``` demo process\r```", Help::new("Empty runmd block"));
+    registry.register_completion("println", "println", Help::new("Prints a line"));
+    registry.register_completion("process", "process", Help::new("Runs a process"));
+
+    assert_eq!(registry.search("FILE").map(|e| e.name.as_str()).collect::<Vec<_>>(), vec![":open"]);
+    assert_eq!(registry.completions("pr").count(), 2);
+    assert_eq!(registry.completions("pri").map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["println"]);
+    assert_eq!(registry.completions("").count(), 0);
+
+    assert_eq!(
+        registry.cheat_sheet(|key| key.to_string()),
+        r#"## commands
+
+- `:open` Opens a file into the editor
+  - `:open demo.runmd`
+
+## snippets
+
+- `block` Empty runmd block
+
+## completions
+
+- `println` Prints a line
+- `process` Runs a process
+"#
+    );
+}
@@ -0,0 +1,177 @@
+/// Edit made to a char device's buffer
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// Text was inserted at the offset
+    Insert { at: usize, text: String },
+    /// Text was deleted from the offset
+    Delete { at: usize, text: String },
+    /// Edits applied in order, that are undone and redone together, i.e. swapping two lines
+    Group(Vec<Edit>),
+}
+
+impl Edit {
+    /// Returns the edit that reverses this edit
+    pub fn inverse(&self) -> Edit {
+        match self {
+            Edit::Insert { at, text } => Edit::Delete {
+                at: *at,
+                text: text.clone(),
+            },
+            Edit::Delete { at, text } => Edit::Insert {
+                at: *at,
+                text: text.clone(),
+            },
+            Edit::Group(edits) => Edit::Group(edits.iter().rev().map(Edit::inverse).collect()),
+        }
+    }
+
+    /// Applies this edit to the buffer, returns the new cursor position
+    pub fn apply(&self, buffer: &mut String) -> usize {
+        match self {
+            Edit::Insert { at, text } => {
+                buffer.insert_str(*at, text);
+                at + text.len()
+            }
+            Edit::Delete { at, text } => {
+                buffer.replace_range(*at..at + text.len(), "");
+                *at
+            }
+            Edit::Group(edits) => edits.iter().fold(0, |_, edit| edit.apply(buffer)),
+        }
+    }
+
+    /// Returns the inserts and deletes this edit is made of, in the order they're applied
+    pub fn flatten(self) -> Vec<Edit> {
+        match self {
+            Edit::Group(edits) => edits.into_iter().flat_map(Edit::flatten).collect(),
+            edit => vec![edit],
+        }
+    }
+}
+
+/// Max number of edits kept for undo, the oldest edits are dropped past it
+pub const MAX_EDITS: usize = 1000;
+
+/// Operation log of edits for undo/redo
+///
+#[derive(Debug, Default, Clone)]
+pub struct History {
+    /// Edits that can be undone, most recent last
+    undo: Vec<Edit>,
+    /// Edits that can be redone, most recent last
+    redo: Vec<Edit>,
+}
+
+impl History {
+    /// Records an edit, clearing the redo stack
+    ///
+    /// Consecutive inserts, or consecutive deletes from backspacing, are merged so that undo works on runs of typing.
+    /// Only the last MAX_EDITS edits are kept
+    pub fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+
+        match (self.undo.last_mut(), &edit) {
+            (Some(Edit::Insert { at, text }), Edit::Insert { at: next, text: next_text })
+                if *at + text.len() == *next && !text.ends_with('\r') =>
+            {
+                text.push_str(next_text);
+            }
+            (Some(Edit::Delete { at, text }), Edit::Delete { at: next, text: next_text })
+                if *next + next_text.len() == *at && !next_text.ends_with('\r') =>
+            {
+                text.insert_str(0, next_text);
+                *at = *next;
+            }
+            _ => self.undo.push(edit),
+        }
+
+        if self.undo.len() > MAX_EDITS {
+            self.undo.drain(..self.undo.len() - MAX_EDITS);
+        }
+    }
+
+    /// Returns the edit that undoes the most recent edit, and moves it to the redo stack
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop()?;
+        let inverse = edit.inverse();
+        self.redo.push(edit);
+        Some(inverse)
+    }
+
+    /// Returns the most recently undone edit, and moves it back to the undo stack
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.undo.push(edit.clone());
+        Some(edit)
+    }
+
+    /// Returns the edits that can be undone, most recent last
+    pub fn edits(&self) -> &[Edit] {
+        &self.undo
+    }
+
+    /// Replaces the recorded edits w/ edits that can be undone, i.e. from a saved session
+    pub fn restore(&mut self, edits: Vec<Edit>) {
+        self.undo = edits;
+        self.redo.clear();
+    }
+
+    /// Clears all recorded edits
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[test]
+fn test_history() {
+    let mut buffer = String::new();
+    let mut history = History::default();
+
+    for (at, c) in "hello".char_indices() {
+        let edit = Edit::Insert {
+            at,
+            text: c.to_string(),
+        };
+        edit.apply(&mut buffer);
+        history.record(edit);
+    }
+
+    let edit = Edit::Delete {
+        at: 4,
+        text: "o".to_string(),
+    };
+    edit.apply(&mut buffer);
+    history.record(edit);
+    assert_eq!(buffer, "hell");
+
+    assert_eq!(history.undo().map(|e| e.apply(&mut buffer)), Some(5));
+    assert_eq!(buffer, "hello");
+    assert_eq!(history.undo().map(|e| e.apply(&mut buffer)), Some(0));
+    assert_eq!(buffer, "");
+    assert_eq!(history.redo().map(|e| e.apply(&mut buffer)), Some(5));
+    assert_eq!(buffer, "hello");
+    assert_eq!(history.redo().map(|e| e.apply(&mut buffer)), Some(4));
+    assert_eq!(buffer, "hell");
+    assert!(history.redo().is_none());
+
+    // A group is undone as a single edit, and isn't merged w/ the typing before it
+    let edit = Edit::Group(vec![
+        Edit::Delete { at: 0, text: "hell".to_string() },
+        Edit::Insert { at: 0, text: "\rhi".to_string() },
+    ]);
+    edit.apply(&mut buffer);
+    history.record(edit);
+    assert_eq!(buffer, "\rhi");
+    assert_eq!(history.undo().map(|e| e.apply(&mut buffer)), Some(4));
+    assert_eq!(buffer, "hell");
+    assert_eq!(history.redo().map(|e| e.apply(&mut buffer)), Some(3));
+    assert_eq!(buffer, "\rhi");
+
+    let mut history = History::default();
+    for at in 0..MAX_EDITS + 10 {
+        history.record(Edit::Group(vec![Edit::Insert { at, text: "a".to_string() }]));
+    }
+    assert_eq!(history.edits().len(), MAX_EDITS);
+}
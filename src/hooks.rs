@@ -0,0 +1,51 @@
+use wgpu::SurfaceConfiguration;
+
+use crate::ChannelInfo;
+
+/// Called when a channel is added, w/ its metadata if it belongs to an entity
+pub type ChannelCreatedHook = Box<dyn FnMut(u32, Option<&ChannelInfo>) + Send + Sync>;
+
+/// Called w/ each line submitted from the shell buffer, before it's sent to the connection or routed to a plugin
+pub type LineSubmittedHook = Box<dyn FnMut(&str) + Send + Sync>;
+
+/// Called w/ the peer when a connection is opened, and None when it closes
+pub type ConnectionChangedHook = Box<dyn FnMut(Option<&str>) + Send + Sync>;
+
+/// Called before each frame is rendered
+pub type BeforeRenderHook = Box<dyn FnMut(&SurfaceConfiguration) + Send + Sync>;
+
+/// Callbacks an embedding application can register to customize the shell at well-defined points
+///
+#[derive(Default)]
+pub struct ShellHooks {
+    pub(crate) channel_created: Vec<ChannelCreatedHook>,
+    pub(crate) line_submitted: Vec<LineSubmittedHook>,
+    pub(crate) connection_changed: Vec<ConnectionChangedHook>,
+    pub(crate) before_render: Vec<BeforeRenderHook>,
+}
+
+impl ShellHooks {
+    pub(crate) fn channel_created(&mut self, channel: u32, info: Option<&ChannelInfo>) {
+        for hook in self.channel_created.iter_mut() {
+            hook(channel, info);
+        }
+    }
+
+    pub(crate) fn line_submitted(&mut self, line: &str) {
+        for hook in self.line_submitted.iter_mut() {
+            hook(line);
+        }
+    }
+
+    pub(crate) fn connection_changed(&mut self, peer: Option<&str>) {
+        for hook in self.connection_changed.iter_mut() {
+            hook(peer);
+        }
+    }
+
+    pub(crate) fn before_render(&mut self, config: &SurfaceConfiguration) {
+        for hook in self.before_render.iter_mut() {
+            hook(config);
+        }
+    }
+}
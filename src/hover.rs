@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+/// How long the mouse has to rest over a token before its tooltip is shown
+pub const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+/// Tracks the offset of the editor's buffer that the mouse is resting over
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Hover {
+    /// Offset under the mouse, and when the mouse moved over it
+    resting: Option<(usize, Instant)>,
+}
+
+impl Hover {
+    /// Updates the offset under the mouse, None if the mouse isn't over the editor
+    pub fn update(&mut self, offset: Option<usize>, now: Instant) {
+        self.resting = match (self.resting, offset) {
+            (Some((last, since)), Some(offset)) if last == offset => Some((last, since)),
+            (_, offset) => offset.map(|o| (o, now)),
+        };
+    }
+
+    /// Returns the offset the mouse has rested over for at least HOVER_DELAY
+    pub fn resting(&self, now: Instant) -> Option<usize> {
+        self.resting
+            .filter(|(_, since)| now.saturating_duration_since(*since) >= HOVER_DELAY)
+            .map(|(offset, _)| offset)
+    }
+}
+
+/// What a hovered runmd token refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HoverTarget {
+    /// Keyword or value type, w/ the locale key of its description
+    Keyword(&'static str),
+    /// Name of an attribute, described by its current value in the app world
+    Attribute(String),
+}
+
+impl HoverTarget {
+    /// Returns the target of token, line is the line of the runmd buffer the token is on
+    pub fn of(token: &str, line: &str) -> Option<Self> {
+        let token = token.trim();
+        let key = match token {
+            "add" => Some("hover_add"),
+            "define" => Some("hover_define"),
+            "```" => Some("hover_block"),
+            ".text" => Some("hover_text"),
+            ".bool" | ".enable" | ".disable" => Some("hover_bool"),
+            ".int" => Some("hover_int"),
+            ".int2" | ".int3" => Some("hover_int_pair"),
+            ".int_range" => Some("hover_int_range"),
+            ".float" => Some("hover_float"),
+            ".float2" | ".float3" => Some("hover_float_pair"),
+            ".float_range" => Some("hover_float_range"),
+            ".bin" => Some("hover_bin"),
+            ".symbol" => Some("hover_symbol"),
+            ".empty" => Some("hover_empty"),
+            _ => None,
+        };
+        if let Some(key) = key {
+            return Some(HoverTarget::Keyword(key));
+        }
+
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("add" | "define"), Some(name)) if name == token => Some(HoverTarget::Attribute(name.to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_hover() {
+    let start = Instant::now();
+    let mut hover = Hover::default();
+    hover.update(Some(4), start);
+    assert_eq!(hover.resting(start), None);
+    hover.update(Some(4), start + HOVER_DELAY / 2);
+    assert_eq!(hover.resting(start + HOVER_DELAY), Some(4));
+    hover.update(Some(5), start + HOVER_DELAY);
+    assert_eq!(hover.resting(start + HOVER_DELAY), None);
+    hover.update(None, start + HOVER_DELAY * 2);
+    assert_eq!(hover.resting(start + HOVER_DELAY * 3), None);
+
+    let line = "add duration .int2 5, 6";
+    assert_eq!(HoverTarget::of("add", line), Some(HoverTarget::Keyword("hover_add")));
+    assert_eq!(HoverTarget::of(".int2", line), Some(HoverTarget::Keyword("hover_int_pair")));
+    assert_eq!(HoverTarget::of("duration", line), Some(HoverTarget::Attribute("duration".to_string())));
+    assert_eq!(HoverTarget::of("5,", line), None);
+}
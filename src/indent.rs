@@ -0,0 +1,35 @@
+/// Indentation added for each level, i.e. for the lines that follow `+ .engine` in a runmd block
+pub const INDENT: &str = "    ";
+
+/// Returns the leading whitespace of line
+pub fn leading_whitespace(line: &str) -> &str {
+    let len = line.len() - line.trim_start_matches([' ', '\t']).len();
+    &line[..len]
+}
+
+/// Returns the indentation of the line after line in a runmd source
+///
+/// Lines after `+ .engine` or a `define` are indented one level deeper, the end of a block resets the indentation,
+/// and otherwise the indentation of line is continued
+pub fn runmd_indent(line: &str) -> String {
+    let current = leading_whitespace(line);
+    let trimmed = line.trim();
+
+    if trimmed == "```" {
+        String::new()
+    } else if trimmed.starts_with("+ .engine") || trimmed.starts_with("define ") {
+        format!("{current}{INDENT}")
+    } else {
+        current.to_string()
+    }
+}
+
+#[test]
+fn test_runmd_indent() {
+    assert_eq!(runmd_indent("add name .text hello"), "");
+    assert_eq!(runmd_indent("  add name .text hello"), "  ");
+    assert_eq!(runmd_indent("+ .engine"), INDENT);
+    assert_eq!(runmd_indent("  define a b .text c"), format!("  {INDENT}"));
+    assert_eq!(runmd_indent("    ```"), "");
+    assert_eq!(leading_whitespace("\t x"), "\t ");
+}
@@ -0,0 +1,41 @@
+/// Max number of chars of an attribute's value shown in the inspector
+pub const VALUE_LEN: usize = 60;
+
+/// Returns the rows the inspector shows for an entity's attributes, sorted by name
+///
+/// Attributes w/o filter in their name are skipped, and values are cut off at VALUE_LEN chars
+pub fn attribute_rows(attributes: impl IntoIterator<Item = (String, String)>, filter: &str) -> Vec<(String, String)> {
+    let mut rows = attributes
+        .into_iter()
+        .filter(|(name, _)| name.contains(filter))
+        .map(|(name, value)| {
+            let value = if value.chars().count() > VALUE_LEN {
+                format!("{}…", value.chars().take(VALUE_LEN).collect::<String>())
+            } else {
+                value
+            };
+            (name, value)
+        })
+        .collect::<Vec<_>>();
+
+    rows.sort();
+    rows
+}
+
+#[test]
+fn test_attribute_rows() {
+    let attributes = vec![
+        ("shell_running".to_string(), "Bool(true)".to_string()),
+        ("enable_char_device".to_string(), "Bool(true)".to_string()),
+        ("buffer".to_string(), "x".repeat(VALUE_LEN + 1)),
+    ];
+
+    let rows = attribute_rows(attributes.clone(), "");
+    assert_eq!(rows[0], ("buffer".to_string(), format!("{}…", "x".repeat(VALUE_LEN))));
+    assert_eq!(rows[1].0, "enable_char_device");
+
+    assert_eq!(
+        attribute_rows(attributes, "shell"),
+        vec![("shell_running".to_string(), "Bool(true)".to_string())]
+    );
+}
@@ -0,0 +1,134 @@
+use async_trait::async_trait;
+use std::io::{Error, ErrorKind};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tracing::{event, Level};
+
+use crate::{ShellChannel, Transport};
+
+/// Address of a local ipc endpoint
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpcAddress {
+    /// Path of a unix domain socket, from `unix:///path/to.sock`
+    Unix(String),
+    /// Full name of a windows named pipe, from `pipe://name` or `\\.\pipe\name`
+    Pipe(String),
+}
+
+impl IpcAddress {
+    /// Parses address, returns None if it isn't an ipc address
+    pub fn parse(address: &str) -> Option<Self> {
+        if let Some(path) = address.strip_prefix("unix://") {
+            return Some(Self::Unix(path.to_string())).filter(|_| !path.is_empty());
+        }
+
+        if let Some(name) = address.strip_prefix("pipe://") {
+            return Some(Self::Pipe(format!(r"\\.\pipe\{name}"))).filter(|_| !name.is_empty());
+        }
+
+        address
+            .starts_with(r"\\.\pipe\")
+            .then(|| Self::Pipe(address.to_string()))
+    }
+}
+
+/// Transport to a local daemon that only exposes an ipc endpoint, a unix domain socket or a windows named pipe
+///
+/// Lines are sent as-is, the same as a tcp connection, and everything the endpoint writes is written to a shell channel
+pub struct Ipc {
+    writer: Box<dyn AsyncWrite + Send + Sync + Unpin>,
+    peer: String,
+}
+
+impl Ipc {
+    /// Connects to the endpoint at address, output is written to the shell channel
+    pub async fn connect(address: IpcAddress, output: ShellChannel) -> std::io::Result<Self> {
+        match address {
+            IpcAddress::Unix(path) => connect_unix(path, output).await,
+            IpcAddress::Pipe(name) => connect_pipe(name, output),
+        }
+    }
+
+    /// Starts copying from reader to the shell channel
+    fn start(
+        reader: impl AsyncRead + Send + Unpin + 'static,
+        writer: impl AsyncWrite + Send + Sync + Unpin + 'static,
+        peer: String,
+        output: ShellChannel,
+    ) -> Self {
+        if let Some(mut output) = output.writer() {
+            let mut reader = reader;
+            let endpoint = peer.clone();
+            tokio::spawn(async move {
+                match tokio::io::copy(&mut reader, &mut output).await {
+                    Ok(copied) => event!(Level::DEBUG, "{endpoint} closed after {copied} bytes"),
+                    Err(err) => event!(Level::DEBUG, "{endpoint} closed, {err}"),
+                }
+            });
+        }
+
+        Self {
+            writer: Box::new(writer),
+            peer,
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for Ipc {
+    async fn write(&mut self, message: &[u8]) -> std::io::Result<usize> {
+        self.writer.write_all(message).await?;
+        Ok(message.len())
+    }
+
+    fn peer(&self) -> String {
+        self.peer.clone()
+    }
+}
+
+#[cfg(unix)]
+async fn connect_unix(path: String, output: ShellChannel) -> std::io::Result<Ipc> {
+    let stream = tokio::net::UnixStream::connect(&path).await?;
+    let (reader, writer) = stream.into_split();
+    Ok(Ipc::start(reader, writer, format!("unix {path}"), output))
+}
+
+#[cfg(not(unix))]
+async fn connect_unix(path: String, _: ShellChannel) -> std::io::Result<Ipc> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        format!("Unix domain sockets aren't supported on this platform, {path}"),
+    ))
+}
+
+#[cfg(windows)]
+fn connect_pipe(name: String, output: ShellChannel) -> std::io::Result<Ipc> {
+    let client = tokio::net::windows::named_pipe::ClientOptions::new().open(&name)?;
+    let (reader, writer) = tokio::io::split(client);
+    Ok(Ipc::start(reader, writer, format!("pipe {name}"), output))
+}
+
+#[cfg(not(windows))]
+fn connect_pipe(name: String, _: ShellChannel) -> std::io::Result<Ipc> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        format!("Named pipes are only supported on windows, {name}"),
+    ))
+}
+
+#[test]
+fn test_ipc_address() {
+    assert_eq!(
+        IpcAddress::parse("unix:///var/run/lifec.sock"),
+        Some(IpcAddress::Unix("/var/run/lifec.sock".to_string()))
+    );
+    assert_eq!(
+        IpcAddress::parse("pipe://lifec"),
+        Some(IpcAddress::Pipe(r"\\.\pipe\lifec".to_string()))
+    );
+    assert_eq!(
+        IpcAddress::parse(r"\\.\pipe\lifec"),
+        Some(IpcAddress::Pipe(r"\\.\pipe\lifec".to_string()))
+    );
+    assert_eq!(IpcAddress::parse("unix://"), None);
+    assert_eq!(IpcAddress::parse("localhost:22"), None);
+}
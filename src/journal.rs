@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use tracing::{event, Level};
+
+use crate::history::Edit;
+use crate::session_file::{escape, unescape};
+
+/// Write-ahead journal of the edits made to each buffer, kept under `<data_dir>/journal/`
+///
+/// Each buffer has a checkpoint, `<channel>.base`, and a log of the edits made since, `<channel>.wal`.
+/// Edits are appended to the log before the buffer is saved anywhere else, so after a crash every buffer
+/// can be restored by replaying its log over its checkpoint.
+///
+/// Writes happen in batches on a background thread, so that journaling doesn't stall rendering
+#[derive(Debug, Clone)]
+pub struct Journal {
+    dir: PathBuf,
+    /// Sends batches of writes to the writer thread
+    tx: Sender<Vec<JournalWrite>>,
+}
+
+/// Write to the journal, queued w/ Journal::write
+#[derive(Debug)]
+pub enum JournalWrite {
+    /// Appends entries to the log of a channel
+    Append(u32, String),
+    /// Replaces the checkpoint of a channel, and clears its log
+    Checkpoint(u32, String),
+    /// Signals once every write queued before it is done
+    Flushed(Sender<()>),
+}
+
+impl JournalWrite {
+    /// Returns a write that appends edits to the log of channel
+    pub fn append(channel: u32, edits: &[Edit]) -> Self {
+        Self::Append(channel, edits.iter().map(encode).collect())
+    }
+
+    /// Returns a write that replaces the checkpoint of channel w/ text
+    pub fn checkpoint(channel: u32, text: &str) -> Self {
+        Self::Checkpoint(channel, text.to_string())
+    }
+}
+
+impl Journal {
+    /// Opens the journal under data_dir, returns None if the directory can't be created
+    ///
+    /// The writer thread exits once every copy of the journal is dropped
+    pub fn open(data_dir: impl AsRef<Path>) -> Option<Self> {
+        let dir = data_dir.as_ref().join("journal");
+        if let Err(err) = std::fs::create_dir_all(&dir) {
+            event!(Level::ERROR, "Could not create journal directory {:?}, {err}", dir);
+            return None;
+        }
+
+        let (tx, rx) = channel::<Vec<JournalWrite>>();
+        let writer_dir = dir.clone();
+        std::thread::spawn(move || {
+            for batch in rx {
+                for write in batch {
+                    if let Err(err) = apply_write(&writer_dir, write) {
+                        event!(Level::ERROR, "Could not write to journal {:?}, {err}", writer_dir);
+                    }
+                }
+            }
+        });
+
+        Some(Self { dir, tx })
+    }
+
+    /// Queues a batch of writes, they are written in order on the writer thread
+    pub fn write(&self, batch: Vec<JournalWrite>) {
+        if batch.is_empty() {
+            return;
+        }
+
+        if self.tx.send(batch).is_err() {
+            event!(Level::WARN, "Journal writer for {:?} has stopped", self.dir);
+        }
+    }
+
+    /// Queues appending edits to the log of channel
+    pub fn append(&self, channel: u32, edits: &[Edit]) {
+        self.write(vec![JournalWrite::append(channel, edits)]);
+    }
+
+    /// Queues replacing the checkpoint of channel w/ text, and clearing its log
+    pub fn checkpoint(&self, channel: u32, text: &str) {
+        self.write(vec![JournalWrite::checkpoint(channel, text)]);
+    }
+
+    /// Blocks until every queued write is done
+    pub fn flush(&self) {
+        let (tx, rx) = channel();
+        self.write(vec![JournalWrite::Flushed(tx)]);
+        rx.recv().ok();
+    }
+
+    /// Returns the contents of each journaled buffer, by replaying its log over its checkpoint
+    ///
+    /// Replay stops at the first entry that is incomplete or doesn't apply, i.e. one that was being written during a crash
+    pub fn recover(&self) -> BTreeMap<u32, String> {
+        self.flush();
+
+        let mut buffers = BTreeMap::new();
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return buffers,
+        };
+
+        for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+            let channel = match (path.extension(), path.file_stem().and_then(|s| s.to_str())) {
+                (Some(ext), Some(stem)) if ext == "base" => match stem.parse::<u32>() {
+                    Ok(channel) => channel,
+                    Err(_) => continue,
+                },
+                _ => continue,
+            };
+
+            let mut buffer = std::fs::read_to_string(&path).unwrap_or_default();
+            let log = std::fs::read_to_string(path.with_extension("wal")).unwrap_or_default();
+
+            // The last line is only complete if it ends w/ a line break
+            let mut lines = log.split('\n').collect::<Vec<_>>();
+            lines.pop();
+
+            let mut replayed = 0;
+            for line in lines {
+                match decode(line).filter(|edit| applies(edit, &buffer)) {
+                    Some(edit) => {
+                        edit.apply(&mut buffer);
+                        replayed += 1;
+                    }
+                    None => {
+                        event!(Level::WARN, "Stopped replaying the journal of channel {channel} at an invalid entry");
+                        break;
+                    }
+                }
+            }
+
+            event!(Level::DEBUG, "Replayed {replayed} edits for channel {channel}");
+            buffers.insert(channel, buffer);
+        }
+
+        buffers
+    }
+}
+
+/// Performs a write to the journal in dir
+fn apply_write(dir: &Path, write: JournalWrite) -> std::io::Result<()> {
+    match write {
+        JournalWrite::Append(channel, entries) => OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(format!("{channel}.wal")))?
+            .write_all(entries.as_bytes()),
+        JournalWrite::Checkpoint(channel, text) => {
+            std::fs::write(dir.join(format!("{channel}.base")), text)?;
+            std::fs::write(dir.join(format!("{channel}.wal")), "")
+        }
+        JournalWrite::Flushed(done) => {
+            done.send(()).ok();
+            Ok(())
+        }
+    }
+}
+
+/// Returns an edit as a journal entry, `i <at> <text>` or `d <at> <text>`, w/ the text escaped
+///
+/// A group is journaled as an entry for each of its edits
+fn encode(edit: &Edit) -> String {
+    match edit {
+        Edit::Insert { at, text } => format!("i {at} {}\n", escape(text)),
+        Edit::Delete { at, text } => format!("d {at} {}\n", escape(text)),
+        Edit::Group(edits) => edits.iter().map(encode).collect(),
+    }
+}
+
+/// Parses a journal entry
+fn decode(entry: &str) -> Option<Edit> {
+    let mut parts = entry.splitn(3, ' ');
+    let (kind, at, text) = (parts.next()?, parts.next()?.parse().ok()?, unescape(parts.next()?));
+    match kind {
+        "i" => Some(Edit::Insert { at, text }),
+        "d" => Some(Edit::Delete { at, text }),
+        _ => None,
+    }
+}
+
+/// Returns true if edit can be applied to buffer
+fn applies(edit: &Edit, buffer: &str) -> bool {
+    match edit {
+        Edit::Insert { at, .. } => buffer.is_char_boundary(*at),
+        Edit::Delete { at, text } => buffer.get(*at..at + text.len()) == Some(text.as_str()),
+        // Groups aren't decoded from entries, their edits are replayed one at a time
+        Edit::Group(_) => false,
+    }
+}
+
+#[test]
+fn test_journal() {
+    let data_dir = std::env::temp_dir().join(format!("lifec_shell_journal_{}", std::process::id()));
+    let journal = Journal::open(&data_dir).expect("should open");
+
+    journal.checkpoint(0, "hello");
+    journal.append(
+        0,
+        &[
+            Edit::Insert { at: 5, text: "\rworld".to_string() },
+            Edit::Delete { at: 0, text: "h".to_string() },
+        ],
+    );
+
+    journal.write(vec![
+        JournalWrite::checkpoint(3, "output"),
+        JournalWrite::append(3, &[Edit::Delete { at: 0, text: "missing".to_string() }]),
+    ]);
+
+    // An entry cut off by a crash is ignored
+    journal.flush();
+    std::fs::write(data_dir.join("journal").join("4.base"), "abc").unwrap();
+    std::fs::write(data_dir.join("journal").join("4.wal"), "i 3 d\ni 0 partial").unwrap();
+
+    let recovered = journal.recover();
+    assert_eq!(recovered.get(&0).map(String::as_str), Some("ello\rworld"));
+    assert_eq!(recovered.get(&3).map(String::as_str), Some("output"));
+    assert_eq!(recovered.get(&4).map(String::as_str), Some("abcd"));
+
+    // Checkpoints clear the log
+    journal.checkpoint(0, "saved");
+    assert_eq!(journal.recover().get(&0).map(String::as_str), Some("saved"));
+
+    std::fs::remove_dir_all(data_dir).unwrap();
+}
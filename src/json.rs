@@ -0,0 +1,95 @@
+use lifec::plugins::ThunkContext;
+use logos::Lexer;
+use logos::Logos;
+use logos::Span;
+
+use crate::{theme::ThemeToken, Grammer, Token};
+
+/// Grammer for json, i.e. structured output from plugins
+#[derive(Logos, PartialEq, Eq, Debug, Clone)]
+#[logos(extras = ThunkContext)]
+pub enum Json {
+    /// Object key, and the colon that follows it
+    #[regex(r#""([^"\\\r\n]|\\.)*"[ \t]*:"#, on_key)]
+    Key((Span, Span)),
+    /// String values
+    #[regex(r#""([^"\\\r\n]|\\.)*""#)]
+    String,
+    /// Number values
+    #[regex(r"-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?")]
+    Number,
+    /// true, false, and null
+    #[token("true")]
+    #[token("false")]
+    #[token("null")]
+    Constant,
+    /// Object and array delimitters
+    #[token("{")]
+    #[token("}")]
+    #[token("[")]
+    #[token("]")]
+    Bracket,
+    /// Separates values
+    #[token(",")]
+    Comma,
+    #[regex(r"[ \t\r\n\f]+", logos::skip)]
+    #[error]
+    Error,
+}
+
+impl Grammer for Json {
+    fn name() -> &'static str {
+        "json"
+    }
+}
+
+impl Into<Vec<ThemeToken>> for Json {
+    fn into(self) -> Vec<ThemeToken> {
+        match self {
+            Json::Key((key, colon)) => vec![
+                (Token::Identifier, Some(key)),
+                (Token::Operator, Some(colon)),
+            ],
+            Json::String => vec![(Token::Literal, None)],
+            Json::Number => vec![(Token::Modifier, None)],
+            Json::Constant => vec![(Token::Keyword, None)],
+            Json::Bracket => vec![(Token::Bracket, None)],
+            Json::Comma => vec![(Token::Operator, None)],
+            // Output channels often mix json w/ plain text, so anything else isn't highlighted
+            Json::Error => vec![],
+        }
+    }
+}
+
+/// Splits the key from the colon that follows it
+fn on_key(lexer: &mut Lexer<Json>) -> (Span, Span) {
+    let span = lexer.span();
+    let key_end = lexer.slice().rfind('"').unwrap_or_default() + 1;
+
+    (span.start..span.start + key_end, span.end - 1..span.end)
+}
+
+#[test]
+fn test_json() {
+    let source = r#"{ "name": "shell", "count": -1.5e3, "ok": true, "tags": [null] }"#;
+    let mut lexer = Json::lexer_with_extras(source, ThunkContext::default());
+
+    assert_eq!(lexer.next(), Some(Json::Bracket));
+    assert_eq!(lexer.next(), Some(Json::Key((2..8, 8..9))));
+    assert_eq!(lexer.next(), Some(Json::String));
+    assert_eq!(lexer.slice(), r#""shell""#);
+    assert_eq!(lexer.next(), Some(Json::Comma));
+    assert!(matches!(lexer.next(), Some(Json::Key(_))));
+    assert_eq!(lexer.next(), Some(Json::Number));
+    assert_eq!(lexer.slice(), "-1.5e3");
+    assert_eq!(lexer.next(), Some(Json::Comma));
+    assert!(matches!(lexer.next(), Some(Json::Key(_))));
+    assert_eq!(lexer.next(), Some(Json::Constant));
+    assert_eq!(lexer.next(), Some(Json::Comma));
+    assert!(matches!(lexer.next(), Some(Json::Key(_))));
+    assert_eq!(lexer.next(), Some(Json::Bracket));
+    assert_eq!(lexer.next(), Some(Json::Constant));
+    assert_eq!(lexer.next(), Some(Json::Bracket));
+    assert_eq!(lexer.next(), Some(Json::Bracket));
+    assert_eq!(lexer.next(), None);
+}
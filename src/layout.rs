@@ -0,0 +1,470 @@
+/// Area of the surface covered by a pane, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    /// Returns true if position is inside this rect
+    pub fn contains(&self, position: (f32, f32)) -> bool {
+        let (x, y) = position;
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// Splits this rect into two, ratio is the fraction of space given to the first rect
+    pub fn split(&self, split: Split, ratio: f32) -> (Rect, Rect) {
+        let ratio = ratio.clamp(0.0, 1.0);
+        match split {
+            Split::Horizontal => {
+                let height = self.height * ratio;
+                (
+                    Rect { height, ..*self },
+                    Rect {
+                        y: self.y + height,
+                        height: self.height - height,
+                        ..*self
+                    },
+                )
+            }
+            Split::Vertical => {
+                let width = self.width * ratio;
+                (
+                    Rect { width, ..*self },
+                    Rect {
+                        x: self.x + width,
+                        width: self.width - width,
+                        ..*self
+                    },
+                )
+            }
+        }
+    }
+}
+
+/// Direction a pane is split in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// Panes are stacked top to bottom
+    Horizontal,
+    /// Panes are placed side by side
+    Vertical,
+}
+
+/// Divider between the two sides of a split, dragging it resizes both sides
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Divider {
+    /// Direction of the split
+    pub split: Split,
+    /// Area of the split, covering both sides
+    pub area: Rect,
+    /// Position of the divider, x for vertical splits, and y for horizontal splits
+    pub position: f32,
+}
+
+impl Divider {
+    /// Returns true if position is within tolerance of this divider
+    pub fn hit(&self, position: (f32, f32), tolerance: f32) -> bool {
+        let (x, y) = position;
+        let Rect { x: left, y: top, width, height } = self.area;
+        match self.split {
+            Split::Vertical => (x - self.position).abs() <= tolerance && y >= top && y < top + height,
+            Split::Horizontal => (y - self.position).abs() <= tolerance && x >= left && x < left + width,
+        }
+    }
+}
+
+/// What a pane displays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaneContent {
+    /// The char device being edited, w/ a cursor and line numbers
+    Editor,
+    /// Follows the shell's current output channel
+    ActiveChannel,
+    /// A specific char device channel
+    Channel(u32),
+}
+
+/// Tree of panes the shell's surface is split into
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaneLayout {
+    /// A single pane
+    Pane(PaneContent),
+    /// Two layouts, split in a direction
+    Split {
+        split: Split,
+        /// Fraction of space given to the first layout
+        ratio: f32,
+        first: Box<PaneLayout>,
+        second: Box<PaneLayout>,
+    },
+}
+
+impl Default for PaneLayout {
+    /// Editor on the left half, and the current output channel on the right half
+    fn default() -> Self {
+        PaneLayout::Split {
+            split: Split::Vertical,
+            ratio: 0.5,
+            first: Box::new(PaneLayout::Pane(PaneContent::Editor)),
+            second: Box::new(PaneLayout::Pane(PaneContent::ActiveChannel)),
+        }
+    }
+}
+
+impl PaneLayout {
+    /// Returns the layout for a preset name, `split`, `stacked`, `editor`, or `triple`
+    pub fn preset(name: &str) -> Option<Self> {
+        let split = |split, first, second| PaneLayout::Split {
+            split,
+            ratio: 0.5,
+            first: Box::new(first),
+            second: Box::new(second),
+        };
+
+        match name.trim() {
+            "split" => Some(PaneLayout::default()),
+            "stacked" => Some(split(
+                Split::Horizontal,
+                PaneLayout::Pane(PaneContent::Editor),
+                PaneLayout::Pane(PaneContent::ActiveChannel),
+            )),
+            "editor" => Some(PaneLayout::Pane(PaneContent::Editor)),
+            "triple" => Some(split(
+                Split::Vertical,
+                PaneLayout::Pane(PaneContent::Editor),
+                split(
+                    Split::Horizontal,
+                    PaneLayout::Pane(PaneContent::ActiveChannel),
+                    PaneLayout::Pane(PaneContent::Channel(crate::BuiltinChannel::Session.id())),
+                ),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns the area and content of each pane, in depth-first order
+    ///
+    /// The index of a pane in this list is used to refer to it in the other methods
+    pub fn panes(&self, area: Rect) -> Vec<(Rect, PaneContent)> {
+        let mut panes = vec![];
+        self.collect_panes(area, &mut panes);
+        panes
+    }
+
+    /// Returns the index, area, and content of the pane under position
+    pub fn pane_at(&self, area: Rect, position: (f32, f32)) -> Option<(usize, Rect, PaneContent)> {
+        self.panes(area)
+            .into_iter()
+            .enumerate()
+            .find(|(_, (rect, _))| rect.contains(position))
+            .map(|(idx, (rect, content))| (idx, rect, content))
+    }
+
+    /// Returns the area of the first pane showing content
+    pub fn find(&self, area: Rect, content: PaneContent) -> Option<Rect> {
+        self.panes(area)
+            .into_iter()
+            .find(|(_, c)| *c == content)
+            .map(|(rect, _)| rect)
+    }
+
+    /// Assigns content to the pane at index, returns false if there is no pane at index
+    pub fn assign(&mut self, index: usize, content: PaneContent) -> bool {
+        match self.pane_mut(&mut index.clone()) {
+            Some(pane) => {
+                *pane = PaneLayout::Pane(content);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Splits the pane at index, the new pane is placed second and displays content
+    pub fn split_pane(&mut self, index: usize, split: Split, content: PaneContent) -> bool {
+        match self.pane_mut(&mut index.clone()) {
+            Some(pane) => {
+                let first = std::mem::replace(pane, PaneLayout::Pane(content));
+                *pane = PaneLayout::Split {
+                    split,
+                    ratio: 0.5,
+                    first: Box::new(first),
+                    second: Box::new(PaneLayout::Pane(content)),
+                };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Closes the pane at index, its sibling takes over the space, the last pane can't be closed
+    pub fn close_pane(&mut self, index: usize) -> bool {
+        self.close(&mut index.clone())
+    }
+
+    /// Returns the divider of each split, in the same order as ratios_mut
+    pub fn dividers(&self, area: Rect) -> Vec<Divider> {
+        let mut dividers = vec![];
+        self.collect_dividers(area, &mut dividers);
+        dividers
+    }
+
+    /// Returns the index of the divider within tolerance of position
+    pub fn divider_at(&self, area: Rect, position: (f32, f32), tolerance: f32) -> Option<usize> {
+        self.dividers(area).iter().position(|d| d.hit(position, tolerance))
+    }
+
+    /// Moves the divider at index to position, keeping both sides at least min_size
+    ///
+    /// Returns false if there is no divider at index
+    pub fn drag_divider(&mut self, area: Rect, index: usize, position: (f32, f32), min_size: f32) -> bool {
+        let divider = match self.dividers(area).get(index) {
+            Some(divider) => *divider,
+            None => return false,
+        };
+
+        let (origin, size, position) = match divider.split {
+            Split::Vertical => (divider.area.x, divider.area.width, position.0),
+            Split::Horizontal => (divider.area.y, divider.area.height, position.1),
+        };
+
+        let ratio = if size <= min_size * 2.0 {
+            0.5
+        } else {
+            ((position - origin) / size).clamp(min_size / size, 1.0 - min_size / size)
+        };
+
+        match self.ratios_mut().into_iter().nth(index) {
+            Some(r) => {
+                *r = ratio;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Resets the divider at index to the middle of its split
+    pub fn reset_divider(&mut self, index: usize) -> bool {
+        match self.ratios_mut().into_iter().nth(index) {
+            Some(ratio) => {
+                *ratio = 0.5;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the ratio of each split in depth-first order
+    pub fn ratios(&self) -> Vec<f32> {
+        match self {
+            PaneLayout::Pane(_) => vec![],
+            PaneLayout::Split {
+                ratio,
+                first,
+                second,
+                ..
+            } => {
+                let mut ratios = vec![*ratio];
+                ratios.extend(first.ratios());
+                ratios.extend(second.ratios());
+                ratios
+            }
+        }
+    }
+
+    /// Sets the ratio of each split in depth-first order, i.e. restoring ratios saved in a session
+    ///
+    /// Extra ratios are ignored, and splits without a ratio are left as-is
+    pub fn set_ratios(&mut self, ratios: &[f32]) {
+        for (ratio, saved) in self.ratios_mut().into_iter().zip(ratios) {
+            *ratio = saved.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Returns the ratio of each split in depth-first order, for resizing
+    pub fn ratios_mut(&mut self) -> Vec<&mut f32> {
+        match self {
+            PaneLayout::Pane(_) => vec![],
+            PaneLayout::Split {
+                ratio,
+                first,
+                second,
+                ..
+            } => {
+                let mut ratios = vec![ratio];
+                ratios.extend(first.ratios_mut());
+                ratios.extend(second.ratios_mut());
+                ratios
+            }
+        }
+    }
+
+    fn collect_dividers(&self, area: Rect, dividers: &mut Vec<Divider>) {
+        if let PaneLayout::Split {
+            split,
+            ratio,
+            first,
+            second,
+        } = self
+        {
+            let (first_area, second_area) = area.split(*split, *ratio);
+            dividers.push(Divider {
+                split: *split,
+                area,
+                position: match split {
+                    Split::Vertical => second_area.x,
+                    Split::Horizontal => second_area.y,
+                },
+            });
+            first.collect_dividers(first_area, dividers);
+            second.collect_dividers(second_area, dividers);
+        }
+    }
+
+    fn collect_panes(&self, area: Rect, panes: &mut Vec<(Rect, PaneContent)>) {
+        match self {
+            PaneLayout::Pane(content) => panes.push((area, *content)),
+            PaneLayout::Split {
+                split,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first_area, second_area) = area.split(*split, *ratio);
+                first.collect_panes(first_area, panes);
+                second.collect_panes(second_area, panes);
+            }
+        }
+    }
+
+    fn pane_mut(&mut self, index: &mut usize) -> Option<&mut PaneLayout> {
+        match self {
+            PaneLayout::Pane(_) if *index == 0 => Some(self),
+            PaneLayout::Pane(_) => {
+                *index -= 1;
+                None
+            }
+            PaneLayout::Split { first, second, .. } => {
+                if let Some(pane) = first.pane_mut(index) {
+                    Some(pane)
+                } else {
+                    second.pane_mut(index)
+                }
+            }
+        }
+    }
+
+    fn close(&mut self, index: &mut usize) -> bool {
+        if let PaneLayout::Split { first, second, .. } = self {
+            let remaining = if let PaneLayout::Pane(_) = first.as_ref() {
+                if *index == 0 {
+                    Some(std::mem::replace(second.as_mut(), PaneLayout::Pane(PaneContent::Editor)))
+                } else {
+                    *index -= 1;
+                    None
+                }
+            } else if first.close(index) {
+                return true;
+            } else {
+                None
+            };
+
+            let remaining = match remaining {
+                Some(remaining) => Some(remaining),
+                None => {
+                    if let PaneLayout::Pane(_) = second.as_ref() {
+                        if *index == 0 {
+                            Some(std::mem::replace(first.as_mut(), PaneLayout::Pane(PaneContent::Editor)))
+                        } else {
+                            *index -= 1;
+                            None
+                        }
+                    } else if second.close(index) {
+                        return true;
+                    } else {
+                        None
+                    }
+                }
+            };
+
+            if let Some(remaining) = remaining {
+                *self = remaining;
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+#[test]
+fn test_pane_layout() {
+    let area = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 100.0,
+    };
+
+    let mut layout = PaneLayout::default();
+    let panes = layout.panes(area);
+    assert_eq!(panes.len(), 2);
+    assert_eq!(panes[1].0.x, 50.0);
+    assert_eq!(panes[1].1, PaneContent::ActiveChannel);
+
+    assert!(layout.split_pane(1, Split::Horizontal, PaneContent::Channel(4)));
+    let panes = layout.panes(area);
+    assert_eq!(panes.len(), 3);
+    assert_eq!(panes[2].0.y, 50.0);
+    assert_eq!(panes[2].1, PaneContent::Channel(4));
+    assert_eq!(layout.pane_at(area, (75.0, 75.0)).map(|(i, ..)| i), Some(2));
+
+    assert!(layout.assign(0, PaneContent::Channel(2)));
+    assert_eq!(layout.panes(area)[0].1, PaneContent::Channel(2));
+
+    assert!(layout.close_pane(1));
+    let panes = layout.panes(area);
+    assert_eq!(panes.len(), 2);
+    assert_eq!(panes[1].1, PaneContent::Channel(4));
+    assert_eq!(panes[1].0.height, 100.0);
+
+    assert!(layout.close_pane(0));
+    assert!(!layout.close_pane(0));
+}
+
+#[test]
+fn test_drag_divider() {
+    let area = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 100.0,
+    };
+
+    let mut layout = PaneLayout::default();
+    layout.split_pane(1, Split::Horizontal, PaneContent::Channel(4));
+
+    let dividers = layout.dividers(area);
+    assert_eq!(dividers.len(), 2);
+    assert_eq!(layout.divider_at(area, (52.0, 10.0), 4.0), Some(0));
+    assert_eq!(layout.divider_at(area, (75.0, 49.0), 4.0), Some(1));
+    assert_eq!(layout.divider_at(area, (25.0, 49.0), 4.0), None);
+
+    assert!(layout.drag_divider(area, 0, (30.0, 10.0), 10.0));
+    assert_eq!(layout.panes(area)[1].0.x, 30.0);
+
+    // Sides can't be smaller than the min size
+    assert!(layout.drag_divider(area, 1, (75.0, 99.0), 10.0));
+    assert_eq!(layout.panes(area)[2].0.height, 10.0);
+
+    assert_eq!(layout.ratios(), vec![0.3, 0.9]);
+    assert!(layout.reset_divider(1));
+    assert_eq!(layout.ratios(), vec![0.3, 0.5]);
+
+    let mut restored = PaneLayout::default();
+    restored.split_pane(1, Split::Horizontal, PaneContent::Channel(4));
+    restored.set_ratios(&[0.3, 0.5]);
+    assert_eq!(restored, layout);
+}
@@ -3,26 +3,65 @@ use lifec::editor::{Builder, Call};
 use lifec::plugins::{Config, Connection, Plugin, Remote, Sequence, ThunkContext};
 use lifec::{Component, DenseVecStorage, Entity, Extension, Value, WorldExt, System, WriteStorage, Entities, Join};
 use specs::RunNow;
-use std::collections::BTreeMap;
-use std::ops::DerefMut;
-use tokio::net::TcpStream;
+use std::collections::{BTreeMap, BTreeSet};
+use std::collections::VecDeque;
+use std::ops::{DerefMut, Range};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 use tracing::{event, Level};
-use wgpu::{DepthStencilState, SurfaceConfiguration};
+use wgpu::SurfaceConfiguration;
 use wgpu_glyph::{
-    ab_glyph, BuiltInLineBreaker, GlyphBrush, GlyphBrushBuilder, HorizontalAlign, Layout, Section,
+    ab_glyph, BuiltInLineBreaker, HorizontalAlign, Layout, Section,
     Text, VerticalAlign,
 };
+use winit::event::{ElementState, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode};
+
+use crate::brackets::highlight_overlay;
+use crate::brackets::matching_pair;
+use crate::theme::DEFAULT_FONT_SIZE;
+
+mod brackets;
+mod selection;
+
+mod block_selection;
+pub use block_selection::BlockSelection;
 
 mod char_device;
+mod history;
 pub use char_device::CharDevice;
+pub use char_device::DEFAULT_MAX_LINES;
 
 mod theme;
+pub use theme::CursorStyle;
 pub use theme::Theme;
 pub use theme::Token;
 
 mod color;
 pub use color::ColorTheme;
+pub use color::color_to_linear;
+pub use color::color_to_srgb;
+pub use color::linear_to_srgb;
+pub use color::srgb_to_linear;
+pub use color::sgr_foreground;
+pub use color::truecolor;
+
+mod grammer;
+pub use grammer::Grammer;
+
+mod indent;
+
+mod gutter;
+use gutter::gutter_rows;
+
+mod minimap;
+pub use minimap::Minimap;
+
+mod auto_close;
+pub use auto_close::AutoClose;
+pub use auto_close::AutoCloseEdit;
 
 mod runmd;
 pub use runmd::Runmd;
@@ -30,49 +69,831 @@ pub use runmd::Runmd;
 mod plain;
 pub use plain::Plain;
 
+mod transport;
+pub use transport::Transport;
+
+#[cfg(feature = "relay")]
+mod relay;
+#[cfg(feature = "relay")]
+pub use relay::Relay;
+
+#[cfg(feature = "ssh")]
+mod ssh;
+#[cfg(feature = "ssh")]
+pub use ssh::Ssh;
+#[cfg(feature = "ssh")]
+pub use ssh::SshAddress;
+
+mod telnet;
+pub use telnet::Telnet;
+
+#[cfg(feature = "websocket")]
+mod websocket;
+#[cfg(feature = "websocket")]
+pub use websocket::WebSocket;
+
+mod datagram;
+pub use datagram::Datagram;
+
+mod ipc;
+pub use ipc::Ipc;
+pub use ipc::IpcAddress;
+
+mod reconnect;
+pub(crate) use reconnect::Reconnect;
+pub use reconnect::ConnectionState;
+pub use reconnect::ReconnectPolicy;
+
+mod framing;
+pub use framing::Framing;
+pub use framing::LengthPrefix;
+pub use framing::LineEnding;
+
+mod line_policy;
+pub use line_policy::Aliases;
+pub use line_policy::LinePolicy;
+
+mod clock;
+pub use clock::Clock;
+pub use clock::SystemClock;
+
+#[cfg(feature = "test-support")]
+pub mod testing;
+
+pub mod prelude;
+
+mod command;
+pub use command::CommandGrammer;
+
+mod json;
+pub use json::Json;
+
+mod toml;
+pub use toml::Toml;
+
+mod markdown;
+pub use markdown::Markdown;
+
+mod sh;
+pub use sh::Sh;
+
+mod log;
+pub use log::LogGrammer;
+
+mod builtin_channel;
+pub use builtin_channel::BuiltinChannel;
+
+mod channel_info;
+pub use channel_info::ChannelInfo;
+
+mod diagnostics;
+pub use diagnostics::Diagnostic;
+
+mod watch;
+pub(crate) use watch::FileWatcher;
+
+mod glyph_metrics;
+pub use glyph_metrics::GlyphMetrics;
+
+mod layout;
+pub use layout::Divider;
+pub use layout::PaneContent;
+pub use layout::PaneLayout;
+pub use layout::Rect;
+pub use layout::Split;
+
+mod locale;
+pub use locale::Locale;
+
+mod background;
+pub(crate) use background::BackgroundRenderer;
+pub use background::PaneBackground;
+
+mod decoration;
+pub use decoration::PaneDecoration;
+pub use decoration::PANE_TITLE_HEIGHT;
+use decoration::{mix, pane_content};
+
+mod overflow;
+pub(crate) use overflow::Backlog;
+pub use overflow::OverflowPolicy;
+
+mod drain;
+pub(crate) use drain::DrainQueue;
+pub use drain::DrainPolicy;
+
+mod resolve;
+
+mod terminal_grid;
+pub use terminal_grid::Cell;
+pub use terminal_grid::CellColor;
+pub use terminal_grid::CellStyle;
+pub use terminal_grid::TerminalGrid;
+
+mod text_counts;
+pub use text_counts::TextCounts;
+use text_counts::text_value_at;
+
+mod channel_writer;
+pub use channel_writer::ChannelWriter;
+
+mod shell_input;
+pub use shell_input::ShellInput;
+
+mod process;
+
+mod metrics;
+pub use metrics::SessionMetrics;
+
+mod stopwatch;
+pub use stopwatch::Stopwatch;
+pub(crate) use stopwatch::format_elapsed;
+
+mod pending;
+pub(crate) use pending::{DrainProgress, PendingWrite};
+
+mod spelling;
+pub use spelling::Dictionary;
+pub(crate) use spelling::SpellChecker;
+pub use spelling::WordList;
+
+mod actions;
+pub use actions::ShellAction;
+
+mod settings;
+pub use settings::ShellSettings;
+
+mod focus;
+pub use focus::FocusPolicy;
+pub use focus::FocusRequest;
+
+mod search;
+pub(crate) use search::Search;
+
+mod startup;
+pub use startup::StartupTimings;
+
+mod routing;
+pub use routing::ChannelCommand;
+
+mod dialogs;
+pub(crate) use dialogs::DialogResult;
+pub(crate) use dialogs::FileAction;
+
+mod text_brush;
+pub(crate) use text_brush::TextBrush;
+
+mod render_cache;
+pub(crate) use render_cache::RenderCache;
+pub(crate) use render_cache::StyledSpans;
+
+mod text_batch;
+pub(crate) use text_batch::TextBatch;
+use text_batch::line_chunks;
+
+mod hooks;
+pub(crate) use hooks::ShellHooks;
+
+mod frecency;
+pub(crate) use frecency::Frecency;
+
+mod transcript;
+pub(crate) use transcript::TranscriptBackup;
+
+mod channel_log;
+pub(crate) use channel_log::ChannelLog;
+
+mod civil_time;
+
+mod progress;
+pub use progress::Progress;
+
+mod copy;
+use copy::{to_clipboard_text, to_code_block};
+
+mod global_search;
+pub use global_search::SearchResult;
+use global_search::search_buffers;
+
+mod annotations;
+pub use annotations::Annotation;
+use annotations::{attribute_name, line_ends};
+
+mod dispatch;
+pub use dispatch::BlockAddress;
+
+mod hover;
+use hover::{Hover, HoverTarget};
+
+mod inspector;
+use inspector::attribute_rows;
+
+mod viewport;
+pub use viewport::Viewport;
+use viewport::{block_start, line_start, lines_of, visible_lines};
+
+mod line_prefix;
+pub use line_prefix::LineDecoration;
+use line_prefix::LinePrefixes;
+
+mod registers;
+pub(crate) use registers::RegisterOp;
+pub use registers::Registers;
+
+mod session_file;
+pub(crate) use session_file::{strip_userinfo, ChannelSession, SessionFile};
+
+mod journal;
+pub(crate) use journal::{Journal, JournalWrite};
+
+mod help;
+pub use help::Help;
+pub use help::HelpEntry;
+pub use help::HelpKind;
+pub use help::HelpRegistry;
+
+mod truncate;
+pub use truncate::DEFAULT_MAX_LINE_LEN;
+use truncate::{truncate_lines, truncated_lines};
+
+mod world_names;
+pub use world_names::NameSlot;
+pub use world_names::WorldNames;
+
+mod zoom;
+pub use zoom::ZoomView;
+use zoom::block_at;
+
+mod scroll_link;
+pub use scroll_link::ScrollLinks;
+
+mod triggers;
+pub use triggers::Trigger;
+pub use triggers::TriggerAction;
+pub use triggers::Triggers;
+
+mod scheme;
+pub use scheme::ColorScheme;
+pub use scheme::SchemeFormat;
+
+/// Top of the area that is split between panes, leaves room for the menu bar and status line
+///
+/// This and the other sizes in pixels are at a scale factor of 1.0, see Shell::scaled
+const LAYOUT_TOP: f32 = 180.0;
+
+/// Left padding of the editor pane, leaves room for line numbers
+const EDITOR_GUTTER: f32 = 90.0;
+
+/// Left padding of channel panes
+const CHANNEL_PADDING: f32 = 60.0;
+
+/// Distance from a divider that can grab it, in pixels
+const DIVIDER_TOLERANCE: f32 = 6.0;
+
+/// Smallest size a pane can be resized to, in pixels
+const MIN_PANE_SIZE: f32 = 120.0;
+
+/// Max time between clicks on a divider, or in the editor, for a double-click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Returns the area of the surface below the toolbar, shared by the panes and the minimap
+fn surface_area(width: f32, height: f32, scale_factor: f32) -> Rect {
+    let top = LAYOUT_TOP * scale_factor;
+    Rect {
+        x: 0.0,
+        y: top,
+        width,
+        height: (height - top).max(0.0),
+    }
+}
+
+/// Returns true if name is the name of one of the shell's grammers
+fn known_grammer(name: &str) -> bool {
+    [
+        Runmd::name(),
+        Sh::name(),
+        Json::name(),
+        Toml::name(),
+        Markdown::name(),
+        LogGrammer::name(),
+        CommandGrammer::name(),
+        Plain::name(),
+    ]
+    .contains(&name)
+}
+
+/// Returns the default theme, w/ colors resolved by style
+fn default_theme(style: Arc<dyn ColorTheme>) -> Theme {
+    // TODO: This is a temp setting
+    let mut default_context = ThunkContext::default();
+    default_context
+        .as_mut()
+        .define("bracket", "color")
+        .edit_as(Value::TextBuffer("purple".to_string()));
+    default_context
+        .as_mut()
+        .define("operator", "color")
+        .edit_as(Value::TextBuffer("yellow".to_string()));
+    default_context
+        .as_mut()
+        .define("identifier", "color")
+        .edit_as(Value::TextBuffer("red".to_string()));
+    default_context
+        .as_mut()
+        .define("keyword", "color")
+        .edit_as(Value::TextBuffer("blue".to_string()));
+    default_context
+        .as_mut()
+        .define("literal", "color")
+        .edit_as(Value::TextBuffer("green".to_string()));
+    default_context
+        .as_mut()
+        .define("comment", "color")
+        .edit_as(Value::TextBuffer("green".to_string()));
+    default_context
+        .as_mut()
+        .define("whitespace", "color")
+        .edit_as(Value::TextBuffer("yellow".to_string()));
+
+    Theme::new_with_style(default_context, style)
+}
+
+/// Parses the font bundled w/ the shell, returns it w/ how long parsing took
+fn load_default_font() -> Result<(ab_glyph::FontArc, Duration), ab_glyph::InvalidFont> {
+    let start = Instant::now();
+    ab_glyph::FontArc::try_from_slice(include_bytes!("Inconsolata-Regular.ttf")).map(|font| (font, start.elapsed()))
+}
+
+/// Returns the thunk context of the `shell` settings block in the app world
+fn find_settings_block(app_world: &lifec::World) -> Option<ThunkContext> {
+    let contexts = app_world.read_component::<ThunkContext>();
+    (&contexts)
+        .join()
+        .find(|tc| tc.block.block_name == "shell")
+        .cloned()
+}
+
+/// Returns the thunk context of the `theme` block in the app world
+fn find_theme_block(app_world: &lifec::World) -> Option<ThunkContext> {
+    let contexts = app_world.read_component::<ThunkContext>();
+    (&contexts)
+        .join()
+        .find(|tc| tc.block.block_name == "theme")
+        .cloned()
+}
+
+/// Returns the thunk context of the `triggers` block in the app world
+fn find_triggers_block(app_world: &lifec::World) -> Option<ThunkContext> {
+    let contexts = app_world.read_component::<ThunkContext>();
+    (&contexts)
+        .join()
+        .find(|tc| tc.block.block_name == "triggers")
+        .cloned()
+}
+
+/// Returns the attributes of a block as text, for telling whether the block changed
+fn block_content(tc: &ThunkContext) -> String {
+    tc.as_ref()
+        .iter_attributes()
+        .map(|a| format!("{} {:?}", a.name(), a.value()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the names of the attributes and blocks of every thunk context in the app world
+fn find_world_names(app_world: &lifec::World) -> WorldNames {
+    let mut names = WorldNames::default();
+    for tc in app_world.read_component::<ThunkContext>().join() {
+        names.add_block(tc.block.block_name.clone());
+        for attribute in tc.as_ref().iter_attributes() {
+            names.add_attribute(attribute.name());
+        }
+    }
+    names
+}
+
+/// Shows a color editor for a linear sRGB color, the color is shown and picked in sRGB
+///
+/// Returns true if the color was changed
+fn color_edit_srgb(ui: &imgui::Ui, label: impl AsRef<str>, color: &mut [f32; 4]) -> bool {
+    let mut srgb = color_to_srgb(*color);
+    let changed = ColorEdit::new(label, &mut srgb).build(ui);
+    if changed {
+        *color = color_to_linear(srgb);
+    }
+    changed
+}
+
+/// Shows the description and examples of entry when the previous item is hovered
+fn help_tooltip(ui: &imgui::Ui, entry: &HelpEntry) {
+    if ui.is_item_hovered() && !entry.help.examples.is_empty() {
+        ui.tooltip(|| {
+            ui.text(&entry.help.description);
+            for example in entry.help.examples.iter() {
+                ui.text_disabled(example);
+            }
+        });
+    }
+}
+
+/// Maximum number of bytes drained from the byte channel each frame
+const MAX_BYTES_PER_FRAME: usize = 4096;
+
+/// Maximum number of bytes received ahead of being drained, so that bytes behind a flooding channel can be scheduled
+const MAX_QUEUED_BYTES: usize = MAX_BYTES_PER_FRAME * 16;
+
+/// Capacity of the channel plugins write their output to, it's emptied into the backlog each frame
+const PLUGIN_OUTPUT_CAPACITY: usize = 4096;
+
+/// Maximum number of lines a ShellInput can have waiting to be received
+const MAX_PENDING_INPUT: usize = 64;
+
+/// Time the session waits after a change before it's saved, so that a burst of changes is saved once
+const SESSION_SAVE_DELAY: Duration = Duration::from_secs(2);
+
 /// Shell extension for the lifec runtime
-pub struct Shell<Style = DefaultTheme>
-where
-    Style: ColorTheme + Default,
-{
+pub struct Shell {
     /// glyph_brush, for rendering fonts
-    brush: Option<GlyphBrush<DepthStencilState>>,
+    brush: Option<TextBrush>,
     /// byte receiver
     byte_rx: Option<Receiver<(u32, u8)>>,
     /// byte sender
     byte_tx: Option<Sender<(u32, u8)>>,
+    /// Receives output of plugins, which is queued in the backlog before it's sent to the byte channel
+    plugin_rx: Option<Receiver<(u32, u8)>>,
+    /// Sender given to plugins for their output, w/ enable_output
+    plugin_tx: Option<Sender<(u32, u8)>>,
     /// char_devices, the first device writes to the shell buffer, and the other devices are for displays
     char_devices: BTreeMap<u32, CharDevice>,
+    /// metadata for each char_device that belongs to an entity
+    channel_info: BTreeMap<u32, ChannelInfo>,
+    /// Senders for the ShellInput of channels, by channel
+    inputs: BTreeMap<u32, Sender<String>>,
+    /// Times plugins that report themselves as running, by channel
+    stopwatches: BTreeMap<u32, Stopwatch>,
+    /// Progress reported by plugins, by channel
+    progress: BTreeMap<u32, Progress>,
+    /// Channels whose output is dropped instead of written to their char_device
+    muted: BTreeSet<u32>,
     /// sets the current char_device that can be edited
     editing: Option<u32>,
     /// theme
-    theme: Option<Theme<Style>>,
+    theme: Option<Theme>,
     /// current_output
     channel: i32,
     /// background clear color
     background: [f32; 4],
     /// Current-live connection
-    connection: Option<TcpStream>,
+    connection: Option<Box<dyn Transport>>,
+    /// Address being connected to in the background, and the result once it's connected
+    connecting: Option<(String, tokio::sync::oneshot::Receiver<std::io::Result<Box<dyn Transport>>>)>,
+    /// Error of the last connection attempt, shown in the status line
+    connection_error: Option<String>,
+    /// Channel and path of a buffer being saved in the background, and the result once it's written
+    saving: Option<(u32, String, tokio::sync::oneshot::Receiver<std::io::Result<()>>)>,
+    /// Path of a file being loaded in the background, and its contents once it's read
+    loading: Option<(String, tokio::sync::oneshot::Receiver<std::io::Result<String>>)>,
+    /// Retries of the last connection, after it closes unexpectedly
+    reconnect: Reconnect,
+    /// Framing of lines sent to the connection
+    framing: Framing,
+    /// Checks lines before they're sent to the connection, if the host provided a policy
+    line_policy: Option<Box<dyn LinePolicy>>,
+    /// Error of the last line rejected by the line policy, shown under the prompt
+    line_error: Option<String>,
     /// Address to connect to
     address: Option<String>,
+    /// Session name and pairing code, when connecting through a relay
+    #[cfg(feature = "relay")]
+    relay_pairing: (String, String),
+    /// Channel and pending result of pairing through a relay
+    #[cfg(feature = "relay")]
+    relay_connecting: Option<(u32, tokio::sync::oneshot::Receiver<std::io::Result<Relay>>)>,
+    /// Command line of a process to spawn, entered in the connection menu
+    process_command: String,
+    /// Command lines of spawned processes, by channel
+    processes: BTreeMap<u32, String>,
+    /// Diagnostics for the char_device being edited
+    diagnostics: Vec<Diagnostic>,
+    /// Whether the diagnostics panel is open
+    show_diagnostics: bool,
+    /// If true, the gutter numbers lines by their distance from the cursor's line
+    relative_line_numbers: bool,
+    /// Overview strip of the buffer being edited, on the right edge of the surface
+    minimap: Option<Minimap>,
+    /// Current state of modifier keys
+    modifiers: ModifiersState,
+    /// If false, keyboard input is left to other extensions in the same window
+    focused: bool,
+    /// Whether an imgui widget had keyboard focus on the last frame, i.e. an input field
+    imgui_wants_keyboard: bool,
+    /// Whether the mouse was over an imgui window on the last frame
+    imgui_wants_mouse: bool,
+    /// Whether the search window had focus on the last frame, Enter in it jumps to the next match
+    search_focused: bool,
+    /// Path used by the open/save shortcuts
+    file_path: String,
+    /// Name of the file currently loaded in the editor
+    filename: Option<String>,
+    /// Watches files bound to channels
+    watcher: FileWatcher,
+    /// Metrics of the font, for hit-testing
+    glyph_metrics: GlyphMetrics,
+    /// Size of the window in columns and rows, last reported to the connection
+    window_size: Option<(u16, u16)>,
+    /// Last known position of the mouse cursor
+    mouse_position: (f32, f32),
+    /// Last known surface size
+    surface_size: (f32, f32),
+    /// Clock used for timers
+    clock: Arc<dyn Clock>,
+    /// Panes the surface is split into
+    layout: PaneLayout,
+    /// Localized strings for the built-in UI
+    locale: Locale,
+    /// Draws pane backgrounds
+    background_renderer: Option<BackgroundRenderer>,
+    /// Image paths typed into the background controls, by pane, they're only loaded once applied
+    background_paths: BTreeMap<usize, String>,
+    /// Bytes waiting for room in the byte channel
+    backlog: Backlog,
+    /// Progress of draining a burst of bytes received from the byte channel
+    drain_progress: DrainProgress,
+    /// Bytes received from the byte channel, waiting to be drained
+    drain_queue: DrainQueue,
+    /// Metrics for the current connection
+    session: Option<SessionMetrics>,
+    /// Bulk writes that are processed across frames
+    pending_writes: VecDeque<PendingWrite>,
+    /// Spell checks free text in the editor, if a dictionary was provided
+    spell_checker: Option<SpellChecker>,
+    /// Misspelled words of the editor's buffer, for the channel and revision of the buffer they were found in
+    misspelled: Option<(u32, u64, Vec<Diagnostic>)>,
+    /// Suggestions for the last misspelled word the cursor was on
+    suggested: Option<(String, Vec<String>)>,
+    /// Bracket pairs of the editor as of a channel and revision, and whether it was parsed as a prompt
+    bracket_pairs: Option<(u32, u64, bool, Vec<(Range<usize>, Range<usize>)>)>,
+    /// How focus requests from plugins are handled
+    focus_policy: FocusPolicy,
+    /// Focus requests that are waiting for the user to visit the channel
+    notifications: Vec<FocusRequest>,
+    /// Incremental search in the char_device being edited
+    search: Search,
+    /// Parses the font off the render thread, joined once it's finished
+    font_loader: Option<JoinHandle<Result<(ab_glyph::FontArc, Duration), ab_glyph::InvalidFont>>>,
+    /// Set if the font couldn't be loaded, so that it isn't retried every frame
+    font_error: Option<String>,
+    /// How long each stage of initialization took
+    startup: StartupTimings,
+    /// Plugin channel that lines typed in the editor are routed to
+    command_target: Option<u32>,
+    /// Pane the user last clicked, focusing a pane doesn't change where commands are routed
+    focused_pane: PaneContent,
+    /// Commands waiting to be delivered to plugins
+    commands: Vec<ChannelCommand>,
+    /// Prompt for a path, when native dialogs aren't available
+    path_prompt: Option<(FileAction, String)>,
+    /// Styled spans of each channel, rebuilt only when the buffer or theme changes
+    render_cache: RenderCache,
+    /// Text vectors reused by the sections queued each frame
+    text_batch: TextBatch,
+    /// Channels popped out into floating windows, w/ the pane they were detached from
+    detached: BTreeMap<u32, Option<usize>>,
+    /// Channels whose output scrolls together
+    scroll_links: ScrollLinks,
+    /// Rules evaluated against completed lines of output
+    triggers: Triggers,
+    /// Events from triggers waiting to be dispatched to plugins
+    trigger_events: Vec<(u32, String)>,
+    /// Content of the `triggers` block when its rules were last loaded
+    triggers_block: Option<String>,
+    /// Revision of the app world when the `triggers` block was last read
+    triggers_read: Option<(u64, usize)>,
+    /// Actions requested by runmd blocks and plugins, applied on the next run
+    actions: Vec<ShellAction>,
+    /// Editor view zoomed to fit a block, until it's toggled off
+    zoom: Option<ZoomView>,
+    /// Max number of chars rendered of a single line of channel output, longer lines are cut off w/ a marker
+    max_line_len: usize,
+    /// Max number of lines kept by output channels, the oldest lines are trimmed past it
+    max_channel_lines: Option<usize>,
+    /// If true, line breaks typed in the editor are followed by the indentation the editor's grammer expects
+    auto_indent: bool,
+    /// Closes brackets, quotes, and fences typed in the editor, if enabled
+    auto_close: Option<AutoClose>,
+    /// Title and text of a truncated line, expanded into a popup
+    expanded_line: Option<(String, String)>,
+    /// Commands, snippets, and completions registered w/ the shell, and their help
+    help: HelpRegistry,
+    /// Query of the palette, if it's open
+    palette: Option<String>,
+    /// Query of the search across every channel, if it's open
+    global_search: Option<String>,
+    /// Results of the last search across every channel, in the order they're printed to the search channel
+    search_results: Vec<SearchResult>,
+    /// Results of the last block sent to the app world, for the channel and revision of the buffer they were made for
+    annotations: Option<(u32, u64, Vec<Annotation>)>,
+    /// Attribute filter of the entity inspector, if it's open
+    inspector: Option<String>,
+    /// Index of the divider being dragged
+    dragging: Option<usize>,
+    /// Last divider that was clicked, for detecting double-clicks
+    last_divider_click: Option<(usize, Instant)>,
+    /// Offset of the last click in the editor, for detecting double-clicks
+    last_editor_click: Option<(usize, Instant)>,
+    /// Offset of the editor the mouse is resting over, for showing tooltips
+    hover: Hover,
+    /// Scale factor of the window, text and padding are scaled by it
+    scale_factor: f32,
+    /// Viewports of output channels, that follow the newest line while they're scrolled to the end
+    viewports: BTreeMap<u32, Viewport>,
+    /// Viewport of the editor, that follows the cursor
+    editor_scroll: Viewport,
+    /// If true, channels scrolled to the end keep their newest line visible as output is added
+    follow_output: bool,
+    /// If true, viewports animate towards the line they're following instead of jumping to it
+    smooth_scrolling: bool,
+    /// When the last frame was rendered, for animating viewports
+    last_frame: Option<Instant>,
+    /// If true, moving the mouse extends the rectangular selection, i.e. while Alt+dragging
+    block_dragging: bool,
+    /// Rows of the last rectangular selection that was copied
+    block_clipboard: Vec<String>,
+    /// Where the session is saved, i.e. layout proportions and buffers
+    session_file: Option<PathBuf>,
+    /// When the session first changed since it was last saved
+    session_changed: Option<Instant>,
+    /// Thread the session is being saved on
+    session_writer: Option<JoinHandle<()>>,
+    /// Channels restored from the session file, waiting for a channel w/ the same key to be added
+    restored_channels: BTreeMap<String, ChannelSession>,
+    /// Font the glyph brush was built with, for measuring glyphs at the theme's font size
+    font: Option<ab_glyph::FontArc>,
+    /// If true, changes to the `theme` block in the app world are applied as they happen
+    live_theme: bool,
+    /// Content of the `theme` block when it was last applied, the theme is only reapplied once the block changes
+    theme_block: Option<String>,
+    /// Revision of the app world when the `theme` block was last read
+    theme_read: Option<(u64, usize)>,
+    /// Bumped whenever the shell changes the thunk contexts in the app world, i.e. by dispatching a block
+    world_revision: u64,
+    /// Colors restored from the session, reapplied after the `theme` block is first applied
+    restored_colors: Vec<(Token, [f32; 4])>,
+    /// Settings from the embedding application, applied on the first run before the `shell` block
+    initial_settings: Option<ShellSettings>,
+    /// Whether the `shell` settings block has been read from the app world
+    settings_loaded: bool,
+    /// Callbacks registered by the embedding application
+    hooks: ShellHooks,
+    /// Named registers for yanking and pasting text, persisted in the session
+    registers: Registers,
+    /// Register operation waiting for the name of a register to be typed
+    pending_register: Option<RegisterOp>,
+    /// Directory transcripts of remote sessions are backed up to
+    data_dir: Option<PathBuf>,
+    /// Prefix added to each line of output channels, if enabled
+    line_decoration: Option<LineDecoration>,
+    /// Prefixes of the lines received by output channels, by channel
+    line_prefixes: BTreeMap<u32, LinePrefixes>,
+    /// Files that output of channels is appended to, by channel
+    channel_logs: BTreeMap<u32, ChannelLog>,
+    /// Transcript of the current connection, if it was opened w/ a profile
+    transcript: Option<TranscriptBackup>,
+    /// Journal of edits to buffers, kept in the data directory for crash recovery
+    journal: Option<Journal>,
+    /// Usage of completions and commands, completions are ranked by it
+    frecency: Frecency,
 }
 
-impl<Style> Default for Shell<Style>
-where
-    Style: ColorTheme + Default,
-{
+impl Default for Shell {
     fn default() -> Self {
         Self {
             brush: Default::default(),
             byte_rx: Default::default(),
             byte_tx: Default::default(),
+            plugin_rx: Default::default(),
+            plugin_tx: Default::default(),
             char_devices: Default::default(),
+            channel_info: Default::default(),
+            inputs: Default::default(),
+            stopwatches: Default::default(),
+            progress: Default::default(),
+            muted: Default::default(),
             editing: Default::default(),
             theme: Default::default(),
             channel: Default::default(),
-            background: Style::background(),
+            background: DefaultTheme.background(),
             connection: None,
+            connecting: None,
+            saving: None,
+            loading: None,
+            connection_error: None,
+            reconnect: Reconnect::default(),
+            framing: Framing::default(),
+            line_policy: None,
+            line_error: None,
             address: None,
+            #[cfg(feature = "relay")]
+            relay_pairing: Default::default(),
+            #[cfg(feature = "relay")]
+            relay_connecting: None,
+            process_command: String::default(),
+            processes: Default::default(),
+            diagnostics: vec![],
+            show_diagnostics: false,
+            relative_line_numbers: false,
+            minimap: None,
+            modifiers: ModifiersState::default(),
+            focused: true,
+            imgui_wants_keyboard: false,
+            imgui_wants_mouse: false,
+            search_focused: false,
+            file_path: String::default(),
+            filename: None,
+            watcher: FileWatcher::default(),
+            glyph_metrics: GlyphMetrics::default(),
+            window_size: None,
+            mouse_position: (0.0, 0.0),
+            surface_size: (0.0, 0.0),
+            clock: Arc::new(SystemClock),
+            layout: PaneLayout::default(),
+            locale: Locale::default(),
+            background_renderer: None,
+            background_paths: BTreeMap::new(),
+            backlog: Backlog::default(),
+            drain_progress: DrainProgress::default(),
+            drain_queue: DrainQueue::default(),
+            session: None,
+            pending_writes: VecDeque::new(),
+            spell_checker: None,
+            misspelled: None,
+            suggested: None,
+            bracket_pairs: None,
+            focus_policy: FocusPolicy::default(),
+            notifications: vec![],
+            search: Search::default(),
+            font_loader: None,
+            font_error: None,
+            startup: StartupTimings::default(),
+            command_target: None,
+            focused_pane: PaneContent::Editor,
+            commands: vec![],
+            path_prompt: None,
+            render_cache: RenderCache::default(),
+            text_batch: TextBatch::default(),
+            detached: BTreeMap::new(),
+            scroll_links: ScrollLinks::default(),
+            triggers: Triggers::default(),
+            trigger_events: vec![],
+            triggers_block: None,
+            triggers_read: None,
+            actions: vec![],
+            zoom: None,
+            max_line_len: DEFAULT_MAX_LINE_LEN,
+            max_channel_lines: Some(DEFAULT_MAX_LINES),
+            auto_indent: true,
+            auto_close: None,
+            expanded_line: None,
+            help: HelpRegistry::default(),
+            palette: None,
+            global_search: None,
+            search_results: vec![],
+            inspector: None,
+            annotations: None,
+            dragging: None,
+            last_divider_click: None,
+            last_editor_click: None,
+            hover: Hover::default(),
+            scale_factor: 1.0,
+            viewports: BTreeMap::new(),
+            editor_scroll: Viewport::default(),
+            follow_output: true,
+            smooth_scrolling: false,
+            last_frame: None,
+            block_dragging: false,
+            block_clipboard: vec![],
+            session_file: None,
+            session_changed: None,
+            session_writer: None,
+            restored_channels: BTreeMap::new(),
+            font: None,
+            live_theme: true,
+            theme_block: None,
+            theme_read: None,
+            world_revision: 0,
+            restored_colors: vec![],
+            initial_settings: None,
+            settings_loaded: false,
+            hooks: ShellHooks::default(),
+            registers: Registers::default(),
+            pending_register: None,
+            data_dir: None,
+            line_decoration: None,
+            line_prefixes: BTreeMap::new(),
+            channel_logs: BTreeMap::new(),
+            transcript: None,
+            journal: None,
+            frecency: Frecency::default(),
         }
     }
 }
@@ -81,188 +902,4604 @@ where
 pub struct DefaultTheme;
 
 impl ColorTheme for DefaultTheme {
-    fn prompt() -> Text<'static> {
+    fn prompt(&self) -> Text<'static> {
         Text::new("> ")
             .with_color([1.0, 0.0, 0.0, 1.0])
             .with_scale(40.0)
     }
 
-    fn cursor() -> Text<'static> {
+    fn cursor(&self) -> Text<'static> {
         Text::new("_")
             .with_color([0.4, 0.8, 0.8, 1.0])
             .with_scale(40.0)
             .with_z(0.2)
     }
 
-    fn background() -> [f32; 4] {
+    fn background(&self) -> [f32; 4] {
         [0.02122, 0.02519, 0.03434, 1.0]
     }
 
-    fn foreground() -> [f32; 4] {
-        Self::yellow()
+    fn foreground(&self) -> [f32; 4] {
+        self.yellow()
     }
 
-    fn red() -> [f32; 4] {
+    fn red(&self) -> [f32; 4] {
         [0.7454, 0.14996, 0.17789, 1.0]
     }
 
-    fn blue() -> [f32; 4] {
+    fn blue(&self) -> [f32; 4] {
         [0.11954, 0.42869, 0.86316, 1.0]
     }
 
-    fn purple() -> [f32; 4] {
+    fn purple(&self) -> [f32; 4] {
         [0.56471, 0.18782, 0.72306, 1.0]
     }
 
-    fn green() -> [f32; 4] {
+    fn green(&self) -> [f32; 4] {
         [0.31399, 0.54572, 0.1912, 1.0]
     }
 
-    fn yellow() -> [f32; 4] {
+    fn yellow(&self) -> [f32; 4] {
         [0.78354, 0.52712, 0.19807, 1.0]
     }
 
-    fn orange() -> [f32; 4] {
+    fn orange(&self) -> [f32; 4] {
         [0.78354, 0.52712, 0.19807, 1.0]
     }
 }
 
+/// Dark text on a light background
+#[derive(Default, Clone)]
+pub struct LightTheme;
+
+impl ColorTheme for LightTheme {
+    fn prompt(&self) -> Text<'static> {
+        Text::new("> ")
+            .with_color([0.50289, 0.01444, 0.02217, 1.0])
+            .with_scale(40.0)
+    }
+
+    fn cursor(&self) -> Text<'static> {
+        Text::new("_")
+            .with_color([0.00913, 0.15209, 0.32778, 1.0])
+            .with_scale(40.0)
+            .with_z(0.2)
+    }
+
+    fn background(&self) -> [f32; 4] {
+        [0.91310, 0.91310, 0.89627, 1.0]
+    }
+
+    fn foreground(&self) -> [f32; 4] {
+        [0.01298, 0.01298, 0.01681, 1.0]
+    }
+
+    fn red(&self) -> [f32; 4] {
+        [0.50289, 0.01444, 0.02217, 1.0]
+    }
+
+    fn blue(&self) -> [f32; 4] {
+        [0.00913, 0.15209, 0.52712, 1.0]
+    }
+
+    fn purple(&self) -> [f32; 4] {
+        [0.25818, 0.02732, 0.41789, 1.0]
+    }
+
+    fn green(&self) -> [f32; 4] {
+        [0.02732, 0.24620, 0.01681, 1.0]
+    }
+
+    fn yellow(&self) -> [f32; 4] {
+        [0.38643, 0.22323, 0.0, 1.0]
+    }
+
+    fn orange(&self) -> [f32; 4] {
+        [0.54572, 0.13287, 0.0, 1.0]
+    }
+}
+
+/// Saturated colors on a black background
+#[derive(Default, Clone)]
+pub struct HighContrastTheme;
+
+impl ColorTheme for HighContrastTheme {
+    fn prompt(&self) -> Text<'static> {
+        Text::new("> ")
+            .with_color([1.0, 1.0, 1.0, 1.0])
+            .with_scale(40.0)
+    }
+
+    fn cursor(&self) -> Text<'static> {
+        Text::new("_")
+            .with_color([1.0, 1.0, 0.0, 1.0])
+            .with_scale(40.0)
+            .with_z(0.2)
+    }
+
+    fn background(&self) -> [f32; 4] {
+        [0.0, 0.0, 0.0, 1.0]
+    }
+
+    fn foreground(&self) -> [f32; 4] {
+        [1.0, 1.0, 1.0, 1.0]
+    }
+
+    fn red(&self) -> [f32; 4] {
+        [1.0, 0.05126, 0.05126, 1.0]
+    }
+
+    fn blue(&self) -> [f32; 4] {
+        [0.21223, 0.52712, 1.0, 1.0]
+    }
+
+    fn purple(&self) -> [f32; 4] {
+        [1.0, 0.21223, 1.0, 1.0]
+    }
+
+    fn green(&self) -> [f32; 4] {
+        [0.0, 1.0, 0.0, 1.0]
+    }
+
+    fn yellow(&self) -> [f32; 4] {
+        [1.0, 1.0, 0.0, 1.0]
+    }
+
+    fn orange(&self) -> [f32; 4] {
+        [1.0, 0.37626, 0.0, 1.0]
+    }
+
+    fn critical(&self) -> [f32; 4] {
+        [1.0, 0.0, 0.0, 1.0]
+    }
+}
+
+/// Color themes built into the shell, that can be switched between at runtime
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinTheme {
+    #[default]
+    Default,
+    Light,
+    HighContrast,
+}
+
+impl BuiltinTheme {
+    /// Returns all built-in themes
+    pub fn all() -> [BuiltinTheme; 3] {
+        [BuiltinTheme::Default, BuiltinTheme::Light, BuiltinTheme::HighContrast]
+    }
+
+    /// Returns the built-in theme for a name, i.e. from the `theme` attribute of the `shell` block
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "default" => Some(BuiltinTheme::Default),
+            "light" => Some(BuiltinTheme::Light),
+            "high_contrast" => Some(BuiltinTheme::HighContrast),
+            _ => None,
+        }
+    }
+
+    /// Returns the locale key for the name of this theme
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            BuiltinTheme::Default => "default_theme",
+            BuiltinTheme::Light => "light_theme",
+            BuiltinTheme::HighContrast => "high_contrast_theme",
+        }
+    }
+
+    /// Returns the style of this built-in theme
+    pub fn style(&self) -> Arc<dyn ColorTheme> {
+        match self {
+            BuiltinTheme::Default => Arc::new(DefaultTheme),
+            BuiltinTheme::Light => Arc::new(LightTheme),
+            BuiltinTheme::HighContrast => Arc::new(HighContrastTheme),
+        }
+    }
+}
+
 /// This component adds a channel to this shell
+///
+/// Plugins can write to the channel w/ send_str, send_line, or the AsyncWrite adapter from writer
 #[derive(Component, Default, Clone)]
 #[storage(DenseVecStorage)]
-pub struct ShellChannel(Option<Sender<(u32, u8)>>);
+pub struct ShellChannel(Option<Sender<(u32, u8)>>, u32);
 
-impl<Style> Shell<Style>
-where
-    Style: ColorTheme + Default,
-{
-    /// Connects to a tcp stream
-    pub async fn connect_to(&mut self, address: impl AsRef<str>) {
-        self.connection = TcpStream::connect(address.as_ref()).await.ok()
+impl Shell {
+    /// Sets whether the shell has keyboard focus, when unfocused keyboard input is left to other extensions
+    ///
+    /// Regardless of this setting, keyboard input is ignored while an imgui widget has keyboard focus
+    pub fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
     }
 
-    /// Returns the text brush and char device being edited
-    pub fn prepare_render_input(
-        &mut self,
-    ) -> (
-        Option<&mut GlyphBrush<DepthStencilState>>,
-        Option<&mut CharDevice>,
-        Option<&mut Theme<Style>>,
-    ) {
-        if let Some(editing) = self.editing {
-            if let Some(device) = self.char_devices.get_mut(&editing) {
-                (self.brush.as_mut(), Some(device), self.theme.as_mut())
-            } else {
-                (self.brush.as_mut(), None, self.theme.as_mut())
-            }
-        } else {
-            (None, None, self.theme.as_mut())
-        }
+    /// Returns true if keyboard input is currently handled by the shell
+    pub fn has_keyboard_focus(&self) -> bool {
+        self.focused && !self.imgui_wants_keyboard
     }
 
-    /// Returns devices to render output from a chardevice
-    pub fn prepare_render_output(
-        &mut self,
-        channel: u32,
-    ) -> (
-        Option<&mut GlyphBrush<DepthStencilState>>,
-        Option<&mut CharDevice>,
-        Option<&mut Theme<Style>>,
-    ) {
-        if let Some(device) = self.char_devices.get_mut(&channel) {
-            (self.brush.as_mut(), Some(device), self.theme.as_mut())
-        } else {
-            (self.brush.as_mut(), None, self.theme.as_mut())
+    /// Returns a new shell configured w/ settings, which are applied before the first frame
+    ///
+    /// Settings in a `shell` block in the app world are applied afterwards, and take precedence
+    pub fn new(settings: ShellSettings) -> Self {
+        Self::default().with_settings(settings)
+    }
+
+    /// Configures the shell w/ settings, which are applied before the first frame
+    pub fn with_settings(mut self, settings: ShellSettings) -> Self {
+        if let Some(address) = settings.address.as_ref() {
+            self.address = Some(address.clone());
         }
+        self.initial_settings = Some(settings);
+        self
     }
 
-    /// Returns true if the shell was taken.
-    pub fn add_device(&'_ mut self, entity: Entity) -> Option<ShellChannel> {
-        if let Some(tx) = self.byte_tx.clone() {
-            let channel = entity.id();
-            self.char_devices.insert(channel, CharDevice::default());
+    /// Sets the address shown in the connect ui
+    pub fn with_address(mut self, address: impl Into<String>) -> Self {
+        self.set_address(address);
+        self
+    }
 
-            event!(Level::DEBUG, "Adding channel for {}", entity.id());
-            Some(ShellChannel(Some(tx)))
-        } else {
-            None
-        }
+    /// Sets the address shown in the connect ui, the connection isn't opened until it's submitted
+    pub fn set_address(&mut self, address: impl Into<String>) {
+        self.address = Some(address.into());
     }
 
-    /// Renders the input section
-    pub fn render_input(&'_ mut self, config: &SurfaceConfiguration) {
-        let prompt_enabled = self.connection.is_some();
-        if let (Some(glyph_brush), Some(active), Some(theme)) = self.prepare_render_input() {
-            // Renders the buffer
-            glyph_brush.queue(Section {
-                screen_position: (90.0, 180.0),
-                bounds: (config.width as f32 / 2.0, config.height as f32),
-                // TODO: need to figure out a way to make this generic, but for now this is good enough
-                text: theme.render::<Runmd>(
-                    active.output().as_ref(), 
-                    prompt_enabled
-                ),
-                layout: Layout::Wrap {
-                    line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
-                    h_align: HorizontalAlign::Left,
-                    v_align: VerticalAlign::Top,
-                },
-            });
+    /// Returns the address shown in the connect ui, if one was set
+    pub fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+
+    /// Connects to a tcp stream, a `udp://` peer, a `unix://` socket or `pipe://` named pipe, w/ the `websocket` feature
+    /// to a `ws://` websocket, or w/ the `ssh` feature, to an `ssh://user@host` address
+    ///
+    /// The address is resolved asynchronously, and each candidate address is tried until one accepts
+    pub async fn connect_to(&mut self, address: impl AsRef<str>) {
+        self.transcript = None;
+        self.connecting = None;
+        self.reconnect.cancel();
+        let connected = transport::open(address.as_ref(), self.connection_output()).await;
+        self.connected(address.as_ref(), connected);
+    }
+
+    /// Starts connecting to address on runtime, the connection is used once it's established
+    ///
+    /// Unlike connect_to, this doesn't block the caller, i.e. the UI while a slow host resolves
+    pub fn start_connect(&mut self, address: impl Into<String>, runtime: &tokio::runtime::Runtime) {
+        self.transcript = None;
+        self.reconnect.cancel();
+        self.begin_connect(address.into(), runtime);
+    }
+
+    /// Connects to address in the background, w/o resetting retries or the transcript
+    fn begin_connect(&mut self, address: String, runtime: &tokio::runtime::Runtime) {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        let target = address.clone();
+        let output = self.connection_output();
+        runtime.spawn(async move {
+            let _ = tx.send(transport::open(&target, output).await);
+        });
+
+        self.connection_error = None;
+        self.connecting = Some((address, rx));
+    }
+
+    /// Returns the channel output from the connection is written to, the session channel
+    fn connection_output(&mut self) -> ShellChannel {
+        self.char_devices.entry(BuiltinChannel::Session.id()).or_default();
+        ShellChannel(self.byte_tx.clone(), BuiltinChannel::Session.id())
+    }
+
+    /// Replaces the policy for reconnecting after the connection closes unexpectedly
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect.set_policy(policy);
+        self
+    }
+
+    /// Sets the policy for reconnecting after the connection closes unexpectedly
+    pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+        self.reconnect.set_policy(policy);
+    }
+
+    /// Replaces the framing of lines sent to the connection
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Sets the framing of lines sent to the connection
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
+    }
+
+    /// Returns the framing of lines sent to the connection
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Checks lines w/ policy before they're sent to the connection
+    pub fn with_line_policy(mut self, policy: impl LinePolicy + 'static) -> Self {
+        self.set_line_policy(policy);
+        self
+    }
+
+    /// Sets the policy that checks lines before they're sent to the connection
+    pub fn set_line_policy(&mut self, policy: impl LinePolicy + 'static) {
+        self.line_policy = Some(Box::new(policy));
+    }
+
+    /// Returns the error of the last line rejected by the line policy, if the next line hasn't been sent yet
+    pub fn line_error(&self) -> Option<&str> {
+        self.line_error.as_deref()
+    }
+
+    /// Applies the line policy to a line taken from the prompt, returns the line to send
+    ///
+    /// If the line is rejected, it's put back in the prompt so it can be fixed, and the error is shown under it
+    fn apply_line_policy(&mut self, line: String) -> Option<String> {
+        let policy = match self.line_policy.as_ref() {
+            Some(policy) => policy,
+            None => return Some(line),
+        };
+
+        let typed = line.strip_suffix('\r').unwrap_or(&line);
+        match policy.validate(typed) {
+            Ok(validated) => {
+                self.line_error = None;
+                Some(format!("{validated}\r"))
+            }
+            Err(err) => {
+                event!(Level::DEBUG, "Line was rejected, {err}");
+                if let Some(prompt) = self.char_devices.get_mut(&0) {
+                    let rest = prompt.take_buffer();
+                    prompt.write_bytes(typed.as_bytes());
+                    prompt.write_bytes(rest.as_bytes());
+                }
+                self.line_error = Some(err);
+                None
+            }
+        }
+    }
+
+    /// Returns the state of the connection
+    pub fn connection_state(&self) -> ConnectionState {
+        match (self.connection.is_some(), self.reconnect.retrying(), self.connecting.is_some()) {
+            (true, ..) => ConnectionState::Connected,
+            (false, Some(attempt), _) => ConnectionState::Retrying { attempt },
+            (false, None, true) => ConnectionState::Connecting,
+            (false, None, false) => ConnectionState::Closed,
+        }
+    }
+
+    /// Stops reconnecting, including a retry that is in progress
+    pub fn cancel_reconnect(&mut self) {
+        if self.reconnect.retrying().is_some() {
+            self.reconnect.cancel();
+            self.connecting = None;
+            self.editing = None;
+        }
+    }
+
+    /// Applies actions requested by runmd blocks and plugins
+    fn run_actions(&mut self, app_world: &lifec::World) {
+        for action in std::mem::take(&mut self.actions) {
+            event!(Level::DEBUG, "Running {:?}", action);
+            match action {
+                ShellAction::ClearChannel(channel) => {
+                    if let Some(device) = self.char_devices.get_mut(&channel) {
+                        device.take_buffer();
+                    }
+                }
+                ShellAction::Connect(address) => {
+                    self.editing = Some(0);
+
+                    let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+                    self.start_connect(address, &tokio_runtime);
+                }
+                ShellAction::SetGrammer { channel, grammer } if known_grammer(&grammer) => {
+                    match self.channel_info_mut(channel) {
+                        Some(info) => info.grammer = Some(grammer),
+                        None => event!(Level::WARN, "Could not set grammer, channel {channel} doesn't exist"),
+                    }
+                }
+                ShellAction::SetGrammer { grammer, .. } => {
+                    event!(Level::WARN, "Could not set grammer, {grammer} is not a known grammer");
+                }
+                ShellAction::LoadFile(path) => {
+                    let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+                    let _guard = tokio_runtime.enter();
+
+                    if let Err(err) = tokio_runtime.block_on(self.load_file(&path)) {
+                        event!(Level::ERROR, "Could not load {path}, {err}");
+                    }
+                }
+                ShellAction::SetColor { token, color } => {
+                    if let Some(theme) = self.theme.as_mut() {
+                        match theme.style().named_color(&color) {
+                            Some(linear) => theme.set_color(Token::from_name(&token), linear),
+                            None => event!(Level::WARN, "Could not set color of {token}, {color} is not a color name"),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Starts the next retry of the last connection, once it's due
+    fn poll_reconnect(&mut self, app_world: &lifec::World) {
+        if self.connecting.is_some() || self.connection.is_some() {
+            return;
+        }
+
+        if let Some(address) = self.reconnect.due(self.clock.now()) {
+            event!(Level::DEBUG, "Reconnecting to {address}, retry {:?}", self.reconnect.retrying());
+            let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+            self.begin_connect(address, &tokio_runtime);
+        }
+    }
+
+    /// Returns the address being connected to in the background, if any
+    pub fn connecting(&self) -> Option<&str> {
+        self.connecting.as_ref().map(|(address, _)| address.as_str())
+    }
+
+    /// Returns the error of the last connection attempt, cleared once a connection is established
+    pub fn connection_error(&self) -> Option<&str> {
+        self.connection_error.as_deref()
+    }
+
+    /// Uses the connection once a background connection attempt completes
+    fn poll_connecting(&mut self) {
+        if let Some((address, mut rx)) = self.connecting.take() {
+            match rx.try_recv() {
+                Ok(connected) => self.connected(&address, connected),
+                Err(tokio::sync::oneshot::error::TryRecvError::Empty) => self.connecting = Some((address, rx)),
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                    self.connection_error = Some(format!("{address}, connection attempt was cancelled"));
+                }
+            }
+        }
+    }
+
+    /// Uses the result of connecting to address as the current connection, errors are shown in the status line
+    ///
+    /// Output from the connection is written to the session channel
+    fn connected(&mut self, address: &str, connected: std::io::Result<Box<dyn Transport>>) {
+        match connected {
+            Ok(connection) => {
+                self.reconnect.connected(Some(address));
+                self.connection_error = None;
+                self.connection = Some(connection);
+                self.window_size = None;
+                self.session = Some(SessionMetrics::start(self.clock.now()));
+
+                if let Some(connection) = self.connection.as_ref() {
+                    self.hooks.connection_changed(Some(&connection.peer()));
+                }
+            }
+            Err(err) => {
+                event!(Level::ERROR, "Could not connect to {address}, {err}");
+                self.connection = None;
+                self.session = None;
+                self.connection_error = Some(format!("{address}, {err}"));
+
+                if self.reconnect.retrying().is_some() && !self.reconnect.failed(self.clock.now()) {
+                    event!(Level::WARN, "Gave up reconnecting to {address}");
+                    self.editing = None;
+                    self.transcript = None;
+                }
+            }
+        }
+    }
+
+    /// Uses transport as the current connection
+    pub fn connect_with(&mut self, transport: impl Transport + 'static) {
+        self.transcript = None;
+        self.reconnect.connected(None);
+        self.hooks.connection_changed(Some(&transport.peer()));
+        self.connection = Some(Box::new(transport));
+        self.window_size = None;
+        self.session = Some(SessionMetrics::start(self.clock.now()));
+    }
+
+    /// Binds a udp socket to local, and sends each line entered in the shell buffer to peer as a datagram
+    ///
+    /// Datagrams received on the socket are printed to the session channel, prefixed w/ their source address
+    pub async fn bind_datagram(&mut self, local: impl AsRef<str>, peer: impl AsRef<str>) -> std::io::Result<()> {
+        let output = self.connection_output();
+        let datagram = Datagram::bind(Some(local.as_ref()), peer.as_ref(), output).await?;
+        self.connect_with(datagram);
+        Ok(())
+    }
+
+    /// Reports the size of the window in columns and rows to the connection when it changes, i.e. for telnet NAWS
+    fn report_window_size(&mut self, app_world: &lifec::World) {
+        let metrics = self.glyph_metrics;
+        if metrics.advance <= 0.0 || metrics.line_height <= 0.0 {
+            return;
+        }
+
+        let (width, height) = self.surface_size;
+        let size = ((width / metrics.advance) as u16, (height / metrics.line_height) as u16);
+        if self.window_size == Some(size) {
+            return;
+        }
+
+        if let Some(mut connection) = self.connection.take() {
+            let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+            let _guard = tokio_runtime.enter();
+
+            match tokio_runtime.block_on(connection.resize(size.0, size.1)) {
+                Ok(_) => self.window_size = Some(size),
+                Err(err) => event!(Level::DEBUG, "Could not report window size to {}, {err}", connection.peer()),
+            }
+            self.connection = Some(connection);
+        }
+    }
+
+    /// Writes message to the connection, if the write fails the connection is closed
+    ///
+    /// If command is true, the message is counted as a command sent in the session's metrics
+    fn write_to_connection(&mut self, app_world: &lifec::World, message: Vec<u8>, command: bool) {
+        if let Some(mut connection) = self.connection.take() {
+            let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+            let _guard = tokio_runtime.enter();
+
+            let mut session = self
+                .session
+                .take()
+                .unwrap_or_else(|| SessionMetrics::start(self.clock.now()));
+
+            let (connection, session, closed) = tokio_runtime.block_on(async move {
+                match connection.write_all(&message).await {
+                    Ok(bytes) => {
+                        event!(Level::TRACE, "Wrote {bytes}");
+                        if command {
+                            session.record_command(bytes);
+                        } else {
+                            session.record_bytes(bytes);
+                        }
+
+                        (Some(connection), session, None)
+                    }
+                    Err(ref e) if e.kind() == tokio::io::ErrorKind::WouldBlock => {
+                        event!(Level::WARN, "Connection is not ready.");
+                        session.record_error();
+                        (Some(connection), session, None)
+                    }
+                    Err(err) => {
+                        // Not actually hazardous but useful for posterity
+                        event!(Level::WARN, "Connection to {} closed", connection.peer());
+                        event!(Level::DEBUG, "Error on connection close: {err}");
+                        session.record_error();
+                        (None, session, Some(connection.peer()))
+                    }
+                }
+            });
+
+            self.connection = connection;
+            match closed {
+                Some(peer) => {
+                    self.hooks.connection_changed(None);
+                    self.print_session_summary(peer, session);
+
+                    // The editor and transcript are kept while reconnecting, so lines can still be typed
+                    if !self.reconnect.closed(self.clock.now()) {
+                        self.editing = None;
+                        self.transcript = None;
+                    }
+                }
+                None => self.session = Some(session),
+            }
+        }
+    }
+
+    /// Connects to address as profile, backing up a transcript of the session if a data directory was set
+    pub async fn connect_to_profile(&mut self, profile: impl AsRef<str>, address: impl AsRef<str>) {
+        self.connect_to(address).await;
+
+        let peer = match self.connection.as_ref() {
+            Some(connection) => connection.peer(),
+            None => return,
+        };
+
+        if let Some(data_dir) = self.data_dir.as_ref() {
+            self.transcript = TranscriptBackup::start(data_dir, profile.as_ref());
+            if let Some(transcript) = self.transcript.as_mut() {
+                event!(Level::DEBUG, "Backing up transcript to {:?}", transcript.file());
+                transcript.append(format!("# {} {peer}", profile.as_ref()));
+            }
+        }
+    }
+
+    /// Connects to another shell through the relay at address, bridging channel w/ the peer
+    ///
+    /// Lines entered in the shell buffer are sent to the peer, and lines the peer sends are written to channel
+    #[cfg(feature = "relay")]
+    pub async fn connect_to_relay(
+        &mut self,
+        address: impl AsRef<str>,
+        session: impl AsRef<str>,
+        code: impl AsRef<str>,
+        channel: u32,
+    ) -> std::io::Result<()> {
+        let tx = self.byte_tx.clone().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotConnected, "Shell has not been initialized")
+        })?;
+
+        let relay = Relay::connect(address, session, code, channel, tx).await?;
+        self.char_devices.entry(channel).or_default();
+        self.connect_with(relay);
+        Ok(())
+    }
+
+    /// Starts pairing through the relay at address on runtime, the peer is connected once it has joined the session
+    ///
+    /// Unlike connect_to_relay, this doesn't block the caller, i.e. the UI while waiting for the peer
+    #[cfg(feature = "relay")]
+    pub fn start_relay(
+        &mut self,
+        address: impl Into<String>,
+        session: impl Into<String>,
+        code: impl Into<String>,
+        channel: u32,
+        runtime: &tokio::runtime::Runtime,
+    ) {
+        let tx = match self.byte_tx.clone() {
+            Some(tx) => tx,
+            None => {
+                event!(Level::ERROR, "Could not pair through relay, shell has not been initialized");
+                return;
+            }
+        };
+
+        let (result_tx, rx) = tokio::sync::oneshot::channel();
+        let (address, session, code) = (address.into(), session.into(), code.into());
+        runtime.spawn(async move {
+            let _ = result_tx.send(Relay::connect(address, session, code, channel, tx).await);
+        });
+        self.relay_connecting = Some((channel, rx));
+    }
+
+    /// Uses the relay once pairing has finished, and switches to its channel
+    #[cfg(feature = "relay")]
+    fn poll_relay(&mut self) {
+        if let Some((channel, mut rx)) = self.relay_connecting.take() {
+            match rx.try_recv() {
+                Ok(Ok(relay)) => {
+                    self.char_devices.entry(channel).or_default();
+                    self.connect_with(relay);
+                    self.channel = channel as i32;
+                }
+                Ok(Err(err)) => {
+                    event!(Level::ERROR, "Could not pair through relay, {err}");
+                    self.connection_error = Some(err.to_string());
+                }
+                Err(tokio::sync::oneshot::error::TryRecvError::Empty) => self.relay_connecting = Some((channel, rx)),
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                    event!(Level::WARN, "Pairing through relay was cancelled");
+                }
+            }
+        }
+    }
+
+    /// Shows the fields for pairing w/ another shell through the relay at the current address
+    #[cfg(feature = "relay")]
+    fn relay_ui(&mut self, app_world: &lifec::World, ui: &imgui::Ui) {
+        let (session, code) = &mut self.relay_pairing;
+        ui.input_text(self.locale.get("relay_session"), session).build();
+        ui.input_text(self.locale.get("pairing_code"), code)
+            .password(true)
+            .build();
+
+        ui.same_line();
+        if ui.button(self.locale.get("generate_code")) {
+            match Relay::generate_code() {
+                Ok(generated) => {
+                    ui.set_clipboard_text(&generated);
+                    *code = generated;
+                }
+                Err(err) => event!(Level::ERROR, "Could not generate a pairing code, {err}"),
+            }
+        }
+
+        if self.relay_connecting.is_some() {
+            ui.text_disabled(self.locale.get("waiting_for_peer"));
+        } else if ui.button(self.locale.get("pair_through_relay")) {
+            if let Some(address) = self.address.clone() {
+                let (session, code) = self.relay_pairing.clone();
+                self.editing = Some(0);
+
+                let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+                self.start_relay(address, session, code, BuiltinChannel::Relay.id(), &tokio_runtime);
+            }
+        }
+    }
+
+    /// Returns metrics for the current connection
+    pub fn session(&self) -> Option<&SessionMetrics> {
+        self.session.as_ref()
+    }
+
+    /// Prints a summary of the session that closed into the session channel
+    fn print_session_summary(&mut self, peer: String, session: SessionMetrics) {
+        let summary = session.summary(&self.locale, peer, self.clock.now());
+        event!(Level::INFO, "{summary}");
+
+        if let Some(transcript) = self.transcript.as_mut() {
+            transcript.append(&summary);
+        }
+
+        self.char_devices
+            .entry(BuiltinChannel::Session.id())
+            .or_default()
+            .append(summary);
+        self.channel = BuiltinChannel::Session.id() as i32;
+    }
+
+    /// Calls hook when a channel is added, w/ its metadata if it belongs to an entity
+    pub fn on_channel_created(
+        mut self,
+        hook: impl FnMut(u32, Option<&ChannelInfo>) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.channel_created.push(Box::new(hook));
+        self
+    }
+
+    /// Calls hook w/ each line submitted from the shell buffer, before it's sent to the connection or routed to a plugin
+    pub fn on_line_submitted(mut self, hook: impl FnMut(&str) + Send + Sync + 'static) -> Self {
+        self.hooks.line_submitted.push(Box::new(hook));
+        self
+    }
+
+    /// Calls hook w/ the peer when a connection is opened, and w/ None when it closes
+    pub fn on_connection_changed(mut self, hook: impl FnMut(Option<&str>) + Send + Sync + 'static) -> Self {
+        self.hooks.connection_changed.push(Box::new(hook));
+        self
+    }
+
+    /// Calls hook before each frame is rendered
+    pub fn on_before_render(
+        mut self,
+        hook: impl FnMut(&SurfaceConfiguration) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.before_render.push(Box::new(hook));
+        self
+    }
+
+    /// Uses style for the colors of the theme, instead of the DefaultTheme
+    pub fn with_color_theme(mut self, style: impl ColorTheme + 'static) -> Self {
+        self.background = style.background();
+        self.theme = Some(default_theme(Arc::new(style)));
+        self
+    }
+
+    /// Loads the theme from a runmd file at path, i.e. one saved w/ Theme::save_to, over the default theme
+    ///
+    /// If the file can't be loaded, the default theme is used
+    pub fn with_theme_file(mut self, path: impl AsRef<Path>) -> Self {
+        match Theme::load_from(path.as_ref()) {
+            Ok(mut loaded) => {
+                let style = self.color_theme();
+                loaded.set_style(style.clone());
+
+                let mut theme = default_theme(style);
+                theme.apply(loaded);
+                if let Some(color) = theme.get_color(Token::Custom("background".to_string())) {
+                    self.background = *color;
+                }
+                self.theme = Some(theme);
+            }
+            Err(err) => {
+                event!(Level::ERROR, "Could not load theme from {:?}, {err}", path.as_ref());
+            }
+        }
+        self
+    }
+
+    /// Replaces the clock used for timers, i.e. with a fake clock in tests
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Replaces the policy for bytes that don't fit in the byte channel
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.backlog.set_policy(policy);
+        self
+    }
+
+    /// Sets the policy for bytes that don't fit in the byte channel
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.backlog.set_policy(policy);
+    }
+
+    /// Replaces the strategy for choosing which channels are drained first, when more bytes are received than fit in a frame
+    pub fn with_drain_policy(mut self, policy: DrainPolicy) -> Self {
+        self.drain_queue.set_policy(policy);
+        self
+    }
+
+    /// Sets the strategy for choosing which channels are drained first
+    pub fn set_drain_policy(&mut self, policy: DrainPolicy) {
+        self.drain_queue.set_policy(policy);
+    }
+
+    /// Returns the channels drained first, the editor, the active channel, and channels shown in a pane
+    fn focused_channels(&self) -> Vec<u32> {
+        let area = self.layout_area(self.surface_size.0, self.surface_size.1);
+        let mut focused = vec![0, self.channel as u32];
+        focused.extend(self.layout.panes(area).into_iter().filter_map(|(_, content)| match content {
+            PaneContent::Channel(channel) => Some(channel),
+            _ => None,
+        }));
+        focused
+    }
+
+    /// Returns the number of bytes dropped because the byte channel was full
+    pub fn dropped_bytes(&self) -> usize {
+        self.backlog.dropped()
+    }
+
+    /// Sends bytes to channel, bytes that don't fit are queued in the backlog
+    fn send_bytes(&mut self, channel: u32, bytes: &[u8]) {
+        if let Some(sender) = &self.byte_tx {
+            let dropped = self.backlog.dropped();
+            self.backlog.send(sender, channel, bytes, self.clock.now());
+
+            if self.backlog.dropped() > dropped {
+                event!(Level::WARN, "Byte channel is full, dropped {} bytes", self.backlog.dropped() - dropped);
+            }
+        }
+    }
+
+    /// Writes bytes to the char_device at channel, large writes are processed across several frames
+    pub fn write_to_channel(&mut self, channel: u32, bytes: impl Into<Vec<u8>>) {
+        self.pending_writes.push_back(PendingWrite::new(channel, bytes));
+    }
+
+    /// Returns the progress of the current bulk write, or of draining a burst received from the byte channel
+    pub fn pending_progress(&self) -> Option<usize> {
+        self.pending_writes
+            .front()
+            .map(|p| p.progress())
+            .or_else(|| self.drain_progress.progress())
+    }
+
+    /// Writes up to budget bytes from pending bulk writes
+    fn process_pending_writes(&mut self, mut budget: usize) {
+        while budget > 0 {
+            let pending = match self.pending_writes.front_mut() {
+                Some(pending) => pending,
+                None => break,
+            };
+
+            let channel = pending.channel();
+            let chunk = pending.next_chunk(budget);
+            budget -= chunk.len();
+
+            if self.muted.contains(&channel) {
+                if pending.is_done() {
+                    self.pending_writes.pop_front();
+                }
+                continue;
+            }
+
+            if let Some(log) = self.channel_logs.get(&channel) {
+                log.append(chunk);
+            }
+
+            match self.char_devices.get_mut(&channel) {
+                Some(char_device) => {
+                    if channel != 0 {
+                        char_device.set_undoable(false);
+                        if char_device.max_lines() != self.max_channel_lines {
+                            char_device.set_max_lines(self.max_channel_lines);
+                        }
+                    }
+                    char_device.write_bytes(chunk)
+                }
+                None => {
+                    event!(Level::WARN, "Channel {channel} does not exist, dropping pending write");
+                    self.pending_writes.pop_front();
+                    continue;
+                }
+            }
+
+            if pending.is_done() {
+                self.pending_writes.pop_front();
+            }
+        }
+    }
+
+    /// Enables spell checking of free text w/ a dictionary provided by the host
+    pub fn with_dictionary(mut self, dictionary: impl Dictionary + 'static) -> Self {
+        self.set_dictionary(dictionary);
+        self
+    }
+
+    /// Sets the dictionary used to spell check free text
+    pub fn set_dictionary(&mut self, dictionary: impl Dictionary + 'static) {
+        self.spell_checker = Some(SpellChecker::new(dictionary));
+        self.misspelled = None;
+        self.suggested = None;
+    }
+
+    /// Replaces the span of a diagnostic in the buffer being edited with a suggestion
+    pub fn apply_fix(&mut self, diagnostic: &Diagnostic, suggestion: &str) {
+        if let Some(device) = self.editable_device() {
+            let fixed = diagnostic.apply(device.output().as_ref(), suggestion);
+            device.set_buffer(fixed);
+        }
+    }
+
+    /// Sets how focus requests from plugins are handled
+    pub fn set_focus_policy(&mut self, policy: FocusPolicy) {
+        self.focus_policy = policy;
+    }
+
+    /// Returns focus requests that are waiting for the user to visit the channel
+    pub fn notifications(&self) -> &[FocusRequest] {
+        &self.notifications
+    }
+
+    /// Handles a request from a plugin to surface its channel, according to the focus policy
+    pub fn request_focus(&mut self, request: FocusRequest) {
+        event!(Level::DEBUG, "Channel {} requested focus, {:?}", request.channel, request.reason);
+        match self.focus_policy {
+            FocusPolicy::Follow => {
+                self.channel = request.channel as i32;
+            }
+            FocusPolicy::Notify => {
+                self.notifications.retain(|n| n.channel != request.channel);
+                self.notifications.push(request);
+            }
+            FocusPolicy::Ignore => {}
+        }
+    }
+
+    /// Undoes the most recent edit in the char_device being edited
+    pub fn undo(&mut self) {
+        if let Some(device) = self.editable_device() {
+            device.undo();
+        }
+    }
+
+    /// Redoes the most recently undone edit in the char_device being edited
+    pub fn redo(&mut self) {
+        if let Some(device) = self.editable_device() {
+            device.redo();
+        }
+    }
+
+    /// Deletes from the cursor to the end of the line in the char_device being edited
+    pub fn kill_line(&mut self) {
+        if let Some(device) = self.editable_device() {
+            device.kill_line();
+        }
+    }
+
+    /// Duplicates the current line in the char_device being edited
+    pub fn duplicate_line(&mut self) {
+        if let Some(device) = self.editable_device() {
+            device.duplicate_line();
+        }
+    }
+
+    /// Moves the current line in the char_device being edited down, or up if up is true
+    pub fn move_line(&mut self, up: bool) {
+        if let Some(device) = self.editable_device() {
+            if up {
+                device.move_line_up();
+            } else {
+                device.move_line_down();
+            }
+        }
+    }
+
+    /// Moves the cursor in the char_device being edited to the next search match, or the previous if reverse is true
+    pub fn jump_to_match(&mut self, reverse: bool) {
+        if let Some(device) = self.editing.and_then(|e| self.char_devices.get_mut(&e)) {
+            self.search.update(device.output().as_ref());
+
+            let cursor = device.cursor();
+            let next = if reverse {
+                self.search.prev(cursor)
+            } else {
+                self.search.next(cursor)
+            };
+
+            if let Some(offset) = next {
+                device.set_cursor(offset);
+            }
+        }
+    }
+
+    /// Zooms the editor to fit the runmd block under the cursor, or restores the previous view if already zoomed
+    pub fn toggle_zoom(&mut self) {
+        if self.zoom.take().is_some() {
+            return;
+        }
+
+        let (width, height) = self.surface_size;
+        let rect = match self.layout.find(self.layout_area(width, height), PaneContent::Editor) {
+            Some(rect) => pane_content(rect, self.title_height()),
+            None => return,
+        };
+
+        if let Some(device) = self.editing.and_then(|e| self.char_devices.get(&e)) {
+            self.zoom = ZoomView::fit(
+                device.output().as_ref(),
+                device.cursor(),
+                ((rect.width - self.scaled(EDITOR_GUTTER)).max(0.0), rect.height),
+                &self.glyph_metrics,
+            );
+
+            if self.zoom.is_none() {
+                event!(Level::DEBUG, "Cursor is not inside of a block, nothing to zoom to");
+            }
+        }
+    }
+
+    /// Sends the runmd block under the editor's cursor to the app world, see dispatch_block
+    ///
+    /// The results are shown after the lines of the block until the editor's buffer changes. Returns false if the cursor
+    /// isn't inside of a block, or the block couldn't be dispatched
+    pub fn send_current_block(&mut self, app_world: &lifec::World) -> bool {
+        let editing = match self.editing {
+            Some(editing) => editing,
+            None => return false,
+        };
+
+        let block = self.char_devices.get(&editing).and_then(|device| {
+            let source = device.output();
+            block_at(source.as_ref(), device.cursor()).map(|span| {
+                let line = device.position_of(span.start).0;
+                (line, source.as_ref()[span].to_string())
+            })
+        });
+
+        let (line, block) = match block {
+            Some(block) => block,
+            None => {
+                event!(Level::DEBUG, "Cursor is not inside of a block, nothing to send");
+                return false;
+            }
+        };
+
+        let annotations = self
+            .dispatch_block(&block, app_world)
+            .into_iter()
+            .map(|a| a.offset(line))
+            .collect::<Vec<_>>();
+        let dispatched = !annotations.iter().any(|a| a.error);
+
+        let revision = self.char_devices.get(&editing).map(|d| d.revision()).unwrap_or_default();
+        self.annotations = Some((editing, revision, annotations));
+        dispatched
+    }
+
+    /// Returns the results of the last block sent to the app world, if the editor's buffer hasn't changed since
+    pub fn current_annotations(&self) -> &[Annotation] {
+        match self.annotations.as_ref() {
+            Some((channel, revision, annotations))
+                if self.editing == Some(*channel)
+                    && self.char_devices.get(channel).map(|d| d.revision()) == Some(*revision) =>
+            {
+                annotations
+            }
+            _ => &[],
+        }
+    }
+
+    /// Returns the bracket pair adjacent to the cursor in the editor, pairs are reparsed only when the buffer changes
+    fn matching_brackets(&mut self, prompt_enabled: bool) -> Option<(Range<usize>, Range<usize>)> {
+        let editing = self.editing?;
+        let device = self.char_devices.get(&editing)?;
+        let theme = self.theme.as_ref()?;
+
+        let revision = device.revision();
+        let pairs = match self.bracket_pairs.take() {
+            Some((channel, parsed, prompt, pairs)) if channel == editing && parsed == revision && prompt == prompt_enabled => {
+                pairs
+            }
+            // When connected, the editor is a prompt for the remote, so it's parsed as shell commands
+            _ if prompt_enabled => theme.bracket_pairs::<Sh>(device.output().as_ref()),
+            _ => theme.bracket_pairs::<Runmd>(device.output().as_ref()),
+        };
+
+        let matched = matching_pair(&pairs, device.cursor());
+        self.bracket_pairs = Some((editing, revision, prompt_enabled, pairs));
+        matched
+    }
+
+    /// Returns the misspelled words of the editor's buffer, the buffer is only checked again after it changes
+    ///
+    /// Only the word under the cursor has suggestions, since looking them up scans the whole dictionary
+    fn misspelled_words(&mut self) -> Vec<Diagnostic> {
+        let (spell_checker, editing) = match (self.spell_checker.clone(), self.editing) {
+            (Some(spell_checker), Some(editing)) => (spell_checker, editing),
+            _ => return vec![],
+        };
+        let device = match self.char_devices.get(&editing) {
+            Some(device) => device,
+            None => return vec![],
+        };
+
+        let revision = device.revision();
+        let misspelled = match self.misspelled.take() {
+            Some((channel, checked, misspelled)) if channel == editing && checked == revision => misspelled,
+            _ => spell_checker.check(device.output().as_ref()),
+        };
+
+        let mut diagnostics = misspelled.clone();
+        let (output, cursor) = (device.output(), device.cursor());
+        if let Some(diagnostic) = diagnostics
+            .iter_mut()
+            .find(|d| d.span.start <= cursor && cursor <= d.span.end)
+        {
+            let word = &output.as_ref()[diagnostic.span.clone()];
+            let suggestions = match self.suggested.take() {
+                Some((suggested, suggestions)) if suggested == word => suggestions,
+                _ => spell_checker.suggest(word),
+            };
+            diagnostic.suggestions = suggestions.clone();
+            self.suggested = Some((word.to_string(), suggestions));
+        }
+
+        self.misspelled = Some((editing, revision, misspelled));
+        diagnostics
+    }
+
+    /// Parses a runmd block and dispatches it into the app world, returns an annotation for each line of the block w/ a result
+    ///
+    /// Attributes added by the block are applied to each thunk context w/ the block's name, if there aren't any an
+    /// entity is created for the block. Entities that have a channel are then sent the block's symbol as a `shell_event`
+    pub fn dispatch_block(&mut self, block: &str, app_world: &lifec::World) -> Vec<Annotation> {
+        let address = match BlockAddress::parse(block) {
+            Some(address) => address,
+            None => {
+                event!(Level::WARN, "Block does not have a name and symbol, nothing to dispatch");
+                return vec![Annotation::error(0, self.locale.get("block_without_address"))];
+            }
+        };
+
+        let diagnostics = Runmd::diagnostics(block);
+        if !diagnostics.is_empty() {
+            event!(Level::WARN, "Could not dispatch block {address}, it has {} errors", diagnostics.len());
+            return diagnostics
+                .into_iter()
+                .map(|d| Annotation::error(d.line, d.message))
+                .collect();
+        }
+
+        let parsed = Runmd::parse(block);
+        self.world_revision += 1;
+        let entities = app_world.entities();
+        let channels = app_world.read_component::<ShellChannel>();
+        let mut contexts = app_world.write_component::<ThunkContext>();
+
+        let mut updated = vec![];
+        for (entity, tc) in (&entities, &mut contexts).join() {
+            if tc.block.block_name != address.name {
+                continue;
+            }
+
+            for attribute in parsed.as_ref().iter_attributes() {
+                tc.as_mut().with(attribute.name(), attribute.value().clone());
+            }
+            updated.push(entity);
+        }
+
+        // Each attribute line is annotated w/ the value that was added
+        let mut annotations = block
+            .split(|c| c == '\r' || c == '\n')
+            .enumerate()
+            .filter_map(|(line, text)| Some((line, attribute_name(text)?)))
+            .filter_map(|(line, name)| {
+                let attribute = parsed.as_ref().iter_attributes().find(|a| a.name() == name)?;
+                Some(Annotation::new(line, format!("= {:?}", attribute.value())))
+            })
+            .collect::<Vec<_>>();
+
+        if updated.is_empty() {
+            let entity = entities.create();
+            let mut tc = parsed;
+            tc.block.block_name = address.name.clone();
+            tc.block.block_symbol = address.symbol.clone();
+
+            if let Err(err) = contexts.insert(entity, tc) {
+                event!(Level::ERROR, "Could not create an entity for block {address}, {err}");
+                return vec![Annotation::error(0, err.to_string())];
+            }
+            event!(Level::DEBUG, "Created entity {} for block {address}", entity.id());
+            annotations.insert(0, Annotation::new(0, format!("{} {}", self.locale.get("created_entity"), entity.id())));
+            return annotations;
+        }
+
+        let updated_count = updated.len();
+        for entity in updated.into_iter().filter(|e| channels.contains(*e)) {
+            self.trigger_events.push((entity.id(), address.symbol.clone()));
+        }
+        event!(Level::DEBUG, "Dispatched block {address}");
+        annotations.insert(0, Annotation::new(0, format!("{} {updated_count}", self.locale.get("updated_entities"))));
+        annotations
+    }
+
+    /// Returns the registry of commands, snippets, and completions
+    pub fn help(&self) -> &HelpRegistry {
+        &self.help
+    }
+
+    /// Returns the registry of commands, snippets, and completions, for registering items
+    pub fn help_mut(&mut self) -> &mut HelpRegistry {
+        &mut self.help
+    }
+
+    /// Opens the palette, or closes it if it's already open
+    pub fn toggle_palette(&mut self) {
+        self.palette = match self.palette.take() {
+            Some(_) => None,
+            None => Some(String::new()),
+        };
+    }
+
+    /// Opens the search across every channel, or closes it if it's already open
+    pub fn toggle_global_search(&mut self) {
+        self.global_search = match self.global_search.take() {
+            Some(_) => None,
+            None => Some(String::new()),
+        };
+    }
+
+    /// Searches the buffer of every channel for query, prints the results to the search channel, and switches to it
+    ///
+    /// Returns the number of matching lines, see goto_search_result
+    pub fn search_all_channels(&mut self, query: impl AsRef<str>) -> usize {
+        let results = search_buffers(
+            self.char_devices
+                .iter()
+                .filter(|(channel, _)| **channel != BuiltinChannel::Search.id())
+                .map(|(channel, device)| (*channel, device.output())),
+            query.as_ref(),
+        );
+        self.search_results = results;
+
+        let lines = self
+            .search_results
+            .iter()
+            .map(|r| format!("{}:{}  {}", self.channel_title(r.channel), r.line + 1, r.snippet))
+            .collect::<Vec<_>>();
+
+        let device = self.char_devices.entry(BuiltinChannel::Search.id()).or_default();
+        device.set_read_only(true);
+        device.set_text(lines.join("\r"));
+        self.channel = BuiltinChannel::Search.id() as i32;
+        self.search_results.len()
+    }
+
+    /// Switches the output view to the channel of a search result, scrolled to the matching line
+    pub fn goto_search_result(&mut self, index: usize) {
+        let (channel, line) = match self.search_results.get(index) {
+            Some(result) => (result.channel, result.line),
+            None => return,
+        };
+
+        match self.char_devices.get_mut(&channel) {
+            Some(device) => {
+                device.set_scroll(line);
+                self.channel = channel as i32;
+                self.sync_scroll(channel);
+            }
+            None => event!(Level::DEBUG, "Channel {channel} no longer exists"),
+        }
+    }
+
+    /// Shows the search across every channel, w/ a row for each result
+    fn global_search_ui(&mut self, ui: &imgui::Ui) {
+        let mut query = match self.global_search.take() {
+            Some(query) => query,
+            None => return,
+        };
+
+        let mut opened = true;
+        let mut submitted = false;
+        let mut picked = None;
+        imgui::Window::new(self.locale.get("global_search_window"))
+            .opened(&mut opened)
+            .always_auto_resize(true)
+            .build(ui, || {
+                if ui.is_window_appearing() {
+                    ui.set_keyboard_focus_here();
+                }
+
+                submitted = ui
+                    .input_text(self.locale.get("search"), &mut query)
+                    .enter_returns_true(true)
+                    .build();
+
+                for (idx, result) in self.search_results.iter().enumerate() {
+                    let label = format!(
+                        "{}:{}  {}##result{idx}",
+                        self.channel_title(result.channel),
+                        result.line + 1,
+                        result.snippet
+                    );
+                    if imgui::Selectable::new(label).build(ui) {
+                        picked = Some(idx);
+                    }
+                }
+            });
+
+        if submitted {
+            self.search_all_channels(&query);
+        }
+
+        if let Some(idx) = picked {
+            self.goto_search_result(idx);
+        }
+
+        if opened {
+            self.global_search = Some(query);
+        }
+    }
+
+    /// Opens the entity inspector, or closes it if it's already open
+    pub fn toggle_inspector(&mut self) {
+        self.inspector = match self.inspector.take() {
+            Some(_) => None,
+            None => Some(String::new()),
+        };
+    }
+
+    /// Sets whether output to channel is dropped, muting a channel leaves its current buffer as is
+    pub fn set_muted(&mut self, channel: u32, muted: bool) {
+        if muted {
+            self.muted.insert(channel);
+        } else {
+            self.muted.remove(&channel);
+        }
+    }
+
+    /// Returns true if output to channel is dropped
+    pub fn is_muted(&self, channel: u32) -> bool {
+        self.muted.contains(&channel)
+    }
+
+    /// Shows a side panel w/ every entity in the app world that has a channel, and the attributes of its thunk context
+    fn inspector_ui(&mut self, app_world: &lifec::World, ui: &imgui::Ui) {
+        let mut filter = match self.inspector.take() {
+            Some(filter) => filter,
+            None => return,
+        };
+
+        let entities = app_world.entities();
+        let channels = app_world.read_component::<ShellChannel>();
+        let contexts = app_world.read_component::<ThunkContext>();
+
+        let [width, height] = ui.io().display_size;
+        let panel_width = 360.0;
+        let menu_height = ui.frame_height();
+
+        let mut opened = true;
+        let mut focus = None;
+        let mut clear = None;
+        let mut mute = None;
+        imgui::Window::new(self.locale.get("inspector_window"))
+            .opened(&mut opened)
+            .position([width - panel_width, menu_height], imgui::Condition::FirstUseEver)
+            .size([panel_width, height - menu_height], imgui::Condition::FirstUseEver)
+            .build(ui, || {
+                ui.input_text(self.locale.get("filter_attributes"), &mut filter).build();
+                ui.separator();
+
+                for (entity, channel, tc) in (&entities, &channels, &contexts).join() {
+                    let channel = channel.1;
+                    let header = format!("{} ({})##entity{}", self.channel_title(channel), entity.id(), entity.id());
+                    if !imgui::CollapsingHeader::new(header).build(ui) {
+                        continue;
+                    }
+
+                    if ui.button(format!("{}##{channel}", self.locale.get("focus_channel"))) {
+                        focus = Some(channel);
+                    }
+                    ui.same_line();
+                    if ui.button(format!("{}##{channel}", self.locale.get("clear_channel"))) {
+                        clear = Some(channel);
+                    }
+                    ui.same_line();
+                    let mut muted = self.is_muted(channel);
+                    if ui.checkbox(format!("{}##{channel}", self.locale.get("mute_channel")), &mut muted) {
+                        mute = Some((channel, muted));
+                    }
+
+                    let attributes = tc
+                        .as_ref()
+                        .iter_attributes()
+                        .map(|a| (a.name().to_string(), format!("{:?}", a.value())));
+                    for (name, value) in attribute_rows(attributes, &filter) {
+                        ui.text_disabled(name);
+                        ui.same_line();
+                        ui.text(value);
+                    }
+                }
+            });
+
+        if let Some(channel) = focus {
+            self.channel = channel as i32;
+        }
+
+        if let Some(device) = clear.and_then(|channel| self.char_devices.get_mut(&channel)) {
+            device.take_buffer();
+        }
+
+        if let Some((channel, muted)) = mute {
+            self.set_muted(channel, muted);
+        }
+
+        if opened {
+            self.inspector = Some(filter);
+        }
+    }
+
+    /// Prints a cheat sheet of every registered item to the help channel, and switches to it
+    pub fn print_cheat_sheet(&mut self) {
+        let sheet = self.help.cheat_sheet(|key| self.locale.get(key).to_string());
+
+        let device = self.char_devices.entry(BuiltinChannel::Help.id()).or_default();
+        device.set_text(sheet.replace('\n', "\r"));
+        self.channel = BuiltinChannel::Help.id() as i32;
+    }
+
+    /// Returns the char_device being edited, or None if it's read-only
+    fn editable_device(&mut self) -> Option<&mut CharDevice> {
+        let device = self.editing.and_then(|e| self.char_devices.get_mut(&e))?;
+        if device.read_only() {
+            event!(Level::DEBUG, "Rejected input to a read-only channel");
+            return None;
+        }
+        Some(device)
+    }
+
+    /// Returns true if keyboard input to the char_device being edited is rejected
+    pub fn editing_read_only(&self) -> bool {
+        self.editing
+            .and_then(|e| self.char_devices.get(&e))
+            .map(|d| d.read_only())
+            .unwrap_or_default()
+    }
+
+    /// Inserts text at the cursor of the char_device being edited
+    fn insert_text(&mut self, text: &str) {
+        if let Some(device) = self.editable_device() {
+            device.write_bytes(text.as_bytes());
+        }
+    }
+
+    /// Extends the rectangular selection of the char_device being edited by lines and columns, starting it at the cursor
+    pub fn extend_block_selection(&mut self, lines: i32, columns: i32) {
+        if let Some(device) = self.editing.and_then(|e| self.char_devices.get_mut(&e)) {
+            let mut selection = device
+                .block_selection()
+                .unwrap_or(BlockSelection::new(device.position_of(device.cursor())));
+
+            let (line, column) = selection.head;
+            let max_line = device.line_count().saturating_sub(1) as i32;
+            selection.extend_to((
+                (line as i32 + lines).clamp(0, max_line) as usize,
+                (column as i32 + columns).max(0) as usize,
+            ));
+            device.set_block_selection(Some(selection));
+        }
+    }
+
+    /// Copies the rectangular selection of the char_device being edited, returns false if nothing is selected
+    pub fn copy_block(&mut self) -> bool {
+        let block = self
+            .editing
+            .and_then(|e| self.char_devices.get(&e))
+            .map(|d| d.copy_block())
+            .unwrap_or_default();
+
+        if block.is_empty() {
+            return false;
+        }
+
+        self.block_clipboard = block;
+        true
+    }
+
+    /// Pastes the last copied rectangular selection at the cursor, each row on its own line at the cursor's column
+    pub fn paste_block(&mut self) -> bool {
+        let block = self.block_clipboard.clone();
+        match self.editable_device() {
+            Some(device) if !block.is_empty() => {
+                device.paste_block(&block);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the rows of the last copied rectangular selection
+    pub fn block_clipboard(&self) -> &[String] {
+        &self.block_clipboard
+    }
+
+    /// Returns the named registers
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// Yanks the line the cursor is on into the register name, returns false if nothing was yanked
+    pub fn yank_line(&mut self, name: char) -> bool {
+        let line = self
+            .editing
+            .and_then(|e| self.char_devices.get(&e))
+            .and_then(|d| d.get_current_line());
+
+        match line {
+            Some(line) if self.registers.yank(name, line) => {
+                self.autosave_session();
+                true
+            }
+            _ => {
+                event!(Level::DEBUG, "Nothing was yanked into register {name}");
+                false
+            }
+        }
+    }
+
+    /// Inserts the contents of the register name at the cursor, returns false if the register is empty
+    pub fn paste_register(&mut self, name: char) -> bool {
+        match self.registers.get(name).map(str::to_string) {
+            Some(text) => {
+                self.insert_text(&text);
+                true
+            }
+            None => {
+                event!(Level::DEBUG, "Register {name} is empty");
+                false
+            }
+        }
+    }
+
+    /// Records the command of a submitted line for ranking completions
+    fn record_command(&mut self, line: &str) {
+        if let Some(command) = line.split_whitespace().next() {
+            self.frecency.record(command);
+            self.autosave_session();
+        }
+    }
+
+    /// Returns the current zoomed view of the editor, if any
+    pub fn zoom(&self) -> Option<&ZoomView> {
+        self.zoom.as_ref()
+    }
+
+    /// Sets the max number of chars rendered of a single line of channel output, longer lines are cut off w/ a marker
+    pub fn with_max_line_len(mut self, max_line_len: usize) -> Self {
+        self.max_line_len = max_line_len.max(1);
+        self
+    }
+
+    /// Sets the max number of lines kept by output channels, None lets output grow without bound
+    ///
+    /// Once a channel has more lines, its oldest lines are trimmed, and the number trimmed is shown in its title
+    pub fn with_max_channel_lines(mut self, max_lines: Option<usize>) -> Self {
+        self.set_max_channel_lines(max_lines);
+        self
+    }
+
+    /// Sets the max number of lines kept by output channels, and trims channels that are already past it
+    pub fn set_max_channel_lines(&mut self, max_lines: Option<usize>) {
+        self.max_channel_lines = max_lines;
+        for (_, device) in self.char_devices.iter_mut().filter(|(channel, _)| **channel != 0) {
+            device.set_max_lines(max_lines);
+        }
+    }
+
+    /// Sets whether line breaks typed in the editor are followed by the indentation the editor's grammer expects
+    pub fn with_auto_indent(mut self, auto_indent: bool) -> Self {
+        self.auto_indent = auto_indent;
+        self
+    }
+
+    /// Sets whether channels scrolled to the end keep their newest line visible as output is added
+    pub fn with_follow_output(mut self, follow_output: bool) -> Self {
+        self.follow_output = follow_output;
+        self
+    }
+
+    /// Sets whether viewports animate towards the line they're following, instead of jumping to it
+    pub fn with_smooth_scrolling(mut self, smooth_scrolling: bool) -> Self {
+        self.smooth_scrolling = smooth_scrolling;
+        self
+    }
+
+    /// Enables closing brackets, quotes, and fences as they're typed in the editor, w/ the pairs of auto_close
+    pub fn with_auto_close(mut self, auto_close: AutoClose) -> Self {
+        self.set_auto_close(Some(auto_close));
+        self
+    }
+
+    /// Sets the filter that closes brackets, quotes, and fences typed in the editor, None disables it
+    pub fn set_auto_close(&mut self, auto_close: Option<AutoClose>) {
+        self.auto_close = auto_close;
+    }
+
+    /// Shows a minimap of the buffer being edited on the right edge of the surface
+    pub fn with_minimap(mut self, minimap: Minimap) -> Self {
+        self.set_minimap(Some(minimap));
+        self
+    }
+
+    /// Sets the minimap shown on the right edge of the surface, None hides it
+    pub fn set_minimap(&mut self, minimap: Option<Minimap>) {
+        self.minimap = minimap;
+    }
+
+    /// Returns the minimap, if it's shown
+    pub fn minimap(&self) -> Option<Minimap> {
+        self.minimap
+    }
+
+    /// Returns the area of the surface that is split between panes
+    fn layout_area(&self, width: f32, height: f32) -> Rect {
+        let area = surface_area(width, height, self.scale_factor);
+        match self.minimap {
+            Some(minimap) => minimap.split(area).0,
+            None => area,
+        }
+    }
+
+    /// Returns the area of the minimap strip, if it's shown
+    fn minimap_area(&self) -> Option<(Minimap, Rect)> {
+        let (width, height) = self.surface_size;
+        self.minimap
+            .map(|minimap| (minimap, minimap.split(surface_area(width, height, self.scale_factor)).1))
+    }
+
+    /// Returns the lines of the buffer being edited, and the first line and number of lines visible in the editor
+    fn editor_viewport(&self) -> Option<(usize, usize, usize)> {
+        let (width, height) = self.surface_size;
+        let device = self.editing.and_then(|e| self.char_devices.get(&e))?;
+        let visible = match self.layout.find(self.layout_area(width, height), PaneContent::Editor) {
+            Some(rect) if self.glyph_metrics.line_height > 0.0 => {
+                (pane_content(rect, self.title_height()).height / self.glyph_metrics.line_height) as usize
+            }
+            _ => 0,
+        };
+        Some((device.line_count(), self.editor_scroll.line(), visible))
+    }
+
+    /// Applies the auto-close filter to a char typed into channel, returns false if the char should be written as-is
+    fn auto_close_typed(&mut self, channel: u32, typed: char) -> bool {
+        // Lines typed at the prompt are sent as they're completed, so only the byte channel can write to it
+        if channel == 0 && self.connection.is_some() {
+            return false;
+        }
+
+        let (filter, device) = match (self.auto_close.as_ref(), self.char_devices.get_mut(&channel)) {
+            (Some(filter), Some(device)) => (filter, device),
+            _ => return false,
+        };
+
+        let cursor = device.cursor();
+        let edit = {
+            let output = device.output();
+            let (before, after) = output.as_ref().split_at(cursor.min(output.as_ref().len()));
+            let before = before.rsplit('\r').next().unwrap_or_default();
+            let after = after.split('\r').next().unwrap_or_default();
+            filter.filter(before, after, typed)
+        };
+
+        match edit {
+            Some(AutoCloseEdit::Insert { text, back }) => {
+                device.write_bytes(text.as_bytes());
+                device.set_cursor(cursor + text.len() - back);
+                true
+            }
+            Some(AutoCloseEdit::Skip) => {
+                device.set_cursor(cursor + typed.len_utf8());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the indentation to type after a line break at the cursor of the char_device being edited
+    ///
+    /// The editor's grammer decides the indentation, the same grammer used to highlight it
+    fn indent_at_cursor(&self) -> String {
+        let device = match self.editing.and_then(|e| self.char_devices.get(&e)) {
+            Some(device) if self.auto_indent => device,
+            _ => return String::new(),
+        };
+
+        let before = device.before_cursor();
+        let line = before.as_ref().rsplit('\r').next().unwrap_or_default();
+        if self.connection.is_some() {
+            Sh::indent_after(line)
+        } else {
+            Runmd::indent_after(line)
+        }
+    }
+
+    /// Expands the first truncated line in the visible output of the active channel into a popup
+    pub fn expand_line(&mut self) {
+        let channel = self.channel as u32;
+        let expanded = self.char_devices.get(&channel).and_then(|device| {
+            let output = device.visible_output();
+            let line_no = truncated_lines(output.as_ref(), self.max_line_len).next()?;
+            let line = output.as_ref().split('\r').nth(line_no)?.to_string();
+            Some((line_no + device.scroll(), line))
+        });
+
+        match expanded {
+            Some((line_no, line)) => {
+                let title = format!("{} {} {}", self.channel_title(channel), self.locale.get("line"), line_no + 1);
+                self.expanded_line = Some((title, line));
+            }
+            None => event!(Level::DEBUG, "No truncated lines are visible in channel {channel}"),
+        }
+    }
+
+    /// Routes lines typed in the editor to the plugin that owns channel, or back to the editor if None
+    ///
+    /// Only channels that belong to an entity, and aren't read-only, can receive commands
+    pub fn set_command_target(&mut self, channel: Option<u32>) {
+        self.command_target = channel.filter(|c| {
+            self.channel_info(*c)
+                .map(|info| !info.read_only)
+                .unwrap_or(self.inputs.contains_key(c))
+        });
+    }
+
+    /// Spawns a local process on runtime, bound to a new channel, returns the channel or None if it couldn't be spawned
+    ///
+    /// Stdout and stderr of the process are written to the channel, and lines typed in the editor are written to its stdin
+    pub fn spawn_process(&mut self, command_line: impl AsRef<str>, runtime: &tokio::runtime::Runtime) -> Option<u32> {
+        let args = match shell_words::split(command_line.as_ref()) {
+            Ok(args) if !args.is_empty() => args,
+            Ok(_) => return None,
+            Err(err) => {
+                event!(Level::WARN, "Could not parse command line {}, {err}", command_line.as_ref());
+                return None;
+            }
+        };
+
+        let tx = self.byte_tx.clone()?;
+        let channel = BuiltinChannel::Process(self.processes.len() as u32).id();
+        self.char_devices.insert(channel, CharDevice::default());
+        self.render_cache.remove(channel);
+        self.line_prefixes.remove(&channel);
+        self.processes.insert(channel, command_line.as_ref().to_string());
+
+        let input = self.add_input(channel);
+        runtime.spawn(process::run_process(
+            args,
+            ShellChannel(Some(tx), channel),
+            input,
+            self.locale.clone(),
+        ));
+
+        event!(Level::DEBUG, "Spawned {} on channel {channel}", command_line.as_ref());
+        self.editing = Some(0);
+        self.channel = channel as i32;
+        self.set_command_target(Some(channel));
+        Some(channel)
+    }
+
+    /// Returns the plugin channel that typed lines are routed to
+    pub fn command_target(&self) -> Option<u32> {
+        self.command_target
+    }
+
+    /// Routes typed lines to channel, or back to the editor if they're already routed to it
+    fn toggle_command_target(&mut self, channel: u32) {
+        if self.command_target == Some(channel) {
+            self.set_command_target(None);
+        } else {
+            self.set_command_target(Some(channel));
+        }
+    }
+
+    /// Returns a component that receives lines typed while channel is focused, instead of parsing them as commands
+    ///
+    /// Insert the component on the channel's entity, so an interactive plugin can read user input w/ ShellInput::recv
+    pub fn add_input(&mut self, channel: u32) -> ShellInput {
+        let (tx, rx) = tokio::sync::mpsc::channel::<String>(MAX_PENDING_INPUT);
+        self.inputs.insert(channel, tx);
+        ShellInput(Some(rx))
+    }
+
+    /// Parses a line typed for the command target, and queues it for delivery to the plugin
+    ///
+    /// If the channel has a ShellInput, the line is sent to it as is
+    fn route_command(&mut self, channel: u32, line: String) {
+        if let Some(input) = self.inputs.get(&channel) {
+            match input.try_send(line.trim_end_matches('\r').to_string()) {
+                Ok(_) => return,
+                Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
+                    event!(Level::WARN, "Input for channel {channel} is full, dropped line");
+                    return;
+                }
+                Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                    event!(Level::DEBUG, "Input for channel {channel} was dropped, routing lines as commands");
+                    self.inputs.remove(&channel);
+                }
+            }
+        }
+
+        match ChannelCommand::parse(channel, &line) {
+            Ok(command) => {
+                event!(Level::DEBUG, "Routing {:?} to channel {channel}", command.args);
+                self.commands.push(command);
+            }
+            Err(err) => {
+                event!(Level::WARN, "Could not parse command for channel {channel}, {err}");
+            }
+        }
+    }
+
+    /// Sets the rules evaluated against completed lines of output
+    ///
+    /// If the app world has a `triggers` block, its rules replace these once the block is loaded or changes
+    pub fn set_triggers(&mut self, triggers: Triggers) {
+        self.triggers = triggers;
+    }
+
+    /// Loads the rules of the `triggers` block in the app world, when it's added or changes
+    fn update_triggers(&mut self, app_world: &lifec::World) {
+        let revision = self.world_revision(app_world);
+        if self.triggers_read.replace(revision) == Some(revision) {
+            return;
+        }
+
+        let tc = match find_triggers_block(app_world) {
+            Some(tc) => tc,
+            None => return,
+        };
+
+        let content = block_content(&tc);
+        if self.triggers_block.as_ref() != Some(&content) {
+            event!(Level::DEBUG, "Triggers block changed, loading triggers");
+            self.triggers.replace_rules(Triggers::from_context(&tc));
+            self.triggers_block = Some(content);
+        }
+    }
+
+    /// Evaluates triggers against lines completed in each output channel, and runs the resulting actions
+    fn evaluate_triggers(&mut self) {
+        if self.triggers.is_empty() {
+            return;
+        }
+
+        let mut actions = vec![];
+        for (channel, device) in self.char_devices.iter().filter(|(c, _)| **c != 0) {
+            for action in self.triggers.evaluate(*channel, device) {
+                actions.push((*channel, action));
+            }
+        }
+
+        for (channel, action) in actions {
+            match action {
+                TriggerAction::Command(command) => {
+                    self.route_command(channel, format!("{command}\r"));
+                }
+                TriggerAction::Event(name) => {
+                    self.trigger_events.push((channel, name));
+                }
+                TriggerAction::Notify(message) => {
+                    self.notifications.retain(|n| n.channel != channel);
+                    self.notifications.push(FocusRequest {
+                        channel,
+                        reason: Some(message).filter(|m| !m.is_empty()),
+                    });
+                }
+                // Highlights are rendered from the trigger state
+                TriggerAction::Highlight => {}
+            }
+        }
+    }
+
+    /// Replaces the localized strings used by the built-in UI
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Sets the localized strings used by the built-in UI
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// Inserts a char device at channel, replacing any existing device
+    pub fn insert_device(&mut self, channel: u32, device: CharDevice) {
+        self.char_devices.insert(channel, device);
+        self.render_cache.remove(channel);
+        self.line_prefixes.remove(&channel);
+    }
+
+    /// Saves the buffer of the char_device at channel to path
+    ///
+    /// Lines are written with `\n` line endings, returns a NotFound error if channel doesn't have a char_device
+    pub async fn save_buffer(&mut self, channel: u32, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let device = match self.char_devices.get_mut(&channel) {
+            Some(device) => device,
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No char device for channel {channel}"),
+                ))
+            }
+        };
+
+        let contents = device.output().as_ref().replace('\r', "\n");
+        tokio::fs::write(path.as_ref(), contents).await?;
+        self.saved(channel, path.as_ref());
+        Ok(())
+    }
+
+    /// Loads the file at path into the char_device being edited
+    ///
+    /// If nothing is being edited, the shell buffer is used
+    pub async fn load_file(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let contents = tokio::fs::read_to_string(path.as_ref()).await?;
+        self.loaded(path.as_ref(), contents);
+        Ok(())
+    }
+
+    /// Starts saving the buffer of channel to path on runtime, the result is applied by a later frame
+    ///
+    /// Unlike save_buffer, this doesn't block the caller, i.e. the UI while a slow disk is written to
+    pub fn start_save(&mut self, channel: u32, path: impl Into<String>, runtime: &tokio::runtime::Runtime) {
+        let path = path.into();
+        let contents = match self.char_devices.get(&channel) {
+            Some(device) => device.output().as_ref().replace('\r', "\n"),
+            None => {
+                event!(Level::ERROR, "Could not save {path}, no char device for channel {channel}");
+                return;
+            }
+        };
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let target = path.clone();
+        runtime.spawn(async move {
+            let _ = tx.send(tokio::fs::write(target, contents).await);
+        });
+        self.saving = Some((channel, path, rx));
+    }
+
+    /// Starts loading the file at path on runtime, it's loaded into the char_device being edited by a later frame
+    pub fn start_load(&mut self, path: impl Into<String>, runtime: &tokio::runtime::Runtime) {
+        let path = path.into();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let source = path.clone();
+        runtime.spawn(async move {
+            let _ = tx.send(tokio::fs::read_to_string(source).await);
+        });
+        self.loading = Some((path, rx));
+    }
+
+    /// Applies saves and loads once they complete in the background, errors are logged
+    fn poll_file_io(&mut self) {
+        if let Some((channel, path, mut rx)) = self.saving.take() {
+            match rx.try_recv() {
+                Ok(Ok(_)) => self.saved(channel, Path::new(&path)),
+                Ok(Err(err)) => event!(Level::ERROR, "Could not save {path}, {err}"),
+                Err(tokio::sync::oneshot::error::TryRecvError::Empty) => self.saving = Some((channel, path, rx)),
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                    event!(Level::ERROR, "Could not save {path}, the save was cancelled");
+                }
+            }
+        }
+
+        if let Some((path, mut rx)) = self.loading.take() {
+            match rx.try_recv() {
+                Ok(Ok(contents)) => self.loaded(Path::new(&path), contents),
+                Ok(Err(err)) => event!(Level::ERROR, "Could not open {path}, {err}"),
+                Err(tokio::sync::oneshot::error::TryRecvError::Empty) => self.loading = Some((path, rx)),
+                Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                    event!(Level::ERROR, "Could not open {path}, the load was cancelled");
+                }
+            }
+        }
+    }
+
+    /// Finishes saving channel to path, the journal starts over from the saved contents
+    fn saved(&mut self, channel: u32, path: &Path) {
+        if let (Some(journal), Some(device)) = (self.journal.as_ref(), self.char_devices.get_mut(&channel)) {
+            if device.journaled() {
+                device.take_unjournaled();
+                journal.checkpoint(channel, device.output().as_ref());
+            }
+        }
+
+        event!(Level::DEBUG, "Saved channel {channel} to {:?}", path);
+        self.filename = Some(path.display().to_string());
+    }
+
+    /// Replaces the char_device being edited w/ contents loaded from path
+    ///
+    /// If nothing is being edited, the shell buffer is used
+    fn loaded(&mut self, path: &Path, contents: String) {
+        let editing = *self.editing.get_or_insert(0);
+
+        let device = self.char_devices.entry(editing).or_default();
+        device.set_text(contents);
+
+        event!(Level::DEBUG, "Loaded {:?} into channel {editing}", path);
+        self.filename = Some(path.display().to_string());
+    }
+
+    /// Binds the file at path to channel, the channel's buffer is refreshed when the file changes on disk
+    pub fn watch_file(&mut self, channel: u32, path: impl AsRef<Path>) -> notify::Result<()> {
+        self.watcher.watch(channel, path.as_ref())?;
+        self.reload_file(channel, path);
+        Ok(())
+    }
+
+    /// Stops refreshing channel from its bound file
+    pub fn unwatch_file(&mut self, channel: u32) {
+        self.watcher.unwatch(channel);
+    }
+
+    /// Reloads the contents of the file at path into channel
+    fn reload_file(&mut self, channel: u32, path: impl AsRef<Path>) {
+        match std::fs::read_to_string(path.as_ref()) {
+            Ok(contents) => {
+                self.char_devices.entry(channel).or_default().set_text(contents);
+            }
+            Err(err) => {
+                event!(Level::ERROR, "Could not reload {:?}, {err}", path.as_ref());
+            }
+        }
+    }
+
+    /// Switches the token colors and the background clear color to a built-in theme
+    pub fn switch_theme(&mut self, builtin: BuiltinTheme, app_world: &lifec::World) {
+        self.set_color_theme(builtin.style(), app_world);
+    }
+
+    /// Installs style, swapping the token colors and the background clear color
+    pub fn set_color_theme(&mut self, style: Arc<dyn ColorTheme>, app_world: &lifec::World) {
+        if let Some(theme) = self.theme.as_mut() {
+            theme.set_style(style);
+
+            if let Some(color) = theme.get_color(Token::Custom("background".to_string())) {
+                self.background = *color;
+                self.update_clear_color(app_world);
+            }
+        }
+    }
+
+    /// Imports a color scheme file into the current theme
+    ///
+    /// The format is picked from the extension, `.yaml` for base16, `.toml` for WezTerm, otherwise Xresources
+    pub async fn import_theme(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let contents = tokio::fs::read_to_string(path.as_ref()).await?;
+
+        let scheme = match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => ColorScheme::parse_base16(&contents),
+            Some("toml") => ColorScheme::parse_wezterm(&contents),
+            _ => ColorScheme::parse_xresources(&contents),
+        };
+
+        if let Some(theme) = self.theme.as_mut() {
+            theme.apply_scheme(&scheme);
+        }
+
+        if let Some(background) = scheme.get("background") {
+            self.background = background;
+        }
+
+        event!(Level::DEBUG, "Imported {} colors from {:?}", scheme.len(), path.as_ref());
+        Ok(())
+    }
+
+    /// Returns the text brush and char device being edited
+    pub fn prepare_render_input(
+        &mut self,
+    ) -> (
+        Option<&mut TextBrush>,
+        Option<&mut CharDevice>,
+        Option<&mut Theme>,
+    ) {
+        if let Some(editing) = self.editing {
+            if let Some(device) = self.char_devices.get_mut(&editing) {
+                (self.brush.as_mut(), Some(device), self.theme.as_mut())
+            } else {
+                (self.brush.as_mut(), None, self.theme.as_mut())
+            }
+        } else {
+            (None, None, self.theme.as_mut())
+        }
+    }
+
+    /// Returns devices to render output from a chardevice
+    pub fn prepare_render_output(
+        &mut self,
+        channel: u32,
+    ) -> (
+        Option<&mut TextBrush>,
+        Option<&mut CharDevice>,
+        Option<&mut Theme>,
+    ) {
+        if let Some(device) = self.char_devices.get_mut(&channel) {
+            (self.brush.as_mut(), Some(device), self.theme.as_mut())
+        } else {
+            (self.brush.as_mut(), None, self.theme.as_mut())
+        }
+    }
+
+    /// Returns true if the shell was taken.
+    pub fn add_device(&'_ mut self, entity: Entity) -> Option<ShellChannel> {
+        let channel = self.insert_entity_device(entity)?;
+        self.hooks.channel_created(entity.id(), None);
+        Some(channel)
+    }
+
+    /// Adds a char device for an entity, and populates its channel info from the entity's thunk context
+    pub fn add_device_for(&mut self, entity: Entity, tc: &ThunkContext) -> Option<ShellChannel> {
+        let channel = self.insert_entity_device(entity)?;
+        let info = ChannelInfo::from_context(entity, tc, self.clock.now());
+        if let Some(device) = self.char_devices.get_mut(&entity.id()) {
+            device.set_read_only(info.read_only);
+        }
+
+        if let Some(grammer) = info.grammer.as_ref().filter(|g| !known_grammer(g)) {
+            event!(Level::WARN, "Unknown grammer {grammer} for channel {}, output will be plain", entity.id());
+        }
+
+        event!(Level::DEBUG, "Channel {} is titled {}", entity.id(), info.title);
+        self.hooks.channel_created(entity.id(), Some(&info));
+        self.channel_info.insert(entity.id(), info);
+        self.restore_channel(entity.id());
+        Some(channel)
+    }
+
+    fn insert_entity_device(&mut self, entity: Entity) -> Option<ShellChannel> {
+        if let Some(tx) = self.byte_tx.clone() {
+            let channel = entity.id();
+            // Keeps a buffer that was already restored for the channel, i.e. from the journal
+            self.char_devices.entry(channel).or_default();
+
+            event!(Level::DEBUG, "Adding channel for {}", entity.id());
+            Some(ShellChannel(Some(tx), channel))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the metadata for channel
+    pub fn channel_info(&self, channel: u32) -> Option<&ChannelInfo> {
+        self.channel_info.get(&channel)
+    }
+
+    /// Returns the metadata for channel, for editing
+    pub fn channel_info_mut(&mut self, channel: u32) -> Option<&mut ChannelInfo> {
+        self.channel_info.get_mut(&channel)
+    }
+
+    /// Sets whether channel only displays output, keyboard input to a read-only channel is rejected
+    pub fn set_read_only(&mut self, channel: u32, read_only: bool) {
+        if let Some(device) = self.char_devices.get_mut(&channel) {
+            device.set_read_only(read_only);
+        }
+
+        if let Some(info) = self.channel_info.get_mut(&channel) {
+            info.read_only = read_only;
+        }
+
+        if read_only && self.command_target == Some(channel) {
+            self.set_command_target(None);
+        }
+    }
+
+    /// Returns the stopwatch of channel, if its plugin has reported itself as running
+    pub fn stopwatch(&self, channel: u32) -> Option<&Stopwatch> {
+        self.stopwatches.get(&channel)
+    }
+
+    /// Updates the stopwatch of each channel from the `shell_running` attribute of its entity's thunk context
+    ///
+    /// When a plugin stops running, the total of the run is recorded in the channel's stopwatch
+    fn update_stopwatches(&mut self, app_world: &lifec::World) {
+        let now = self.clock.now();
+        let contexts = app_world.read_component::<ThunkContext>();
+        for (channel, info) in self.channel_info.iter() {
+            let running = contexts
+                .get(info.entity)
+                .and_then(|tc| tc.as_ref().is_enabled("shell_running"))
+                .unwrap_or_default();
+
+            if !running && !self.stopwatches.contains_key(channel) {
+                continue;
+            }
+
+            let stopwatch = self.stopwatches.entry(*channel).or_default();
+            if let Some(total) = stopwatch.update(running, now) {
+                event!(Level::INFO, "{} finished in {}", info.title, format_elapsed(total));
+            }
+        }
+    }
+
+    /// Updates the progress of each channel from the `shell_progress` and `shell_progress_message` attributes of its
+    /// entity's thunk context
+    ///
+    /// `shell_progress` is the fraction of work done, from 0.0 to 1.0, the bar is removed once the attribute is removed
+    fn update_progress(&mut self, app_world: &lifec::World) {
+        let contexts = app_world.read_component::<ThunkContext>();
+        for (channel, info) in self.channel_info.iter() {
+            let tc = contexts.get(info.entity);
+            match tc.and_then(|tc| tc.as_ref().find_float("shell_progress")) {
+                Some(fraction) => {
+                    let message = tc.and_then(|tc| tc.as_ref().find_text("shell_progress_message"));
+                    self.progress.insert(*channel, Progress::new(fraction, message));
+                }
+                None => {
+                    self.progress.remove(channel);
+                }
+            }
+        }
+    }
+
+    /// Returns the progress last reported by the plugin of channel
+    pub fn progress(&self, channel: u32) -> Option<&Progress> {
+        self.progress.get(&channel)
+    }
+
+    /// Returns the title of channel w/ its stopwatch, the elapsed time while running or the total of the last run
+    pub fn channel_label(&self, channel: u32) -> String {
+        let mut title = self.channel_title(channel);
+        if let Some(trimmed) = self.char_devices.get(&channel).map(|d| d.trimmed_lines()).filter(|t| *t > 0) {
+            title = format!("{title} ({trimmed} {})", self.locale.get("lines_trimmed"));
+        }
+
+        let stopwatch = match self.stopwatches.get(&channel) {
+            Some(stopwatch) => stopwatch,
+            None => return title,
+        };
+
+        match (stopwatch.elapsed(self.clock.now()), stopwatch.last()) {
+            (Some(elapsed), _) => format!("{title} ({} {})", self.locale.get("running"), format_elapsed(elapsed)),
+            (None, Some(total)) => format!("{title} ({} {})", self.locale.get("took"), format_elapsed(total)),
+            (None, None) => title,
+        }
+    }
+
+    /// Returns the title of channel, channels without metadata are titled by their id
+    pub fn channel_title(&self, channel: u32) -> String {
+        match BuiltinChannel::from_id(channel) {
+            Some(BuiltinChannel::Process(_)) => {
+                if let Some(command_line) = self.processes.get(&channel) {
+                    return command_line.clone();
+                }
+            }
+            Some(builtin) => {
+                if let Some(key) = builtin.title_key() {
+                    return self.locale.get(key).to_string();
+                }
+            }
+            None => {}
+        }
+
+        self.channel_info(channel)
+            .map(|i| i.title.clone())
+            .unwrap_or(format!("{} {channel}", self.locale.get("channel")))
+    }
+
+    /// Returns the name of the grammer channel is highlighted w/
+    pub fn channel_grammer(&self, channel: u32) -> String {
+        match self.channel_info(channel).and_then(|i| i.grammer.clone()) {
+            Some(grammer) => grammer,
+            None if channel == 0 && self.connection.is_some() => Sh::name().to_string(),
+            None if channel == 0 => Runmd::name().to_string(),
+            None => Plain::name().to_string(),
+        }
+    }
+
+    /// Returns the buffer of channel, w/ `\n` line breaks so it can be pasted elsewhere
+    pub fn copy_channel(&self, channel: u32) -> Option<String> {
+        let device = self.char_devices.get(&channel)?;
+        Some(to_clipboard_text(device.output().as_ref()))
+    }
+
+    /// Returns the buffer of channel wrapped in a fenced markdown code block, labeled w/ the channel's grammer
+    pub fn copy_channel_as_markdown(&self, channel: u32) -> Option<String> {
+        let device = self.char_devices.get(&channel)?;
+        Some(to_code_block(device.output().as_ref(), &self.channel_grammer(channel)))
+    }
+
+    /// Returns diagnostics found in the char_device being edited
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the layout of panes
+    pub fn layout(&self) -> &PaneLayout {
+        &self.layout
+    }
+
+    /// Returns the layout of panes for editing
+    pub fn layout_mut(&mut self) -> &mut PaneLayout {
+        &mut self.layout
+    }
+
+    /// Renders the input section in the editor pane
+    pub fn render_input(&'_ mut self, config: &SurfaceConfiguration) {
+        let area = self.layout_area(config.width as f32, config.height as f32);
+        let rect = match self.layout.find(area, PaneContent::Editor) {
+            Some(rect) => pane_content(rect, self.title_height()),
+            None => return,
+        };
+        let gutter = self.scaled(EDITOR_GUTTER);
+        let origin = (rect.x + gutter, rect.y);
+        let bounds = ((rect.width - gutter).max(0.0), rect.height);
+
+        let prompt_enabled = self.connection.is_some();
+        let cursor_visible = !self.editing_read_only();
+        let misspelled = if prompt_enabled { vec![] } else { self.misspelled_words() };
+        let line_error = self.line_error.clone();
+        let line_height = self.glyph_metrics.line_height;
+        let advance = self.glyph_metrics.advance;
+        let relative_line_numbers = self.relative_line_numbers;
+        let annotations = self.current_annotations().to_vec();
+        let first_line = self.editor_scroll.line();
+        let matches = match self.editing.and_then(|e| self.char_devices.get(&e)) {
+            Some(device) if self.search.open => {
+                self.search.update(device.output().as_ref());
+                Some(self.search.overlay(device.output().as_ref()))
+            }
+            _ => None,
+        };
+        // Only the lines around the editor's viewport are lexed, unless a block is zoomed to fit the pane
+        let visible_rows = if line_height > 0.0 { (rect.height / line_height) as usize } else { 0 };
+        let visible = visible_lines(first_line, visible_rows);
+        let lines = Some(visible.clone()).filter(|_| self.zoom.is_none());
+        // When connected, the editor is a prompt for the remote, so it's highlighted as shell commands
+        let spans = if prompt_enabled {
+            self.styled_spans::<Sh>(self.editing.unwrap_or_default(), false, lines)
+        } else {
+            self.styled_spans::<Runmd>(self.editing.unwrap_or_default(), false, lines)
+        };
+        if let Some(zoom) = self.zoom.clone() {
+            self.render_zoomed(zoom, origin, bounds, spans.as_deref().unwrap_or_default());
+            return;
+        }
+
+        let brackets = self.matching_brackets(prompt_enabled);
+        let squiggle_offset = self.scaled(16.0);
+        let line_number_padding = self.scaled(10.0);
+        let mut diagnostics = vec![];
+        let mut batch = std::mem::take(&mut self.text_batch);
+        if let (Some(glyph_brush), Some(active), Some(theme)) = self.prepare_render_input() {
+            // The buffer and its overlays are rendered from the first line of the editor's viewport, until the end of the
+            // visible lines
+            let shown = first_line..visible.end;
+            let start = line_start(active.output().as_ref(), first_line);
+            let end = line_start(active.output().as_ref(), visible.end).max(start);
+            let current_line = active.position_of(active.cursor()).0;
+
+            // Highlights search matches
+            if let Some(overlay) = matches.as_ref() {
+                glyph_brush.queue(Section {
+                    screen_position: origin,
+                    bounds,
+                    text: theme.render_matches(lines_of(overlay, shown.clone())),
+                    layout: Layout::Wrap {
+                        line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                        h_align: HorizontalAlign::Left,
+                        v_align: VerticalAlign::Top,
+                    },
+                });
+            }
+
+            // Highlights the selection
+            let selection = match active.block_selection() {
+                Some(block) => block.spans(active.output().as_ref()),
+                None => active.selection().into_iter().collect(),
+            };
+            if !selection.is_empty() {
+                let overlay = highlight_overlay(active.output().as_ref(), &selection);
+                glyph_brush.queue(Section {
+                    screen_position: origin,
+                    bounds,
+                    text: theme.render_highlight(Token::Match, lines_of(&overlay, shown.clone())),
+                    layout: Layout::Wrap {
+                        line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                        h_align: HorizontalAlign::Left,
+                        v_align: VerticalAlign::Top,
+                    },
+                });
+            }
+
+            // Renders the buffer, into a text vector reused between frames
+            let source = active.output();
+            let mut text = batch.take();
+            // TODO: need to figure out a way to make this generic, but for now this is good enough
+            theme.render_spans_into(
+                &mut text,
+                source.as_ref(),
+                spans.as_deref().unwrap_or_default(),
+                start..end,
+                prompt_enabled,
+            );
+            let section = Section {
+                screen_position: origin,
+                bounds,
+                text,
+                layout: Layout::Wrap {
+                    line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                    h_align: HorizontalAlign::Left,
+                    v_align: VerticalAlign::Top,
+                },
+            };
+            glyph_brush.queue(&section);
+            batch.recycle(section.text);
+
+            // Highlights the bracket pair adjacent to the cursor
+            if let Some((open, close)) = brackets {
+                let overlay = highlight_overlay(active.output().as_ref(), &[open, close]);
+                glyph_brush.queue(Section {
+                    screen_position: origin,
+                    bounds,
+                    text: theme.render_highlight(Token::MatchedBracket, lines_of(&overlay, shown.clone())),
+                    layout: Layout::Wrap {
+                        line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                        h_align: HorizontalAlign::Left,
+                        v_align: VerticalAlign::Top,
+                    },
+                });
+            }
+
+            // Renders the cursor
+            if cursor_visible {
+                glyph_brush.queue(Section {
+                    screen_position: origin,
+                    bounds,
+                    text: theme.render_cursor(prompt_enabled)(
+                        lines_of(active.before_cursor().as_ref(), first_line..usize::MAX),
+                        lines_of(active.after_cursor().as_ref(), 0..visible.end.saturating_sub(current_line)),
+                    ),
+                    layout: Layout::Wrap {
+                        line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                        h_align: HorizontalAlign::Left,
+                        v_align: VerticalAlign::Top,
+                    },
+                });
+            }
+
+            if !prompt_enabled {
+                // Renders squiggles under malformed runmd
+                diagnostics = Runmd::diagnostics(active.output().as_ref());
+                if !diagnostics.is_empty() {
+                    let overlay = Diagnostic::squiggles(active.output().as_ref(), &diagnostics);
+                    glyph_brush.queue(Section {
+                        screen_position: (origin.0, origin.1 + squiggle_offset),
+                        bounds,
+                        text: theme.render_squiggles(lines_of(&overlay, shown.clone())),
+                        layout: Layout::Wrap {
+                            line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                            h_align: HorizontalAlign::Left,
+                            v_align: VerticalAlign::Top,
+                        },
+                    });
+                }
+
+                // Renders squiggles under misspelled words
+                if !misspelled.is_empty() {
+                    let overlay = Diagnostic::squiggles(active.output().as_ref(), &misspelled);
+                    glyph_brush.queue(Section {
+                        screen_position: (origin.0, origin.1 + squiggle_offset),
+                        bounds,
+                        text: theme.render_squiggles_as(Token::Misspelled, lines_of(&overlay, shown.clone())),
+                        layout: Layout::Wrap {
+                            line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                            h_align: HorizontalAlign::Left,
+                            v_align: VerticalAlign::Top,
+                        },
+                    });
+                }
+                diagnostics.extend(misspelled);
+
+                // Renders line numbers, w/ a row for each row a line wraps onto so that they stay aligned
+                let columns = if advance > 0.0 { (bounds.0 / advance) as usize } else { usize::MAX };
+                let ends = line_ends(active.output().as_ref(), columns);
+                let first_row = match first_line.checked_sub(1).and_then(|l| ends.get(l)) {
+                    Some((row, _)) => row + 1,
+                    None => 0,
+                };
+                let last_row = ends.get(visible.end - 1).map_or(usize::MAX, |(row, _)| row + 1);
+                let rows = gutter_rows(active.output().as_ref(), columns, current_line, relative_line_numbers);
+                let last = rows.len().saturating_sub(1);
+                let rows = rows
+                    .into_iter()
+                    .enumerate()
+                    .skip(first_row)
+                    .take(last_row.saturating_sub(first_row))
+                    .map(|(idx, (label, current))| (if idx < last { format!("{label}\r") } else { label }, current))
+                    .collect::<Vec<_>>();
+                glyph_brush.queue(Section {
+                    screen_position: (rect.x + line_number_padding, rect.y),
+                    bounds,
+                    text: rows
+                        .iter()
+                        .map(|(label, current)| {
+                            Text::new(label)
+                                .with_color(if *current { [1.0, 1.0, 1.0, 1.0] } else { [1.0, 1.0, 1.0, 0.4] })
+                                .with_scale(theme.font_size())
+                                .with_z(1.0)
+                        })
+                        .collect(),
+                    ..Default::default()
+                });
+
+                // Renders the results of the last block sent to the app world, after the lines they belong to
+                let dim = mix(theme.style().background(), theme.style().foreground(), 0.5);
+                let error = theme.get_color(Token::Error).copied().unwrap_or([1.0, 0.3, 0.3, 1.0]);
+                for annotation in annotations.iter() {
+                    if let Some((row, column)) = ends.get(annotation.line).filter(|_| shown.contains(&annotation.line)) {
+                        glyph_brush.queue(Section {
+                            screen_position: (
+                                origin.0 + (column + 2) as f32 * advance,
+                                origin.1 + (row - first_row) as f32 * line_height,
+                            ),
+                            bounds,
+                            text: vec![Text::new(&annotation.text)
+                                .with_color(if annotation.error { mix(dim, error, 0.5) } else { dim })
+                                .with_scale(theme.font_size())
+                                .with_z(1.0)],
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+
+            // Renders the error of the last rejected line under the prompt
+            if let Some(error) = line_error.as_ref().filter(|_| prompt_enabled) {
+                let color = theme.get_color(Token::Error).copied().unwrap_or([1.0, 0.3, 0.3, 1.0]);
+                glyph_brush.queue(Section {
+                    screen_position: (origin.0, origin.1 + active.line_count().saturating_sub(first_line) as f32 * line_height),
+                    bounds,
+                    text: vec![Text::new(error).with_color(color).with_scale(theme.font_size()).with_z(1.0)],
+                    ..Default::default()
+                });
+            }
+        }
+
+        self.text_batch = batch;
+        self.diagnostics = diagnostics;
+    }
+
+    /// Scrolls each viewport to what it follows, the editor to its cursor, and channels scrolled to the end to their newest line
+    ///
+    /// While smooth scrolling, viewports are advanced by the time since the last frame
+    fn update_viewports(&mut self, config: &SurfaceConfiguration) {
+        let now = self.clock.now();
+        let elapsed = self.last_frame.map(|last| now.saturating_duration_since(last)).unwrap_or_default();
+        self.last_frame = Some(now);
+
+        let line_height = self.glyph_metrics.line_height;
+        if line_height <= 0.0 {
+            return;
+        }
+
+        let smooth = self.smooth_scrolling;
+        let area = self.layout_area(config.width as f32, config.height as f32);
+        for (rect, content) in self.layout.panes(area) {
+            let rows = (pane_content(rect, self.title_height()).height / line_height) as usize;
+            let channel = match content {
+                PaneContent::Editor => {
+                    if let Some(device) = self.editing.and_then(|e| self.char_devices.get(&e)) {
+                        let line = device.position_of(device.cursor()).0;
+                        self.editor_scroll.follow_line(line, rows, smooth);
+                        self.editor_scroll.step(elapsed);
+                    }
+                    continue;
+                }
+                PaneContent::ActiveChannel => self.channel as u32,
+                PaneContent::Channel(channel) => channel,
+            };
+
+            // Progress is pinned to the bottom of the pane, so it takes a row from the output
+            let rows = rows.saturating_sub(usize::from(self.progress.contains_key(&channel)));
+            if let Some(device) = self.char_devices.get_mut(&channel).filter(|d| d.grid().is_none()) {
+                let viewport = self.viewports.entry(channel).or_default();
+                // i.e. lines shifted up when the buffer was trimmed
+                if device.scroll() != viewport.line() {
+                    viewport.reset(device.scroll());
+                }
+                if self.follow_output {
+                    viewport.follow_end(device.line_count(), rows, smooth);
+                }
+                device.set_scroll(viewport.step(elapsed));
+            }
+        }
+    }
+
+    /// Renders only the zoomed block of the editor, at the scale that fits it in bounds
+    ///
+    /// If the buffer changed so that the block is no longer valid, the previous view is restored
+    fn render_zoomed(
+        &mut self,
+        zoom: ZoomView,
+        origin: (f32, f32),
+        bounds: (f32, f32),
+        spans: &[(Range<usize>, [f32; 4])],
+    ) {
+        let block = zoom.block.clone();
+        let spans = spans
+            .iter()
+            .filter(|(span, _)| span.end > block.start && span.start < block.end)
+            .map(|(span, color)| {
+                let start = span.start.max(block.start) - block.start;
+                let end = span.end.min(block.end) - block.start;
+                (start..end, *color)
+            })
+            .collect::<Vec<_>>();
+
+        let mut invalid = false;
+        if let (Some(glyph_brush), Some(active), Some(theme)) = self.prepare_render_input() {
+            match active.output().as_ref().get(block) {
+                Some(source) => {
+                    let mut text = theme.render_spans(source, &spans, false);
+                    for text in text.iter_mut() {
+                        text.scale = zoom.scale.into();
+                    }
+
+                    glyph_brush.queue(Section {
+                        screen_position: origin,
+                        bounds,
+                        text,
+                        layout: Layout::Wrap {
+                            line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                            h_align: HorizontalAlign::Left,
+                            v_align: VerticalAlign::Top,
+                        },
+                    });
+                }
+                None => invalid = true,
+            }
+        }
+
+        if invalid {
+            self.zoom = None;
+        }
+    }
+
+    /// Handles mouse events, clicking in the input section moves the cursor, and the wheel scrolls the output section
+    ///
+    /// Returns true if the event was handled
+    fn on_mouse_event(&mut self, event: &lifec::editor::WindowEvent<'_>) -> bool {
+        // Clicks and scrolling over imgui windows are left to imgui, releases still end drags that started in the shell
+        let pressed = matches!(
+            event,
+            lifec::editor::WindowEvent::MouseInput { state: ElementState::Pressed, .. }
+                | lifec::editor::WindowEvent::MouseWheel { .. }
+        );
+        if pressed && self.imgui_wants_mouse {
+            return false;
+        }
+
+        let (width, height) = self.surface_size;
+        let area = self.layout_area(width, height);
+        match event {
+            lifec::editor::WindowEvent::CursorMoved { position, .. } => {
+                self.mouse_position = (position.x as f32, position.y as f32);
+                let hovered = self.editor_offset_at_mouse().filter(|_| self.connection.is_none());
+                self.hover.update(hovered, self.clock.now());
+                if let Some(divider) = self.dragging {
+                    let min_size = self.scaled(MIN_PANE_SIZE);
+                    self.layout.drag_divider(area, divider, self.mouse_position, min_size);
+                }
+
+                if self.block_dragging {
+                    if let Some(offset) = self.editor_offset_at_mouse() {
+                        if let Some(device) = self.editing.and_then(|e| self.char_devices.get_mut(&e)) {
+                            let mut selection = device.block_selection().unwrap_or(BlockSelection::new(device.position_of(offset)));
+                            selection.extend_to(device.position_of(offset));
+                            device.set_block_selection(Some(selection));
+                        }
+                    }
+                }
+                true
+            }
+            lifec::editor::WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } if self.block_dragging => {
+                self.block_dragging = false;
+                true
+            }
+            // Clicking the minimap moves the cursor to the line under the mouse
+            lifec::editor::WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if self.minimap_area().map_or(false, |(_, strip)| strip.contains(self.mouse_position)) => {
+                let (minimap, strip) = self.minimap_area().unwrap_or_default();
+                if let Some(device) = self.editing.and_then(|e| self.char_devices.get_mut(&e)) {
+                    device.goto_line(minimap.line_at(strip, device.line_count(), self.mouse_position.1));
+                }
+                true
+            }
+            // Alt+dragging in the editor selects a rectangle of columns
+            lifec::editor::WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if self.modifiers.alt() => {
+                let offset = match self.editor_offset_at_mouse() {
+                    Some(offset) => offset,
+                    None => return false,
+                };
+
+                if let Some(device) = self.editing.and_then(|e| self.char_devices.get_mut(&e)) {
+                    device.set_cursor(offset);
+                    device.set_block_selection(Some(BlockSelection::new(device.position_of(offset))));
+                    self.block_dragging = true;
+                }
+                true
+            }
+            lifec::editor::WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } if self.dragging.is_some() => {
+                self.dragging = None;
+                self.autosave_session();
+                true
+            }
+            lifec::editor::WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if self.layout.divider_at(area, self.mouse_position, self.scaled(DIVIDER_TOLERANCE)).is_some() => {
+                let divider = self
+                    .layout
+                    .divider_at(area, self.mouse_position, self.scaled(DIVIDER_TOLERANCE))
+                    .unwrap_or_default();
+                let now = self.clock.now();
+
+                match self.last_divider_click.take() {
+                    Some((last, clicked_at)) if last == divider && now - clicked_at <= DOUBLE_CLICK => {
+                        self.layout.reset_divider(divider);
+                        self.autosave_session();
+                    }
+                    _ => {
+                        self.last_divider_click = Some((divider, now));
+                        self.dragging = Some(divider);
+                    }
+                }
+                true
+            }
+            lifec::editor::WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let (x, y) = self.mouse_position;
+                let rect = match self.layout.pane_at(area, self.mouse_position) {
+                    Some((_, rect, PaneContent::Editor)) => {
+                        self.focused_pane = PaneContent::Editor;
+                        pane_content(rect, self.title_height())
+                    }
+                    Some((.., content)) => {
+                        self.focused_pane = content;
+                        return true;
+                    }
+                    _ => return false,
+                };
+
+                let prompt_enabled = self.connection.is_some();
+                let metrics = self.glyph_metrics;
+                let gutter = self.scaled(EDITOR_GUTTER);
+                let now = self.clock.now();
+                let last_click = self.last_editor_click.take();
+                let first_line = self.editor_scroll.line();
+                if let (.., Some(editing), theme) = self.prepare_render_input() {
+                    let mut x = x - rect.x - gutter;
+                    if prompt_enabled {
+                        // Accounts for the width of the prompt
+                        x -= metrics.advance * 2.0;
+                    }
+
+                    let start = line_start(editing.output().as_ref(), first_line);
+                    let offset = start + metrics.hit_test(
+                        &editing.output().as_ref()[start..],
+                        rect.width - gutter,
+                        (x, y - rect.y),
+                    );
+
+                    match (last_click, theme) {
+                        // Double-clicking selects the grammer token under the cursor
+                        (Some((last, clicked_at)), Some(theme)) if last == offset && now - clicked_at <= DOUBLE_CLICK => {
+                            let source = editing.output().as_ref().to_string();
+                            let token = if prompt_enabled {
+                                theme.token_at::<Sh>(&source, offset)
+                            } else {
+                                theme.token_at::<Runmd>(&source, offset)
+                            };
+
+                            match token {
+                                Some(token) => editing.set_selection(token),
+                                None => editing.set_cursor(offset),
+                            }
+                        }
+                        _ => {
+                            editing.set_cursor(offset);
+                            self.last_editor_click = Some((offset, now));
+                        }
+                    }
+                }
+                true
+            }
+            lifec::editor::WindowEvent::MouseWheel { delta, .. } => {
+                let channel = match self.layout.pane_at(area, self.mouse_position) {
+                    Some((.., PaneContent::ActiveChannel)) => self.channel as u32,
+                    Some((.., PaneContent::Channel(channel))) => channel,
+                    _ => return false,
+                };
+
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => -y.round() as i32,
+                    MouseScrollDelta::PixelDelta(position) => {
+                        -(position.y as f32 / self.glyph_metrics.line_height).round() as i32
+                    }
+                };
+
+                self.scroll_channel(channel, lines);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Shows a tooltip for the runmd token the mouse is resting over
+    ///
+    /// Keywords and value types are described, and attribute names show their current values in the app world
+    fn hover_ui(&mut self, app_world: &lifec::World, ui: &imgui::Ui) {
+        if self.connection.is_some() || ui.io().want_capture_mouse {
+            return;
+        }
+
+        let offset = match self.hover.resting(self.clock.now()) {
+            Some(offset) => offset,
+            None => return,
+        };
+
+        let source = match self.editing.and_then(|e| self.char_devices.get(&e)) {
+            Some(device) => device.output().as_ref().to_string(),
+            None => return,
+        };
+
+        let span = match self.theme.as_ref().and_then(|t| t.token_at::<Runmd>(&source, offset)) {
+            Some(span) => span,
+            None => return,
+        };
+        let line_start = source[..span.start].rfind('\r').map(|i| i + 1).unwrap_or_default();
+        let line_end = source[span.start..].find('\r').map(|i| span.start + i).unwrap_or(source.len());
+
+        match HoverTarget::of(&source[span], &source[line_start..line_end]) {
+            Some(HoverTarget::Keyword(key)) => ui.tooltip_text(self.locale.get(key)),
+            Some(HoverTarget::Attribute(name)) => {
+                let contexts = app_world.read_component::<ThunkContext>();
+                let values = (&contexts).join().flat_map(|tc| {
+                    tc.as_ref()
+                        .iter_attributes()
+                        .filter(|a| a.name() == name.as_str())
+                        .map(|a| (tc.block.block_name.clone(), format!("{:?}", a.value())))
+                        .collect::<Vec<_>>()
+                });
+                let rows = attribute_rows(values, "");
+
+                ui.tooltip(|| {
+                    ui.text(&name);
+                    if rows.is_empty() {
+                        ui.text_disabled(self.locale.get("hover_not_in_world"));
+                    }
+                    for (block, value) in rows {
+                        ui.text_disabled(format!("{block}: {value}"));
+                    }
+                });
+            }
+            None => {}
+        }
+    }
+
+    /// Returns the offset in the char_device being edited under the mouse, if the mouse is over the editor
+    fn editor_offset_at_mouse(&self) -> Option<usize> {
+        let area = self.layout_area(self.surface_size.0, self.surface_size.1);
+        let rect = match self.layout.pane_at(area, self.mouse_position) {
+            Some((_, rect, PaneContent::Editor)) => pane_content(rect, self.title_height()),
+            _ => return None,
+        };
+        let device = self.editing.and_then(|e| self.char_devices.get(&e))?;
+
+        let (x, y) = self.mouse_position;
+        let mut x = x - rect.x - self.scaled(EDITOR_GUTTER);
+        if self.connection.is_some() {
+            // Accounts for the width of the prompt
+            x -= self.glyph_metrics.advance * 2.0;
+        }
+
+        // The editor is rendered from the first line of its viewport
+        let start = line_start(device.output().as_ref(), self.editor_scroll.line());
+        Some(start + self.glyph_metrics.hit_test(
+            &device.output().as_ref()[start..],
+            rect.width - self.scaled(EDITOR_GUTTER),
+            (x, y - rect.y),
+        ))
+    }
+
+    /// Shows controls for assigning channels to panes, splitting, and resizing panes
+    fn layout_ui(&mut self, ui: &imgui::Ui) {
+        let mut choices = vec![PaneContent::Editor, PaneContent::ActiveChannel];
+        choices.extend(self.char_devices.keys().map(|c| PaneContent::Channel(*c)));
+        let labels = choices
+            .iter()
+            .map(|c| match c {
+                PaneContent::Editor => self.locale.get("editor").to_string(),
+                PaneContent::ActiveChannel => self.locale.get("active_channel").to_string(),
+                PaneContent::Channel(channel) => self.channel_title(*channel),
+            })
+            .collect::<Vec<_>>();
+
+        let area = self.layout_area(self.surface_size.0, self.surface_size.1);
+        for (idx, (_, content)) in self.layout.panes(area).into_iter().enumerate() {
+            let mut selected = choices.iter().position(|c| *c == content).unwrap_or_default();
+            if ui.combo_simple_string(format!("{} {idx}", self.locale.get("pane")), &mut selected, &labels) {
+                self.layout.assign(idx, choices[selected]);
+            }
+
+            if ui.button(format!("{}##{idx}", self.locale.get("split_horizontal"))) {
+                self.layout.split_pane(idx, Split::Horizontal, PaneContent::ActiveChannel);
+            }
+            ui.same_line();
+            if ui.button(format!("{}##{idx}", self.locale.get("split_vertical"))) {
+                self.layout.split_pane(idx, Split::Vertical, PaneContent::ActiveChannel);
+            }
+            ui.same_line();
+            if ui.button(format!("{}##{idx}", self.locale.get("close_pane"))) {
+                self.layout.close_pane(idx);
+            }
+
+            let channel = match content {
+                PaneContent::Editor => None,
+                PaneContent::ActiveChannel => Some(self.channel as u32),
+                PaneContent::Channel(channel) => Some(channel),
+            };
+            if let Some(channel) = channel.filter(|c| !self.is_detached(*c)) {
+                ui.same_line();
+                if ui.button(format!("{}##{idx}", self.locale.get("detach"))) {
+                    let pane = match content {
+                        PaneContent::Channel(_) => Some(idx),
+                        _ => None,
+                    };
+                    self.detach_channel(channel, pane);
+                }
+            }
+
+            if let Some(channel) = channel {
+                let others = self
+                    .char_devices
+                    .keys()
+                    .copied()
+                    .filter(|c| *c != channel)
+                    .collect::<Vec<_>>();
+                let mut link_labels = vec![self.locale.get("not_linked").to_string()];
+                link_labels.extend(others.iter().map(|c| self.channel_title(*c)));
+
+                let mut selected = self
+                    .scroll_link(channel)
+                    .and_then(|linked| others.iter().position(|c| *c == linked))
+                    .map(|idx| idx + 1)
+                    .unwrap_or_default();
+                if ui.combo_simple_string(
+                    format!("{}##{idx}", self.locale.get("scroll_with")),
+                    &mut selected,
+                    &link_labels,
+                ) {
+                    match selected.checked_sub(1).and_then(|idx| others.get(idx)) {
+                        Some(other) => self.link_scroll(channel, *other),
+                        None => {
+                            self.unlink_scroll(channel);
+                        }
+                    }
+                }
+
+                let mut routed = self.command_target == Some(channel);
+                if ui.checkbox(format!("{}##{idx}", self.locale.get("route_commands")), &mut routed) {
+                    self.toggle_command_target(channel);
+                }
+
+                if ui.button(format!("{}##{idx}", self.locale.get("copy_channel"))) {
+                    if let Some(text) = self.copy_channel(channel) {
+                        ui.set_clipboard_text(text);
+                    }
+                }
+                ui.same_line();
+                if ui.button(format!("{}##{idx}", self.locale.get("copy_as_markdown"))) {
+                    if let Some(text) = self.copy_channel_as_markdown(channel) {
+                        ui.set_clipboard_text(text);
+                    }
+                }
+
+                let mut grid_mode = self.char_devices.get(&channel).map_or(false, |d| d.grid().is_some());
+                if ui.checkbox(format!("{}##{idx}", self.locale.get("terminal_grid")), &mut grid_mode) {
+                    self.set_grid_mode(channel, grid_mode);
+                }
+
+                let mut logging = self.channel_logs.contains_key(&channel);
+                if ui.checkbox(format!("{}##{idx}", self.locale.get("log_to_file")), &mut logging) {
+                    if !logging {
+                        self.stop_logging(channel);
+                    } else if let Err(err) = self.log_channel(channel) {
+                        event!(Level::ERROR, "Could not log channel {channel} to a file, {err}");
+                    }
+                }
+                if let Some(path) = self.channel_log(channel) {
+                    ui.same_line();
+                    ui.text_disabled(path.display().to_string());
+                }
+            }
+
+            if let Some(theme) = self.theme.as_mut() {
+                let applied = Self::pane_background_ui(ui, &self.locale, theme, &mut self.background_paths, idx);
+                if let (Some(path), Some(renderer)) = (applied, self.background_renderer.as_mut()) {
+                    renderer.retry(&path);
+                }
+            }
+        }
+
+        ui.separator();
+        let mut resized = false;
+        for (idx, ratio) in self.layout.ratios_mut().into_iter().enumerate() {
+            imgui::Slider::new(format!("{} {idx}", self.locale.get("split")), 0.1, 0.9).build(ui, ratio);
+            resized |= ui.is_item_deactivated_after_edit();
+        }
+
+        if resized {
+            self.autosave_session();
+        }
+    }
+
+    /// Sets the directory data is kept in, i.e. transcripts of sessions connected w/ a profile
+    ///
+    /// Edits to buffers are also journaled there, and buffers are recovered from the journal when the shell starts
+    pub fn with_data_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        self.journal = Journal::open(&path);
+        self.data_dir = Some(path);
+        self
+    }
+
+    /// Prefixes each line received by output channels w/ a timestamp and/or the channel's title, in a dimmed color
+    pub fn with_line_decoration(mut self, decoration: LineDecoration) -> Self {
+        self.set_line_decoration(Some(decoration));
+        self
+    }
+
+    /// Sets the prefix added to lines received by output channels, None disables it
+    ///
+    /// Only lines received after this is set are decorated
+    pub fn set_line_decoration(&mut self, decoration: Option<LineDecoration>) {
+        self.line_decoration = decoration;
+    }
+
+    /// Sets whether channel is in grid mode, where output is written to a fixed grid of cells like a terminal
+    ///
+    /// The grid is sized to fit the pane the channel is shown in, and resized along w/ it
+    pub fn set_grid_mode(&mut self, channel: u32, enabled: bool) {
+        let area = self.layout_area(self.surface_size.0, self.surface_size.1);
+        let rect = self
+            .layout
+            .panes(area)
+            .into_iter()
+            .find(|(_, content)| match content {
+                PaneContent::ActiveChannel => self.channel as u32 == channel,
+                PaneContent::Channel(c) => *c == channel,
+                PaneContent::Editor => false,
+            })
+            .map(|(rect, _)| pane_content(rect, self.title_height()))
+            .unwrap_or(area);
+        let (rows, cols) = self.grid_size(rect);
+
+        match self.char_devices.get_mut(&channel) {
+            Some(device) if enabled => device.enable_grid(rows, cols),
+            Some(device) => device.disable_grid(),
+            None => event!(Level::DEBUG, "Channel {channel} does not exist"),
+        }
+    }
+
+    /// Returns the rows and columns of a grid that fits the output area of a pane
+    fn grid_size(&self, rect: Rect) -> (usize, usize) {
+        let GlyphMetrics { line_height, advance } = self.glyph_metrics;
+        if line_height <= 0.0 || advance <= 0.0 {
+            return (1, 1);
+        }
+
+        let rows = (rect.height / line_height) as usize;
+        let cols = ((rect.width - self.scaled(CHANNEL_PADDING)).max(0.0) / advance) as usize;
+        (rows, cols)
+    }
+
+    /// Starts appending everything written to channel to a new timestamped file, returns the path of the file
+    ///
+    /// Files are written to `<data_dir>/logs`, or a temp directory if no data directory was set
+    pub fn log_channel(&mut self, channel: u32) -> std::io::Result<PathBuf> {
+        let dir = match self.data_dir.as_ref() {
+            Some(data_dir) => data_dir.join("logs"),
+            None => std::env::temp_dir().join("lifec_shell_logs"),
+        };
+
+        let log = ChannelLog::start(dir, channel)?;
+        let path = log.path().to_path_buf();
+        event!(Level::DEBUG, "Logging channel {channel} to {:?}", path);
+        self.channel_logs.insert(channel, log);
+        Ok(path)
+    }
+
+    /// Stops logging channel to a file
+    pub fn stop_logging(&mut self, channel: u32) {
+        self.channel_logs.remove(&channel);
+    }
+
+    /// Returns the file channel is being logged to, if it's being logged
+    pub fn channel_log(&self, channel: u32) -> Option<&Path> {
+        self.channel_logs.get(&channel).map(|log| log.path())
+    }
+
+    /// Restores each buffer in the journal to its last state, returns the number of buffers recovered
+    ///
+    /// Recovered buffers are checkpointed, so the journal starts over from the recovered state
+    pub fn recover_journal(&mut self) -> usize {
+        let journal = match self.journal.as_ref() {
+            Some(journal) => journal,
+            None => return 0,
+        };
+
+        let recovered = journal.recover();
+        for (channel, text) in recovered.iter() {
+            let device = self.char_devices.entry(*channel).or_default();
+            device.set_buffer(text);
+            device.set_journaled(true);
+            journal.checkpoint(*channel, text);
+        }
+
+        if !recovered.is_empty() {
+            event!(Level::INFO, "Recovered {} buffers from the journal", recovered.len());
+        }
+        recovered.len()
+    }
+
+    /// Queues the edits made to journaled buffers since the last frame as one batch of writes to the journal
+    ///
+    /// The buffer being edited starts being journaled w/ a checkpoint of its current contents
+    fn flush_journal(&mut self) {
+        let journal = match self.journal.as_ref() {
+            Some(journal) => journal,
+            None => return,
+        };
+
+        let mut batch = vec![];
+        if let Some((channel, device)) = self.editing.and_then(|e| self.char_devices.get_mut(&e).map(|d| (e, d))) {
+            if !device.journaled() {
+                batch.push(JournalWrite::checkpoint(channel, device.output().as_ref()));
+                device.set_journaled(true);
+            }
+        }
+
+        for (channel, device) in self.char_devices.iter_mut() {
+            let edits = device.take_unjournaled();
+            if !edits.is_empty() {
+                batch.push(JournalWrite::append(*channel, &edits));
+            }
+        }
+
+        journal.write(batch);
+    }
+
+    /// Restores the session saved at path, and saves changes to the session there, and when the window is closed
+    ///
+    /// If the file doesn't exist yet, it's created the next time the session changes
+    pub fn with_session_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        if let Err(err) = self.load_session(&path) {
+            event!(Level::DEBUG, "Could not load session from {:?}, {err}", path);
+        }
+
+        self.session_file = Some(path);
+        self
+    }
+
+    /// Saves the layout, registers, channel buffers w/ their scroll positions and undo history, theme colors, and
+    /// address to a session file at path
+    pub fn save_session(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.session_file().save(path)
+    }
+
+    /// Returns the state of the shell that's kept in the session file
+    ///
+    /// Restored channels that haven't been added yet are kept, so that they can be restored in a later run
+    fn session_file(&self) -> SessionFile {
+        let mut channels = self.restored_channels.clone();
+        for (channel, device) in self.char_devices.iter().filter(|(_, d)| !d.output().as_ref().is_empty()) {
+            channels.insert(
+                self.session_key(*channel),
+                ChannelSession {
+                    buffer: device.output().as_ref().to_string(),
+                    scroll: device.scroll(),
+                    history: device.history().to_vec(),
+                },
+            );
+        }
+
+        SessionFile {
+            layout_ratios: self.layout.ratios(),
+            registers: self.registers.clone(),
+            frecency: self.frecency.clone(),
+            address: self.address.as_deref().map(strip_userinfo),
+            colors: self
+                .theme
+                .iter()
+                .flat_map(|theme| theme.colors())
+                .map(|(token, color)| (token.clone(), *color))
+                .collect(),
+            channels,
+        }
+    }
+
+    /// Restores a session saved w/ save_session, buffers of channels in the session replace the current buffers
+    ///
+    /// Channels of plugins that haven't been added yet are restored when they're added
+    pub fn load_session(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let session = SessionFile::load(path)?;
+        self.layout.set_ratios(&session.layout_ratios);
+        self.registers = session.registers;
+        self.frecency = session.frecency;
+        if session.address.is_some() {
+            self.address = session.address;
+        }
+
+        self.restored_colors = session.colors.clone();
+        self.set_colors(session.colors);
+
+        self.restored_channels = session.channels;
+        let channels = self.char_devices.keys().copied().collect::<Vec<_>>();
+        for channel in channels {
+            self.restore_channel(channel);
+        }
+
+        // Channels w/o metadata, i.e. the shell's own buffer, are keyed by their id, so they can be restored right away
+        let keys = self.restored_channels.keys().filter_map(|k| k.parse::<u32>().ok()).collect::<Vec<_>>();
+        for channel in keys.into_iter().filter(|c| !self.channel_info.contains_key(c)) {
+            self.restore_channel(channel);
+        }
+        Ok(())
+    }
+
+    /// Sets the colors of tokens in the theme, i.e. colors restored from the session
+    fn set_colors(&mut self, colors: Vec<(Token, [f32; 4])>) {
+        if colors.is_empty() {
+            return;
+        }
+
+        let theme = self
+            .theme
+            .get_or_insert_with(|| default_theme(Arc::new(DefaultTheme)));
+        for (token, color) in colors {
+            theme.set_color(token, color);
+        }
+
+        if let Some(color) = theme.get_color(Token::Custom("background".to_string())) {
+            self.background = *color;
+        }
+    }
+
+    /// Returns the key channel is saved under in the session, plugin channels are keyed by their title
+    ///
+    /// Entity ids can change between runs, while titles come from the runmd blocks that add the channels
+    fn session_key(&self, channel: u32) -> String {
+        self.channel_info(channel)
+            .map(|info| info.title.clone())
+            .unwrap_or_else(|| channel.to_string())
+    }
+
+    /// Restores the buffer, scroll position, and undo history of channel from the session, if it was saved
+    ///
+    /// Buffers recovered from the journal are newer than the session, so only their scroll position is restored
+    fn restore_channel(&mut self, channel: u32) {
+        let saved = match self.restored_channels.remove(&self.session_key(channel)) {
+            Some(saved) => saved,
+            None => return,
+        };
+
+        let device = self.char_devices.entry(channel).or_default();
+        if !device.journaled() {
+            device.set_buffer(saved.buffer);
+            device.restore_history(saved.history);
+        }
+        device.set_scroll(saved.scroll);
+        self.viewports.entry(channel).or_default().jump(device.scroll(), device.line_count());
+    }
+
+    /// Schedules saving the session to the session file, if one was set
+    ///
+    /// The session is saved by flush_session, once SESSION_SAVE_DELAY has passed since the first unsaved change
+    fn autosave_session(&mut self) {
+        if self.session_file.is_some() && self.session_changed.is_none() {
+            self.session_changed = Some(self.clock.now());
+        }
+    }
+
+    /// Saves the session on a background thread, if it changed at least SESSION_SAVE_DELAY ago
+    ///
+    /// If the last save is still being written, saving waits for a later frame
+    fn flush_session(&mut self) {
+        let path = match (self.session_changed, self.session_file.as_ref()) {
+            (Some(changed), Some(path)) if self.clock.now() - changed >= SESSION_SAVE_DELAY => path.clone(),
+            _ => return,
+        };
+
+        if self.session_writer.as_ref().map_or(false, |writer| !writer.is_finished()) {
+            return;
+        }
+
+        self.session_changed = None;
+        let session = self.session_file();
+        self.session_writer = Some(std::thread::spawn(move || {
+            if let Err(err) = session.save(&path) {
+                event!(Level::ERROR, "Could not save session to {:?}, {err}", path);
+            }
+        }));
+    }
+
+    /// Saves the session to the session file right away, after the last background save has finished
+    fn save_session_now(&mut self) {
+        if let Some(writer) = self.session_writer.take() {
+            let _ = writer.join();
+        }
+
+        self.session_changed = None;
+        if let Some(path) = self.session_file.as_ref() {
+            if let Err(err) = self.save_session(path) {
+                event!(Level::ERROR, "Could not save session to {:?}, {err}", path);
+            }
+        }
+    }
+
+    /// Pops channel out into a floating window, if pane is set the pane is switched to the active channel
+    pub fn detach_channel(&mut self, channel: u32, pane: Option<usize>) {
+        if let Some(idx) = pane {
+            self.layout.assign(idx, PaneContent::ActiveChannel);
+        }
+        self.detached.insert(channel, pane);
+    }
+
+    /// Closes the floating window of channel, and returns it to the pane it was detached from
+    pub fn reattach_channel(&mut self, channel: u32) {
+        if let Some(Some(idx)) = self.detached.remove(&channel) {
+            if !self.layout.assign(idx, PaneContent::Channel(channel)) {
+                event!(Level::DEBUG, "Pane {idx} no longer exists, channel {channel} was not reattached");
+            }
+        }
+    }
+
+    /// Returns true if channel is shown in a floating window
+    pub fn is_detached(&self, channel: u32) -> bool {
+        self.detached.contains_key(&channel)
+    }
+
+    /// Links the scrolling of channels a and b, scrolling either scrolls the other proportionally
+    pub fn link_scroll(&mut self, a: u32, b: u32) {
+        self.scroll_links.link(a, b);
+        self.sync_scroll(a);
+    }
+
+    /// Breaks the scroll link of channel, returns the channel it was linked to
+    pub fn unlink_scroll(&mut self, channel: u32) -> Option<u32> {
+        self.scroll_links.unlink(channel)
+    }
+
+    /// Returns the channel whose scrolling is linked to channel
+    pub fn scroll_link(&self, channel: u32) -> Option<u32> {
+        self.scroll_links.linked(channel)
+    }
+
+    /// Scrolls the output of channel by lines, and the output of the channel linked to it
+    pub fn scroll_channel(&mut self, channel: u32, lines: i32) {
+        if let Some(device) = self.char_devices.get_mut(&channel) {
+            device.scroll_by(lines);
+            self.sync_scroll(channel);
+        }
+    }
+
+    /// Scrolls the channel linked to channel to the same proportion of its output
+    ///
+    /// Called after channel is scrolled by the user, so the viewports of both channels are moved to their new lines
+    fn sync_scroll(&mut self, channel: u32) {
+        let (scroll, lines) = match self.char_devices.get(&channel) {
+            Some(device) => (device.scroll(), device.line_count()),
+            None => return,
+        };
+        self.viewports.entry(channel).or_default().jump(scroll, lines);
+
+        let linked = self.scroll_links.linked(channel);
+        if let Some((linked, device)) = linked.and_then(|c| self.char_devices.get_mut(&c).map(|d| (c, d))) {
+            let line = scroll_link::proportional(scroll, lines, device.line_count());
+            device.set_scroll(line);
+            self.viewports.entry(linked).or_default().jump(device.scroll(), device.line_count());
+        }
+    }
+
+    /// Shows the command palette while it's open, the picked entry is inserted into the editor
+    fn palette_ui(&mut self, ui: &imgui::Ui) {
+        let mut query = match self.palette.take() {
+            Some(query) => query,
+            None => return,
+        };
+
+        let mut opened = true;
+        let mut picked = None;
+        imgui::Window::new(self.locale.get("palette"))
+            .opened(&mut opened)
+            .always_auto_resize(true)
+            .build(ui, || {
+                if ui.is_window_appearing() {
+                    ui.set_keyboard_focus_here();
+                }
+                ui.input_text(self.locale.get("search"), &mut query).build();
+
+                for entry in self.help.search(&query) {
+                    let label = format!("{}  {}", entry.name, entry.help.description);
+                    if imgui::Selectable::new(&label).build(ui) {
+                        picked = Some(entry.insert_text.clone());
+                    }
+                    help_tooltip(ui, entry);
+                }
+
+                let registers = self
+                    .registers
+                    .iter()
+                    .filter(|(name, text)| {
+                        format!("\"{name}") == query || text.to_lowercase().contains(&query.to_lowercase())
+                    })
+                    .collect::<Vec<_>>();
+                if !registers.is_empty() {
+                    ui.separator();
+                    ui.text_disabled(self.locale.get("registers"));
+                    for (name, text) in registers {
+                        let preview = text.split('\r').next().unwrap_or_default();
+                        if imgui::Selectable::new(format!("\"{name}  {preview}")).build(ui) {
+                            picked = Some(text.to_string());
+                        }
+                    }
+                }
+            });
+
+        match picked {
+            Some(text) => self.insert_text(&text),
+            None if opened => self.palette = Some(query),
+            None => {}
+        }
+    }
+
+    /// Shows completions for the word before the cursor, picking one inserts the rest of it
+    ///
+    /// On `add`, `define`, and block delimitter lines, the names of attributes and blocks in the app world are offered too
+    fn completions_ui(&mut self, app_world: &lifec::World, ui: &imgui::Ui) {
+        if self.connection.is_some() {
+            return;
+        }
+
+        let (line, word) = match self.editing.and_then(|e| self.char_devices.get(&e)) {
+            Some(device) => {
+                let before = device.before_cursor().as_ref().to_string();
+                let start = before
+                    .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .map(|i| i + 1)
+                    .unwrap_or_default();
+                let line_start = before.rfind(|c| c == '\r' || c == '\n').map(|i| i + 1).unwrap_or_default();
+                (before[line_start..].to_string(), before[start..].to_string())
+            }
+            None => return,
+        };
+
+        let world_completions = match NameSlot::at(&line) {
+            Some(slot) => {
+                let description = match slot {
+                    NameSlot::Attribute => self.locale.get("world_attribute"),
+                    NameSlot::Block => self.locale.get("world_block"),
+                };
+                find_world_names(app_world)
+                    .complete(slot, &word)
+                    .map(|name| HelpEntry {
+                        kind: HelpKind::Completion,
+                        name: name.clone(),
+                        insert_text: name.clone(),
+                        help: Help::new(description),
+                    })
+                    .collect::<Vec<_>>()
+            }
+            None => vec![],
+        };
+
+        let mut completions = self
+            .help
+            .completions(&word)
+            .chain(world_completions.iter())
+            .collect::<Vec<_>>();
+        if completions.is_empty() {
+            return;
+        }
+        self.frecency.rank(&mut completions, |e| &e.name);
+
+        let mut picked = None;
+        imgui::Window::new(self.locale.get("completions"))
+            .always_auto_resize(true)
+            .focus_on_appearing(false)
+            .build(ui, || {
+                for entry in completions {
+                    let label = format!("{}  {}", entry.name, entry.help.description);
+                    if imgui::Selectable::new(&label).build(ui) {
+                        picked = entry
+                            .insert_text
+                            .strip_prefix(word.as_str())
+                            .map(|rest| (entry.name.clone(), rest.to_string()));
+                    }
+                    help_tooltip(ui, entry);
+                }
+            });
+
+        if let Some((name, rest)) = picked {
+            self.insert_text(&rest);
+            self.frecency.record(name);
+            self.autosave_session();
+        }
+    }
+
+    /// Shows a floating window for each detached channel
+    fn detached_ui(&mut self, ui: &imgui::Ui) {
+        let mut reattach = vec![];
+        for channel in self.detached.keys().cloned().collect::<Vec<_>>() {
+            let title = format!("{}##detached{channel}", self.channel_title(channel));
+            let mut opened = true;
+            imgui::Window::new(title)
+                .opened(&mut opened)
+                .size([600.0, 400.0], imgui::Condition::FirstUseEver)
+                .build(ui, || {
+                    if ui.button(self.locale.get("reattach")) {
+                        reattach.push(channel);
+                    }
+                    ui.separator();
+
+                    // Lines are rendered from the shared device, and only the lines scrolled into view
+                    if let Some(device) = self.char_devices.get(&channel) {
+                        ui.child_window(format!("output##{channel}"))
+                            .horizontal_scrollbar(true)
+                            .build(|| {
+                                let stick_to_bottom = ui.scroll_y() >= ui.scroll_max_y();
+                                let output = device.output();
+                                let mut clipper = imgui::ListClipper::new(device.line_count() as i32).begin(ui);
+                                while clipper.step() {
+                                    let shown = clipper.display_start() as usize..clipper.display_end() as usize;
+                                    for line in lines_of(output.as_ref(), shown).split_terminator('\r') {
+                                        ui.text(line);
+                                    }
+                                }
+                                if stick_to_bottom {
+                                    ui.set_scroll_here_y_with_ratio(1.0);
+                                }
+                            });
+                    }
+                });
+
+            if !opened {
+                reattach.push(channel);
+            }
+        }
+
+        for channel in reattach {
+            self.reattach_channel(channel);
+        }
+    }
+
+    /// Shows controls for editing the framing of lines sent to the connection
+    fn framing_ui(ui: &imgui::Ui, locale: &Locale, framing: &mut Framing) {
+        let line_endings = [LineEnding::CrLf, LineEnding::Lf, LineEnding::None];
+        let labels = [locale.get("line_ending_crlf"), locale.get("line_ending_lf"), locale.get("line_ending_none")];
+        let mut selected = line_endings.iter().position(|e| *e == framing.line_ending).unwrap_or_default();
+        if ui.combo_simple_string(locale.get("line_ending"), &mut selected, &labels) {
+            framing.line_ending = line_endings[selected];
+        }
+
+        let prefixes = [None, Some(LengthPrefix::U16), Some(LengthPrefix::U32)];
+        let labels = [locale.get("length_prefix_none"), locale.get("length_prefix_u16"), locale.get("length_prefix_u32")];
+        let mut selected = prefixes.iter().position(|p| *p == framing.length_prefix).unwrap_or_default();
+        if ui.combo_simple_string(locale.get("length_prefix"), &mut selected, &labels) {
+            framing.length_prefix = prefixes[selected];
+        }
+
+        ui.checkbox(locale.get("raw_mode"), &mut framing.raw);
+    }
+
+    /// Shows controls for editing the background of the pane at idx, returns the image path if one was applied
+    ///
+    /// Image paths are kept in paths while they're typed, and only applied on Enter or w/ the apply button
+    fn pane_background_ui(
+        ui: &imgui::Ui,
+        locale: &Locale,
+        theme: &mut Theme,
+        paths: &mut BTreeMap<usize, String>,
+        idx: usize,
+    ) -> Option<String> {
+        let kinds = [
+            locale.get("background_none"),
+            locale.get("background_gradient"),
+            locale.get("background_image"),
+        ];
+        let mut selected = match theme.pane_background(idx) {
+            None => 0,
+            Some(PaneBackground::Gradient { .. }) => 1,
+            Some(PaneBackground::Image { .. }) => 2,
+        };
+
+        if ui.combo_simple_string(format!("{}##{idx}", locale.get("background")), &mut selected, &kinds) {
+            paths.remove(&idx);
+            theme.set_pane_background(
+                idx,
+                match selected {
+                    1 => Some(PaneBackground::Gradient {
+                        top: theme.style().background(),
+                        bottom: theme.style().purple(),
+                        opacity: 0.2,
+                    }),
+                    2 => Some(PaneBackground::Image {
+                        path: String::default(),
+                        opacity: 0.2,
+                    }),
+                    _ => None,
+                },
+            );
+        }
+
+        match theme.pane_background_mut(idx) {
+            Some(PaneBackground::Gradient {
+                top,
+                bottom,
+                opacity,
+            }) => {
+                color_edit_srgb(ui, format!("{}##{idx}", locale.get("gradient_top")), top);
+                color_edit_srgb(ui, format!("{}##{idx}", locale.get("gradient_bottom")), bottom);
+                imgui::Slider::new(format!("{}##{idx}", locale.get("opacity")), 0.0, 1.0)
+                    .build(ui, opacity);
+            }
+            Some(PaneBackground::Image { path, opacity }) => {
+                let typed = paths.entry(idx).or_insert_with(|| path.clone());
+                let entered = ui
+                    .input_text(format!("{}##{idx}", locale.get("background_image")), typed)
+                    .enter_returns_true(true)
+                    .build();
+                ui.same_line();
+                let applied = ui.button(format!("{}##{idx}", locale.get("apply"))) || entered;
+                imgui::Slider::new(format!("{}##{idx}", locale.get("opacity")), 0.0, 1.0)
+                    .build(ui, opacity);
+
+                if applied {
+                    *path = typed.clone();
+                    return Some(path.clone());
+                }
+            }
+            None => {}
+        }
+        None
+    }
+
+    /// Saves the buffer being edited to the current file path
+    fn save_active(&mut self, app_world: &lifec::World) {
+        let path = match self.filename.clone() {
+            Some(filename) if self.file_path.is_empty() => filename,
+            _ => self.file_path.clone(),
+        };
+        if path.is_empty() {
+            event!(Level::WARN, "No file path to save to");
+            return;
+        }
+
+        let channel = self.editing.unwrap_or_default();
+        let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+        self.start_save(channel, path, &tokio_runtime);
+    }
+
+    /// Loads the current file path into the buffer being edited
+    fn open_file(&mut self, app_world: &lifec::World) {
+        let path = self.file_path.clone();
+        if path.is_empty() {
+            event!(Level::WARN, "No file path to open");
+            return;
+        }
+
+        let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+        self.start_load(path, &tokio_runtime);
+    }
+
+    /// Imports the color scheme at the current file path into the theme
+    fn import_active(&mut self, app_world: &lifec::World) {
+        let path = self.file_path.clone();
+        let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+        let _guard = tokio_runtime.enter();
+
+        if let Err(err) = tokio_runtime.block_on(self.import_theme(&path)) {
+            event!(Level::ERROR, "Could not import theme from {path}, {err}");
+        } else {
+            self.update_clear_color(app_world);
+        }
+    }
+
+    /// Asks the user for a path for action, w/ a native dialog if available, otherwise w/ the path prompt
+    fn request_path(&mut self, action: FileAction, app_world: &lifec::World) {
+        match action.pick_path() {
+            DialogResult::Picked(path) => self.perform(action, path, app_world),
+            DialogResult::Cancelled => {}
+            DialogResult::Unavailable => {
+                self.path_prompt = Some((action, self.file_path.clone()));
+            }
+        }
+    }
+
+    /// Performs the file action w/ path
+    fn perform(&mut self, action: FileAction, path: String, app_world: &lifec::World) {
+        self.file_path = path;
+        match action {
+            FileAction::Open => self.open_file(app_world),
+            FileAction::Save => self.save_active(app_world),
+            FileAction::ImportTheme => self.import_active(app_world),
+            FileAction::ExportTheme(format) => {
+                if let Some(theme) = self.theme.as_ref() {
+                    let path = self.file_path.clone();
+                    let exported = theme.export(format);
+
+                    let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+                    let _guard = tokio_runtime.enter();
+
+                    if let Err(err) = tokio_runtime.block_on(tokio::fs::write(&path, exported)) {
+                        event!(Level::ERROR, "Could not export theme to {path}, {err}");
+                    }
+                }
+            }
+            FileAction::LoadThemeFile => match Theme::load_from(&self.file_path) {
+                Ok(mut theme) => {
+                    theme.set_style(self.color_theme());
+                    self.apply_theme(theme);
+                    if let Some(color) = self
+                        .theme
+                        .as_ref()
+                        .and_then(|t| t.get_color(Token::Custom("background".to_string())))
+                    {
+                        self.background = *color;
+                        self.update_clear_color(app_world);
+                    }
+                }
+                Err(err) => {
+                    event!(Level::ERROR, "Could not load theme from {}, {err}", self.file_path);
+                }
+            },
+            FileAction::SaveThemeFile => {
+                if let Some(Err(err)) = self.theme.as_ref().map(|t| t.save_to(&self.file_path)) {
+                    event!(Level::ERROR, "Could not save theme to {}, {err}", self.file_path);
+                }
+            }
+        }
+    }
+
+    /// Writes the background color to the clear color resource
+    fn update_clear_color(&self, app_world: &lifec::World) {
+        let [r, g, b, a] = self.background;
+        let mut clear_color = app_world.write_resource::<wgpu::Color>();
+        let clear_color = clear_color.deref_mut();
+        *clear_color = wgpu::Color {
+            r: r.into(),
+            g: g.into(),
+            b: b.into(),
+            a: a.into(),
+        };
+    }
+
+    /// Draws the backgrounds of panes set by the theme
+    ///
+    /// The background renderer is created the first time a pane has a background
+    pub fn render_backgrounds(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        let (width, height) = self.surface_size;
+        let mut backgrounds = match self.theme.as_ref() {
+            Some(theme) => self
+                .layout
+                .panes(self.layout_area(width, height))
+                .into_iter()
+                .enumerate()
+                .filter_map(|(idx, (rect, _))| {
+                    theme.pane_background(idx).map(|b| (rect, b.clone()))
+                })
+                .collect::<Vec<_>>(),
+            None => return,
+        };
+
+        // Borders and title bars are drawn over the backgrounds
+        let style = self.color_theme();
+        let title_bar = mix(style.background(), style.foreground(), 0.1);
+        for decoration in self.pane_decorations() {
+            let border = if decoration.focused {
+                style.cyan()
+            } else {
+                mix(style.background(), style.foreground(), 0.25)
+            };
+            backgrounds.extend(decoration.quads(title_bar, border));
+        }
+
+        // The minimap is drawn like a title bar, w/ the part visible in the editor highlighted
+        if let (Some((minimap, strip)), Some((lines, first, visible))) = (self.minimap_area(), self.editor_viewport()) {
+            let solid = |rect: Rect, color: [f32; 4]| {
+                (
+                    rect,
+                    PaneBackground::Gradient {
+                        top: color,
+                        bottom: color,
+                        opacity: 1.0,
+                    },
+                )
+            };
+            backgrounds.push(solid(strip, title_bar));
+            backgrounds.push(solid(
+                minimap.viewport(strip, lines, first, visible),
+                mix(style.background(), style.foreground(), 0.25),
+            ));
+        }
+
+        if backgrounds.is_empty() {
+            return;
+        }
+
+        if self.background_renderer.is_none() {
+            self.background_renderer = Some(self.startup.time("background renderer", || {
+                BackgroundRenderer::new(device, queue, format)
+            }));
+        }
+
+        if let Some(renderer) = self.background_renderer.as_mut() {
+            renderer.draw(device, queue, encoder, view, self.surface_size, &backgrounds);
+        }
+    }
+
+    /// Finishes initializing resources that were deferred by on_render_init, returns true once everything is ready
+    ///
+    /// The glyph brush is built w/ depth if the host provides a depth view, and rebuilt if that changes
+    fn finish_init(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat, depth: bool) -> bool {
+        if self.theme.is_none() {
+            self.theme = Some(self.startup.time("theme", || default_theme(Arc::new(DefaultTheme))));
+        }
+
+        if self.brush.is_none() {
+            let loaded = match self.font_loader.take() {
+                Some(loader) if loader.is_finished() => match loader.join() {
+                    Ok(loaded) => loaded.map_err(|err| err.to_string()),
+                    Err(_) => Err("font loader panicked".to_string()),
+                },
+                Some(loader) => {
+                    self.font_loader = Some(loader);
+                    return false;
+                }
+                None if self.font_error.is_some() => return false,
+                None => Err("font loader wasn't started".to_string()),
+            };
+
+            // Falls back to parsing the default font on the render thread, if it still can't be parsed the error is shown once
+            let loaded = match loaded {
+                Ok(loaded) => Some(loaded),
+                Err(err) => {
+                    event!(Level::ERROR, "Could not load font, {err}, falling back to the default font");
+                    match load_default_font() {
+                        Ok(loaded) => Some(loaded),
+                        Err(err) => {
+                            event!(Level::ERROR, "Could not load the default font, {err}");
+                            self.font_error = Some(err.to_string());
+                            self.show_diagnostics = true;
+                            None
+                        }
+                    }
+                }
+            };
+
+            match loaded {
+                Some((inconsolata, parse_time)) => {
+                    self.startup.record("font", parse_time);
+                    self.font = Some(inconsolata.clone());
+                    self.update_glyph_metrics();
+
+                    let glyph_brush = self.startup.time("glyph brush", || {
+                        TextBrush::build(inconsolata, device, format, depth)
+                    });
+                    self.brush = Some(glyph_brush);
+
+                    event!(Level::INFO, "Shell initialized in {:?}", self.startup.total());
+                }
+                None => return false,
+            }
+        }
+
+        if self.brush.as_ref().map(|b| b.has_depth() != depth).unwrap_or_default() {
+            if let Some(font) = self.font.clone() {
+                event!(Level::DEBUG, "Depth view changed, rebuilding glyph brush w/ depth: {depth}");
+                self.brush = Some(TextBrush::build(font, device, format, depth));
+            }
+        }
+
+        true
+    }
+
+    /// Loads the theme from the `theme` block in the app world, returns false if there isn't a theme block
+    ///
+    /// The block can define colors, i.e. `define keyword color .text blue`, and add `font_size` (.int),
+    /// `prompt` (.text), and `cursor` (.text underscore, block, or bar)
+    pub fn reload_theme(&mut self, app_world: &lifec::World) -> bool {
+        match find_theme_block(app_world) {
+            Some(tc) => {
+                self.theme_block = Some(block_content(&tc));
+                self.apply_theme(Theme::new_with_style(tc, self.color_theme()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the revision of the thunk contexts in the app world, blocks are only read again once it changes
+    ///
+    /// Blocks dispatched from the shell bump the revision, blocks added by the app are noticed by the number of contexts
+    fn world_revision(&self, app_world: &lifec::World) -> (u64, usize) {
+        let contexts = app_world.read_component::<ThunkContext>();
+        (self.world_revision, (&contexts).join().count())
+    }
+
+    /// Applies changes to the `theme` block in the app world, if there are any
+    ///
+    /// Colors changed in the shell, i.e. w/ the color editor, are kept until the block itself changes
+    fn update_theme(&mut self, app_world: &lifec::World) {
+        let revision = self.world_revision(app_world);
+        if self.theme_read.replace(revision) == Some(revision) {
+            return;
+        }
+
+        let tc = match find_theme_block(app_world) {
+            Some(tc) => tc,
+            None => return,
+        };
+
+        let content = block_content(&tc);
+        if self.theme_block.as_ref() == Some(&content) {
+            return;
+        }
+
+        let first = self.theme_block.replace(content).is_none();
+        let theme = Theme::new_with_style(tc, self.color_theme());
+        if !self.theme.as_ref().map(|t| t.same_settings(&theme)).unwrap_or_default() {
+            event!(Level::DEBUG, "Theme block changed, applying theme");
+            self.apply_theme(theme);
+        }
+
+        // Colors saved in the session were edited on top of the block
+        if first {
+            let colors = std::mem::take(&mut self.restored_colors);
+            self.set_colors(colors);
+        }
+    }
+
+    /// Returns the style of the current theme
+    pub fn color_theme(&self) -> Arc<dyn ColorTheme> {
+        match self.theme.as_ref() {
+            Some(theme) => theme.style().clone(),
+            None => Arc::new(DefaultTheme),
+        }
+    }
+
+    fn apply_theme(&mut self, theme: Theme) {
+        match self.theme.as_mut() {
+            Some(current) => current.apply(theme),
+            None => self.theme = Some(theme),
+        }
+        self.update_glyph_metrics();
+    }
+
+    /// Applies the scale factor to the theme, and measures the glyph metrics at the resulting font size
+    fn update_glyph_metrics(&mut self) {
+        if let Some(theme) = self.theme.as_mut() {
+            theme.set_scale_factor(self.scale_factor);
+        }
+
+        if let Some(font) = self.font.as_ref() {
+            let font_size = self.theme.as_ref().map(|t| t.font_size()).unwrap_or(DEFAULT_FONT_SIZE * self.scale_factor);
+            self.glyph_metrics = GlyphMetrics::from_font(font, font_size);
+        }
+    }
+
+    /// Returns the shell w/ the window's initial scale factor, later changes are picked up from ScaleFactorChanged events
+    pub fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        self.set_scale_factor(scale_factor);
+        self
+    }
+
+    /// Sets the scale factor of the window, text and the shell's padding are scaled by it
+    ///
+    /// Set from the window's ScaleFactorChanged events, so that the shell stays readable on hiDPI monitors
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        if scale_factor <= 0.0 {
+            return;
+        }
+
+        self.scale_factor = scale_factor;
+        self.update_glyph_metrics();
+        self.reflow();
+    }
+
+    /// Returns the scale factor of the window
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Returns a size in pixels at a scale factor of 1.0, scaled to the window's scale factor
+    fn scaled(&self, pixels: f32) -> f32 {
+        pixels * self.scale_factor
+    }
+
+    /// Returns the height of pane title bars, scaled w/ the window
+    fn title_height(&self) -> f32 {
+        self.scaled(PANE_TITLE_HEIGHT)
+    }
+
+    /// Lays out the shell again after the surface was resized or rescaled
+    ///
+    /// Wrapped lines are laid out from the new size on the next frame, views that were fit to the old size are fit again
+    fn reflow(&mut self) {
+        if self.zoom.take().is_some() {
+            self.toggle_zoom();
+        }
+
+        let channels = self
+            .char_devices
+            .iter()
+            .filter(|(_, d)| d.grid().is_some())
+            .map(|(c, _)| *c)
+            .collect::<Vec<_>>();
+        for channel in channels {
+            self.set_grid_mode(channel, true);
+        }
+    }
+
+    /// Applies settings, i.e. from the `shell` block in the app world, unknown layout or theme names are skipped
+    pub fn apply_settings(&mut self, settings: ShellSettings, app_world: &lifec::World) {
+        event!(Level::DEBUG, "Applying shell settings, {:?}", settings);
+        if let Some(preset) = settings.layout.as_ref() {
+            match PaneLayout::preset(preset) {
+                Some(layout) => self.layout = layout,
+                None => event!(Level::WARN, "Unknown layout {preset}"),
+            }
+        }
+
+        if settings.theme.is_some() || settings.font_size.is_some() {
+            if self.theme.is_none() {
+                self.theme = Some(default_theme(Arc::new(DefaultTheme)));
+            }
+
+            if let Some(name) = settings.theme.as_ref() {
+                match BuiltinTheme::from_name(name) {
+                    Some(builtin) => self.switch_theme(builtin, app_world),
+                    None => event!(Level::WARN, "Unknown theme {name}"),
+                }
+            }
+
+            if let (Some(font_size), Some(theme)) = (settings.font_size, self.theme.as_mut()) {
+                theme.set_font_size(font_size);
+                self.update_glyph_metrics();
+            }
+        }
+
+        if let Some(address) = settings.address {
+            self.address = Some(address.clone());
+            if settings.connect {
+                self.editing = Some(0);
+
+                let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+                self.start_connect(address, &tokio_runtime);
+            }
+        }
+    }
+
+    /// Returns how long each stage of initialization took
+    pub fn startup_timings(&self) -> &StartupTimings {
+        &self.startup
+    }
+
+    /// Returns the counts of the `.text` value under the cursor of the char_device being edited
+    pub fn text_counts(&self) -> Option<TextCounts> {
+        let device = self.editing.and_then(|e| self.char_devices.get(&e))?;
+        let line = device.get_current_line()?;
+        let offset = device.before_cursor().as_ref().rsplit('\r').next().map(str::len)?;
+        text_value_at(&line, offset).map(TextCounts::of)
+    }
+
+    /// Renders the status line
+    pub fn render_status(&mut self, config: &SurfaceConfiguration) {
+        let mut status = format!(
+            "{} | {}",
+            self.filename.clone().unwrap_or(self.locale.get("no_file").to_string()),
+            self.channel_label(self.channel as u32)
+        );
+
+        if let Some(target) = self.command_target {
+            status = format!("{status} | {} {}", self.locale.get("commands_to"), self.channel_title(target));
+        }
+
+        if let Some(TextCounts { chars, words, bytes }) = self.text_counts() {
+            status = format!(
+                "{status} | {chars} {} {words} {} {bytes} {}",
+                self.locale.get("chars"),
+                self.locale.get("words"),
+                self.locale.get("bytes")
+            );
+        }
+
+        let state = self.connection_state();
+        match state {
+            ConnectionState::Connected => {
+                let peer = self.connection.as_ref().map(|c| c.peer()).unwrap_or_default();
+                status = format!("{status} | {} {peer}", self.locale.get(state.label_key()));
+            }
+            ConnectionState::Connecting => {
+                let address = self.connecting().unwrap_or_default();
+                status = format!("{status} | {} {address}", self.locale.get("connecting_to"));
+            }
+            ConnectionState::Retrying { attempt } => {
+                let max_retries = self.reconnect.policy().max_retries;
+                status = format!("{status} | {} {attempt}/{max_retries}", self.locale.get(state.label_key()));
+                if let Some(next_retry) = self.reconnect.next_retry() {
+                    let wait = next_retry.saturating_duration_since(self.clock.now());
+                    status = format!("{status} ({})", format_elapsed(wait));
+                }
+            }
+            ConnectionState::Closed => {
+                if let Some(err) = self.connection_error() {
+                    status = format!("{status} | {} {err}", self.locale.get("could_not_connect"));
+                }
+            }
+        }
+
+        // Notifications are dismissed once their channel is active
+        let active = self.channel as u32;
+        self.notifications.retain(|n| n.channel != active);
+        if let Some(FocusRequest { channel, reason }) = self.notifications.last() {
+            status = format!(
+                "{status} | {} {}",
+                self.channel_title(*channel),
+                reason.clone().unwrap_or(self.locale.get("needs_attention").to_string())
+            );
+        }
+
+        if let Some(progress) = self.pending_progress() {
+            status = format!("{status} | {} {progress}%", self.locale.get("processing"));
+        }
+
+        if self.backlog.dropped() > 0 {
+            status = format!("{status} | {} {}", self.backlog.dropped(), self.locale.get("dropped_bytes"));
+        }
+
+        let (position, font_size) = ((self.scaled(10.0), self.scaled(120.0)), self.scaled(30.0));
+        if let Some(glyph_brush) = self.brush.as_mut() {
+            glyph_brush.queue(Section {
+                screen_position: position,
+                bounds: (config.width as f32, config.height as f32),
+                text: vec![Text::new(&status)
+                    .with_color([1.0, 1.0, 1.0, 0.4])
+                    .with_scale(font_size)
+                    .with_z(1.0)],
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Returns the border and title bar of each pane
+    ///
+    /// The pane the user last clicked is focused, titles of panes that commands are routed to are marked
+    pub fn pane_decorations(&self) -> Vec<PaneDecoration> {
+        let (width, height) = self.surface_size;
+        self.layout
+            .panes(self.layout_area(width, height))
+            .into_iter()
+            .map(|(rect, content)| {
+                let (title, routed) = match content {
+                    PaneContent::Editor => (
+                        self.filename.clone().unwrap_or(self.locale.get("editor").to_string()),
+                        false,
+                    ),
+                    PaneContent::ActiveChannel => (
+                        self.channel_label(self.channel as u32),
+                        self.command_target == Some(self.channel as u32),
+                    ),
+                    PaneContent::Channel(channel) => {
+                        (self.channel_label(channel), self.command_target == Some(channel))
+                    }
+                };
+                let title = if routed {
+                    format!("{} {title}", self.locale.get("commands_to"))
+                } else {
+                    title
+                };
+
+                PaneDecoration {
+                    rect,
+                    title,
+                    focused: self.focused_pane == content,
+                    title_height: self.title_height(),
+                }
+            })
+            .collect()
+    }
+
+    /// Renders the title of each pane in its title bar
+    pub fn render_pane_titles(&mut self) {
+        let style = self.color_theme();
+        let decorations = self.pane_decorations();
+        let (padding, font_size) = (self.scaled(8.0), self.scaled(PANE_TITLE_HEIGHT - 4.0));
+        if let Some(glyph_brush) = self.brush.as_mut() {
+            for decoration in decorations.iter() {
+                let color = if decoration.focused {
+                    style.foreground()
+                } else {
+                    mix(style.background(), style.foreground(), 0.6)
+                };
+
+                let bar = decoration.title_bar();
+                glyph_brush.queue(Section {
+                    screen_position: (bar.x + padding, bar.y + padding / 4.0),
+                    bounds: ((bar.width - padding * 2.0).max(0.0), bar.height),
+                    text: vec![Text::new(&decoration.title)
+                        .with_color(color)
+                        .with_scale(font_size)
+                        .with_z(1.0)],
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    /// Returns the styled spans of the char_device at channel, from the render cache if nothing changed
+    ///
+    /// If visible is true, only the visible output of the device is styled. If lines is set, only those lines are lexed,
+    /// w/ spans that are still relative to the start of the output
+    fn styled_spans<G: Grammer>(
+        &mut self,
+        channel: u32,
+        visible: bool,
+        lines: Option<Range<usize>>,
+    ) -> Option<StyledSpans> {
+        let device = self.char_devices.get(&channel)?;
+        let theme = self.theme.as_ref()?;
+
+        Some(self.render_cache.get_or_update::<G>(
+            channel,
+            visible,
+            lines.clone(),
+            device.revision(),
+            theme.revision(),
+            || {
+                let (visible_output, output) = (device.visible_output(), device.output());
+                let output = if visible { visible_output.as_ref() } else { output.as_ref() };
+                match lines {
+                    Some(lines) => {
+                        // Lexing starts at the block the lines start in, so that fences above the lines are seen
+                        let first = block_start(output, lines.start);
+                        let start = line_start(output, first);
+                        let mut spans = theme.styled_spans::<G>(lines_of(output, first..lines.end));
+                        for (span, _) in spans.iter_mut() {
+                            *span = span.start + start..span.end + start;
+                        }
+                        spans
+                    }
+                    None => theme.styled_spans::<G>(output),
+                }
+            },
+        ))
+    }
+
+    /// Returns the styled spans of the visible output of channel, lexed w/ the grammer named by its channel info
+    ///
+    /// Channels without a grammer, or w/ a grammer that isn't known, are lexed as Plain. Only the lines that fit in
+    /// rows, and a margin past them are lexed
+    fn channel_spans(&mut self, channel: u32, rows: usize) -> Option<StyledSpans> {
+        let name = self.channel_info(channel).and_then(|i| i.grammer.clone()).unwrap_or_default();
+        let lines = Some(visible_lines(0, rows));
+        match name.as_str() {
+            n if n == Runmd::name() => self.styled_spans::<Runmd>(channel, true, lines),
+            n if n == Sh::name() => self.styled_spans::<Sh>(channel, true, lines),
+            n if n == Json::name() => self.styled_spans::<Json>(channel, true, lines),
+            n if n == Toml::name() => self.styled_spans::<Toml>(channel, true, lines),
+            n if n == Markdown::name() => self.styled_spans::<Markdown>(channel, true, lines),
+            n if n == LogGrammer::name() => self.styled_spans::<LogGrammer>(channel, true, lines),
+            n if n == CommandGrammer::name() => self.styled_spans::<CommandGrammer>(channel, true, lines),
+            _ => self.styled_spans::<Plain>(channel, true, lines),
+        }
+    }
+
+    /// Renders a miniature view of the buffer being edited in the minimap strip
+    pub fn render_minimap(&mut self) {
+        let (minimap, strip) = match self.minimap_area() {
+            Some(area) => area,
+            None => return,
+        };
+
+        // When connected, the editor is a prompt for the remote, so it's highlighted as shell commands
+        let spans = if self.connection.is_some() {
+            self.styled_spans::<Sh>(self.editing.unwrap_or_default(), false, None)
+        } else {
+            self.styled_spans::<Runmd>(self.editing.unwrap_or_default(), false, None)
+        };
+        if let (Some(glyph_brush), Some(active), Some(theme)) = self.prepare_render_input() {
+            let output = active.output();
+            let spans = spans.as_deref().unwrap_or_default();
+            let scale = minimap.row_height(strip, active.line_count());
+
+            // Lines are cut off at the edge of the strip instead of wrapping, so that rows stay aligned w/ lines
+            let max_len = (strip.width / (scale * 0.5).max(1.0)) as usize;
+            let marker_color = theme
+                .get_color(Token::Comment)
+                .copied()
+                .unwrap_or_else(|| theme.style().foreground());
+            let truncated = truncate_lines(output.as_ref(), spans, max_len.max(1), marker_color);
+            let (output, spans) = match truncated.as_ref() {
+                Some(truncated) => (truncated.source.as_str(), truncated.spans.as_slice()),
+                None => (output.as_ref(), spans),
+            };
 
-            // Renders the cursor
             glyph_brush.queue(Section {
-                screen_position: (90.0, 180.0),
-                bounds: (config.width as f32 / 2.0, config.height as f32),
-                text: theme.render_cursor(prompt_enabled)(
-                    active.before_cursor().as_ref(),
-                    active.after_cursor().as_ref(),
-                ),
+                screen_position: (strip.x, strip.y),
+                bounds: (strip.width, strip.height),
+                text: theme
+                    .render_spans(output, spans, false)
+                    .into_iter()
+                    .map(|text| text.with_scale(scale))
+                    .collect(),
                 layout: Layout::Wrap {
                     line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
                     h_align: HorizontalAlign::Left,
                     v_align: VerticalAlign::Top,
                 },
             });
+        }
+    }
 
-            if !prompt_enabled {
-                // Renders line numbers
-                glyph_brush.queue(Section {
-                    screen_position: (10.0, 180.0),
-                    bounds: (config.width as f32 / 2.0, config.height as f32),
-                    text: {
-                        vec![Text::new(active.line_nos().as_ref())
-                            .with_color([1.0, 1.0, 1.0, 0.4])
-                            .with_scale(40.0)
-                            .with_z(1.0)]
-                    },
-                    ..Default::default()
-                });
+    /// Renders the cells of a grid at position, w/ cells that have a background drawn behind them as blocks
+    fn render_grid(
+        glyph_brush: &mut TextBrush,
+        grid: &TerminalGrid,
+        theme: &Theme,
+        position: (f32, f32),
+        (line_height, advance): (f32, f32),
+    ) {
+        let (rows, cols) = grid.size();
+        let mut foreground = vec![];
+        let mut background = vec![];
+        for row in 0..rows {
+            let chars = grid.row_text(row).chars().collect::<Vec<_>>();
+            for (range, style) in grid.runs(row) {
+                let (fg, bg) = style.colors(theme.style().as_ref());
+                let mut text = chars[range.clone()].iter().collect::<String>();
+                let mut blocks = match bg {
+                    Some(_) => "█".repeat(range.len()),
+                    None => " ".repeat(range.len()),
+                };
+                if range.end == cols && row + 1 < rows {
+                    text.push('\r');
+                    blocks.push('\r');
+                }
+                foreground.push((text, fg));
+                background.push((blocks, bg.unwrap_or([0.0; 4])));
             }
         }
-    }
 
-    /// Renders the currently active channel
-    pub fn render_channel(&mut self, config: &SurfaceConfiguration) {
-        if let (Some(glyph_brush), Some(active), Some(theme)) =
-            self.prepare_render_output(self.channel as u32)
-        {
+        // Rows are broken explicitly, the extra column keeps a full row from wrapping early
+        let bounds = ((cols + 1) as f32 * advance, rows as f32 * line_height);
+        for (runs, z) in [(&background, 0.5), (&foreground, 0.8)] {
             glyph_brush.queue(Section {
-                screen_position: ((config.width as f32) / 2.0 + 60.0, 180.0),
-                bounds: (config.width as f32 / 2.0, config.height as f32),
-                text: theme.render::<Plain>(active.output().as_ref(), false),
-                layout: Layout::Wrap {
-                    line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
-                    h_align: HorizontalAlign::Left,
-                    v_align: VerticalAlign::Top,
-                },
+                screen_position: position,
+                bounds,
+                text: runs
+                    .iter()
+                    .map(|(text, color)| Text::new(text).with_color(*color).with_scale(theme.font_size()).with_z(z))
+                    .collect(),
+                ..Default::default()
+            });
+        }
+
+        if grid.cursor_visible() {
+            let (row, col) = grid.cursor();
+            glyph_brush.queue(Section {
+                screen_position: (position.0 + col as f32 * advance, position.1 + row as f32 * line_height),
+                bounds: (advance, line_height),
+                text: vec![Text::new("█")
+                    .with_color(mix(theme.style().background(), theme.style().foreground(), 0.5))
+                    .with_scale(theme.font_size())
+                    .with_z(0.6)],
+                ..Default::default()
             });
         }
     }
+
+    /// Renders channels in each pane that displays a channel
+    pub fn render_channel(&mut self, config: &SurfaceConfiguration) {
+        let area = self.layout_area(config.width as f32, config.height as f32);
+        let mut batch = std::mem::take(&mut self.text_batch);
+        let line_prefixes = std::mem::take(&mut self.line_prefixes);
+        for (rect, content) in self.layout.panes(area) {
+            let rect = pane_content(rect, self.title_height());
+            let channel = match content {
+                PaneContent::Editor => continue,
+                PaneContent::ActiveChannel => self.channel as u32,
+                PaneContent::Channel(channel) => channel,
+            };
+
+            let highlighted = self.triggers.highlighted_lines(channel).cloned();
+            let prefixes = line_prefixes.get(&channel).filter(|_| self.line_decoration.is_some());
+            let max_line_len = self.max_line_len;
+
+            // Progress is pinned to the bottom of the pane, below the output
+            let progress = self.progress.get(&channel).cloned();
+            let (line_height, advance) = (self.glyph_metrics.line_height, self.glyph_metrics.advance);
+            let output_height = match progress {
+                Some(_) => (rect.height - line_height).max(0.0),
+                None => rect.height,
+            };
+
+            // Only the lines that fit in the pane, and a margin past them are lexed and queued
+            let visible_rows = if line_height > 0.0 { (output_height / line_height) as usize } else { 0 };
+            let spans = self.channel_spans(channel, visible_rows);
+            let (rows, cols) = self.grid_size(Rect { height: output_height, ..rect });
+            let padding = self.scaled(CHANNEL_PADDING);
+            if let (Some(glyph_brush), Some(active), Some(theme)) =
+                self.prepare_render_output(channel)
+            {
+                if let Some(progress) = progress {
+                    let columns = if advance > 0.0 {
+                        ((rect.width - padding) / advance) as usize
+                    } else {
+                        0
+                    };
+                    let bar = progress.bar(columns.saturating_sub(20).min(40));
+                    glyph_brush.queue(Section {
+                        screen_position: (rect.x + padding, rect.y + output_height),
+                        bounds: ((rect.width - padding).max(0.0), line_height),
+                        text: vec![Text::new(&bar)
+                            .with_color(theme.style().cyan())
+                            .with_scale(theme.font_size())
+                            .with_z(0.8)],
+                        ..Default::default()
+                    });
+                }
+
+                // In grid mode, the grid is rendered instead of the buffer, and resized to fit the pane
+                if active.grid().is_some() {
+                    active.enable_grid(rows, cols);
+                    if let Some(grid) = active.grid() {
+                        let position = (rect.x + padding, rect.y);
+                        Self::render_grid(glyph_brush, grid, theme, position, (line_height, advance));
+                    }
+                    continue;
+                }
+
+                let visible = active.visible_output();
+                let visible = lines_of(visible.as_ref(), visible_lines(0, visible_rows));
+                let spans = spans.as_deref().unwrap_or_default();
+
+                // Long lines are cut off, so they don't bloat the glyph queue
+                let marker_color = theme
+                    .get_color(Token::Comment)
+                    .copied()
+                    .unwrap_or_else(|| theme.style().foreground());
+                let truncated = truncate_lines(visible, spans, max_line_len, marker_color);
+                let (output, spans) = match truncated.as_ref() {
+                    Some(truncated) => (truncated.source.as_str(), truncated.spans.as_slice()),
+                    None => (visible, spans),
+                };
+
+                // Line prefixes are rendered in a dimmed column left of the output, so they can be told apart from it
+                let first_line = active.trimmed_lines() + active.scroll();
+                let line_count = output.split('\r').count();
+                let prefix_width = prefixes
+                    .map(|p| p.width(first_line..first_line + line_count) as f32 * advance)
+                    .unwrap_or_default();
+                if let Some(prefixes) = prefixes.filter(|_| prefix_width > 0.0) {
+                    let columns = if advance > 0.0 {
+                        ((rect.width - padding - prefix_width) / advance) as usize
+                    } else {
+                        usize::MAX
+                    };
+                    let column = prefixes.column(first_line, output, columns);
+                    let dim = mix(theme.style().background(), theme.style().foreground(), 0.5);
+                    glyph_brush.queue(Section {
+                        screen_position: (rect.x + padding, rect.y),
+                        bounds: (prefix_width + advance, output_height),
+                        text: vec![Text::new(&column).with_color(dim).with_scale(theme.font_size())],
+                        ..Default::default()
+                    });
+                }
+                let padding = padding + prefix_width;
+
+                // Highlights lines matched by a trigger
+                if let Some(lines) = highlighted {
+                    let mut start = 0;
+                    let mut spans = vec![];
+                    for (line_no, line) in output.split('\r').enumerate() {
+                        if lines.contains(&(line_no + active.scroll() + active.trimmed_lines())) {
+                            spans.push(start..start + line.len());
+                        }
+                        start += line.len() + 1;
+                    }
+
+                    let overlay = highlight_overlay(output, &spans);
+                    glyph_brush.queue(Section {
+                        screen_position: (rect.x + padding, rect.y),
+                        bounds: ((rect.width - padding).max(0.0), output_height),
+                        text: theme.render_highlight(Token::Match, &overlay),
+                        layout: Layout::Wrap {
+                            line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                            h_align: HorizontalAlign::Left,
+                            v_align: VerticalAlign::Top,
+                        },
+                    });
+                }
+
+                // Output is queued in chunks of lines, so that only the chunks that changed are laid out again
+                let columns = if advance > 0.0 { ((rect.width - padding) / advance) as usize } else { usize::MAX };
+                for (range, row) in line_chunks(output, columns) {
+                    let offset = row as f32 * line_height;
+                    if offset >= output_height {
+                        break;
+                    }
+
+                    let mut text = batch.take();
+                    theme.render_spans_into(&mut text, output, spans, range, false);
+                    let section = Section {
+                        screen_position: (rect.x + padding, rect.y + offset),
+                        bounds: ((rect.width - padding).max(0.0), output_height - offset),
+                        text,
+                        layout: Layout::Wrap {
+                            line_breaker: BuiltInLineBreaker::AnyCharLineBreaker,
+                            h_align: HorizontalAlign::Left,
+                            v_align: VerticalAlign::Top,
+                        },
+                    };
+                    glyph_brush.queue(&section);
+                    batch.recycle(section.text);
+                }
+            }
+        }
+        self.text_batch = batch;
+        self.line_prefixes = line_prefixes;
+    }
 }
 
 impl Extension for Shell {
     fn configure_app_world(_world: &mut lifec::World) {
         _world.register::<ShellChannel>();
+        _world.register::<ShellInput>();
 
         _world.insert(wgpu::Color {
             r: 0.02122,
@@ -292,15 +5529,122 @@ impl Extension for Shell {
 
     fn on_window_event(
         &'_ mut self,
-        _app_world: &lifec::World,
+        app_world: &lifec::World,
         event: &'_ lifec::editor::WindowEvent<'_>,
     ) {
+        match event {
+            lifec::editor::WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = *modifiers;
+            }
+            lifec::editor::WindowEvent::CloseRequested | lifec::editor::WindowEvent::Destroyed => {
+                self.save_session_now();
+            }
+            lifec::editor::WindowEvent::Resized(size) => {
+                self.surface_size = (size.width as f32, size.height as f32);
+                self.reflow();
+            }
+            lifec::editor::WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size } => {
+                self.surface_size = (new_inner_size.width as f32, new_inner_size.height as f32);
+                self.set_scale_factor(*scale_factor as f32);
+            }
+            _ => {}
+        }
+
+        // Otherwise characters typed into an imgui input field would also be typed into the editor
+        let keyboard_event = matches!(
+            event,
+            lifec::editor::WindowEvent::ReceivedCharacter(_) | lifec::editor::WindowEvent::KeyboardInput { .. }
+        );
+        if keyboard_event && !self.has_keyboard_focus() {
+            return;
+        }
+
+        // Enter in the search window jumps to the next match, so it's consumed before it can reach the editor
+        let enter = match event {
+            lifec::editor::WindowEvent::ReceivedCharacter(c) => *c == '\r' || *c == '\n',
+            lifec::editor::WindowEvent::KeyboardInput { input, .. } => input.virtual_keycode == Some(VirtualKeyCode::Return),
+            _ => false,
+        };
+        if enter && self.search_focused {
+            return;
+        }
+
+        if self.on_mouse_event(event) {
+            return;
+        }
+
+        if self.modifiers.ctrl() {
+            if let lifec::editor::WindowEvent::KeyboardInput { input, .. } = event {
+                if let (Some(key), ElementState::Pressed) = (input.virtual_keycode, input.state) {
+                    match key {
+                        VirtualKeyCode::S if self.filename.is_some() => self.save_active(app_world),
+                        VirtualKeyCode::S => self.request_path(FileAction::Save, app_world),
+                        VirtualKeyCode::O => self.request_path(FileAction::Open, app_world),
+                        VirtualKeyCode::Z if self.modifiers.shift() => self.redo(),
+                        VirtualKeyCode::Z => self.undo(),
+                        VirtualKeyCode::Y => self.redo(),
+                        VirtualKeyCode::K => self.kill_line(),
+                        VirtualKeyCode::D => self.duplicate_line(),
+                        VirtualKeyCode::F if self.modifiers.shift() => self.toggle_global_search(),
+                        VirtualKeyCode::I if self.modifiers.shift() => self.toggle_inspector(),
+                        VirtualKeyCode::F => self.search.open = !self.search.open,
+                        VirtualKeyCode::B => self.toggle_zoom(),
+                        VirtualKeyCode::Return => {
+                            self.send_current_block(app_world);
+                        }
+                        VirtualKeyCode::E => self.expand_line(),
+                        VirtualKeyCode::P => self.toggle_palette(),
+                        VirtualKeyCode::R if self.modifiers.shift() => self.pending_register = Some(RegisterOp::Yank),
+                        VirtualKeyCode::R => self.pending_register = Some(RegisterOp::Paste),
+                        VirtualKeyCode::C if self.modifiers.shift() => {
+                            self.copy_block();
+                        }
+                        VirtualKeyCode::V if self.modifiers.shift() => {
+                            self.paste_block();
+                        }
+                        VirtualKeyCode::Left if self.modifiers.shift() => self.extend_block_selection(0, -1),
+                        VirtualKeyCode::Right if self.modifiers.shift() => self.extend_block_selection(0, 1),
+                        VirtualKeyCode::Up if self.modifiers.shift() => self.extend_block_selection(-1, 0),
+                        VirtualKeyCode::Down if self.modifiers.shift() => self.extend_block_selection(1, 0),
+                        _ => {}
+                    }
+                }
+            }
+
+            // Control sequences are handled as shortcuts, so they shouldn't be written to the buffer
+            return;
+        }
+
+        // Alt+Up/Down moves the current line
+        if let (true, lifec::editor::WindowEvent::KeyboardInput { input, .. }) = (self.modifiers.alt(), event) {
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::Up) | Some(VirtualKeyCode::Down) => {
+                    if input.state == ElementState::Pressed {
+                        self.move_line(input.virtual_keycode == Some(VirtualKeyCode::Up));
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+
         match (event, self.prepare_render_input()) {
             (lifec::editor::WindowEvent::ReceivedCharacter(char), _) => {
-                if let Some(sender) = &self.byte_tx {
-                    if let Some(editing) = self.editing {
-                        sender.try_send((editing as u32, *char as u8)).ok();
+                if let Some(op) = self.pending_register.take() {
+                    match op {
+                        RegisterOp::Yank => self.yank_line(*char),
+                        RegisterOp::Paste => self.paste_register(*char),
+                    };
+                } else if let Some(editing) = self.editing.filter(|_| !self.editing_read_only()) {
+                    if self.auto_close_typed(editing, *char) {
+                        return;
+                    }
+
+                    let mut bytes = vec![*char as u8];
+                    if *char == '\r' {
+                        bytes.extend(self.indent_at_cursor().bytes());
                     }
+                    self.send_bytes(editing, &bytes);
                 }
             }
             (
@@ -322,13 +5666,8 @@ impl Extension for Shell {
                         winit::event::VirtualKeyCode::Up => {
                             editing.cursor_up();
                         }
-                        winit::event::VirtualKeyCode::Tab => {
-                            if let Some(sender) = &self.byte_tx {
-                                sender.try_send((0, ' ' as u8)).ok();
-                                sender.try_send((0, ' ' as u8)).ok();
-                                sender.try_send((0, ' ' as u8)).ok();
-                                sender.try_send((0, ' ' as u8)).ok();
-                            }
+                        winit::event::VirtualKeyCode::Tab if !editing.read_only() => {
+                            self.send_bytes(0, b"    ");
                         }
                         _ => {}
                     },
@@ -344,64 +5683,22 @@ impl Extension for Shell {
         _surface: &wgpu::Surface,
         _config: &wgpu::SurfaceConfiguration,
         _adapter: &wgpu::Adapter,
-        device: &wgpu::Device,
+        _device: &wgpu::Device,
         _queue: &wgpu::Queue,
     ) {
-        if let Some(inconsolata) =
-            ab_glyph::FontArc::try_from_slice(include_bytes!("Inconsolata-Regular.ttf")).ok()
-        {
-            let glyph_brush = GlyphBrushBuilder::using_font(inconsolata)
-                .depth_stencil_state(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth32Float,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                })
-                .build(&device, wgpu::TextureFormat::Bgra8UnormSrgb);
-
-            self.brush = Some(glyph_brush);
-
-            let (tx, rx) = channel::<(u32, u8)>(300);
-            self.byte_rx = Some(rx);
-            self.byte_tx = Some(tx);
-            if self.char_devices.is_empty() {
-                self.char_devices.insert(0, CharDevice::default());
-            }
-
-            // TODO: This is a temp setting
-            let mut default_context = ThunkContext::default();
-            default_context
-                .as_mut()
-                .define("bracket", "color")
-                .edit_as(Value::TextBuffer("purple".to_string()));
-            default_context
-                .as_mut()
-                .define("operator", "color")
-                .edit_as(Value::TextBuffer("yellow".to_string()));
-            default_context
-                .as_mut()
-                .define("identifier", "color")
-                .edit_as(Value::TextBuffer("red".to_string()));
-            default_context
-                .as_mut()
-                .define("keyword", "color")
-                .edit_as(Value::TextBuffer("blue".to_string()));
-            default_context
-                .as_mut()
-                .define("literal", "color")
-                .edit_as(Value::TextBuffer("green".to_string()));
-            default_context
-                .as_mut()
-                .define("comment", "color")
-                .edit_as(Value::TextBuffer("green".to_string()));
-            default_context
-                .as_mut()
-                .define("whitespace", "color")
-                .edit_as(Value::TextBuffer("yellow".to_string()));
-
-            self.theme = Some(Theme::new_with(default_context));
+        // Font parsing happens off the render thread, the glyph brush and theme are built on the first frame after
+        self.font_loader = Some(std::thread::spawn(load_default_font));
+
+        let (tx, rx) = channel::<(u32, u8)>(300);
+        self.byte_rx = Some(rx);
+        self.byte_tx = Some(tx);
+        let (tx, rx) = channel::<(u32, u8)>(PLUGIN_OUTPUT_CAPACITY);
+        self.plugin_rx = Some(rx);
+        self.plugin_tx = Some(tx);
+        if self.char_devices.is_empty() {
+            self.char_devices.insert(0, CharDevice::default());
         }
+        self.recover_journal();
     }
 
     fn on_render(
@@ -412,132 +5709,228 @@ impl Extension for Shell {
         config: &wgpu::SurfaceConfiguration,
         _adapter: &wgpu::Adapter,
         device: &wgpu::Device,
-        _queue: &wgpu::Queue,
+        queue: &wgpu::Queue,
         encoder: &mut wgpu::CommandEncoder,
         staging_belt: &mut wgpu::util::StagingBelt,
     ) {
+        self.surface_size = (config.width as f32, config.height as f32);
+        if !self.finish_init(device, wgpu::TextureFormat::Bgra8UnormSrgb, depth_view.is_some()) {
+            return;
+        }
+
+        self.hooks.before_render(config);
+        self.update_viewports(config);
+        self.render_backgrounds(device, queue, config.format, encoder, view);
+        self.render_status(config);
+        self.render_pane_titles();
         self.render_input(config);
         self.render_channel(config);
+        self.render_minimap();
 
-        if let Some(depth_view) = depth_view.as_ref() {
-            if let Some(brush) = self.brush.as_mut() {
-                brush
-                    .draw_queued(
-                        device,
-                        staging_belt,
-                        encoder,
-                        view,
-                        wgpu::RenderPassDepthStencilAttachment {
-                            view: depth_view,
-                            depth_ops: Some(wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(-1.0),
-                                store: true,
-                            }),
-                            stencil_ops: Some(wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(0),
-                                store: true,
-                            }),
-                        },
-                        config.width,
-                        config.height,
-                    )
-                    .expect("Draw queued");
-            }
+        if let Some(brush) = self.brush.as_mut() {
+            brush
+                .draw_queued(device, staging_belt, encoder, view, depth_view, config.width, config.height)
+                .expect("Draw queued");
         }
     }
 
     fn on_run(&'_ mut self, app_world: &lifec::World) {
-        let mut send_to_connection = None;
+        if !self.settings_loaded {
+            self.settings_loaded = true;
+            if let Some(settings) = self.initial_settings.take() {
+                self.apply_settings(settings, app_world);
+            }
+
+            // The block is applied last, so that it can override settings from code
+            if let Some(tc) = find_settings_block(app_world) {
+                self.apply_settings(ShellSettings::from_context(&tc), app_world);
+            }
+        }
+
+        self.poll_connecting();
+        self.poll_file_io();
+        #[cfg(feature = "relay")]
+        self.poll_relay();
+        self.poll_reconnect(app_world);
+        self.run_actions(app_world);
+        self.report_window_size(app_world);
+
+        for (channel, path) in self.watcher.changed() {
+            self.reload_file(channel, path);
+        }
+
+        if self.live_theme && self.theme.is_some() {
+            self.update_theme(app_world);
+        }
+        self.update_triggers(app_world);
+
+        // Output of plugins goes through the backlog, so that the overflow policy applies to it too
+        if let Some(rx) = self.plugin_rx.as_mut() {
+            let now = self.clock.now();
+            while let Ok((channel, next)) = rx.try_recv() {
+                self.backlog.queue(channel, next, now);
+            }
+        }
+
+        if let Some(sender) = &self.byte_tx {
+            self.backlog.flush(sender, self.clock.now());
+        }
+
+        // When bulk writes are pending, they share the frame's budget with the byte channel
+        let channel_budget = if self.pending_writes.is_empty() {
+            MAX_BYTES_PER_FRAME
+        } else {
+            MAX_BYTES_PER_FRAME / 2
+        };
+
+        let mut drained = 0;
+        let mut send_to_connection = vec![];
+        let mut send_raw = vec![];
+        let mut route_to_plugin = vec![];
         if let Some(rx) = self.byte_rx.as_mut() {
-            if let Some((channel, next)) = rx.try_recv().ok() {
+            // Receive ahead of the budget, so the drain policy can schedule bytes queued behind a flooding channel
+            while self.drain_queue.len() < MAX_QUEUED_BYTES {
+                match rx.try_recv().ok() {
+                    Some((channel, next)) => self.drain_queue.push(channel, next),
+                    None => break,
+                }
+            }
+
+            let focused = self.focused_channels();
+            let batches = self.drain_queue.take(channel_budget, &focused);
+            drained = batches.iter().map(|(_, bytes)| bytes.len()).sum();
+
+            for (channel, bytes) in batches {
+                if self.muted.contains(&channel) {
+                    continue;
+                }
+
+                let prefix = self
+                    .line_decoration
+                    .filter(|_| channel != 0)
+                    .map(|decoration| decoration.prefix(SystemTime::now(), &self.channel_title(channel)));
+
                 if let Some(char_device) = self.char_devices.get_mut(&channel) {
-                    if self.channel != channel as i32 && channel != 0 {
-                        // TODO: Add this to a history
-                        char_device.take_buffer();
+                    if channel != 0 {
+                        // Only edits made in the editor can be undone
+                        char_device.set_undoable(false);
+                        if char_device.max_lines() != self.max_channel_lines {
+                            char_device.set_max_lines(self.max_channel_lines);
+                        }
                     }
 
-                    char_device.write_char(next);
-                    if char_device.line_count() > 1 && self.connection.is_some() && channel == 0 {
-                        send_to_connection = Some(char_device.take_buffer());
+                    // Prefixes are kept beside the buffer, so that only the output is copied, searched, and logged
+                    if let Some(prefix) = prefix.as_ref() {
+                        let output = char_device.output();
+                        let at_line_start = output.as_ref().is_empty() || output.as_ref().ends_with('\r');
+                        let line = char_device.trimmed_lines() + char_device.line_count().saturating_sub(1);
+                        let prefixes = self.line_prefixes.entry(channel).or_default();
+                        prefixes.record(line, &bytes, at_line_start, prefix);
+                        prefixes.trim(char_device.trimmed_lines());
                     }
 
-                    self.channel = channel as i32;
-                }
-            }
-        }
-
-        if let Some(line) = send_to_connection.take() {
-            if let Some(connection) = self.connection.take() {
-                let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
-                let _ = tokio_runtime.enter();
-
-                self.connection = tokio_runtime.block_on(async move {
-                    event!(Level::TRACE, "Waiting for connection to be writeable");
-                    connection.writable().await.ok();
-                    // Line-endings need to be handled on the receiving end
-                    let message = format!("{}\r\n", line);
-                    match connection.try_write(message.as_bytes()) {
-                        Ok(bytes) => {
-                            event!(Level::TRACE, "Wrote {bytes}");
-                            if bytes != message.len() {
-                                event!(Level::WARN, "Did not write entire message");
-                                todo!("Need to handle partialy sent messages")
-                            }
+                    if let Some(log) = self.channel_logs.get(&channel) {
+                        log.append(&bytes);
+                    }
 
-                            Some(connection)
+                    if channel == BuiltinChannel::Session.id() {
+                        if let Some(session) = self.session.as_mut() {
+                            session.record_received(bytes.len());
                         }
-                        Err(ref e) if e.kind() == tokio::io::ErrorKind::WouldBlock => {
-                            event!(Level::WARN, "Connection is not ready.");
-                            Some(connection)
+                        if let Some(transcript) = self.transcript.as_mut() {
+                            transcript.append_output(&bytes);
                         }
-                        Err(err) => {
-                            // Not actually hazardous but useful for posterity
-                            event!(
-                                Level::WARN,
-                                "Connection to {} closed",
-                                connection
-                                    .local_addr()
-                                    .ok()
-                                    .and_then(|a| Some(a.to_string()))
-                                    .unwrap_or_default()
-                            );
-                            event!(Level::DEBUG, "Error on connection close: {err}");
-                            None
+                    }
+
+                    if self.connection.is_some() && channel == 0 && self.framing.raw {
+                        // Keystrokes are sent as-is, the connection is expected to echo them
+                        send_raw.extend_from_slice(&bytes);
+                    } else if self.connection.is_some() && channel == 0 {
+                        // Each completed line is sent to the connection
+                        for line in bytes.split_inclusive(|b| *b == b'\r') {
+                            char_device.write_bytes(line);
+                            if char_device.line_count() > 1 {
+                                send_to_connection.push(char_device.take_buffer());
+                            }
+                        }
+                    } else if let (Some(target), 0) = (self.command_target, channel) {
+                        // Each completed line is routed to the focused plugin
+                        for line in bytes.split_inclusive(|b| *b == b'\r') {
+                            char_device.write_bytes(line);
+                            if char_device.line_count() > 1 {
+                                route_to_plugin.push((target, char_device.take_buffer()));
+                            }
                         }
+                    } else {
+                        char_device.write_bytes(&bytes);
                     }
-                });
 
-                if self.connection.is_none() {
-                    self.editing = None;
+                    self.channel = channel as i32;
                 }
             }
         }
+
+        self.drain_progress.update(drained, self.drain_queue.len() + self.backlog.len());
+        self.process_pending_writes(MAX_BYTES_PER_FRAME - drained);
+        self.flush_journal();
+        self.flush_session();
+        if let Some(transcript) = self.transcript.as_mut() {
+            transcript.flush();
+        }
+        self.evaluate_triggers();
+        self.update_stopwatches(app_world);
+        self.update_progress(app_world);
+
+        for (channel, line) in route_to_plugin {
+            self.hooks.line_submitted(&line);
+            self.record_command(&line);
+            self.route_command(channel, line);
+        }
+
+        if !send_raw.is_empty() {
+            self.write_to_connection(app_world, send_raw, false);
+        }
+
+        for line in send_to_connection {
+            let line = match self.apply_line_policy(line) {
+                Some(line) => line,
+                None => continue,
+            };
+
+            self.hooks.line_submitted(&line);
+            self.record_command(&line);
+            if let Some(transcript) = self.transcript.as_mut() {
+                transcript.append(format!("> {line}"));
+            }
+
+            match self.framing.frame(&line) {
+                Some(message) => self.write_to_connection(app_world, message, true),
+                None => event!(Level::WARN, "Line is too long for the length prefix, {} bytes", line.len()),
+            }
+        }
     
         self.run_now(app_world);
     }
 
     fn on_ui(&'_ mut self, app_world: &lifec::World, ui: &'_ imgui::Ui<'_>) {
+        self.imgui_wants_keyboard = ui.io().want_capture_keyboard;
+        self.imgui_wants_mouse = ui.io().want_capture_mouse;
+
+        let shell_menu = self.locale.get("shell_menu").to_string();
+        let layout_menu = self.locale.get("layout_menu").to_string();
         ui.main_menu_bar(|| {
-            ui.menu("Shell", || {
+            ui.menu(&shell_menu, || {
                 if let Some(theme) = self.theme.as_mut() {
                     for (token, color) in theme.colors_mut() {
-                        ColorEdit::new(format!("{:?}", token), color).build(ui);
+                        color_edit_srgb(ui, format!("{:?}", token), color);
                     }
                 }
 
-                if ColorEdit::new("Background clear", &mut self.background).build(ui) {
-                    let [r, g, b, a] = self.background;
-                    let mut clear_color = app_world.write_resource::<wgpu::Color>();
-                    let clear_color = clear_color.deref_mut();
-                    *clear_color = wgpu::Color {
-                        r: r.into(),
-                        g: g.into(),
-                        b: b.into(),
-                        a: a.into(),
-                    };
+                if color_edit_srgb(ui, self.locale.get("background_clear"), &mut self.background) {
+                    self.update_clear_color(app_world);
                 }
 
-                if ui.button("Reset colors") {
+                if ui.button(self.locale.get("reset_colors")) {
                     if let Some(theme) = self.theme.as_mut() {
                         theme.reset_colors();
 
@@ -545,32 +5938,183 @@ impl Extension for Shell {
                             theme.get_color(Token::Custom("background".to_string()))
                         {
                             self.background = *color;
-                            let [r, g, b, a] = self.background;
-                            let mut clear_color = app_world.write_resource::<wgpu::Color>();
-                            let clear_color = clear_color.deref_mut();
-                            *clear_color = wgpu::Color {
-                                r: r.into(),
-                                g: g.into(),
-                                b: b.into(),
-                                a: a.into(),
-                            };
+                            self.update_clear_color(app_world);
+                        }
+                    }
+                }
+
+                let mut switch_to = None;
+                ui.menu(self.locale.get("switch_theme"), || {
+                    for builtin in BuiltinTheme::all() {
+                        if imgui::MenuItem::new(self.locale.get(builtin.label_key())).build(ui) {
+                            switch_to = Some(builtin);
+                        }
+                    }
+                });
+                if let Some(builtin) = switch_to {
+                    self.switch_theme(builtin, app_world);
+                }
+
+                if ui.button(self.locale.get("import_theme")) {
+                    self.request_path(FileAction::ImportTheme, app_world);
+                }
+
+                let mut export_to_file = None;
+                ui.menu(self.locale.get("export_theme"), || {
+                    if let Some(theme) = self.theme.as_ref() {
+                        for format in SchemeFormat::all() {
+                            if imgui::MenuItem::new(format.name()).build(ui) {
+                                ui.set_clipboard_text(theme.export(format));
+                                event!(Level::INFO, "Copied {} theme to clipboard", format.name());
+                            }
+                            ui.same_line();
+                            if ui.small_button(format!("{}##{}", self.locale.get("save_to_file"), format.name())) {
+                                export_to_file = Some(format);
+                            }
                         }
                     }
+                });
+                if let Some(format) = export_to_file {
+                    self.request_path(FileAction::ExportTheme(format), app_world);
+                }
+
+                if ui.button(self.locale.get("load_theme_file")) {
+                    self.request_path(FileAction::LoadThemeFile, app_world);
+                }
+                ui.same_line();
+                if ui.button(self.locale.get("save_theme_file")) {
+                    self.request_path(FileAction::SaveThemeFile, app_world);
+                }
+
+                ui.input_text(self.locale.get("file"), &mut self.file_path).build();
+                if ui.button(self.locale.get("open_file")) {
+                    self.request_path(FileAction::Open, app_world);
+                }
+                ui.same_line();
+                if ui.button(self.locale.get("save_file")) {
+                    self.request_path(FileAction::Save, app_world);
+                }
+
+                if ui.button(self.locale.get("watch_file")) {
+                    let channel = self.channel as u32;
+                    let path = self.file_path.clone();
+                    if let Err(err) = self.watch_file(channel, &path) {
+                        event!(Level::ERROR, "Could not watch {path}, {err}");
+                    }
+                }
+                ui.same_line();
+                if ui.button(self.locale.get("unwatch_file")) {
+                    self.unwatch_file(self.channel as u32);
+                }
+
+                ui.checkbox(self.locale.get("show_diagnostics"), &mut self.show_diagnostics);
+                ui.checkbox(self.locale.get("relative_line_numbers"), &mut self.relative_line_numbers);
+                let mut show_minimap = self.minimap.is_some();
+                if ui.checkbox(self.locale.get("show_minimap"), &mut show_minimap) {
+                    self.set_minimap(show_minimap.then(Minimap::default));
+                }
+                let mut line_prefixes = self.line_decoration.is_some();
+                if ui.checkbox(self.locale.get("line_prefixes"), &mut line_prefixes) {
+                    self.set_line_decoration(line_prefixes.then(LineDecoration::default));
+                }
+                ui.checkbox(self.locale.get("follow_output"), &mut self.follow_output);
+                ui.same_line();
+                ui.checkbox(self.locale.get("smooth_scrolling"), &mut self.smooth_scrolling);
+
+                if ui.button(self.locale.get("reload_theme")) && !self.reload_theme(app_world) {
+                    event!(Level::WARN, "There is no theme block to load a theme from");
+                }
+                ui.same_line();
+                ui.checkbox(self.locale.get("live_theme"), &mut self.live_theme);
+
+                if ui.button(self.locale.get("palette")) {
+                    self.palette = Some(String::new());
+                }
+                ui.same_line();
+                if ui.button(self.locale.get("cheat_sheet")) {
+                    self.print_cheat_sheet();
+                }
+                ui.same_line();
+                if ui.button(self.locale.get("inspector")) {
+                    self.toggle_inspector();
+                }
+
+                let zoom_label = if self.zoom.is_some() { "restore_view" } else { "zoom_to_block" };
+                if ui.button(self.locale.get(zoom_label)) {
+                    self.toggle_zoom();
+                }
+                ui.same_line();
+                if ui.button(self.locale.get("expand_line")) {
+                    self.expand_line();
+                }
+
+                let policies = [
+                    self.locale.get("focus_follow"),
+                    self.locale.get("focus_notify"),
+                    self.locale.get("focus_ignore"),
+                ];
+                let mut selected = match self.focus_policy {
+                    FocusPolicy::Follow => 0,
+                    FocusPolicy::Notify => 1,
+                    FocusPolicy::Ignore => 2,
+                };
+                if ui.combo_simple_string(self.locale.get("focus_policy"), &mut selected, &policies) {
+                    self.focus_policy = match selected {
+                        0 => FocusPolicy::Follow,
+                        1 => FocusPolicy::Notify,
+                        _ => FocusPolicy::Ignore,
+                    };
+                }
+
+                for FocusRequest { channel, reason } in self.notifications.clone() {
+                    let label = format!(
+                        "{}: {}",
+                        self.channel_title(channel),
+                        reason.unwrap_or(self.locale.get("needs_attention").to_string())
+                    );
+                    if ui.menu_item(label) {
+                        self.channel = channel as i32;
+                    }
                 }
 
+                ui.menu(&layout_menu, || {
+                    self.layout_ui(ui);
+                });
+
                 ui.separator();
                 if ui
-                    .input_int("Current output channel", &mut self.channel)
+                    .input_int(self.locale.get("output_channel"), &mut self.channel)
                     .build()
                 {}
 
-                if ui.button("Add Remote") {
+                let mut max_lines = self.max_channel_lines.unwrap_or_default() as i32;
+                if ui.input_int(self.locale.get("max_channel_lines"), &mut max_lines).build() {
+                    // 0 lets output grow without bound
+                    self.set_max_channel_lines(Some(max_lines.max(0) as usize).filter(|max| *max > 0));
+                }
+
+                ui.input_text(self.locale.get("process_command"), &mut self.process_command).build();
+                ui.same_line();
+                if ui.button(self.locale.get("run_process")) {
+                    let tokio_runtime = app_world.read_resource::<tokio::runtime::Runtime>();
+                    let command_line = self.process_command.clone();
+                    self.spawn_process(command_line, &tokio_runtime);
+                }
+
+                if ui.button(self.locale.get("add_remote")) {
                     let runtime = app_world.read_resource::<lifec::editor::RuntimeEditor>();
                     let runtime = runtime.runtime();
 
                     if let Some(created) = runtime.create_event::<Call, Remote>(app_world, "shell")
                     {
-                        if let Some(channel) = self.add_device(created) {
+                        // The device is added even if the event has no thunk context yet, it's only titled w/o one
+                        let tc = app_world.read_component::<ThunkContext>().get(created).cloned();
+                        let added = match tc {
+                            Some(tc) => self.add_device_for(created, &tc),
+                            None => self.add_device(created),
+                        };
+
+                        if let Some(channel) = added {
                             app_world
                                 .write_component()
                                 .insert(created, channel.clone())
@@ -585,8 +6129,8 @@ impl Extension for Shell {
                                 .ok();
 
                             let mut contexts = app_world.write_component::<ThunkContext>();
-                            if let Some(tc) = contexts.get_mut(created) {
-                                tc.enable_output(channel.0.clone().unwrap());
+                            if let (Some(tc), Some(tx)) = (contexts.get_mut(created), self.plugin_tx.clone()) {
+                                tc.enable_output(tx);
                             }
                         }
                     }
@@ -595,32 +6139,161 @@ impl Extension for Shell {
                 }
 
                 if let Some(address) = self.address.as_mut() {
-                    ui.input_text("address", address).build();
+                    ui.input_text(self.locale.get("address"), address).build();
 
                     ui.same_line();
-                    if ui.button("Connect to") {
+                    if ui.button(self.locale.get("connect_to")) {
                         if let Some(address) = self.address.clone() {
                             self.editing = Some(0);
 
                             let tokio_runtime =
                                 app_world.read_resource::<tokio::runtime::Runtime>();
-                            let _ = tokio_runtime.enter();
+                            self.start_connect(address, &tokio_runtime);
+                        }
+                    }
 
-                            tokio_runtime.block_on(async move {
-                                self.connect_to(address).await;
-                            });
+                    if self.reconnect.retrying().is_some() {
+                        ui.same_line();
+                        if ui.button(self.locale.get("cancel_reconnect")) {
+                            self.cancel_reconnect();
                         }
                     }
+
+                    Self::framing_ui(ui, &self.locale, &mut self.framing);
+
+                    #[cfg(feature = "relay")]
+                    self.relay_ui(app_world, ui);
                 }
             });
         });
+
+        self.detached_ui(ui);
+
+        if let Some((action, mut path)) = self.path_prompt.take() {
+            let mut opened = true;
+            let mut submitted = false;
+            imgui::Window::new(self.locale.get(action.label_key()))
+                .opened(&mut opened)
+                .always_auto_resize(true)
+                .build(ui, || {
+                    if ui.is_window_appearing() {
+                        ui.set_keyboard_focus_here();
+                    }
+
+                    submitted = ui
+                        .input_text(self.locale.get("path"), &mut path)
+                        .enter_returns_true(true)
+                        .build();
+                });
+
+            if submitted {
+                self.perform(action, path, app_world);
+            } else if opened {
+                self.path_prompt = Some((action, path));
+            }
+        }
+
+        self.search_focused = false;
+        if self.search.open {
+            let match_count = self.search.matches().len();
+            let mut jump = None;
+            let mut focused = false;
+            imgui::Window::new(self.locale.get("search_window"))
+                .opened(&mut self.search.open)
+                .always_auto_resize(true)
+                .build(ui, || {
+                    // The window takes focus when it appears, so Enter is consumed from the first frame
+                    focused = ui.is_window_focused() || ui.is_window_appearing();
+                    if ui.is_window_appearing() {
+                        ui.set_keyboard_focus_here();
+                    }
+
+                    if ui
+                        .input_text(self.locale.get("search"), &mut self.search.query)
+                        .enter_returns_true(true)
+                        .build()
+                    {
+                        jump = Some(ui.io().key_shift);
+                        ui.set_keyboard_focus_here_with_offset(imgui::FocusedWidget::Previous);
+                    }
+
+                    ui.text(format!("{match_count} {}", self.locale.get("matches")));
+                });
+
+            self.search_focused = focused;
+            if let Some(reverse) = jump {
+                self.jump_to_match(reverse);
+            }
+        }
+
+        self.palette_ui(ui);
+        self.global_search_ui(ui);
+        self.inspector_ui(app_world, ui);
+        self.completions_ui(app_world, ui);
+        self.hover_ui(app_world, ui);
+
+        if let Some((title, line)) = self.expanded_line.as_ref() {
+            let mut opened = true;
+            imgui::Window::new(format!("{title}##expanded_line"))
+                .opened(&mut opened)
+                .size([600.0, 300.0], imgui::Condition::FirstUseEver)
+                .build(ui, || {
+                    if ui.button(self.locale.get("copy_line")) {
+                        ui.set_clipboard_text(line);
+                    }
+                    ui.separator();
+
+                    ui.child_window("line##expanded_line").build(|| {
+                        ui.text_wrapped(line);
+                    });
+                });
+
+            if !opened {
+                self.expanded_line = None;
+            }
+        }
+
+        let mut fix = None;
+        if self.show_diagnostics {
+            let critical = self.color_theme().critical();
+            imgui::Window::new(self.locale.get("diagnostics_window"))
+                .opened(&mut self.show_diagnostics)
+                .build(ui, || {
+                    if let Some(err) = self.font_error.as_ref() {
+                        ui.text_colored(critical, format!("{}: {err}", self.locale.get("font_error")));
+                    } else if self.diagnostics.is_empty() {
+                        ui.text(self.locale.get("no_problems"));
+                    }
+
+                    if ui.collapsing_header(self.locale.get("startup"), imgui::TreeNodeFlags::empty()) {
+                        for (stage, duration) in self.startup.stages() {
+                            ui.text(format!("{stage}: {duration:?}"));
+                        }
+                        ui.text(format!("{}: {:?}", self.locale.get("total"), self.startup.total()));
+                    }
+
+                    for (idx, diagnostic) in self.diagnostics.iter().enumerate() {
+                        let Diagnostic { line, message, suggestions, .. } = diagnostic;
+                        ui.text_colored(critical, format!("{} {line}: {message}", self.locale.get("line")));
+
+                        // Quick fixes
+                        for suggestion in suggestions {
+                            ui.same_line();
+                            if ui.small_button(format!("{suggestion}##{idx}")) {
+                                fix = Some((diagnostic.clone(), suggestion.clone()));
+                            }
+                        }
+                    }
+                });
+        }
+
+        if let Some((diagnostic, suggestion)) = fix {
+            self.apply_fix(&diagnostic, &suggestion);
+        }
     }
 }
 
-impl<'a, Style> System<'a> for Shell<Style>
-where
-    Style: ColorTheme + Default,
-{
+impl<'a> System<'a> for Shell {
     type SystemData = (
         Entities<'a>,
         WriteStorage<'a, ThunkContext>,
@@ -629,12 +6302,16 @@ where
 
     fn run(&mut self, (entities, mut contexts, mut channels): Self::SystemData) {
         for (entity, tc) in (&entities, &mut contexts).join() {
+            self.actions.extend(ShellAction::take_from_context(entity, tc));
+
             if tc.as_ref().is_enabled("enable_char_device").unwrap_or_default() && !channels.contains(entity) {
-                if let Some(channel) = self.add_device(entity) {
+                if let Some(channel) = self.add_device_for(entity, tc) {
                     match channels.insert(entity, channel.clone()) {
                         Ok(_) => {
                             event!(Level::DEBUG, "Enabled char device for {:?}", entity);
-                            tc.enable_output(channel.0.clone().unwrap());
+                            if let Some(tx) = self.plugin_tx.clone() {
+                                tc.enable_output(tx);
+                            }
                         },
                         Err(err) => {
                             event!(Level::ERROR, "Could not insert channel for {:?}, {err}", entity);
@@ -642,6 +6319,24 @@ where
                     }
                 }
             }
+
+            if channels.contains(entity) {
+                if let Some(request) = FocusRequest::take_from_context(entity, tc) {
+                    self.request_focus(request);
+                }
+
+                // One command is delivered per run, so that the plugin has a chance to take it before the next one
+                if let Some(pos) = self.commands.iter().position(|c| c.channel == entity.id()) {
+                    self.commands.remove(pos).deliver(tc);
+                }
+
+                if let Some(pos) = self.trigger_events.iter().position(|(c, _)| *c == entity.id()) {
+                    let (_, name) = self.trigger_events.remove(pos);
+                    tc.as_mut()
+                        .with_text("shell_event", name)
+                        .with_bool("shell_event_ready", true);
+                }
+            }
         }
     }
 }
\ No newline at end of file
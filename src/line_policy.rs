@@ -0,0 +1,62 @@
+/// Trait for checking lines before they're sent to the connection, provided by the host
+///
+/// i.e. to reject malformed runmd, expand aliases, or rewrite commands for the remote
+pub trait LinePolicy: Send + Sync {
+    /// Returns the line to send in place of line, or an error to show instead of sending it
+    ///
+    /// Lines are passed w/o their line ending, which is added by the connection's framing
+    fn validate(&self, line: &str) -> Result<String, String>;
+}
+
+impl<F> LinePolicy for F
+where
+    F: Fn(&str) -> Result<String, String> + Send + Sync,
+{
+    fn validate(&self, line: &str) -> Result<String, String> {
+        self(line)
+    }
+}
+
+/// Line policy that expands the first word of a line if it's an alias
+///
+#[derive(Default, Clone)]
+pub struct Aliases {
+    aliases: Vec<(String, String)>,
+}
+
+impl Aliases {
+    /// Returns these aliases w/ name expanding to expansion
+    pub fn with(mut self, name: impl Into<String>, expansion: impl Into<String>) -> Self {
+        self.aliases.push((name.into(), expansion.into()));
+        self
+    }
+}
+
+impl LinePolicy for Aliases {
+    fn validate(&self, line: &str) -> Result<String, String> {
+        let (first, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match self.aliases.iter().find(|(name, _)| name == first) {
+            Some((_, expansion)) if rest.is_empty() => Ok(expansion.to_string()),
+            Some((_, expansion)) => Ok(format!("{expansion} {rest}")),
+            None => Ok(line.to_string()),
+        }
+    }
+}
+
+#[test]
+fn test_line_policy() {
+    let aliases = Aliases::default().with("ll", "ls -la");
+    assert_eq!(aliases.validate("ll"), Ok("ls -la".to_string()));
+    assert_eq!(aliases.validate("ll /tmp"), Ok("ls -la /tmp".to_string()));
+    assert_eq!(aliases.validate("lll"), Ok("lll".to_string()));
+
+    let reject_empty = |line: &str| {
+        if line.trim().is_empty() {
+            Err("empty line".to_string())
+        } else {
+            Ok(line.to_string())
+        }
+    };
+    assert_eq!(reject_empty.validate(" "), Err("empty line".to_string()));
+    assert_eq!(reject_empty.validate("ls"), Ok("ls".to_string()));
+}
@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::SystemTime;
+
+use crate::civil_time::time_of_day;
+
+/// Decoration prefixed to each line written to an output channel, i.e. `[14:25:30 build] `
+///
+/// Prefixes are rendered in a dimmed column beside the output, so they aren't part of the channel's buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineDecoration {
+    /// If true, lines are prefixed w/ the UTC time they were received
+    pub timestamps: bool,
+    /// If true, lines are prefixed w/ the title of the channel, i.e. the name of the entity that owns it
+    pub source: bool,
+}
+
+impl Default for LineDecoration {
+    fn default() -> Self {
+        Self {
+            timestamps: true,
+            source: true,
+        }
+    }
+}
+
+impl LineDecoration {
+    /// Returns the prefix for a line received at time from source, or an empty string if nothing is enabled
+    pub fn prefix(&self, time: SystemTime, source: &str) -> String {
+        let time = self.timestamps.then(|| clock_time(time));
+        let source = Some(source).filter(|s| self.source && !s.is_empty());
+        match (time, source) {
+            (Some(time), Some(source)) => format!("[{time} {source}] "),
+            (Some(time), None) => format!("[{time}] "),
+            (None, Some(source)) => format!("[{source}] "),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Prefixes of the lines of an output channel, kept apart from its buffer so that the buffer only holds the output
+///
+/// Lines are numbered from the first line the channel received, so that numbers don't change when old lines are trimmed
+#[derive(Debug, Default, Clone)]
+pub struct LinePrefixes {
+    /// Number of the line the first prefix belongs to
+    first: usize,
+    /// Prefix of each line from first, lines received w/o a prefix have an empty one
+    prefixes: VecDeque<String>,
+}
+
+impl LinePrefixes {
+    /// Records prefix for each line bytes start, line is the number of the line bytes are appended to
+    ///
+    /// Lines are expected to be separated by `\r`, a line break at the end of bytes isn't prefixed until more bytes arrive
+    pub fn record(&mut self, mut line: usize, bytes: &[u8], at_line_start: bool, prefix: &str) {
+        let mut line_start = at_line_start;
+        for b in bytes {
+            if line_start {
+                self.set(line, prefix);
+            }
+            if *b == b'\r' {
+                line += 1;
+            }
+            line_start = *b == b'\r';
+        }
+    }
+
+    /// Drops the prefixes of lines before first, i.e. lines that were trimmed from the buffer
+    pub fn trim(&mut self, first: usize) {
+        while self.first < first && self.prefixes.pop_front().is_some() {
+            self.first += 1;
+        }
+    }
+
+    /// Returns the prefix of line, or an empty string if it wasn't prefixed
+    pub fn get(&self, line: usize) -> &str {
+        line.checked_sub(self.first)
+            .and_then(|idx| self.prefixes.get(idx))
+            .map(|p| p.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Returns the width in chars of the widest prefix of lines
+    pub fn width(&self, lines: Range<usize>) -> usize {
+        lines.map(|l| self.get(l).chars().count()).max().unwrap_or_default()
+    }
+
+    /// Returns the prefixes of the lines of source, which starts at line first, w/ a row for each row a line wraps onto
+    ///
+    /// Rendered beside source, so that prefixes line up w/ the lines they belong to
+    pub fn column(&self, first: usize, source: &str, columns: usize) -> String {
+        let columns = columns.max(1);
+        let mut column = String::new();
+        for (idx, line) in source.split('\r').enumerate() {
+            column.push_str(self.get(first + idx));
+            let rows = 1 + line.chars().count().saturating_sub(1) / columns;
+            column.extend(std::iter::repeat('\r').take(rows));
+        }
+        column
+    }
+
+    /// Sets the prefix of line, lines are expected to be set in order
+    fn set(&mut self, line: usize, prefix: &str) {
+        if line < self.first || self.prefixes.is_empty() {
+            self.first = line;
+            self.prefixes.clear();
+        }
+
+        // Prefixes past line were left by output that was cleared
+        self.prefixes.truncate(line - self.first);
+        self.prefixes.resize(line - self.first, String::new());
+        self.prefixes.push_back(prefix.to_string());
+    }
+}
+
+/// Returns the UTC time of day of time, formatted as `HH:MM:SS`
+fn clock_time(time: SystemTime) -> String {
+    let (hours, minutes, seconds) = time_of_day(time);
+    format!("{hours:02}:{minutes:02}:{seconds:02}")
+}
+
+#[test]
+fn test_line_prefix() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let time = UNIX_EPOCH + Duration::from_secs(14 * 3600 + 25 * 60 + 30);
+    let prefix = LineDecoration::default().prefix(time, "build");
+    assert_eq!(prefix, "[14:25:30 build] ");
+
+    let mut prefixes = LinePrefixes::default();
+    prefixes.record(0, b"one\rtwo\r", true, &prefix);
+    assert_eq!(prefixes.get(0), "[14:25:30 build] ");
+    assert_eq!(prefixes.get(1), "[14:25:30 build] ");
+    assert_eq!(prefixes.get(2), "");
+
+    // A line break at the end isn't prefixed until the line receives output
+    prefixes.record(2, b"three", true, "[a] ");
+    prefixes.record(2, b" four", false, "[b] ");
+    assert_eq!(prefixes.get(2), "[a] ");
+    assert_eq!(prefixes.width(0..3), 17);
+    assert_eq!(prefixes.column(1, "two\rthree four", 4), "[14:25:30 build] \r[a] \r\r\r");
+
+    prefixes.trim(2);
+    assert_eq!(prefixes.get(1), "");
+    assert_eq!(prefixes.get(2), "[a] ");
+}
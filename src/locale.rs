@@ -0,0 +1,245 @@
+use lifec::plugins::ThunkContext;
+use lifec::Value;
+use logos::Logos;
+use std::collections::BTreeMap;
+
+use crate::Runmd;
+
+/// Built-in strings and their english defaults
+const DEFAULTS: &[(&str, &str)] = &[
+    ("shell_menu", "Shell"),
+    ("background_clear", "Background clear"),
+    ("reset_colors", "Reset colors"),
+    ("import_theme", "Import theme"),
+    ("export_theme", "Export theme"),
+    ("load_theme_file", "Load theme file…"),
+    ("switch_theme", "Switch theme"),
+    ("default_theme", "Default"),
+    ("light_theme", "Light"),
+    ("high_contrast_theme", "High contrast"),
+    ("save_theme_file", "Save theme file…"),
+    ("file", "file"),
+    ("open_file", "Open (Ctrl+O)"),
+    ("save_file", "Save (Ctrl+S)"),
+    ("no_file", "[no file]"),
+    ("save_to_file", "Save to file…"),
+    ("path", "path"),
+    ("dropped_bytes", "bytes dropped"),
+    ("processing", "processing…"),
+    ("watch_file", "Watch file in output channel"),
+    ("unwatch_file", "Unwatch"),
+    ("show_diagnostics", "Show diagnostics"),
+    ("zoom_to_block", "Zoom to block (Ctrl+B)"),
+    ("palette", "Palette (Ctrl+P)"),
+    ("registers", "Registers (Ctrl+R paste, Ctrl+Shift+R yank)"),
+    ("reload_theme", "Reload theme"),
+    ("live_theme", "Live updates"),
+    ("cheat_sheet", "Cheat sheet"),
+    ("inspector", "Inspector"),
+    ("commands", "Commands"),
+    ("snippets", "Snippets"),
+    ("completions", "Completions"),
+    ("world_attribute", "Attribute in the app world"),
+    ("world_block", "Block in the app world"),
+    ("hover_add", "add {name} {value}, adds an attribute to the block"),
+    ("hover_define", "define {name} {symbol} {value}, defines a transient value for an attribute"),
+    ("hover_block", "Starts a block with a name and symbol, or ends the current block"),
+    ("hover_text", "Text value"),
+    ("hover_bool", "Boolean value, .enable and .disable are shorthands for true and false"),
+    ("hover_int", "Integer value"),
+    ("hover_int_pair", "Integers separated by commas, i.e. 5, 6"),
+    ("hover_int_range", "Integer range, i.e. 0, 10, 5"),
+    ("hover_float", "Float value"),
+    ("hover_float_pair", "Floats separated by commas, i.e. 0.5, 1.0"),
+    ("hover_float_range", "Float range, i.e. 0.0, 1.0, 0.5"),
+    ("hover_bin", "Binary value, encoded as base64"),
+    ("hover_symbol", "Symbol value"),
+    ("hover_empty", "Empty value"),
+    ("hover_not_in_world", "Not in the app world yet"),
+    ("help_channel", "help"),
+    ("restore_view", "Restore view (Ctrl+B)"),
+    ("expand_line", "Expand truncated line (Ctrl+E)"),
+    ("copy_line", "Copy"),
+    ("focus_policy", "When a channel requests focus"),
+    ("focus_follow", "switch to it"),
+    ("focus_notify", "notify"),
+    ("focus_ignore", "ignore"),
+    ("needs_attention", "needs attention"),
+    ("running", "running"),
+    ("chars", "chars"),
+    ("words", "words"),
+    ("bytes", "bytes"),
+    ("took", "took"),
+    ("commands_to", "commands →"),
+    ("route_commands", "Send typed lines to this channel"),
+    ("search_window", "Find (Ctrl+F)"),
+    ("global_search_window", "Find in all channels (Ctrl+Shift+F)"),
+    ("search_channel", "search results"),
+    ("search", "search"),
+    ("inspector_window", "Entities (Ctrl+Shift+I)"),
+    ("filter_attributes", "filter attributes"),
+    ("focus_channel", "Focus"),
+    ("clear_channel", "Clear"),
+    ("mute_channel", "Mute"),
+    ("block_without_address", "block needs a name and a symbol, i.e. ``` demo process"),
+    ("created_entity", "created entity"),
+    ("updated_entities", "entities updated:"),
+    ("matches", "matches"),
+    ("diagnostics_window", "Diagnostics"),
+    ("no_problems", "No problems found"),
+    ("startup", "Startup"),
+    ("total", "total"),
+    ("font_error", "could not load font"),
+    ("line", "line"),
+    ("layout_menu", "Layout"),
+    ("pane", "pane"),
+    ("split", "split"),
+    ("split_horizontal", "Split horizontal"),
+    ("split_vertical", "Split vertical"),
+    ("close_pane", "Close"),
+    ("detach", "Detach"),
+    ("reattach", "Reattach"),
+    ("scroll_with", "Scroll with"),
+    ("not_linked", "not linked"),
+    ("background", "background"),
+    ("background_none", "none"),
+    ("background_gradient", "gradient"),
+    ("background_image", "image"),
+    ("apply", "Apply"),
+    ("gradient_top", "top"),
+    ("gradient_bottom", "bottom"),
+    ("opacity", "opacity"),
+    ("editor", "editor"),
+    ("active_channel", "active channel"),
+    ("channel", "channel"),
+    ("session_channel", "sessions"),
+    ("output_channel", "Current output channel"),
+    ("add_remote", "Add Remote"),
+    ("address", "address"),
+    ("connect_to", "Connect to"),
+    ("process_command", "command"),
+    ("run_process", "Run"),
+    ("could_not_start", "could not start"),
+    ("process_exited", "exited"),
+    ("connecting_to", "connecting to"),
+    ("could_not_connect", "could not connect to"),
+    ("connection_closed", "closed"),
+    ("connection_connecting", "connecting"),
+    ("connection_connected", "connected to"),
+    ("connection_retrying", "reconnecting, retry"),
+    ("cancel_reconnect", "Cancel reconnect"),
+    ("line_ending", "Line ending"),
+    ("line_ending_crlf", "CRLF"),
+    ("line_ending_lf", "LF"),
+    ("line_ending_none", "None"),
+    ("length_prefix", "Length prefix"),
+    ("length_prefix_none", "None"),
+    ("length_prefix_u16", "u16 (big-endian)"),
+    ("length_prefix_u32", "u32 (big-endian)"),
+    ("raw_mode", "Send keystrokes immediately"),
+    ("relative_line_numbers", "Relative line numbers"),
+    ("show_minimap", "Show minimap"),
+    ("follow_output", "Follow output"),
+    ("smooth_scrolling", "Smooth scrolling"),
+    ("log_to_file", "Log to file"),
+    ("terminal_grid", "Terminal grid"),
+    ("copy_channel", "Copy"),
+    ("copy_as_markdown", "Copy as markdown"),
+    ("line_prefixes", "Prefix output lines with time and source"),
+    ("max_channel_lines", "Max lines per channel"),
+    ("lines_trimmed", "lines trimmed"),
+    ("relay_session", "relay session"),
+    ("pairing_code", "pairing code"),
+    ("pair_through_relay", "Pair through relay"),
+    ("generate_code", "Generate"),
+    ("waiting_for_peer", "Waiting for the peer to join..."),
+    ("relay_channel", "relay peer"),
+    ("session_with", "session with"),
+    ("closed_after", "closed after"),
+    ("commands_sent", "commands sent"),
+    ("bytes_sent", "bytes sent"),
+    ("bytes_received", "bytes received"),
+    ("errors", "errors"),
+];
+
+/// Localized strings for the shell's built-in UI, by key
+///
+/// Keys without a localized string fall back to the english default
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    strings: BTreeMap<String, String>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            strings: DEFAULTS
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl Locale {
+    /// Returns a locale from a fluent file
+    ///
+    /// Only simple messages are supported, i.e. `reset_colors = Farben zurücksetzen`
+    pub fn from_fluent(source: &str) -> Self {
+        let mut locale = Locale::default();
+        for line in source.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                locale.set(key.trim(), value.trim());
+            }
+        }
+        locale
+    }
+
+    /// Returns a locale from runmd, strings are defined with the `locale` symbol
+    ///
+    /// ex: define reset_colors locale .text Farben zurücksetzen
+    pub fn from_runmd(source: &str) -> Self {
+        let mut lexer = Runmd::lexer_with_extras(source, ThunkContext::default());
+        while lexer.next().is_some() {}
+
+        let mut locale = Locale::default();
+        for (name, value) in lexer.extras.as_ref().find_symbol_values("locale") {
+            if let Value::TextBuffer(text) = value {
+                locale.set(name.trim_end_matches("::locale"), text);
+            }
+        }
+        locale
+    }
+
+    /// Returns the string for key, or key if there is no string
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key).map(|s| s.as_str()).unwrap_or(key)
+    }
+
+    /// Sets the string for key
+    pub fn set(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) {
+        self.strings
+            .insert(key.as_ref().to_string(), value.as_ref().to_string());
+    }
+}
+
+#[test]
+fn test_locale() {
+    let locale = Locale::from_fluent(
+        r#"
+# German
+reset_colors = Farben zurücksetzen
+shell_menu = Schale
+"#,
+    );
+
+    assert_eq!(locale.get("reset_colors"), "Farben zurücksetzen");
+    assert_eq!(locale.get("shell_menu"), "Schale");
+    assert_eq!(locale.get("add_remote"), "Add Remote");
+    assert_eq!(locale.get("unknown_key"), "unknown_key");
+}
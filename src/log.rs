@@ -0,0 +1,102 @@
+use lifec::plugins::ThunkContext;
+use logos::Lexer;
+use logos::Logos;
+
+use crate::grammer::preceding;
+use crate::{theme::ThemeToken, Grammer, Token};
+
+/// Grammer for log lines, i.e. the output of tracing-subscriber's default formatter
+///
+/// Levels are mapped to custom tokens, `log_trace`, `log_debug`, `log_info`, `log_warn`, and `log_error`, so each level
+/// can be colored separately
+#[derive(Logos, PartialEq, Eq, Debug, Clone)]
+#[logos(extras = ThunkContext)]
+pub enum LogGrammer {
+    /// Timestamps, i.e. `2022-07-20T01:02:03.123456Z` or `01:02:03.123`
+    #[regex(r"[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]([Tt ][0-9][0-9]:[0-9][0-9]:[0-9][0-9](\.[0-9]+)?([Zz]|[+-][0-9][0-9]:?[0-9][0-9])?)?")]
+    #[regex(r"[0-9][0-9]:[0-9][0-9]:[0-9][0-9](\.[0-9]+)?")]
+    Timestamp,
+    /// Log level at the start of a line, and the name of its custom token
+    #[regex(r"TRACE|DEBUG|INFO|WARN|WARNING|ERROR", on_level)]
+    #[regex(r"trace|debug|info|warn|warning|error", on_level)]
+    Level(&'static str),
+    /// Module paths, i.e. `lifec_shell::relay`
+    #[regex(r"[A-Za-z_][A-Za-z0-9_]*(::[A-Za-z_][A-Za-z0-9_]*)+")]
+    ModulePath,
+    /// Quoted strings
+    #[regex(r#""([^"\\\r\n]|\\.)*""#)]
+    String,
+    /// Any other word, so that levels are only matched as whole words
+    #[regex(r"[A-Za-z0-9_]+")]
+    Word,
+    #[regex(r"[ \t\r\n\f]+", logos::skip)]
+    #[error]
+    Error,
+}
+
+impl Grammer for LogGrammer {
+    fn name() -> &'static str {
+        "log"
+    }
+}
+
+impl Into<Vec<ThemeToken>> for LogGrammer {
+    fn into(self) -> Vec<ThemeToken> {
+        match self {
+            LogGrammer::Timestamp => vec![(Token::Comment, None)],
+            LogGrammer::Level(token) => vec![(Token::Custom(token.to_string()), None)],
+            LogGrammer::ModulePath => vec![(Token::Identifier, None)],
+            LogGrammer::String => vec![(Token::Literal, None)],
+            // The message isn't highlighted
+            LogGrammer::Word | LogGrammer::Error => vec![],
+        }
+    }
+}
+
+/// Returns the name of the custom token for the level, or None if the level isn't at the start of its line
+///
+/// Only timestamps and brackets can come before the level, so levels inside of a message aren't highlighted
+fn on_level(lexer: &mut Lexer<LogGrammer>) -> Option<&'static str> {
+    if !preceding(lexer).chars().all(|c| c.is_ascii_digit() || "-:.+TtZz[ \t".contains(c)) {
+        return None;
+    }
+
+    let token = match lexer.slice().to_lowercase().as_str() {
+        "trace" => "log_trace",
+        "debug" => "log_debug",
+        "info" => "log_info",
+        "warn" | "warning" => "log_warn",
+        _ => "log_error",
+    };
+    Some(token)
+}
+
+#[test]
+fn test_log_grammer() {
+    let source = "2022-07-20T01:02:03.123456Z  WARN lifec_shell::relay: Relay closed, \"peer reset\"\rINFORMATION";
+    let mut lexer = LogGrammer::lexer_with_extras(source, ThunkContext::default());
+
+    assert_eq!(lexer.next(), Some(LogGrammer::Timestamp));
+    assert_eq!(lexer.next(), Some(LogGrammer::Level("log_warn")));
+    assert_eq!(lexer.next(), Some(LogGrammer::ModulePath));
+    assert_eq!(lexer.slice(), "lifec_shell::relay");
+    assert_eq!(lexer.next(), Some(LogGrammer::Error));
+    assert_eq!(lexer.next(), Some(LogGrammer::Word));
+    assert_eq!(lexer.next(), Some(LogGrammer::Word));
+    assert_eq!(lexer.next(), Some(LogGrammer::Error));
+    assert_eq!(lexer.next(), Some(LogGrammer::String));
+
+    // Levels are only matched as whole words
+    assert_eq!(lexer.next(), Some(LogGrammer::Word));
+    assert_eq!(lexer.next(), None);
+
+    // Levels are only matched at the start of a line, after the timestamp
+    let mut lexer = LogGrammer::lexer_with_extras("[info] retrying after error", ThunkContext::default());
+    assert_eq!(lexer.next(), Some(LogGrammer::Error));
+    assert_eq!(lexer.next(), Some(LogGrammer::Level("log_info")));
+    assert_eq!(lexer.next(), Some(LogGrammer::Error));
+    assert_eq!(lexer.next(), Some(LogGrammer::Word));
+    assert_eq!(lexer.next(), Some(LogGrammer::Word));
+    assert_eq!(lexer.next(), Some(LogGrammer::Error));
+    assert_eq!(lexer.next(), None);
+}
@@ -0,0 +1,220 @@
+use lifec::plugins::ThunkContext;
+use lifec::AttributeGraphElements;
+use logos::Lexer;
+use logos::Logos;
+use logos::Span;
+
+use crate::grammer::preceding;
+use crate::{theme::ThemeToken, Grammer, Runmd, Token};
+
+/// Grammer for markdown, the prose that runmd documents live in
+///
+/// Fenced code blocks tagged `runmd`, and fences that are runmd blocks themselves, are lexed w/ the Runmd grammer, so their
+/// blocks are highlighted and added to the graph
+#[derive(Logos, PartialEq, Eq, Debug, Clone)]
+#[logos(extras = ThunkContext)]
+pub enum Markdown {
+    /// Headings, i.e. `## Usage`
+    #[regex(r"#+[ \t][^\r\n]*", on_line_start)]
+    Heading,
+    /// Fenced code block, from the opening fence to the closing fence
+    #[regex(r"```+", on_code_fence)]
+    CodeFence(Vec<(Token, Span)>),
+    /// Inline code, i.e. `cargo run`
+    #[regex(r"`[^`\r\n]+`")]
+    InlineCode,
+    /// Links, and the spans of the text and the url
+    #[regex(r"!?\[[^\]\r\n]*\]\([^)\r\n]*\)", on_link)]
+    Link((Span, Span)),
+    /// Bold and italic text
+    #[regex(r"\*\*[^*\r\n]+\*\*")]
+    #[regex(r"\*[^* \t\r\n][^*\r\n]*\*")]
+    #[regex(r"__[^_\r\n]+__", on_emphasis)]
+    #[regex(r"_[^_ \t\r\n][^_\r\n]*_", on_emphasis)]
+    Emphasis,
+    /// List item markers, i.e. `-`, `*`, or `1.`
+    #[regex(r"[-*+][ \t]", on_line_start)]
+    #[regex(r"[0-9]+\.[ \t]", on_line_start)]
+    ListMarker,
+    #[regex(r"[ \t\r\n\f]+", logos::skip)]
+    #[error]
+    Error,
+}
+
+impl Grammer for Markdown {
+    fn name() -> &'static str {
+        "markdown"
+    }
+}
+
+impl Into<Vec<ThemeToken>> for Markdown {
+    fn into(self) -> Vec<ThemeToken> {
+        match self {
+            Markdown::Heading => vec![(Token::Keyword, None)],
+            Markdown::CodeFence(tokens) => tokens
+                .into_iter()
+                .map(|(token, span)| (token, Some(span)))
+                .collect(),
+            Markdown::InlineCode => vec![(Token::Literal, None)],
+            Markdown::Link((text, url)) => vec![
+                (Token::Identifier, Some(text)),
+                (Token::Literal, Some(url)),
+            ],
+            Markdown::Emphasis => vec![(Token::Modifier, None)],
+            Markdown::ListMarker => vec![(Token::Operator, None)],
+            // Anything else is prose
+            Markdown::Error => vec![],
+        }
+    }
+}
+
+/// Returns true if only whitespace comes before the token on its line
+fn on_line_start(lexer: &mut Lexer<Markdown>) -> bool {
+    preceding(lexer).trim().is_empty()
+}
+/// Underscores inside of a word don't start emphasis, i.e. `shell_read_only`
+fn on_emphasis(lexer: &mut Lexer<Markdown>) -> bool {
+    let start = lexer.span().start;
+    !lexer.source()[..start]
+        .chars()
+        .last()
+        .map(|c| c.is_alphanumeric())
+        .unwrap_or_default()
+}
+
+/// Splits a link into its text and url
+fn on_link(lexer: &mut Lexer<Markdown>) -> (Span, Span) {
+    let span = lexer.span();
+    let split = lexer.slice().find("](").unwrap_or_default();
+
+    (span.start..span.start + split + 1, span.start + split + 1..span.end)
+}
+
+/// Consumes a fenced code block, up to and including the closing fence
+///
+/// The closing fence must be at least as long as the opening fence. If the block is tagged `runmd`, its body is lexed
+/// w/ the Runmd grammer, otherwise the body is a literal
+fn on_code_fence(lexer: &mut Lexer<Markdown>) -> Option<Vec<(Token, Span)>> {
+    if !on_line_start(lexer) {
+        return None;
+    }
+
+    let open = lexer.span();
+    let fence = lexer.slice().to_string();
+    let remaining = lexer.remainder();
+    let info_len = remaining
+        .find(|c| c == '\r' || c == '\n')
+        .unwrap_or(remaining.len());
+    let info = remaining[..info_len].trim();
+
+    let mut tokens = vec![(Token::Bracket, open.clone())];
+    if !info.is_empty() {
+        let info_start = open.end + remaining.find(info).unwrap_or_default();
+        tokens.push((Token::Keyword, info_start..info_start + info.len()));
+    }
+
+    // Find the closing fence, it must start a line
+    let body_start = (open.end + info_len + 1).min(lexer.source().len());
+    let mut line_start = body_start;
+    let mut close = None;
+    for line in lexer.source()[body_start..].split(|c| c == '\r' || c == '\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with(&fence) && trimmed.trim_end().chars().all(|c| c == '`') {
+            let fence_start = line_start + (line.len() - trimmed.len());
+            close = Some(fence_start..fence_start + trimmed.trim_end().len());
+            break;
+        }
+        line_start += line.len() + 1;
+    }
+
+    let body_end = close
+        .as_ref()
+        .map(|c| c.start)
+        .unwrap_or(lexer.source().len());
+    let body = body_start..body_end.max(body_start);
+
+    // An unclosed fence runs to the end of the source
+    let end = close.as_ref().map(|c| c.end).unwrap_or(lexer.source().len());
+
+    if info != "runmd" && info != "md" && fence == "```" && is_block_header(info) {
+        // The fence is itself a runmd block, so it's lexed w/ its delimitters to highlight the block's name and symbol
+        tokens = runmd_tokens(lexer, open.start..end);
+    } else {
+        if info == "runmd" {
+            tokens.extend(runmd_tokens(lexer, body));
+        } else if !body.is_empty() {
+            tokens.push((Token::Literal, body));
+        }
+        tokens.extend(close.map(|close| (Token::Bracket, close)));
+    }
+    lexer.bump(end - open.end);
+
+    Some(tokens)
+}
+
+/// Returns true if info is the header of a runmd block, i.e. a block name and symbol, or only a symbol
+fn is_block_header(info: &str) -> bool {
+    let elements = AttributeGraphElements::lexer(info).collect::<Vec<_>>();
+    (1..=2).contains(&elements.len())
+        && elements
+            .iter()
+            .all(|e| matches!(e, AttributeGraphElements::Symbol(_)))
+}
+
+/// Lexes span of the source w/ the Runmd grammer, returns its tokens w/ spans in the source
+fn runmd_tokens(lexer: &mut Lexer<Markdown>, span: Span) -> Vec<(Token, Span)> {
+    let mut tokens = vec![];
+    let mut runmd = Runmd::lexer_with_extras(&lexer.source()[span.clone()], lexer.extras.clone());
+    while let Some(token) = runmd.next() {
+        let runmd_tokens: Vec<ThemeToken> = token.into();
+        for (token, token_span) in runmd_tokens {
+            let token_span = token_span.unwrap_or(runmd.span());
+            tokens.push((token, span.start + token_span.start..span.start + token_span.end));
+        }
+    }
+    lexer.extras = runmd.extras;
+    tokens
+}
+
+#[test]
+fn test_markdown() {
+    let source = "# Demo\r- see [docs](https://example.com) for **more**\r````runmd\r``` demo process\radd debug .text hello\r```\r````\r";
+    let mut lexer = Markdown::lexer_with_extras(source, ThunkContext::default());
+
+    let mut tokens = vec![];
+    while let Some(token) = lexer.next() {
+        // Prose isn't highlighted
+        if token != Markdown::Error {
+            tokens.push((token, lexer.slice()));
+        }
+    }
+
+    assert_eq!(tokens[0], (Markdown::Heading, "# Demo"));
+    assert_eq!(tokens[1], (Markdown::ListMarker, "- "));
+    assert_eq!(tokens[2].0, Markdown::Link((13..19, 19..40)));
+    assert_eq!(tokens[3], (Markdown::Emphasis, "**more**"));
+
+    // The inner ``` belongs to the runmd block, so the fence is closed by ````
+    match &tokens[4] {
+        (Markdown::CodeFence(fence), slice) => {
+            assert!(slice.ends_with("```\r````"));
+            assert!(fence.iter().any(|(t, s)| *t == Token::Keyword && &source[s.clone()] == "runmd"));
+            assert!(fence.iter().any(|(t, s)| *t == Token::Identifier && source[s.clone()].contains("demo")));
+            assert_eq!(fence.last().map(|(_, s)| &source[s.clone()]), Some("````"));
+        }
+        token => panic!("expected a code fence, {:?}", token),
+    }
+    assert_eq!(tokens.len(), 5);
+
+    // A fence w/ a block header is a runmd block, so its name and symbol are highlighted like in the editor
+    let source = "``` demo process\radd debug .text hello\r```\r";
+    let mut lexer = Markdown::lexer_with_extras(source, ThunkContext::default());
+    match lexer.next() {
+        Some(Markdown::CodeFence(fence)) => {
+            assert!(fence.iter().any(|(t, s)| *t == Token::Identifier && &source[s.clone()] == "demo"));
+            assert!(fence.iter().any(|(t, s)| *t == Token::Keyword && &source[s.clone()] == "process"));
+            assert_eq!(fence.last().map(|(_, s)| s.end), Some(source.len() - 1));
+        }
+        token => panic!("expected a code fence, {:?}", token),
+    }
+}
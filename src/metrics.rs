@@ -0,0 +1,114 @@
+use std::time::{Duration, Instant};
+
+use crate::Locale;
+
+/// Counters for a remote session, from connect until the connection closes
+///
+#[derive(Debug, Clone, Copy)]
+pub struct SessionMetrics {
+    /// When the connection was opened
+    connected_at: Instant,
+    /// Number of commands written to the connection
+    commands_sent: usize,
+    /// Number of bytes written to the connection
+    bytes_sent: usize,
+    /// Number of bytes received from the connection
+    bytes_received: usize,
+    /// Number of failed or partial writes
+    errors: usize,
+}
+
+impl SessionMetrics {
+    /// Starts tracking a session that connected at now
+    pub fn start(now: Instant) -> Self {
+        Self {
+            connected_at: now,
+            commands_sent: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            errors: 0,
+        }
+    }
+
+    /// Records a command that was written to the connection
+    pub fn record_command(&mut self, bytes: usize) {
+        self.commands_sent += 1;
+        self.bytes_sent += bytes;
+    }
+
+    /// Records bytes written to the connection that aren't a command, i.e. raw keystrokes
+    pub fn record_bytes(&mut self, bytes: usize) {
+        self.bytes_sent += bytes;
+    }
+
+    /// Records bytes received from the connection
+    pub fn record_received(&mut self, bytes: usize) {
+        self.bytes_received += bytes;
+    }
+
+    /// Records an error on the connection
+    pub fn record_error(&mut self) {
+        self.errors += 1;
+    }
+
+    /// Returns the number of commands sent
+    pub fn commands_sent(&self) -> usize {
+        self.commands_sent
+    }
+
+    /// Returns the number of bytes sent
+    pub fn bytes_sent(&self) -> usize {
+        self.bytes_sent
+    }
+
+    /// Returns the number of bytes received
+    pub fn bytes_received(&self) -> usize {
+        self.bytes_received
+    }
+
+    /// Returns the number of errors
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    /// Returns how long the session has been connected at now
+    pub fn duration(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.connected_at)
+    }
+
+    /// Returns a summary of the session to print when the connection to peer closes, in the language of locale
+    pub fn summary(&self, locale: &Locale, peer: impl AsRef<str>, now: Instant) -> String {
+        let duration = self.duration(now).as_secs();
+        format!(
+            "-- {} {} {} {}m {}s, {} {}, {} {}, {} {}, {} {} --\r",
+            locale.get("session_with"),
+            peer.as_ref(),
+            locale.get("closed_after"),
+            duration / 60,
+            duration % 60,
+            self.commands_sent,
+            locale.get("commands_sent"),
+            self.bytes_sent,
+            locale.get("bytes_sent"),
+            self.bytes_received,
+            locale.get("bytes_received"),
+            self.errors,
+            locale.get("errors"),
+        )
+    }
+}
+
+#[test]
+fn test_session_summary() {
+    let now = Instant::now();
+    let mut metrics = SessionMetrics::start(now);
+    metrics.record_command(12);
+    metrics.record_command(8);
+    metrics.record_received(100);
+    metrics.record_error();
+
+    assert_eq!(
+        metrics.summary(&Locale::default(), "localhost:9000", now + Duration::from_secs(75)),
+        "-- session with localhost:9000 closed after 1m 15s, 2 commands sent, 20 bytes sent, 100 bytes received, 1 errors --\r"
+    );
+}
@@ -0,0 +1,97 @@
+use crate::Rect;
+
+/// Overview strip on the right edge of the surface, w/ a miniature view of the whole buffer being edited
+///
+/// The part of the buffer visible in the editor is highlighted, and clicking the strip moves the cursor to the line
+/// under the mouse
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minimap {
+    /// Width of the strip, in pixels
+    pub width: f32,
+    /// Height of a line in the strip, in pixels, lines are squeezed further if the buffer doesn't fit
+    pub line_height: f32,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self {
+            width: 120.0,
+            line_height: 4.0,
+        }
+    }
+}
+
+impl Minimap {
+    /// Splits area into the area left for panes, and the strip on its right edge
+    pub fn split(&self, area: Rect) -> (Rect, Rect) {
+        let width = self.width.clamp(0.0, area.width);
+        (
+            Rect {
+                width: area.width - width,
+                ..area
+            },
+            Rect {
+                x: area.x + area.width - width,
+                width,
+                ..area
+            },
+        )
+    }
+
+    /// Returns the height of each line in strip, for a buffer w/ line_count lines
+    pub fn row_height(&self, strip: Rect, line_count: usize) -> f32 {
+        match line_count {
+            0 => self.line_height,
+            count => self.line_height.min(strip.height / count as f32),
+        }
+    }
+
+    /// Returns the area of strip covering the visible lines of the buffer, starting at first
+    pub fn viewport(&self, strip: Rect, line_count: usize, first: usize, visible: usize) -> Rect {
+        let row_height = self.row_height(strip, line_count);
+        let first = first.min(line_count);
+        let visible = visible.min(line_count - first).max(1);
+        Rect {
+            y: strip.y + first as f32 * row_height,
+            height: visible as f32 * row_height,
+            ..strip
+        }
+    }
+
+    /// Returns the line of the buffer under y in strip
+    pub fn line_at(&self, strip: Rect, line_count: usize, y: f32) -> usize {
+        let row_height = self.row_height(strip, line_count);
+        if row_height <= 0.0 {
+            return 0;
+        }
+
+        let line = ((y - strip.y).max(0.0) / row_height) as usize;
+        line.min(line_count.saturating_sub(1))
+    }
+}
+
+#[test]
+fn test_minimap() {
+    let minimap = Minimap::default();
+    let area = Rect {
+        x: 0.0,
+        y: 100.0,
+        width: 1000.0,
+        height: 400.0,
+    };
+
+    let (panes, strip) = minimap.split(area);
+    assert_eq!(panes.width, 880.0);
+    assert_eq!(strip.x, 880.0);
+    assert_eq!(strip.width, 120.0);
+
+    // 50 lines fit at the default line height
+    assert_eq!(minimap.row_height(strip, 50), 4.0);
+    assert_eq!(minimap.viewport(strip, 50, 10, 20).y, 140.0);
+    assert_eq!(minimap.viewport(strip, 50, 10, 20).height, 80.0);
+    assert_eq!(minimap.line_at(strip, 50, 142.0), 10);
+
+    // 200 lines are squeezed to fit
+    assert_eq!(minimap.row_height(strip, 200), 2.0);
+    assert_eq!(minimap.line_at(strip, 200, 1000.0), 199);
+}
@@ -0,0 +1,186 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc::Sender;
+
+/// Max number of bytes a backlog holds regardless of the policy, past it the oldest bytes are dropped
+pub const MAX_BACKLOG: usize = 1 << 18;
+
+/// Policy for bytes that don't fit in the byte channel
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Holds pending bytes for up to the timeout, and then drops them
+    ///
+    /// The receiver is drained on the same thread, so pending bytes wait for the next frame instead of blocking
+    Block(Duration),
+    /// Holds up to capacity pending bytes, dropping the oldest bytes first
+    DropOldest(usize),
+    /// Holds pending bytes until the channel has room, up to MAX_BACKLOG bytes
+    Grow,
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        OverflowPolicy::Grow
+    }
+}
+
+/// Queue of bytes waiting for room in the byte channel
+///
+#[derive(Default)]
+pub struct Backlog {
+    /// Policy applied when the channel is full
+    policy: OverflowPolicy,
+    /// Bytes waiting to be sent, with the time they were queued
+    pending: VecDeque<(Instant, (u32, u8))>,
+    /// Number of bytes dropped since the last reset
+    dropped: usize,
+}
+
+impl Backlog {
+    /// Returns a new backlog with policy
+    pub fn new(policy: OverflowPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the current policy
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Sets the current policy
+    pub fn set_policy(&mut self, policy: OverflowPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns the number of bytes dropped since the last reset
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Resets the dropped byte count
+    pub fn reset_dropped(&mut self) {
+        self.dropped = 0;
+    }
+
+    /// Returns the number of bytes waiting to be sent
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns true if no bytes are waiting to be sent
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Queues bytes for channel and sends as many as the sender has room for
+    ///
+    /// Bytes are queued behind any pending bytes so that ordering is preserved, i.e. when pasting text
+    pub fn send(&mut self, sender: &Sender<(u32, u8)>, channel: u32, bytes: &[u8], now: Instant) {
+        for byte in bytes {
+            self.queue(channel, *byte, now);
+        }
+
+        self.flush(sender, now);
+    }
+
+    /// Queues a byte for channel behind any pending bytes, it's sent on the next flush
+    pub fn queue(&mut self, channel: u32, byte: u8, now: Instant) {
+        self.pending.push_back((now, (channel, byte)));
+    }
+
+    /// Sends pending bytes until the sender is full, and then applies the overflow policy to what's left
+    pub fn flush(&mut self, sender: &Sender<(u32, u8)>, now: Instant) {
+        while let Some((queued_at, next)) = self.pending.pop_front() {
+            match sender.try_send(next) {
+                Ok(_) => continue,
+                Err(TrySendError::Full(next)) => {
+                    self.pending.push_front((queued_at, next));
+                    break;
+                }
+                Err(TrySendError::Closed(_)) => {
+                    self.dropped += 1 + self.pending.len();
+                    self.pending.clear();
+                    return;
+                }
+            }
+        }
+
+        match self.policy {
+            OverflowPolicy::Block(timeout) => {
+                while let Some((queued_at, _)) = self.pending.front() {
+                    if now.saturating_duration_since(*queued_at) < timeout {
+                        break;
+                    }
+                    self.pending.pop_front();
+                    self.dropped += 1;
+                }
+            }
+            OverflowPolicy::DropOldest(capacity) => {
+                while self.pending.len() > capacity {
+                    self.pending.pop_front();
+                    self.dropped += 1;
+                }
+            }
+            OverflowPolicy::Grow => {}
+        }
+
+        while self.pending.len() > MAX_BACKLOG {
+            self.pending.pop_front();
+            self.dropped += 1;
+        }
+    }
+}
+
+#[test]
+fn test_backlog() {
+    use tokio::sync::mpsc::channel;
+
+    let now = Instant::now();
+    let (tx, mut rx) = channel::<(u32, u8)>(2);
+
+    let mut backlog = Backlog::new(OverflowPolicy::Grow);
+    backlog.send(&tx, 0, b"hello", now);
+    assert_eq!(backlog.len(), 3);
+    assert_eq!(rx.try_recv().ok(), Some((0, b'h')));
+    assert_eq!(rx.try_recv().ok(), Some((0, b'e')));
+
+    backlog.flush(&tx, now);
+    assert_eq!(backlog.len(), 1);
+    assert_eq!(rx.try_recv().ok(), Some((0, b'l')));
+    assert_eq!(backlog.dropped(), 0);
+
+    let (tx, mut rx) = channel::<(u32, u8)>(2);
+    let mut backlog = Backlog::new(OverflowPolicy::DropOldest(1));
+    backlog.send(&tx, 0, b"hello", now);
+    assert_eq!(backlog.len(), 1);
+    assert_eq!(backlog.dropped(), 2);
+    rx.try_recv().ok();
+    rx.try_recv().ok();
+    backlog.flush(&tx, now);
+    assert_eq!(rx.try_recv().ok(), Some((0, b'o')));
+
+    let (tx, _rx) = channel::<(u32, u8)>(1);
+    let mut backlog = Backlog::new(OverflowPolicy::Block(Duration::from_millis(100)));
+    backlog.send(&tx, 0, b"abc", now);
+    assert_eq!(backlog.len(), 2);
+    backlog.flush(&tx, now + Duration::from_millis(50));
+    assert_eq!(backlog.len(), 2);
+    backlog.flush(&tx, now + Duration::from_millis(100));
+    assert!(backlog.is_empty());
+    assert_eq!(backlog.dropped(), 2);
+
+    // Growing is bounded, so a stalled receiver can't exhaust memory
+    let (tx, _rx) = channel::<(u32, u8)>(1);
+    let mut backlog = Backlog::new(OverflowPolicy::Grow);
+    for _ in 0..MAX_BACKLOG + 10 {
+        backlog.queue(1, b'a', now);
+    }
+    backlog.flush(&tx, now);
+    assert_eq!(backlog.len(), MAX_BACKLOG);
+    assert_eq!(backlog.dropped(), 9);
+}
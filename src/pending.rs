@@ -0,0 +1,104 @@
+/// Bulk write to a channel that is processed across several frames
+///
+pub struct PendingWrite {
+    /// Channel being written to
+    channel: u32,
+    /// Bytes to write
+    bytes: Vec<u8>,
+    /// Number of bytes written so far
+    written: usize,
+}
+
+impl PendingWrite {
+    /// Returns a new pending write of bytes to channel
+    pub fn new(channel: u32, bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            channel,
+            bytes: bytes.into(),
+            written: 0,
+        }
+    }
+
+    /// Returns the channel being written to
+    pub fn channel(&self) -> u32 {
+        self.channel
+    }
+
+    /// Returns the next chunk of up to budget bytes, and advances past it
+    pub fn next_chunk(&mut self, budget: usize) -> &[u8] {
+        let start = self.written;
+        self.written = (start + budget).min(self.bytes.len());
+        &self.bytes[start..self.written]
+    }
+
+    /// Returns the number of bytes left to write
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.written
+    }
+
+    /// Returns true if every byte has been written
+    pub fn is_done(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Returns the percentage of bytes written so far
+    pub fn progress(&self) -> usize {
+        if self.bytes.is_empty() {
+            100
+        } else {
+            self.written * 100 / self.bytes.len()
+        }
+    }
+}
+
+/// Number of bytes that have to be queued on the byte channel, before draining them shows progress
+const BURST_THRESHOLD: usize = 16 * 1024;
+
+/// Progress of draining a burst of bytes received over the byte channel, i.e. a file dumped into a channel by a plugin
+///
+/// The size of a burst isn't known up front, so its total grows w/ the bytes that are still queued
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DrainProgress {
+    /// Number of bytes drained since the burst started
+    drained: usize,
+    /// Number of bytes in the burst, as of the last update
+    total: usize,
+}
+
+impl DrainProgress {
+    /// Records bytes drained in a frame, and the number of bytes still queued after it
+    ///
+    /// A burst starts once more than BURST_THRESHOLD bytes are queued, and ends once nothing is queued
+    pub fn update(&mut self, drained: usize, queued: usize) {
+        if queued == 0 {
+            *self = Self::default();
+        } else if self.total > 0 || queued > BURST_THRESHOLD {
+            self.drained += drained;
+            self.total = self.total.max(self.drained + queued);
+        }
+    }
+
+    /// Returns the percentage of the current burst that was drained, or None if there isn't a burst
+    pub fn progress(&self) -> Option<usize> {
+        (self.total > 0).then(|| self.drained * 100 / self.total)
+    }
+}
+
+#[test]
+fn test_pending_write() {
+    let mut pending = PendingWrite::new(1, "hello world");
+    assert_eq!(pending.next_chunk(4), b"hell");
+    assert_eq!(pending.progress(), 36);
+    assert_eq!(pending.next_chunk(100), b"o world");
+    assert!(pending.is_done());
+    assert_eq!(pending.progress(), 100);
+    assert!(pending.next_chunk(4).is_empty());
+
+    let mut progress = DrainProgress::default();
+    progress.update(4096, 100);
+    assert_eq!(progress.progress(), None);
+    progress.update(4096, BURST_THRESHOLD * 3);
+    assert_eq!(progress.progress(), Some(7));
+    progress.update(BURST_THRESHOLD * 3, 0);
+    assert_eq!(progress.progress(), None);
+}
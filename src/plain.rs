@@ -1,7 +1,7 @@
 use logos::Logos;
 use lifec::plugins::ThunkContext;
 
-use crate::{theme::ThemeToken, Token};
+use crate::{theme::ThemeToken, Grammer, Token};
 
 #[derive(Logos, PartialEq, Eq, Debug, Clone)]
 #[logos(extras = ThunkContext)]
@@ -12,6 +12,12 @@ pub enum Plain {
 }
 
 
+impl Grammer for Plain {
+    fn name() -> &'static str {
+        "plain"
+    }
+}
+
 impl Into<Vec<ThemeToken>> for Plain {
     fn into(self) -> Vec<ThemeToken> {
         vec![ (Token::Literal, None) ]
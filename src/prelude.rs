@@ -0,0 +1,86 @@
+//! Stable API of the shell, for lifec applications that embed it
+//!
+//! Items re-exported here follow semver, breaking changes to them are only made in a major release.
+//! Everything else exported from the crate root may change between minor releases.
+//!
+//! ```ignore
+//! use lifec_shell::prelude::*;
+//!
+//! let shell = Shell::default()
+//!     .with_color_theme(LightTheme)
+//!     .on_line_submitted(|line| println!("{line}"));
+//! ```
+
+// Shell, and its builder methods
+pub use crate::Shell;
+pub use crate::ShellChannel;
+pub use crate::ChannelWriter;
+pub use crate::ShellInput;
+pub use crate::ShellSettings;
+
+// Channels
+pub use crate::BuiltinChannel;
+pub use crate::ChannelCommand;
+pub use crate::ChannelInfo;
+pub use crate::FocusPolicy;
+pub use crate::FocusRequest;
+pub use crate::LineDecoration;
+pub use crate::DrainPolicy;
+pub use crate::OverflowPolicy;
+pub use crate::Progress;
+pub use crate::TerminalGrid;
+
+// Connections
+pub use crate::ConnectionState;
+pub use crate::Framing;
+pub use crate::LengthPrefix;
+pub use crate::LineEnding;
+pub use crate::ReconnectPolicy;
+
+// Layout
+pub use crate::Minimap;
+pub use crate::PaneContent;
+pub use crate::Split;
+
+// Themes
+pub use crate::BuiltinTheme;
+pub use crate::ColorTheme;
+pub use crate::CursorStyle;
+pub use crate::DefaultTheme;
+pub use crate::HighContrastTheme;
+pub use crate::LightTheme;
+pub use crate::Theme;
+pub use crate::Token;
+pub use crate::color_to_linear;
+pub use crate::color_to_srgb;
+pub use crate::linear_to_srgb;
+pub use crate::srgb_to_linear;
+
+// Grammers
+pub use crate::CommandGrammer;
+pub use crate::Grammer;
+pub use crate::Json;
+pub use crate::LogGrammer;
+pub use crate::Markdown;
+pub use crate::Plain;
+pub use crate::Runmd;
+pub use crate::Sh;
+pub use crate::Toml;
+
+// Extension points
+pub use crate::Clock;
+pub use crate::Dictionary;
+pub use crate::Help;
+pub use crate::HelpKind;
+pub use crate::LinePolicy;
+pub use crate::ShellAction;
+pub use crate::Locale;
+pub use crate::SystemClock;
+pub use crate::Transport;
+pub use crate::Trigger;
+pub use crate::TriggerAction;
+pub use crate::Triggers;
+
+// Test support
+#[cfg(feature = "test-support")]
+pub use crate::testing::*;
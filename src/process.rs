@@ -0,0 +1,77 @@
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{event, Level};
+
+use crate::{Locale, ShellChannel, ShellInput};
+
+/// Runs the process described by args, binding it to a shell channel
+///
+/// Stdout and stderr are written to output as they're read, and lines received from input are written to stdin.
+/// Once the process exits, its exit status is written to output, messages written by the shell are localized w/ locale
+pub async fn run_process(args: Vec<String>, output: ShellChannel, mut input: ShellInput, locale: Locale) {
+    let (program, args) = match args.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+
+    let mut child = match Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            event!(Level::ERROR, "Could not start {program}, {err}");
+            output
+                .send_line(format!("{} {program}, {err}", locale.get("could_not_start")))
+                .await;
+            return;
+        }
+    };
+
+    let mut copies = vec![];
+    if let (Some(mut stdout), Some(mut writer)) = (child.stdout.take(), output.writer()) {
+        copies.push(tokio::spawn(async move { tokio::io::copy(&mut stdout, &mut writer).await }));
+    }
+    if let (Some(mut stderr), Some(mut writer)) = (child.stderr.take(), output.writer()) {
+        copies.push(tokio::spawn(async move { tokio::io::copy(&mut stderr, &mut writer).await }));
+    }
+
+    let mut stdin = child.stdin.take();
+    let status = loop {
+        tokio::select! {
+            line = input.recv(), if stdin.is_some() => match (line, stdin.as_mut()) {
+                (Some(line), Some(writer)) => {
+                    if let Err(err) = writer.write_all(format!("{line}\n").as_bytes()).await {
+                        event!(Level::DEBUG, "Stdin of {program} closed, {err}");
+                        stdin = None;
+                    }
+                }
+                // The shell stopped sending input, so stdin is closed
+                _ => stdin = None,
+            },
+            status = child.wait() => break status,
+        }
+    };
+
+    // Output is written before the exit status
+    for copy in copies {
+        let _ = copy.await;
+    }
+
+    match status {
+        Ok(status) => {
+            event!(Level::DEBUG, "{program} exited, {status}");
+            output
+                .send_line(format!("[{program} {}, {status}]", locale.get("process_exited")))
+                .await;
+        }
+        Err(err) => {
+            event!(Level::ERROR, "Could not wait for {program}, {err}");
+        }
+    }
+}
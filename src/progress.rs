@@ -0,0 +1,42 @@
+/// Progress of a channel's plugin, driven by the plugin's `shell_progress` and `shell_progress_message` attributes
+///
+/// Rendered as a bar pinned under the channel's output, instead of interleaving updates into the output
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    /// Fraction of work done, clamped to 0.0..=1.0
+    pub fraction: f32,
+    /// Describes the current step, if the plugin provided one
+    pub message: Option<String>,
+}
+
+impl Progress {
+    /// Returns progress for a fraction of work done, w/ an optional message
+    pub fn new(fraction: f32, message: Option<String>) -> Self {
+        let fraction = if fraction.is_nan() { 0.0 } else { fraction.clamp(0.0, 1.0) };
+        Self { fraction, message }
+    }
+
+    /// Returns the progress as text, i.e. `▕█████░░░░░▏  50% copying files`, the bar is width glyphs wide
+    pub fn bar(&self, width: usize) -> String {
+        let filled = (self.fraction * width as f32).round() as usize;
+        let percent = (self.fraction * 100.0).round() as usize;
+        let mut bar = format!(
+            "▕{}{}▏ {percent:>3}%",
+            "█".repeat(filled),
+            "░".repeat(width.saturating_sub(filled))
+        );
+
+        if let Some(message) = self.message.as_ref().filter(|m| !m.is_empty()) {
+            bar.push(' ');
+            bar.push_str(message);
+        }
+        bar
+    }
+}
+
+#[test]
+fn test_progress_bar() {
+    assert_eq!(Progress::new(0.5, None).bar(4), "▕██░░▏  50%");
+    assert_eq!(Progress::new(1.5, Some("done".to_string())).bar(2), "▕██▏ 100% done");
+    assert_eq!(Progress::new(f32::NAN, None).bar(2), "▕░░▏   0%");
+}
@@ -0,0 +1,191 @@
+use std::time::{Duration, Instant};
+
+/// Policy for reconnecting after the connection closes unexpectedly
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Delays double after each failed retry, up to this delay
+    pub max_delay: Duration,
+    /// Number of retries before giving up, 0 disables reconnecting
+    pub max_retries: u32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            max_retries: 5,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns a policy that never reconnects
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the delay before retry number attempt, starting from 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// State of the connection, shown in the status line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// No connection is open
+    Closed,
+    /// A connection is being opened
+    Connecting,
+    /// A connection is open
+    Connected,
+    /// The connection closed, and retry number attempt is scheduled or in progress
+    Retrying { attempt: u32 },
+}
+
+impl ConnectionState {
+    /// Returns the locale key of the state's label
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            ConnectionState::Closed => "connection_closed",
+            ConnectionState::Connecting => "connection_connecting",
+            ConnectionState::Connected => "connection_connected",
+            ConnectionState::Retrying { .. } => "connection_retrying",
+        }
+    }
+}
+
+/// Schedules retries of the last address connected to, w/ exponential backoff
+///
+#[derive(Debug, Default, Clone)]
+pub struct Reconnect {
+    policy: ReconnectPolicy,
+    /// Address of the last connection opened by address, None if it can't be reopened
+    address: Option<String>,
+    /// Current retry, starting from 1, 0 if not retrying
+    attempt: u32,
+    /// When the current retry should start, None once it's in progress
+    due: Option<Instant>,
+}
+
+impl Reconnect {
+    /// Returns the current policy
+    pub fn policy(&self) -> ReconnectPolicy {
+        self.policy
+    }
+
+    /// Sets the current policy
+    pub fn set_policy(&mut self, policy: ReconnectPolicy) {
+        self.policy = policy;
+    }
+
+    /// Remembers the address of a connection that was opened, resetting retries
+    pub fn connected(&mut self, address: Option<&str>) {
+        self.address = address.map(str::to_string);
+        self.cancel();
+    }
+
+    /// Schedules the first retry after the connection closed, returns false if the connection won't be reopened
+    pub fn closed(&mut self, now: Instant) -> bool {
+        self.cancel();
+        self.schedule(now)
+    }
+
+    /// Schedules the next retry after a retry failed, returns false once the policy's retries are used up
+    pub fn failed(&mut self, now: Instant) -> bool {
+        if self.attempt == 0 {
+            return false;
+        }
+
+        let scheduled = self.schedule(now);
+        if !scheduled {
+            self.cancel();
+        }
+        scheduled
+    }
+
+    /// Returns the address to reconnect to if a retry is due, the retry is then in progress
+    pub fn due(&mut self, now: Instant) -> Option<String> {
+        match self.due {
+            Some(due) if due <= now => {
+                self.due = None;
+                self.address.clone()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the current retry, starting from 1, if retrying
+    pub fn retrying(&self) -> Option<u32> {
+        Some(self.attempt).filter(|a| *a > 0)
+    }
+
+    /// Returns when the current retry starts, None if it's in progress or not retrying
+    pub fn next_retry(&self) -> Option<Instant> {
+        self.due
+    }
+
+    /// Stops retrying
+    pub fn cancel(&mut self) {
+        self.attempt = 0;
+        self.due = None;
+    }
+
+    /// Schedules the next retry, if the policy allows it
+    fn schedule(&mut self, now: Instant) -> bool {
+        if self.address.is_none() || self.attempt >= self.policy.max_retries {
+            return false;
+        }
+
+        self.attempt += 1;
+        self.due = Some(now + self.policy.delay(self.attempt));
+        true
+    }
+}
+
+#[test]
+fn test_reconnect() {
+    let policy = ReconnectPolicy {
+        initial_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(3),
+        max_retries: 3,
+    };
+    assert_eq!(policy.delay(1), Duration::from_secs(1));
+    assert_eq!(policy.delay(2), Duration::from_secs(2));
+    assert_eq!(policy.delay(3), Duration::from_secs(3));
+
+    let now = Instant::now();
+    let mut reconnect = Reconnect::default();
+    reconnect.set_policy(policy);
+
+    // Connections w/o an address can't be reopened
+    reconnect.connected(None);
+    assert!(!reconnect.closed(now));
+
+    reconnect.connected(Some("localhost:8080"));
+    assert!(reconnect.closed(now));
+    assert_eq!(reconnect.retrying(), Some(1));
+    assert_eq!(reconnect.due(now), None);
+    assert_eq!(reconnect.due(now + Duration::from_secs(1)).as_deref(), Some("localhost:8080"));
+    assert_eq!(reconnect.next_retry(), None);
+
+    assert!(reconnect.failed(now));
+    assert_eq!(reconnect.next_retry(), Some(now + Duration::from_secs(2)));
+    assert!(reconnect.failed(now));
+    assert!(!reconnect.failed(now));
+    assert_eq!(reconnect.retrying(), None);
+
+    // Opening a connection resets retries
+    reconnect.connected(Some("localhost:8080"));
+    assert!(reconnect.closed(now));
+    reconnect.cancel();
+    assert_eq!(reconnect.due(now + Duration::from_secs(60)), None);
+}
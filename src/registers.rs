@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+/// Pending register operation, waiting for the name of the register
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterOp {
+    /// Copies the current line into the register
+    Yank,
+    /// Inserts the contents of the register at the cursor
+    Paste,
+}
+
+/// Named registers, `a` through `z`, for yanking and pasting text within the shell
+///
+/// Like vim, yanking into an uppercase name appends to the lowercase register
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Registers {
+    registers: BTreeMap<char, String>,
+}
+
+impl Registers {
+    /// Yanks text into the register name, returns false if name isn't a register
+    pub fn yank(&mut self, name: char, text: impl AsRef<str>) -> bool {
+        match name {
+            'a'..='z' => {
+                self.registers.insert(name, text.as_ref().to_string());
+                true
+            }
+            'A'..='Z' => {
+                let register = self.registers.entry(name.to_ascii_lowercase()).or_default();
+                if !register.is_empty() {
+                    register.push('\r');
+                }
+                register.push_str(text.as_ref());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the contents of the register name
+    pub fn get(&self, name: char) -> Option<&str> {
+        self.registers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// Returns each register that isn't empty, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (char, &str)> {
+        self.registers.iter().map(|(name, text)| (*name, text.as_str()))
+    }
+
+    /// Returns true if nothing has been yanked
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+    }
+}
+
+#[test]
+fn test_registers() {
+    let mut registers = Registers::default();
+    assert!(registers.yank('a', "add a .text one"));
+    assert!(registers.yank('A', "add b .text two"));
+    assert!(!registers.yank('1', "ignored"));
+
+    assert_eq!(registers.get('a'), Some("add a .text one\radd b .text two"));
+    assert_eq!(registers.get('A'), registers.get('a'));
+    assert_eq!(registers.iter().count(), 1);
+}
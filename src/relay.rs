@@ -0,0 +1,262 @@
+use argon2::Argon2;
+use async_trait::async_trait;
+use snow::{Builder, HandshakeState, TransportState};
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::Sender;
+use tracing::{event, Level};
+
+use crate::Transport;
+
+/// Noise pattern used between peers, the key derived from the pairing code is mixed in as the pre-shared key
+const NOISE_PARAMS: &str = "Noise_NNpsk0_25519_ChaChaPoly_BLAKE2s";
+
+/// Min number of letters and digits in a pairing code
+pub const MIN_CODE_LEN: usize = 16;
+
+/// Letters and digits generated codes are made of, w/o ones that are easily confused like `0` and `o`
+const CODE_ALPHABET: &[u8; 32] = b"abcdefghijkmnpqrstuvwxyz23456789";
+
+/// Salt the pairing key is derived w/, followed by the session name
+const KEY_SALT: &str = "lifec_shell relay";
+
+/// Max length of a noise message
+const MAX_MESSAGE_LEN: usize = 65535;
+
+/// Length of the authentication tag added to each encrypted message
+const TAG_LEN: usize = 16;
+
+/// Transport to another shell, through a relay server
+///
+/// Both shells connect to the relay w/ the same session name, the relay pairs them and forwards frames between them.
+/// Frames are encrypted end-to-end w/ the noise protocol, so the relay can't read or forge them unless it guesses the
+/// pairing code. A relay can try guessing codes offline from the first handshake message, so the key is derived from the
+/// code w/ argon2, and codes must have at least MIN_CODE_LEN letters and digits, see generate_code.
+///
+/// The relay protocol is minimal, each shell writes the session name followed by `\n`, and once both shells have joined
+/// the relay replies w/ a single byte, `i` to the shell that should initiate the handshake and `r` to the other. After that,
+/// everything is forwarded as-is. Frames are a u16 (big-endian) length, followed by a noise message.
+pub struct Relay {
+    /// Write half of the connection to the relay
+    writer: OwnedWriteHalf,
+    /// Cipher state, shared w/ the task receiving messages from the peer
+    noise: Arc<Mutex<TransportState>>,
+    /// Address of the relay
+    relay: String,
+}
+
+impl Relay {
+    /// Connects to the relay at address, and pairs w/ the shell that joins the same session
+    ///
+    /// Both shells must use the same pairing code. Messages received from the peer are written to channel w/ tx
+    pub async fn connect(
+        address: impl AsRef<str>,
+        session: impl AsRef<str>,
+        code: impl AsRef<str>,
+        channel: u32,
+        tx: Sender<(u32, u8)>,
+    ) -> std::io::Result<Self> {
+        let key = pairing_key(code.as_ref(), session.as_ref())?;
+        let stream = TcpStream::connect(address.as_ref()).await?;
+        let relay = stream
+            .peer_addr()
+            .ok()
+            .map(|a| a.to_string())
+            .unwrap_or_default();
+        let (mut reader, mut writer) = stream.into_split();
+
+        event!(Level::DEBUG, "Joining session {} on relay {relay}", session.as_ref());
+        writer
+            .write_all(format!("{}\n", session.as_ref()).as_bytes())
+            .await?;
+
+        let mut buf = vec![0; MAX_MESSAGE_LEN];
+        let noise = match reader.read_u8().await? {
+            b'i' => {
+                let mut handshake = handshake(&key, true).map_err(noise_error)?;
+                let len = handshake.write_message(&[], &mut buf).map_err(noise_error)?;
+                write_frame(&mut writer, &buf[..len]).await?;
+
+                let frame = read_frame(&mut reader).await?;
+                handshake.read_message(&frame, &mut buf).map_err(noise_error)?;
+                handshake.into_transport_mode().map_err(noise_error)?
+            }
+            b'r' => {
+                let mut handshake = handshake(&key, false).map_err(noise_error)?;
+                let frame = read_frame(&mut reader).await?;
+                handshake.read_message(&frame, &mut buf).map_err(noise_error)?;
+
+                let len = handshake.write_message(&[], &mut buf).map_err(noise_error)?;
+                write_frame(&mut writer, &buf[..len]).await?;
+                handshake.into_transport_mode().map_err(noise_error)?
+            }
+            role => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unexpected role {role} from relay"),
+                ));
+            }
+        };
+        event!(Level::DEBUG, "Paired w/ peer on relay {relay}");
+
+        let noise = Arc::new(Mutex::new(noise));
+        tokio::spawn(receive(reader, noise.clone(), channel, tx));
+
+        Ok(Self {
+            writer,
+            noise,
+            relay,
+        })
+    }
+
+    /// Returns a random pairing code, 20 letters and digits in groups of 4, i.e. `k7mq-2xcv-9tre-hw3a-pn5d`
+    pub fn generate_code() -> std::io::Result<String> {
+        let mut bytes = [0; 20];
+        getrandom::getrandom(&mut bytes).map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+
+        let chars = bytes.iter().map(|b| CODE_ALPHABET[*b as usize % CODE_ALPHABET.len()] as char).collect::<Vec<_>>();
+        Ok(chars.chunks(4).map(|c| c.iter().collect::<String>()).collect::<Vec<_>>().join("-"))
+    }
+}
+
+#[async_trait]
+impl Transport for Relay {
+    async fn write(&mut self, message: &[u8]) -> std::io::Result<usize> {
+        let mut buf = vec![0; MAX_MESSAGE_LEN];
+        for chunk in message.chunks(MAX_MESSAGE_LEN - TAG_LEN) {
+            let len = self
+                .noise
+                .lock()
+                .map_err(|_| Error::new(ErrorKind::Other, "Relay cipher state is poisoned"))?
+                .write_message(chunk, &mut buf)
+                .map_err(noise_error)?;
+
+            write_frame(&mut self.writer, &buf[..len]).await?;
+        }
+
+        Ok(message.len())
+    }
+
+    fn peer(&self) -> String {
+        format!("relay {}", self.relay)
+    }
+}
+
+/// Derives the pre-shared key from the pairing code w/ argon2, salted w/ the session name
+///
+/// Returns an error if the code is shorter than MIN_CODE_LEN letters and digits
+fn pairing_key(code: &str, session: &str) -> std::io::Result<[u8; 32]> {
+    if code.chars().filter(char::is_ascii_alphanumeric).count() < MIN_CODE_LEN {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Pairing code must have at least {MIN_CODE_LEN} letters and digits"),
+        ));
+    }
+
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(code.as_bytes(), format!("{KEY_SALT} {session}").as_bytes(), &mut key)
+        .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+    Ok(key)
+}
+
+/// Returns the handshake state for one side of the pairing
+///
+/// Only a peer that derived the same key, from the same code and session, can complete the handshake
+fn handshake(key: &[u8; 32], initiator: bool) -> Result<HandshakeState, snow::Error> {
+    let builder = Builder::new(NOISE_PARAMS.parse()?).psk(0, key);
+    if initiator {
+        builder.build_initiator()
+    } else {
+        builder.build_responder()
+    }
+}
+
+/// Decrypts messages from the peer and writes them to channel, until the relay closes
+async fn receive(
+    mut reader: OwnedReadHalf,
+    noise: Arc<Mutex<TransportState>>,
+    channel: u32,
+    tx: Sender<(u32, u8)>,
+) {
+    let mut buf = vec![0; MAX_MESSAGE_LEN];
+    loop {
+        let frame = match read_frame(&mut reader).await {
+            Ok(frame) => frame,
+            Err(err) => {
+                event!(Level::DEBUG, "Relay closed, {err}");
+                break;
+            }
+        };
+
+        let len = match noise.lock().map(|mut n| n.read_message(&frame, &mut buf)) {
+            Ok(Ok(len)) => len,
+            _ => {
+                event!(Level::WARN, "Could not decrypt message from peer, closing relay");
+                break;
+            }
+        };
+
+        for byte in &buf[..len] {
+            if tx.send((channel, *byte)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Writes a length-prefixed frame
+async fn write_frame(writer: &mut OwnedWriteHalf, frame: &[u8]) -> std::io::Result<()> {
+    writer.write_u16(frame.len() as u16).await?;
+    writer.write_all(frame).await
+}
+
+/// Reads a length-prefixed frame
+async fn read_frame(reader: &mut OwnedReadHalf) -> std::io::Result<Vec<u8>> {
+    let len = reader.read_u16().await? as usize;
+    let mut frame = vec![0; len];
+    reader.read_exact(&mut frame).await?;
+    Ok(frame)
+}
+
+fn noise_error(err: snow::Error) -> Error {
+    Error::new(ErrorKind::InvalidData, err.to_string())
+}
+
+#[test]
+fn test_relay_handshake() {
+    let pair = |initiator_code: &str, responder_code: &str| {
+        let initiator_key = pairing_key(initiator_code, "demo").expect("should derive");
+        let responder_key = pairing_key(responder_code, "demo").expect("should derive");
+        let mut initiator = handshake(&initiator_key, true).expect("should build");
+        let mut responder = handshake(&responder_key, false).expect("should build");
+        let (mut msg, mut buf) = (vec![0; MAX_MESSAGE_LEN], vec![0; MAX_MESSAGE_LEN]);
+
+        let len = initiator.write_message(&[], &mut msg).ok()?;
+        responder.read_message(&msg[..len], &mut buf).ok()?;
+        let len = responder.write_message(&[], &mut msg).ok()?;
+        initiator.read_message(&msg[..len], &mut buf).ok()?;
+
+        Some((
+            initiator.into_transport_mode().ok()?,
+            responder.into_transport_mode().ok()?,
+        ))
+    };
+
+    let code = Relay::generate_code().expect("should generate");
+    assert_eq!(code.len(), 24);
+    let (mut initiator, mut responder) = pair(&code, &code).expect("should pair");
+    let (mut msg, mut buf) = (vec![0; MAX_MESSAGE_LEN], vec![0; MAX_MESSAGE_LEN]);
+    let len = initiator.write_message(b"println hello\r", &mut msg).expect("should encrypt");
+    assert_ne!(&msg[..len], b"println hello\r");
+
+    let len = responder.read_message(&msg[..len], &mut buf).expect("should decrypt");
+    assert_eq!(&buf[..len], b"println hello\r");
+
+    // Mismatched pairing codes fail the handshake, and short codes are rejected
+    assert!(pair("blue-otter-42-k7mq-2xcv", "red-otter-42-k7mq-2xcv").is_none());
+    assert!(pairing_key("blue-otter-42", "demo").is_err());
+}
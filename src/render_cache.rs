@@ -0,0 +1,105 @@
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Colored spans of a source, produced by Theme::styled_spans
+pub type StyledSpans = Arc<[(Range<usize>, [f32; 4])]>;
+
+/// Caches the styled spans of each channel between frames
+///
+/// Entries are rebuilt only when the char_device's revision, or the theme's revision changes. Each view of a channel, i.e.
+/// the editor and a pane showing the same channel, is cached apart by the part of the buffer it styles.
+/// Entries for a channel must be removed when its char_device is replaced, since the new device's revisions start over.
+#[derive(Default)]
+pub struct RenderCache {
+    entries: BTreeMap<CacheKey, CachedSpans>,
+}
+
+/// Channel, grammer, whether only the visible output was styled, and the range of lines that were lexed
+type CacheKey = (u32, TypeId, bool, Option<(usize, usize)>);
+
+/// Styled spans, and the revisions they were built from
+struct CachedSpans {
+    buffer_revision: u64,
+    theme_revision: u64,
+    spans: StyledSpans,
+}
+
+impl RenderCache {
+    /// Returns the cached spans of channel lexed w/ Grammer, calling update to rebuild them if either revision changed
+    ///
+    /// visible is true if only the visible output was styled, and lines is the range of lines that were lexed, None if the
+    /// whole buffer was. Entries for each are kept apart, entries of the channel built from older revisions are evicted
+    pub fn get_or_update<Grammer: 'static>(
+        &mut self,
+        channel: u32,
+        visible: bool,
+        lines: Option<Range<usize>>,
+        buffer_revision: u64,
+        theme_revision: u64,
+        update: impl FnOnce() -> Vec<(Range<usize>, [f32; 4])>,
+    ) -> StyledSpans {
+        let key = (channel, TypeId::of::<Grammer>(), visible, lines.map(|l| (l.start, l.end)));
+        match self.entries.get(&key) {
+            Some(cached) if cached.buffer_revision == buffer_revision && cached.theme_revision == theme_revision => {
+                cached.spans.clone()
+            }
+            _ => {
+                self.entries.retain(|(c, ..), cached| {
+                    *c != channel
+                        || (cached.buffer_revision == buffer_revision && cached.theme_revision == theme_revision)
+                });
+
+                let spans: StyledSpans = update().into();
+                self.entries.insert(
+                    key,
+                    CachedSpans {
+                        buffer_revision,
+                        theme_revision,
+                        spans: spans.clone(),
+                    },
+                );
+                spans
+            }
+        }
+    }
+
+    /// Removes every cached entry for channel
+    pub fn remove(&mut self, channel: u32) {
+        self.entries.retain(|(c, ..), _| *c != channel);
+    }
+}
+
+#[test]
+fn test_render_cache() {
+    let mut cache = RenderCache::default();
+    let mut updates = 0;
+
+    let mut get = |cache: &mut RenderCache, lines: Option<Range<usize>>, buffer_revision, theme_revision| {
+        cache.get_or_update::<()>(0, false, lines, buffer_revision, theme_revision, || {
+            updates += 1;
+            vec![(0..1, [1.0; 4])]
+        })
+    };
+
+    get(&mut cache, None, 1, 1);
+    get(&mut cache, None, 1, 1);
+    get(&mut cache, None, 2, 1);
+    get(&mut cache, None, 2, 2);
+    cache.remove(0);
+    get(&mut cache, None, 2, 2);
+
+    // Lexing part of the buffer doesn't evict the spans of the whole buffer, or of other parts
+    get(&mut cache, Some(0..32), 2, 2);
+    get(&mut cache, Some(0..32), 2, 2);
+    get(&mut cache, Some(32..64), 2, 2);
+    get(&mut cache, Some(0..32), 2, 2);
+    get(&mut cache, None, 2, 2);
+    assert_eq!(cache.entries.len(), 3);
+
+    // Entries of older revisions are evicted once the channel changes
+    get(&mut cache, None, 3, 2);
+    assert_eq!(cache.entries.len(), 1);
+    assert_eq!(updates, 7);
+}
@@ -0,0 +1,103 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tracing::{event, Level};
+
+/// How long a single connection attempt can take before it's abandoned
+pub const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Delay before the next candidate address is tried, while earlier attempts are still pending
+pub const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Resolves address asynchronously, returns the candidate addresses in the order they should be tried
+pub async fn resolve(address: &str) -> std::io::Result<Vec<SocketAddr>> {
+    let candidates = interleave(tokio::net::lookup_host(address).await?.collect());
+    if candidates.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{address} did not resolve to any addresses"),
+        ));
+    }
+    Ok(candidates)
+}
+
+/// Connects to the first candidate of address that accepts, happy-eyeballs style
+///
+/// Attempts are started ATTEMPT_DELAY apart, alternating between IPv6 and IPv4, and each is abandoned after ATTEMPT_TIMEOUT.
+/// If every attempt fails, the error of the last attempt is returned
+pub async fn connect(address: &str) -> std::io::Result<TcpStream> {
+    let candidates = resolve(address).await?;
+    event!(Level::DEBUG, "{address} resolved to {:?}", candidates);
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(candidates.len());
+    let attempts = candidates
+        .into_iter()
+        .enumerate()
+        .map(|(idx, candidate)| {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(ATTEMPT_DELAY * idx as u32).await;
+                let result = match tokio::time::timeout(ATTEMPT_TIMEOUT, TcpStream::connect(candidate)).await {
+                    Ok(result) => result,
+                    Err(_) => Err(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        format!("{candidate} timed out"),
+                    )),
+                };
+                let _ = tx.send((candidate, result)).await;
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(tx);
+
+    let mut last_err = None;
+    while let Some((candidate, result)) = rx.recv().await {
+        match result {
+            Ok(stream) => {
+                for attempt in attempts.iter() {
+                    attempt.abort();
+                }
+                event!(Level::DEBUG, "Connected to {candidate}");
+                return Ok(stream);
+            }
+            Err(err) => {
+                event!(Level::DEBUG, "Could not connect to {candidate}, {err}");
+                last_err = Some(err);
+            }
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| std::io::Error::new(std::io::ErrorKind::NotConnected, "No attempts completed")))
+}
+
+/// Orders addresses alternating between families, starting w/ the family of the first address
+fn interleave(addresses: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first_v6 = addresses.first().map(|a| a.is_ipv6()).unwrap_or_default();
+    let (mut preferred, mut other): (Vec<_>, Vec<_>) = addresses.into_iter().partition(|a| a.is_ipv6() == first_v6);
+    preferred.reverse();
+    other.reverse();
+
+    let mut ordered = vec![];
+    while let Some(next) = preferred.pop() {
+        ordered.push(next);
+        if let Some(next) = other.pop() {
+            ordered.push(next);
+        }
+    }
+    ordered.extend(other.into_iter().rev());
+    ordered
+}
+
+#[test]
+fn test_interleave() {
+    let addresses = ["[::1]:80", "[::2]:80", "[::3]:80", "10.0.0.1:80", "10.0.0.2:80"]
+        .iter()
+        .map(|a| a.parse().unwrap())
+        .collect::<Vec<SocketAddr>>();
+
+    let ordered = interleave(addresses.clone());
+    assert_eq!(ordered, vec![addresses[0], addresses[3], addresses[1], addresses[4], addresses[2]]);
+
+    let ordered = interleave(vec![addresses[3], addresses[0], addresses[1], addresses[2]]);
+    assert_eq!(ordered, vec![addresses[3], addresses[0], addresses[1], addresses[2]]);
+}
@@ -0,0 +1,79 @@
+use lifec::plugins::ThunkContext;
+use lifec::Value;
+
+/// Line typed while a plugin's channel was focused, split into arguments
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelCommand {
+    /// Channel the command is routed to
+    pub channel: u32,
+    /// The line as it was typed
+    pub line: String,
+    /// Arguments, split shell-words style
+    pub args: Vec<String>,
+}
+
+impl ChannelCommand {
+    /// Parses a line typed for channel, arguments are split w/ shell quoting and escaping rules
+    pub fn parse(channel: u32, line: impl AsRef<str>) -> Result<Self, shell_words::ParseError> {
+        let line = line.as_ref().trim_end_matches(|c| c == '\r' || c == '\n');
+
+        Ok(Self {
+            channel,
+            line: line.to_string(),
+            args: shell_words::split(line)?,
+        })
+    }
+
+    /// Delivers this command to the plugin's thunk context
+    ///
+    /// Writes the line to `shell_command`, the number of arguments to `shell_argc`, and each argument as `{idx}::shell_arg`.
+    /// `shell_command_ready` is enabled so that the plugin knows a new command arrived
+    pub fn deliver(&self, tc: &mut ThunkContext) {
+        tc.as_mut()
+            .with_text("shell_command", self.line.as_str())
+            .with_int("shell_argc", self.args.len() as i32)
+            .with_bool("shell_command_ready", true);
+
+        for (idx, arg) in self.args.iter().enumerate() {
+            tc.as_mut()
+                .define(&idx.to_string(), "shell_arg")
+                .edit_as(Value::TextBuffer(arg.to_string()));
+        }
+    }
+
+    /// Takes the most recent command delivered to a plugin's thunk context, returns None if there isn't a new command
+    pub fn take_from_context(tc: &mut ThunkContext) -> Option<Vec<String>> {
+        if !tc.as_ref().is_enabled("shell_command_ready").unwrap_or_default() {
+            return None;
+        }
+        tc.as_mut().with_bool("shell_command_ready", false);
+
+        let argc = tc.as_ref().find_int("shell_argc").unwrap_or_default() as usize;
+        let mut args = tc
+            .as_ref()
+            .find_symbol_values("shell_arg")
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let idx = name.trim_end_matches("::shell_arg").parse::<usize>().ok()?;
+                match value {
+                    Value::TextBuffer(arg) if idx < argc => Some((idx, arg)),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        args.sort_by_key(|(idx, _)| *idx);
+        Some(args.into_iter().map(|(_, arg)| arg).collect())
+    }
+}
+
+#[test]
+fn test_parse_channel_command() {
+    let command = ChannelCommand::parse(3, "run --name \"hello world\" it\\'s\r").expect("should parse");
+    assert_eq!(command.channel, 3);
+    assert_eq!(command.line, "run --name \"hello world\" it\\'s");
+    assert_eq!(command.args, vec!["run", "--name", "hello world", "it's"]);
+
+    assert!(ChannelCommand::parse(3, "echo \"unterminated").is_err());
+}
@@ -8,7 +8,10 @@ use logos::Span;
 use tracing::event;
 use tracing::Level;
 
+use crate::indent::runmd_indent;
 use crate::theme::ThemeToken;
+use crate::Grammer;
+use crate::Diagnostic;
 use crate::Token;
 
 /// Better runmd language parser, built on top of the v1 parser
@@ -41,7 +44,7 @@ pub enum Runmd {
     #[token("-", on_comment)]
     #[token("#", on_comment)]
     Comment,
-    #[regex(r"[ \t\n\f]+", logos::skip)]
+    #[regex(r"[ \t\r\n\f]+", logos::skip)]
     #[error]
     Error,
 }
@@ -52,6 +55,20 @@ impl Default for Runmd {
     }
 }
 
+impl Grammer for Runmd {
+    fn name() -> &'static str {
+        "runmd"
+    }
+
+    fn indent_after(line: &str) -> String {
+        runmd_indent(line)
+    }
+
+    fn parses_commands() -> bool {
+        true
+    }
+}
+
 impl Into<Vec<ThemeToken>> for Runmd {
     fn into(self) -> Vec<ThemeToken> {
         match self {
@@ -108,12 +125,61 @@ impl Into<Vec<ThemeToken>> for Runmd {
                 vec![(Token::Comment, None)]
             }
             Runmd::Error => {
-                vec![]
+                vec![(Token::Error, None)]
             }
         }
     }
 }
 
+impl Runmd {
+    /// Lexes the source and returns a thunk context w/ the attributes added by its events
+    pub fn parse(source: &str) -> ThunkContext {
+        let mut lexer = Runmd::lexer_with_extras(source, ThunkContext::default());
+        while lexer.next().is_some() {}
+        lexer.extras
+    }
+
+    /// Lexes the source and returns diagnostics for any malformed blocks, events, or attributes
+    ///
+    /// Adjacent errors on the same line are merged into a single diagnostic
+    pub fn diagnostics(source: &str) -> Vec<Diagnostic> {
+        let mut lexer = Runmd::lexer_with_extras(source, ThunkContext::default());
+        let mut diagnostics: Vec<Diagnostic> = vec![];
+
+        while let Some(token) = lexer.next() {
+            if token != Runmd::Error {
+                continue;
+            }
+
+            let span = lexer.span();
+            if let Some(last) = diagnostics.last_mut() {
+                let between = &source[last.span.end..span.start];
+                if between.chars().all(|c| c == ' ' || c == '\t') {
+                    last.span.end = span.end;
+                    continue;
+                }
+            }
+
+            let slice = lexer.slice();
+            let message = if slice.starts_with("```") {
+                "malformed block delimiter"
+            } else if slice.starts_with("add") {
+                "malformed add event, expected: add {name} {value}"
+            } else if slice.starts_with("define") {
+                "malformed define event, expected: define {name} {symbol} {value}"
+            } else if slice.starts_with('.') {
+                "unknown attribute value type"
+            } else {
+                "unexpected input"
+            };
+
+            diagnostics.push(Diagnostic::new(source, span, message));
+        }
+
+        diagnostics
+    }
+}
+
 fn on_comment(lexer: &mut Lexer<Runmd>) -> Option<()> {
     if let Some(eol) = lexer.remainder().find(|c| c == '\r' || c == '\n') {
         let line = &lexer.remainder()[..eol];
@@ -160,8 +226,11 @@ fn on_block_event(lexer: &mut Lexer<Runmd>) -> Option<Vec<Span>> {
                             });
                         } else {
                             event!(Level::WARN, "Could not parse value to add event");
+                            return None;
                         }
                         lexer.bump(value_span.start);
+                    } else {
+                        return None;
                     }
                 }
 
@@ -200,6 +269,8 @@ fn on_block_event(lexer: &mut Lexer<Runmd>) -> Option<Vec<Span>> {
                         if let Some(value) = get_value(value) {
                             transient.edit_as(value.clone());
                         }
+                    } else {
+                        return None;
                     }
                 }
 
@@ -216,6 +287,7 @@ fn on_block_event(lexer: &mut Lexer<Runmd>) -> Option<Vec<Span>> {
                 | AttributeGraphEvents::BlockDelimitter => unreachable!("unsupported events"),
                 AttributeGraphEvents::Error => {
                     event!(Level::WARN, "Error parsing, {}", event.slice());
+                    return None;
                 }
             },
             None => {
@@ -224,6 +296,7 @@ fn on_block_event(lexer: &mut Lexer<Runmd>) -> Option<Vec<Span>> {
                     "Did not parse a supported event, {}",
                     event.slice()
                 );
+                return None;
             }
         }
 
@@ -390,3 +463,18 @@ add duration .int2 5, 6
     let project = lifec::plugins::Project::from(tc.as_ref().clone());
     let _ = project.find_block("demo").unwrap();
 }
+
+#[test]
+fn test_runmd_diagnostics() {
+    let runmd = r#"
+``` demo process
+add test_val .text test hello world
+define test_val
+```
+"#;
+
+    let diagnostics = Runmd::diagnostics(runmd);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, 3);
+    assert_eq!(&runmd[diagnostics[0].span.clone()], "define test_val");
+}
@@ -0,0 +1,440 @@
+use std::collections::BTreeMap;
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::{Theme, Token};
+
+/// Color scheme formats the theme can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemeFormat {
+    /// Snippet for VS Code's settings.json, (editor.tokenColorCustomizations)
+    VsCode,
+    /// iTerm2 .itermcolors property list
+    ITerm,
+    /// WezTerm color scheme toml
+    WezTerm,
+}
+
+impl SchemeFormat {
+    /// Returns all supported formats
+    pub fn all() -> [SchemeFormat; 3] {
+        [SchemeFormat::VsCode, SchemeFormat::ITerm, SchemeFormat::WezTerm]
+    }
+
+    /// Returns the file extension for this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SchemeFormat::VsCode => "json",
+            SchemeFormat::ITerm => "itermcolors",
+            SchemeFormat::WezTerm => "toml",
+        }
+    }
+
+    /// Returns the display name of this format
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemeFormat::VsCode => "VS Code",
+            SchemeFormat::ITerm => "iTerm2",
+            SchemeFormat::WezTerm => "WezTerm",
+        }
+    }
+}
+
+impl Theme {
+    /// Exports the current color map in the given format
+    ///
+    /// Colors are converted from linear sRGB to sRGB, since that is what other tools expect
+    pub fn export(&self, format: SchemeFormat) -> String {
+        match format {
+            SchemeFormat::VsCode => self.export_vscode(),
+            SchemeFormat::ITerm => self.export_iterm(),
+            SchemeFormat::WezTerm => self.export_wezterm(),
+        }
+    }
+
+    /// Returns the ansi palette, black, red, green, yellow, blue, magenta, cyan, white
+    fn ansi(&self) -> [[f32; 4]; 8] {
+        let style = self.style();
+        [
+            self.palette_color("background", style.background()),
+            self.palette_color("red", style.red()),
+            self.palette_color("green", style.green()),
+            self.palette_color("yellow", style.yellow()),
+            self.palette_color("blue", style.blue()),
+            self.palette_color("purple", style.purple()),
+            self.palette_color("cyan", style.cyan()),
+            self.palette_color("foreground", style.foreground()),
+        ]
+    }
+
+    fn palette_color(&self, name: &str, fallback: [f32; 4]) -> [f32; 4] {
+        self.get_color(Token::Custom(name.to_string()))
+            .cloned()
+            .unwrap_or(fallback)
+    }
+
+    fn export_vscode(&self) -> String {
+        let rules = [
+            (Token::Keyword, "keyword"),
+            (Token::Bracket, "punctuation"),
+            (Token::Operator, "keyword.operator"),
+            (Token::Modifier, "storage.modifier"),
+            (Token::Identifier, "variable"),
+            (Token::Literal, "string"),
+            (Token::Comment, "comment"),
+            (Token::Error, "invalid"),
+        ]
+        .iter()
+        .filter_map(|(token, scope)| {
+            self.get_color(token.clone()).map(|color| {
+                format!(
+                    "      {{ \"scope\": \"{scope}\", \"settings\": {{ \"foreground\": \"{}\" }} }}",
+                    to_hex(color)
+                )
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+        let [background, .., foreground] = self.ansi();
+        format!(
+            r#""workbench.colorCustomizations": {{
+  "editor.background": "{}",
+  "editor.foreground": "{}"
+}},
+"editor.tokenColorCustomizations": {{
+  "textMateRules": [
+{rules}
+  ]
+}}
+"#,
+            to_hex(&background),
+            to_hex(&foreground)
+        )
+    }
+
+    fn export_iterm(&self) -> String {
+        let ansi = self.ansi();
+        let mut entries = vec![];
+        for (idx, color) in ansi.iter().chain(ansi.iter()).enumerate() {
+            entries.push(iterm_entry(&format!("Ansi {idx} Color"), color));
+        }
+        entries.push(iterm_entry("Background Color", &ansi[0]));
+        entries.push(iterm_entry("Foreground Color", &ansi[7]));
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+{}
+</dict>
+</plist>
+"#,
+            entries.join("\n")
+        )
+    }
+
+    fn export_wezterm(&self) -> String {
+        let ansi = self.ansi();
+        let palette = ansi
+            .iter()
+            .map(|c| format!("\"{}\"", to_hex(c)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!(
+            r#"[colors]
+foreground = "{}"
+background = "{}"
+cursor_bg = "{}"
+ansi = [{palette}]
+brights = [{palette}]
+"#,
+            to_hex(&ansi[7]),
+            to_hex(&ansi[0]),
+            to_hex(&self.palette_color("orange", self.style().orange())),
+        )
+    }
+}
+
+/// Palette imported from a color scheme file, colors are stored as linear sRGB
+///
+/// Palette names match the custom tokens of the theme, i.e. background, foreground, red, green, etc
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColorScheme {
+    palette: BTreeMap<String, [f32; 4]>,
+}
+
+impl ColorScheme {
+    /// Parses a base16 scheme yaml
+    ///
+    /// Follows the base16 styling guidelines for mapping base00-base0F to palette names
+    pub fn parse_base16(source: &str) -> Self {
+        let mut scheme = ColorScheme::default();
+        for line in source.lines() {
+            if let Some((key, value)) = line.split_once(':') {
+                let name = match key.trim() {
+                    "base00" => "background",
+                    "base03" => "comment",
+                    "base05" => "foreground",
+                    "base08" => "red",
+                    "base09" => "orange",
+                    "base0A" => "yellow",
+                    "base0B" => "green",
+                    "base0C" => "cyan",
+                    "base0D" => "blue",
+                    "base0E" => "purple",
+                    _ => continue,
+                };
+                scheme.insert_hex(name, value);
+            }
+        }
+        scheme
+    }
+
+    /// Parses the colors section of a WezTerm color scheme toml
+    ///
+    /// The ansi array can span multiple lines, i.e. one color per line
+    pub fn parse_wezterm(source: &str) -> Self {
+        let mut scheme = ColorScheme::default();
+        let mut ansi: Option<String> = None;
+        for line in source.lines() {
+            if let Some(values) = ansi.as_mut() {
+                values.push('\n');
+                values.push_str(line);
+                if quoted_values(line).1 {
+                    scheme.insert_ansi(values);
+                    ansi = None;
+                }
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                match key.trim() {
+                    "foreground" | "background" => scheme.insert_hex(key.trim(), value),
+                    "ansi" if quoted_values(value).1 => scheme.insert_ansi(value),
+                    "ansi" => ansi = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+        scheme
+    }
+
+    /// Parses Xresources style color definitions, i.e. `*.color1: #cc241d`
+    pub fn parse_xresources(source: &str) -> Self {
+        let mut scheme = ColorScheme::default();
+        for line in source.lines().filter(|l| !l.trim_start().starts_with('!')) {
+            if let Some((key, value)) = line.split_once(':') {
+                let key = key.trim().trim_start_matches('*').trim_start_matches('.');
+                match key {
+                    "foreground" | "background" => scheme.insert_hex(key, value),
+                    color => {
+                        if let Some(name) = color
+                            .strip_prefix("color")
+                            .and_then(|idx| idx.parse::<usize>().ok())
+                            .and_then(ansi_name)
+                        {
+                            scheme.insert_hex(name, value);
+                        }
+                    }
+                }
+            }
+        }
+        scheme
+    }
+
+    /// Returns the color for a palette name
+    pub fn get(&self, name: &str) -> Option<[f32; 4]> {
+        self.palette.get(name).cloned()
+    }
+
+    /// Returns the number of colors in this scheme
+    pub fn len(&self) -> usize {
+        self.palette.len()
+    }
+
+    /// Returns true if no colors were imported
+    pub fn is_empty(&self) -> bool {
+        self.palette.is_empty()
+    }
+
+    fn insert_ansi(&mut self, array: &str) {
+        let colors = array.lines().flat_map(|line| quoted_values(line).0);
+        for (idx, color) in colors.enumerate() {
+            if let Some(name) = ansi_name(idx) {
+                self.insert_hex(name, color);
+            }
+        }
+    }
+
+    fn insert_hex(&mut self, name: &str, value: &str) {
+        if let Some(color) = from_hex(value) {
+            self.palette.insert(name.to_string(), color);
+        }
+    }
+}
+
+impl Theme {
+    /// Applies an imported color scheme onto the palette colors and the token colors
+    pub fn apply_scheme(&mut self, scheme: &ColorScheme) {
+        for (name, color) in scheme.palette.iter() {
+            self.set_color(Token::Custom(name.to_string()), *color);
+        }
+
+        let tokens = [
+            (Token::Bracket, "purple"),
+            (Token::Operator, "yellow"),
+            (Token::Modifier, "orange"),
+            (Token::Identifier, "red"),
+            (Token::Keyword, "blue"),
+            (Token::Literal, "green"),
+            (Token::Comment, "comment"),
+            (Token::Whitespace, "foreground"),
+            (Token::Error, "red"),
+        ];
+
+        for (token, name) in tokens {
+            if let Some(color) = scheme.get(name) {
+                self.set_color(token, color);
+            }
+        }
+    }
+}
+
+/// Returns the quoted values of a line of a toml array, and true if the line closes the array
+///
+/// Anything after a `#` outside of quotes is a comment
+fn quoted_values(line: &str) -> (Vec<&str>, bool) {
+    let mut values = vec![];
+    let mut quote: Option<(char, usize)> = None;
+    for (idx, c) in line.char_indices() {
+        match (quote, c) {
+            (Some((q, start)), _) if c == q => {
+                values.push(&line[start..idx]);
+                quote = None;
+            }
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some((c, idx + 1)),
+            (None, '#') => break,
+            (None, ']') => return (values, true),
+            _ => {}
+        }
+    }
+    (values, false)
+}
+
+/// Returns the palette name for an ansi color index, bright colors map to the same names
+fn ansi_name(idx: usize) -> Option<&'static str> {
+    match idx % 8 {
+        1 => Some("red"),
+        2 => Some("green"),
+        3 => Some("yellow"),
+        4 => Some("blue"),
+        5 => Some("purple"),
+        6 => Some("cyan"),
+        _ => None,
+    }
+}
+
+fn iterm_entry(key: &str, color: &[f32; 4]) -> String {
+    let [r, g, b, a] = *color;
+    let (r, g, b) = (linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b));
+    format!(
+        r#"  <key>{key}</key>
+  <dict>
+    <key>Alpha Component</key>
+    <real>{a}</real>
+    <key>Blue Component</key>
+    <real>{b}</real>
+    <key>Color Space</key>
+    <string>sRGB</string>
+    <key>Green Component</key>
+    <real>{g}</real>
+    <key>Red Component</key>
+    <real>{r}</real>
+  </dict>"#
+    )
+}
+
+/// Converts a linear sRGB color to a #rrggbb hex string
+fn to_hex(color: &[f32; 4]) -> String {
+    let [r, g, b, _] = color.map(|c| (linear_to_srgb(c) * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Parses an sRGB hex color, i.e. `"#rrggbb"` or `rrggbb`, into linear sRGB
+fn from_hex(value: &str) -> Option<[f32; 4]> {
+    let hex = value.trim().trim_matches('"').trim_matches('\'').trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let component = |idx: usize| {
+        u8::from_str_radix(&hex[idx..idx + 2], 16)
+            .ok()
+            .map(|c| srgb_to_linear(c as f32 / 255.0))
+    };
+
+    Some([component(0)?, component(2)?, component(4)?, 1.0])
+}
+
+#[test]
+fn test_export_wezterm() {
+    let mut theme = crate::Theme::new();
+    theme.set_color(Token::Custom("background".to_string()), [0.0, 0.0, 0.0, 1.0]);
+    theme.set_color(Token::Custom("foreground".to_string()), [1.0, 1.0, 1.0, 1.0]);
+
+    theme.set_color(Token::Custom("cyan".to_string()), [0.0, 1.0, 1.0, 1.0]);
+    theme.set_color(Token::Custom("orange".to_string()), [1.0, 0.0, 0.0, 1.0]);
+
+    let exported = theme.export(SchemeFormat::WezTerm);
+    assert!(exported.contains("foreground = \"#ffffff\""));
+    assert!(exported.contains("background = \"#000000\""));
+    assert!(exported.contains("\"#00ffff\", \"#ffffff\"]"));
+    assert!(exported.contains("cursor_bg = \"#ff0000\""));
+}
+
+#[test]
+fn test_import_wezterm() {
+    let source = r##"
+[colors]
+foreground = "#ffffff"
+ansi = [
+    "#000000", # black
+    "#ff0000",
+    "#00ff00",
+]
+brights = ["#000000", "#ff0000"]
+"##;
+
+    let scheme = ColorScheme::parse_wezterm(source);
+    assert_eq!(scheme.len(), 3);
+    assert_eq!(scheme.get("foreground"), Some([1.0, 1.0, 1.0, 1.0]));
+    assert_eq!(scheme.get("red"), Some([1.0, 0.0, 0.0, 1.0]));
+    assert_eq!(scheme.get("green"), Some([0.0, 1.0, 0.0, 1.0]));
+
+    let scheme = ColorScheme::parse_wezterm("ansi = [\"#000000\", \"#0000ff\", \"#000000\", \"#000000\", \"#0000ff\"]");
+    assert_eq!(scheme.get("blue"), Some([0.0, 0.0, 1.0, 1.0]));
+}
+
+#[test]
+fn test_import_base16() {
+    let source = r##"
+scheme: "Test"
+author: "lifec"
+base00: "000000"
+base05: "ffffff"
+base08: "ff0000"
+base0E: "#800080"
+"##;
+
+    let scheme = ColorScheme::parse_base16(source);
+    assert_eq!(scheme.len(), 4);
+    assert_eq!(scheme.get("background"), Some([0.0, 0.0, 0.0, 1.0]));
+    assert_eq!(scheme.get("red"), Some([1.0, 0.0, 0.0, 1.0]));
+
+    let mut theme = crate::Theme::new();
+    theme.apply_scheme(&scheme);
+    assert_eq!(theme.get_color(Token::Identifier), Some(&[1.0, 0.0, 0.0, 1.0]));
+}
@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+
+/// Pairs of channels whose output scrolls together, i.e. a channel and its filtered view, or two channels being compared
+///
+/// A channel can only be linked to one other channel, linking it again replaces its previous link
+#[derive(Debug, Default, Clone)]
+pub struct ScrollLinks {
+    links: BTreeMap<u32, u32>,
+}
+
+impl ScrollLinks {
+    /// Links the scrolling of a and b
+    pub fn link(&mut self, a: u32, b: u32) {
+        if a == b {
+            return;
+        }
+
+        self.unlink(a);
+        self.unlink(b);
+        self.links.insert(a, b);
+        self.links.insert(b, a);
+    }
+
+    /// Breaks the link of channel, returns the channel it was linked to
+    pub fn unlink(&mut self, channel: u32) -> Option<u32> {
+        let other = self.links.remove(&channel)?;
+        self.links.remove(&other);
+        Some(other)
+    }
+
+    /// Returns the channel linked to channel
+    pub fn linked(&self, channel: u32) -> Option<u32> {
+        self.links.get(&channel).copied()
+    }
+
+    /// Links a and b, or breaks their link if they're already linked, returns true if they're linked after
+    pub fn toggle(&mut self, a: u32, b: u32) -> bool {
+        if self.linked(a) == Some(b) {
+            self.unlink(a);
+            false
+        } else {
+            self.link(a, b);
+            self.linked(a) == Some(b)
+        }
+    }
+}
+
+/// Returns the line to scroll to in output w/ to_lines, so that it's scrolled the same proportion as scroll is of from_lines
+pub fn proportional(scroll: usize, from_lines: usize, to_lines: usize) -> usize {
+    let (from_max, to_max) = (from_lines.saturating_sub(1), to_lines.saturating_sub(1));
+    if from_max == 0 {
+        return 0;
+    }
+
+    ((scroll.min(from_max) as f32 / from_max as f32) * to_max as f32).round() as usize
+}
+
+#[test]
+fn test_scroll_links() {
+    let mut links = ScrollLinks::default();
+    links.link(1, 2);
+    assert_eq!(links.linked(2), Some(1));
+
+    links.link(2, 3);
+    assert_eq!(links.linked(1), None);
+    assert_eq!(links.linked(3), Some(2));
+
+    assert!(!links.toggle(3, 2));
+    assert_eq!(links.linked(2), None);
+    assert!(!links.toggle(4, 4));
+
+    assert_eq!(proportional(50, 101, 11), 5);
+    assert_eq!(proportional(100, 101, 11), 10);
+    assert_eq!(proportional(5, 1, 11), 0);
+    assert_eq!(proportional(3, 4, 1), 0);
+}
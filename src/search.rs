@@ -0,0 +1,99 @@
+use logos::Span;
+
+/// State of the incremental search prompt
+///
+#[derive(Debug, Default, Clone)]
+pub struct Search {
+    /// Text being searched for
+    pub query: String,
+    /// Whether the search prompt is open
+    pub open: bool,
+    /// Spans of the source that match the query
+    matches: Vec<Span>,
+}
+
+impl Search {
+    /// Finds matches for the query in source
+    ///
+    /// If the query is all lowercase, matching ignores case
+    pub fn update(&mut self, source: &str) {
+        self.matches.clear();
+        if self.query.is_empty() {
+            return;
+        }
+
+        let ignore_case = !self.query.chars().any(|c| c.is_uppercase());
+        let (haystack, needle) = if ignore_case {
+            (source.to_lowercase(), self.query.to_lowercase())
+        } else {
+            (source.to_string(), self.query.clone())
+        };
+
+        // Lowercasing can change byte lengths, in which case the offsets would no longer line up w/ source
+        if haystack.len() != source.len() {
+            return;
+        }
+
+        self.matches = haystack
+            .match_indices(&needle)
+            .map(|(start, m)| start..start + m.len())
+            .collect();
+    }
+
+    /// Returns the spans that match the query
+    pub fn matches(&self) -> &[Span] {
+        &self.matches
+    }
+
+    /// Returns the start of the first match after the cursor, wrapping around to the first match
+    pub fn next(&self, cursor: usize) -> Option<usize> {
+        self.matches
+            .iter()
+            .map(|m| m.start)
+            .find(|start| *start > cursor)
+            .or_else(|| self.matches.first().map(|m| m.start))
+    }
+
+    /// Returns the start of the last match before the cursor, wrapping around to the last match
+    pub fn prev(&self, cursor: usize) -> Option<usize> {
+        self.matches
+            .iter()
+            .map(|m| m.start)
+            .rev()
+            .find(|start| *start < cursor)
+            .or_else(|| self.matches.last().map(|m| m.start))
+    }
+
+    /// Returns a copy of source where every character outside of a match is replaced with a space
+    ///
+    /// Since the font is monospace, the overlay can be rendered on top of the source to highlight matches
+    pub fn overlay(&self, source: &str) -> String {
+        source
+            .char_indices()
+            .map(|(idx, c)| match c {
+                '\r' | '\n' => c,
+                _ if self.matches.iter().any(|m| m.contains(&idx)) => c,
+                _ => ' ',
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_search() {
+    let mut search = Search::default();
+    search.query = "define".to_string();
+
+    let source = "define a\rDefine b\radd c";
+    search.update(source);
+    assert_eq!(search.matches(), &[0..6, 9..15]);
+    assert_eq!(search.next(0), Some(9));
+    assert_eq!(search.next(9), Some(0));
+    assert_eq!(search.prev(9), Some(0));
+    assert_eq!(search.prev(0), Some(9));
+    assert_eq!(search.overlay(source), "define  \rDefine  \r     ");
+
+    search.query = "Define".to_string();
+    search.update(source);
+    assert_eq!(search.matches(), &[9..15]);
+}
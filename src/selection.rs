@@ -0,0 +1,77 @@
+use std::ops::Range;
+
+use crate::Token;
+
+/// Returns the span of the grammer token under offset, i.e. what a double-click selects
+///
+/// The smallest token containing offset is picked, so a value is selected rather than the attribute it belongs to.
+/// Clicking a ``` block delimitter selects the whole delimitter line, and if offset isn't inside of a token,
+/// the whitespace-delimited word under offset is selected instead
+pub fn token_at(source: &str, tokens: &[(Token, Range<usize>)], offset: usize) -> Option<Range<usize>> {
+    let token = tokens
+        .iter()
+        .filter(|(token, span)| !matches!(token, Token::Whitespace | Token::Newline) && span.contains(&offset))
+        .min_by_key(|(_, span)| span.len());
+
+    match token {
+        Some((Token::Bracket, span)) if source.get(span.clone()) == Some("```") => Some(line_at(source, span.start)),
+        Some((_, span)) => Some(span.clone()),
+        None => word_at(source, offset),
+    }
+}
+
+/// Returns the span of the whitespace-delimited word under offset
+pub fn word_at(source: &str, offset: usize) -> Option<Range<usize>> {
+    let offset = offset.min(source.len());
+    if !source.is_char_boundary(offset) {
+        return None;
+    }
+
+    let start = source[..offset]
+        .rfind(char::is_whitespace)
+        .map(|idx| idx + source[idx..].chars().next().map(char::len_utf8).unwrap_or(1))
+        .unwrap_or_default();
+    let end = source[offset..]
+        .find(char::is_whitespace)
+        .map(|idx| offset + idx)
+        .unwrap_or(source.len());
+
+    Some(start..end).filter(|span| !span.is_empty())
+}
+
+/// Returns the span of the line containing offset, w/o its line break
+fn line_at(source: &str, offset: usize) -> Range<usize> {
+    let start = source[..offset].rfind(['\r', '\n']).map(|idx| idx + 1).unwrap_or_default();
+    let end = source[offset..]
+        .find(['\r', '\n'])
+        .map(|idx| offset + idx)
+        .unwrap_or(source.len());
+    start..end
+}
+
+#[test]
+fn test_token_at() {
+    let source = "```runmd\radd scale .float2 1.0, 2.0\r```";
+    let tokens = vec![
+        (Token::Bracket, 0..3),
+        (Token::Identifier, 3..8),
+        (Token::Newline, 8..9),
+        (Token::Keyword, 9..12),
+        (Token::Identifier, 13..18),
+        (Token::Modifier, 19..35),
+        (Token::Literal, 27..35),
+        (Token::Newline, 35..36),
+        (Token::Bracket, 36..39),
+    ];
+
+    assert_eq!(token_at(source, &tokens, 1), Some(0..8));
+    assert_eq!(token_at(source, &tokens, 5), Some(3..8));
+    assert_eq!(token_at(source, &tokens, 15), Some(13..18));
+    assert_eq!(token_at(source, &tokens, 21), Some(19..35));
+    assert_eq!(token_at(source, &tokens, 30), Some(27..35));
+    assert_eq!(token_at(source, &tokens, 37), Some(36..39));
+
+    // Falls back to words when offset isn't in a token
+    assert_eq!(token_at(source, &[], 30), Some(27..31));
+    assert_eq!(word_at("a  b", 2), None);
+}
@@ -0,0 +1,43 @@
+use lifec::plugins::ThunkContext;
+
+/// Initial settings of the shell, i.e. from the `shell` block in the app world
+///
+/// ~~~text
+/// ``` shell
+/// add address   .text 127.0.0.1:7070
+/// add connect   .enable
+/// add layout    .text stacked
+/// add theme     .text light
+/// add font_size .int 32
+/// ```
+/// ~~~
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShellSettings {
+    /// Address shown in the connect ui
+    pub address: Option<String>,
+    /// If true, the shell connects to address on startup
+    pub connect: bool,
+    /// Name of a layout preset, see PaneLayout::preset
+    pub layout: Option<String>,
+    /// Name of a built-in theme, `default`, `light`, or `high_contrast`
+    pub theme: Option<String>,
+    /// Scale text is rendered at
+    pub font_size: Option<f32>,
+}
+
+impl ShellSettings {
+    /// Returns settings read from the `address`, `connect`, `layout`, `theme`, and `font_size` attributes of a block
+    pub fn from_context(tc: &ThunkContext) -> Self {
+        Self {
+            address: tc.as_ref().find_text("address").filter(|a| !a.is_empty()),
+            connect: tc.as_ref().is_enabled("connect").unwrap_or_default(),
+            layout: tc.as_ref().find_text("layout"),
+            theme: tc.as_ref().find_text("theme"),
+            font_size: tc
+                .as_ref()
+                .find_int("font_size")
+                .filter(|size| *size > 0)
+                .map(|size| size as f32),
+        }
+    }
+}
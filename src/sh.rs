@@ -0,0 +1,150 @@
+use lifec::plugins::ThunkContext;
+use logos::Lexer;
+use logos::Logos;
+use logos::Span;
+
+use crate::grammer::preceding;
+use crate::{theme::ThemeToken, Grammer, Token};
+
+/// Grammer for typical shell syntax, i.e. `grep -r "TODO" $HOME/src | wc -l`
+///
+/// Used by the prompt when the shell is connected to a remote
+#[derive(Logos, PartialEq, Eq, Debug, Clone)]
+#[logos(extras = ThunkContext)]
+pub enum Sh {
+    /// A word, true if it's in command position, i.e. the start of a line or after a pipe
+    #[regex(r#"[^ \t\r\n\f|;&<>()"'$#=\-][^ \t\r\n\f|;&<>()"'$=]*"#, on_word)]
+    Word(bool),
+    /// Flags, i.e. `-r` or `--color=auto`
+    #[regex(r"--?[A-Za-z0-9][A-Za-z0-9_\-]*(=[^ \t\r\n\f|;&<>()]*)?")]
+    Flag,
+    /// Quoted strings
+    #[regex(r#""([^"\\]|\\.)*""#)]
+    #[regex(r"'[^']*'")]
+    String,
+    /// Environment variables, i.e. `$HOME`, `${PATH}`, or `$?`
+    #[regex(r"\$[A-Za-z_][A-Za-z0-9_]*")]
+    #[regex(r"\$\{[^}\r\n]*\}")]
+    #[regex(r"\$[0-9?@#*$!]")]
+    Variable,
+    /// Variable assignment, and the spans of the name and the `=`
+    #[regex(r"[A-Za-z_][A-Za-z0-9_]*=", on_assignment)]
+    Assignment((Span, Span)),
+    /// Pipes, redirects, and command separators
+    #[token("|")]
+    #[token("||")]
+    #[token("&")]
+    #[token("&&")]
+    #[token(";")]
+    #[token(">")]
+    #[token(">>")]
+    #[token("<")]
+    #[regex(r"[0-9]>&?[0-9]?")]
+    Operator,
+    /// Comments, only if the `#` starts a word
+    #[regex(r"#[^\r\n]*", on_comment)]
+    Comment,
+    #[regex(r"[ \t\r\n\f]+", logos::skip)]
+    #[error]
+    Error,
+}
+
+impl Grammer for Sh {
+    fn name() -> &'static str {
+        "sh"
+    }
+
+    fn parses_commands() -> bool {
+        true
+    }
+}
+
+impl Into<Vec<ThemeToken>> for Sh {
+    fn into(self) -> Vec<ThemeToken> {
+        match self {
+            Sh::Word(true) => vec![(Token::Keyword, None)],
+            // Plain arguments aren't highlighted
+            Sh::Word(false) => vec![],
+            Sh::Flag => vec![(Token::Modifier, None)],
+            Sh::String => vec![(Token::Literal, None)],
+            Sh::Variable => vec![(Token::Identifier, None)],
+            Sh::Assignment((name, equals)) => vec![
+                (Token::Identifier, Some(name)),
+                (Token::Operator, Some(equals)),
+            ],
+            Sh::Operator => vec![(Token::Operator, None)],
+            Sh::Comment => vec![(Token::Comment, None)],
+            Sh::Error => vec![],
+        }
+    }
+}
+
+/// A word is in command position if it starts the line, or follows a pipe, separator, or assignment
+fn on_word(lexer: &mut Lexer<Sh>) -> bool {
+    let preceding = preceding(lexer);
+
+    // Continues the previous token, i.e. the value of `RUST_LOG=debug`
+    if preceding.ends_with(|c: char| !c.is_whitespace() && !matches!(c, '|' | ';' | '&' | '(')) {
+        return false;
+    }
+
+    let preceding = preceding.trim_end();
+    if preceding.is_empty() || preceding.ends_with(|c| matches!(c, '|' | ';' | '&' | '(')) {
+        return true;
+    }
+
+    // i.e. `RUST_LOG=debug cargo run`, the last word before the command is an assignment
+    let last_word = preceding
+        .rsplit(|c: char| c.is_whitespace())
+        .next()
+        .unwrap_or_default();
+    let is_assignment = last_word
+        .split_once('=')
+        .map(|(name, _)| !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+        .unwrap_or_default();
+
+    is_assignment && {
+        let before = preceding[..preceding.len() - last_word.len()].trim_end();
+        before.is_empty() || before.ends_with(|c| matches!(c, '|' | ';' | '&' | '('))
+    }
+}
+
+/// Splits the name of an assignment from the `=`
+fn on_assignment(lexer: &mut Lexer<Sh>) -> (Span, Span) {
+    let span = lexer.span();
+    (span.start..span.end - 1, span.end - 1..span.end)
+}
+
+/// `#` only starts a comment at the start of a word, i.e. not in `a#b`
+fn on_comment(lexer: &mut Lexer<Sh>) -> bool {
+    let start = lexer.span().start;
+    lexer.source()[..start]
+        .chars()
+        .last()
+        .map(|c| c.is_whitespace())
+        .unwrap_or(true)
+}
+
+#[test]
+fn test_sh() {
+    let source = "RUST_LOG=debug cargo run --release | grep \"error\" > $HOME/out.txt # note";
+    let mut lexer = Sh::lexer_with_extras(source, ThunkContext::default());
+
+    assert_eq!(lexer.next(), Some(Sh::Assignment((0..8, 8..9))));
+    assert_eq!(lexer.next(), Some(Sh::Word(false)));
+    assert_eq!(lexer.slice(), "debug");
+    assert_eq!(lexer.next(), Some(Sh::Word(true)));
+    assert_eq!(lexer.slice(), "cargo");
+    assert_eq!(lexer.next(), Some(Sh::Word(false)));
+    assert_eq!(lexer.next(), Some(Sh::Flag));
+    assert_eq!(lexer.next(), Some(Sh::Operator));
+    assert_eq!(lexer.next(), Some(Sh::Word(true)));
+    assert_eq!(lexer.slice(), "grep");
+    assert_eq!(lexer.next(), Some(Sh::String));
+    assert_eq!(lexer.next(), Some(Sh::Operator));
+    assert_eq!(lexer.next(), Some(Sh::Variable));
+    assert_eq!(lexer.next(), Some(Sh::Word(false)));
+    assert_eq!(lexer.slice(), "/out.txt");
+    assert_eq!(lexer.next(), Some(Sh::Comment));
+    assert_eq!(lexer.next(), None);
+}
@@ -0,0 +1,22 @@
+use lifec::Component;
+use lifec::DenseVecStorage;
+use tokio::sync::mpsc::Receiver;
+
+/// This component receives lines typed in the shell while its channel is focused
+///
+/// The reverse of ShellChannel, for interactive plugins like REPLs and prompts, see Shell::add_input
+#[derive(Component, Default)]
+#[storage(DenseVecStorage)]
+pub struct ShellInput(pub(crate) Option<Receiver<String>>);
+
+impl ShellInput {
+    /// Waits for the next line typed for this channel, returns None once the shell stops sending input
+    pub async fn recv(&mut self) -> Option<String> {
+        self.0.as_mut()?.recv().await
+    }
+
+    /// Returns the next line typed for this channel, if one is available
+    pub fn try_recv(&mut self) -> Option<String> {
+        self.0.as_mut()?.try_recv().ok()
+    }
+}
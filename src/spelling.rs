@@ -0,0 +1,181 @@
+use lifec::plugins::ThunkContext;
+use logos::Logos;
+use logos::Span;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use crate::Diagnostic;
+use crate::Runmd;
+
+/// Trait for a dictionary provided by the host, used to spell check free text
+///
+pub trait Dictionary: Send + Sync {
+    /// Returns true if the word is spelled correctly
+    fn contains(&self, word: &str) -> bool;
+
+    /// Returns suggested corrections for a misspelled word
+    fn suggest(&self, _word: &str) -> Vec<String> {
+        vec![]
+    }
+}
+
+/// Dictionary backed by a list of words, suggests words within a small edit distance
+///
+#[derive(Default, Clone)]
+pub struct WordList {
+    words: BTreeSet<String>,
+}
+
+impl WordList {
+    /// Returns a word list from words, i.e. the lines of a dictionary file
+    pub fn from_words(words: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            words: words
+                .into_iter()
+                .map(|w| w.as_ref().trim().to_lowercase())
+                .filter(|w| !w.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl Dictionary for WordList {
+    fn contains(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    fn suggest(&self, word: &str) -> Vec<String> {
+        let word = word.to_lowercase();
+        let mut suggestions = self
+            .words
+            .iter()
+            .map(|w| (edit_distance(&word, w), w))
+            .filter(|(distance, _)| *distance <= 2)
+            .collect::<Vec<_>>();
+
+        suggestions.sort_by_key(|(distance, _)| *distance);
+        suggestions
+            .into_iter()
+            .take(3)
+            .map(|(_, w)| w.to_string())
+            .collect()
+    }
+}
+
+/// Spell checks .text attribute values and comments in runmd
+///
+#[derive(Clone)]
+pub struct SpellChecker {
+    dictionary: Arc<dyn Dictionary>,
+}
+
+impl SpellChecker {
+    /// Returns a spell checker that uses dictionary
+    pub fn new(dictionary: impl Dictionary + 'static) -> Self {
+        Self {
+            dictionary: Arc::new(dictionary),
+        }
+    }
+
+    /// Returns a diagnostic for each misspelled word in the free text of source
+    ///
+    /// Diagnostics don't include suggestions, since looking them up scans the dictionary, see `suggest`
+    pub fn check(&self, source: &str) -> Vec<Diagnostic> {
+        let mut lexer = Runmd::lexer_with_extras(source, ThunkContext::default());
+        let mut diagnostics = vec![];
+
+        while let Some(token) = lexer.next() {
+            let span = match token {
+                Runmd::AttributeValue((type_span, literal_span))
+                    if source[type_span].trim() == ".text" =>
+                {
+                    literal_span
+                }
+                Runmd::Comment => lexer.span(),
+                _ => continue,
+            };
+
+            for word in words(source, span) {
+                let text = &source[word.clone()];
+                if !self.dictionary.contains(text) {
+                    diagnostics.push(Diagnostic::new(source, word.clone(), format!("unknown word: {text}")));
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Returns suggested corrections for a misspelled word
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        self.dictionary.suggest(word)
+    }
+}
+
+/// Returns the spans of words within span of source
+fn words(source: &str, span: Span) -> Vec<Span> {
+    let mut words = vec![];
+    let mut start = None;
+
+    for (idx, c) in source[span.clone()].char_indices() {
+        let idx = span.start + idx;
+        match (c.is_alphabetic() || c == '\'', start) {
+            (true, None) => start = Some(idx),
+            (false, Some(s)) => {
+                words.push(s..idx);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        words.push(s..span.end);
+    }
+
+    words
+        .into_iter()
+        .map(|w| {
+            let trimmed = source[w.clone()].trim_matches('\'');
+            let offset = source[w.clone()].find(trimmed).unwrap_or_default();
+            w.start + offset..w.start + offset + trimmed.len()
+        })
+        .filter(|w| w.len() > 1)
+        .collect()
+}
+
+/// Returns the number of single character edits to turn a into b
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+#[test]
+fn test_spell_check() {
+    let checker = SpellChecker::new(WordList::from_words(["hello", "world", "greeting"]));
+    let source = "``` test\r# hello wrld\r.text helo world\r```\r";
+
+    let diagnostics = checker.check(source);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(&source[diagnostics[0].span.clone()], "wrld");
+    assert!(diagnostics[0].suggestions.is_empty());
+    assert_eq!(&source[diagnostics[1].span.clone()], "helo");
+    assert_eq!(checker.suggest("wrld"), vec!["world"]);
+    assert_eq!(checker.suggest("helo"), vec!["hello"]);
+}
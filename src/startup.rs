@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant};
+
+/// Timings of the stages of initializing the shell, shown in the diagnostics window
+///
+#[derive(Debug, Default, Clone)]
+pub struct StartupTimings {
+    /// Name and duration of each stage, in the order they completed
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl StartupTimings {
+    /// Records the duration of a stage
+    pub fn record(&mut self, stage: &'static str, duration: Duration) {
+        self.stages.push((stage, duration));
+    }
+
+    /// Runs f, recording how long it took as stage
+    pub fn time<T>(&mut self, stage: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(stage, start.elapsed());
+        result
+    }
+
+    /// Returns the recorded stages
+    pub fn stages(&self) -> &[(&'static str, Duration)] {
+        &self.stages
+    }
+
+    /// Returns the sum of every recorded stage
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, d)| *d).sum()
+    }
+}
+
+#[test]
+fn test_startup_timings() {
+    let mut timings = StartupTimings::default();
+    timings.record("font", Duration::from_millis(5));
+    let value = timings.time("theme", || 42);
+
+    assert_eq!(value, 42);
+    assert_eq!(timings.stages().len(), 2);
+    assert_eq!(timings.stages()[1].0, "theme");
+    assert!(timings.total() >= Duration::from_millis(5));
+}
@@ -0,0 +1,75 @@
+use std::time::{Duration, Instant};
+
+/// Times how long a channel's plugin runs, driven by the plugin's `shell_running` attribute
+///
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stopwatch {
+    /// When the current run started, None if the plugin isn't running
+    started: Option<Instant>,
+    /// Total of the most recently completed run
+    last: Option<Duration>,
+}
+
+impl Stopwatch {
+    /// Updates whether the plugin is running at now, returns the total of the run if it just completed
+    pub fn update(&mut self, running: bool, now: Instant) -> Option<Duration> {
+        match (self.started, running) {
+            (None, true) => {
+                self.started = Some(now);
+                None
+            }
+            (Some(started), false) => {
+                let total = now.saturating_duration_since(started);
+                self.started = None;
+                self.last = Some(total);
+                Some(total)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if the plugin is running
+    pub fn running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    /// Returns how long the current run has taken at now, None if the plugin isn't running
+    pub fn elapsed(&self, now: Instant) -> Option<Duration> {
+        self.started.map(|started| now.saturating_duration_since(started))
+    }
+
+    /// Returns the total of the most recently completed run
+    pub fn last(&self) -> Option<Duration> {
+        self.last
+    }
+}
+
+/// Formats a duration as `m:ss`, or `h:mm:ss` once it's over an hour
+pub fn format_elapsed(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+#[test]
+fn test_stopwatch() {
+    let start = Instant::now();
+    let mut stopwatch = Stopwatch::default();
+    assert_eq!(stopwatch.update(false, start), None);
+
+    assert_eq!(stopwatch.update(true, start), None);
+    assert!(stopwatch.running());
+    assert_eq!(stopwatch.update(true, start + Duration::from_secs(5)), None);
+    assert_eq!(stopwatch.elapsed(start + Duration::from_secs(65)), Some(Duration::from_secs(65)));
+
+    assert_eq!(stopwatch.update(false, start + Duration::from_secs(70)), Some(Duration::from_secs(70)));
+    assert!(!stopwatch.running());
+    assert_eq!(stopwatch.last(), Some(Duration::from_secs(70)));
+
+    assert_eq!(format_elapsed(Duration::from_secs(70)), "1:10");
+    assert_eq!(format_elapsed(Duration::from_secs(3725)), "1:02:05");
+}
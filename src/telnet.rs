@@ -0,0 +1,377 @@
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tracing::{event, Level};
+
+use crate::{ShellChannel, Transport};
+
+/// Interpret as command, starts every telnet command
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+/// Starts a subnegotiation
+const SB: u8 = 250;
+/// Ends a subnegotiation
+const SE: u8 = 240;
+
+/// Option for the server echoing what's sent to it
+const ECHO: u8 = 1;
+/// Option for suppressing go-ahead, i.e. character at a time mode
+const SGA: u8 = 3;
+/// Option for negotiating about window size, RFC 1073
+const NAWS: u8 = 31;
+
+/// Max bytes read from the connection at a time
+const READ_BUFFER_LEN: usize = 4096;
+
+/// Transport to a tcp endpoint, w/ a minimal telnet protocol layer
+///
+/// Telnet commands received from the endpoint are stripped from its output and answered. The only option the shell
+/// enables is NAWS, so the endpoint is told the size of the shell's window. The layer is only active for `telnet://`
+/// addresses, or once the endpoint sends a telnet command. Until then bytes pass through as-is both ways, so that
+/// binary protocols aren't altered.
+pub struct Telnet {
+    /// Write half of the connection, shared w/ the task answering commands
+    writer: Arc<tokio::sync::Mutex<OwnedWriteHalf>>,
+    /// State of option negotiation, shared w/ the task receiving output
+    negotiation: Arc<Mutex<Negotiation>>,
+    /// Address of the endpoint
+    peer: String,
+}
+
+impl Telnet {
+    /// Starts receiving output from stream, output is written to the shell channel
+    ///
+    /// If telnet is false, the telnet layer is only activated once the endpoint sends a telnet command.
+    /// Must be called within a tokio runtime
+    pub fn start(stream: TcpStream, output: ShellChannel, telnet: bool) -> Self {
+        let peer = stream
+            .peer_addr()
+            .ok()
+            .map(|a| a.to_string())
+            .unwrap_or_default();
+        let (reader, writer) = stream.into_split();
+
+        let writer = Arc::new(tokio::sync::Mutex::new(writer));
+        let negotiation = Arc::new(Mutex::new(Negotiation::new(telnet)));
+        tokio::spawn(receive(reader, writer.clone(), negotiation.clone(), output));
+
+        Self {
+            writer,
+            negotiation,
+            peer,
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for Telnet {
+    async fn write(&mut self, message: &[u8]) -> std::io::Result<usize> {
+        let active = self
+            .negotiation
+            .lock()
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Telnet negotiation state is poisoned"))?
+            .is_active();
+
+        if active {
+            self.writer.lock().await.write_all(&escape(message)).await?;
+        } else {
+            self.writer.lock().await.write_all(message).await?;
+        }
+        Ok(message.len())
+    }
+
+    fn peer(&self) -> String {
+        self.peer.clone()
+    }
+
+    async fn resize(&mut self, cols: u16, rows: u16) -> std::io::Result<()> {
+        let report = self
+            .negotiation
+            .lock()
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "Telnet negotiation state is poisoned"))?
+            .resize(cols, rows);
+
+        match report {
+            Some(report) => self.writer.lock().await.write_all(&report).await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Where the parser is in the stream of bytes received from the endpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Data,
+    /// After a `\r`, a following NUL is padding
+    Cr,
+    Iac,
+    /// After an IAC, before the telnet layer is active, the next byte tells if it starts a command
+    RawIac,
+    /// After IAC and one of WILL, WONT, DO, or DONT, waiting for the option
+    Verb(u8),
+    Sub,
+    SubIac,
+}
+
+/// Telnet option negotiation, separated from the connection
+#[derive(Debug, Clone)]
+pub struct Negotiation {
+    state: State,
+    /// True once the endpoint is known to speak telnet, before that bytes are received as-is
+    active: bool,
+    /// Options the endpoint has been told to enable
+    remote: Vec<u8>,
+    /// True if the shell agreed to report its window size
+    naws: bool,
+    /// Window size in columns and rows
+    size: (u16, u16),
+}
+
+impl Negotiation {
+    /// Returns the negotiation state of a connection, if active is false it's activated by the first telnet command
+    pub fn new(active: bool) -> Self {
+        Self {
+            state: State::Data,
+            active,
+            remote: vec![],
+            naws: false,
+            size: (80, 24),
+        }
+    }
+
+    /// Returns true if the endpoint speaks telnet, so that data sent to it must be escaped
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Parses bytes received from the endpoint, output is appended to data and answers to commands are appended to replies
+    pub fn receive(&mut self, bytes: &[u8], data: &mut Vec<u8>, replies: &mut Vec<u8>) {
+        for next in bytes.iter().copied() {
+            self.state = match (self.state, next) {
+                // Until the endpoint negotiates an option, an IAC is only data
+                (State::Data, IAC) if !self.active => State::RawIac,
+                (State::RawIac, WILL | WONT | DO | DONT | SB) => {
+                    event!(Level::DEBUG, "Endpoint sent a telnet command, activating telnet");
+                    self.active = true;
+                    if next == SB {
+                        State::Sub
+                    } else {
+                        State::Verb(next)
+                    }
+                }
+                (State::RawIac, IAC) => {
+                    data.push(IAC);
+                    State::RawIac
+                }
+                (State::RawIac, next) => {
+                    data.extend([IAC, next]);
+                    State::Data
+                }
+                (State::Data, next) if !self.active => {
+                    data.push(next);
+                    State::Data
+                }
+                (State::Data | State::Cr, IAC) => State::Iac,
+                (State::Cr, 0) => State::Data,
+                (State::Data | State::Cr, next) => {
+                    data.push(next);
+                    if next == b'\r' {
+                        State::Cr
+                    } else {
+                        State::Data
+                    }
+                }
+                (State::Iac, IAC) => {
+                    data.push(IAC);
+                    State::Data
+                }
+                (State::Iac, WILL | WONT | DO | DONT) => State::Verb(next),
+                (State::Iac, SB) => State::Sub,
+                // Go-ahead, NOP, and other commands w/o an option don't affect the output
+                (State::Iac, _) => State::Data,
+                (State::Verb(verb), option) => {
+                    self.negotiate(verb, option, replies);
+                    State::Data
+                }
+                // Subnegotiation of options the shell didn't enable are skipped
+                (State::Sub, IAC) => State::SubIac,
+                (State::Sub, _) => State::Sub,
+                (State::SubIac, SE) => State::Data,
+                (State::SubIac, _) => State::Sub,
+            };
+        }
+    }
+
+    /// Sets the window size, returns the report to send if the endpoint asked for it
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Option<Vec<u8>> {
+        self.size = (cols, rows);
+        if self.naws {
+            Some(self.window_size())
+        } else {
+            None
+        }
+    }
+
+    /// Answers a request to change an option, requests that don't change the state of an option aren't answered
+    fn negotiate(&mut self, verb: u8, option: u8, replies: &mut Vec<u8>) {
+        event!(Level::TRACE, "Received telnet command {verb} {option}");
+        match (verb, option) {
+            (DO, NAWS) => {
+                if !self.naws {
+                    self.naws = true;
+                    replies.extend([IAC, WILL, NAWS]);
+                }
+                replies.extend(self.window_size());
+            }
+            (DONT, NAWS) if self.naws => {
+                self.naws = false;
+                replies.extend([IAC, WONT, NAWS]);
+            }
+            (DO, option) => replies.extend([IAC, WONT, option]),
+            (WILL, ECHO | SGA) if !self.remote.contains(&option) => {
+                self.remote.push(option);
+                replies.extend([IAC, DO, option]);
+            }
+            (WILL, option) if !self.remote.contains(&option) => replies.extend([IAC, DONT, option]),
+            (WONT, option) if self.remote.contains(&option) => {
+                self.remote.retain(|o| *o != option);
+                replies.extend([IAC, DONT, option]);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the NAWS subnegotiation reporting the window size
+    fn window_size(&self) -> Vec<u8> {
+        let (cols, rows) = self.size;
+        let mut report = vec![IAC, SB, NAWS];
+        for byte in cols.to_be_bytes().into_iter().chain(rows.to_be_bytes()) {
+            report.push(byte);
+            if byte == IAC {
+                report.push(IAC);
+            }
+        }
+        report.extend([IAC, SE]);
+        report
+    }
+}
+
+/// Returns message w/ IAC bytes doubled, so they're received as data
+fn escape(message: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(message.len());
+    for byte in message {
+        escaped.push(*byte);
+        if *byte == IAC {
+            escaped.push(IAC);
+        }
+    }
+    escaped
+}
+
+/// Writes output from the endpoint to the shell channel, and answers its commands, until the connection closes
+async fn receive(
+    mut reader: OwnedReadHalf,
+    writer: Arc<tokio::sync::Mutex<OwnedWriteHalf>>,
+    negotiation: Arc<Mutex<Negotiation>>,
+    output: ShellChannel,
+) {
+    let mut output = match output.writer() {
+        Some(output) => output,
+        None => return,
+    };
+
+    let mut buf = vec![0; READ_BUFFER_LEN];
+    let (mut data, mut replies) = (vec![], vec![]);
+    loop {
+        let len = match reader.read(&mut buf).await {
+            Ok(0) => {
+                event!(Level::DEBUG, "Connection closed by endpoint");
+                break;
+            }
+            Ok(len) => len,
+            Err(err) => {
+                event!(Level::DEBUG, "Connection closed, {err}");
+                break;
+            }
+        };
+
+        match negotiation.lock() {
+            Ok(mut negotiation) => negotiation.receive(&buf[..len], &mut data, &mut replies),
+            Err(_) => {
+                event!(Level::WARN, "Telnet negotiation state is poisoned, closing connection");
+                break;
+            }
+        }
+
+        if !replies.is_empty() {
+            if let Err(err) = writer.lock().await.write_all(&replies).await {
+                event!(Level::DEBUG, "Could not answer telnet commands, {err}");
+                break;
+            }
+            replies.clear();
+        }
+
+        if output.write_all(&data).await.is_err() {
+            break;
+        }
+        data.clear();
+    }
+}
+
+#[test]
+fn test_negotiation() {
+    let mut negotiation = Negotiation::new(true);
+    let (mut data, mut replies) = (vec![], vec![]);
+
+    assert_eq!(negotiation.resize(100, 30), None);
+
+    let received = [
+        &b"login:"[..],
+        &[IAC, DO, NAWS, IAC, WILL, ECHO, IAC, DO, 24],
+        &[IAC, SB, 24, 1, IAC, IAC, IAC, SE],
+        &[b'a', IAC, IAC, b'\r', 0, b'\r', b'\n'],
+    ]
+    .concat();
+
+    // Commands can be split across reads
+    let (first, second) = received.split_at(9);
+    negotiation.receive(first, &mut data, &mut replies);
+    negotiation.receive(second, &mut data, &mut replies);
+
+    assert_eq!(data, [&b"login:a"[..], &[IAC, b'\r', b'\r', b'\n']].concat());
+    assert_eq!(
+        replies,
+        [
+            IAC, WILL, NAWS, IAC, SB, NAWS, 0, 100, 0, 30, IAC, SE,
+            IAC, DO, ECHO,
+            IAC, WONT, 24,
+        ]
+    );
+
+    // Requests that don't change an option aren't answered
+    replies.clear();
+    negotiation.receive(&[IAC, WILL, ECHO, IAC, DONT, 24], &mut data, &mut replies);
+    assert!(replies.is_empty());
+
+    assert_eq!(
+        negotiation.resize(255, 40),
+        Some(vec![IAC, SB, NAWS, 0, IAC, IAC, 0, 40, IAC, SE])
+    );
+    assert_eq!(escape(&[1, IAC, 2]), vec![1, IAC, IAC, 2]);
+
+    // Bytes pass through as-is, until the endpoint sends a telnet command
+    let mut negotiation = Negotiation::new(false);
+    let (mut data, mut replies) = (vec![], vec![]);
+    negotiation.receive(&[0, 3, IAC, 1, IAC, IAC, b'\r', 0], &mut data, &mut replies);
+    assert_eq!(data, vec![0, 3, IAC, 1, IAC, IAC, b'\r', 0]);
+    assert!(!negotiation.is_active());
+
+    negotiation.receive(&[IAC, DO, NAWS], &mut data, &mut replies);
+    assert!(negotiation.is_active());
+    assert_eq!(&replies[..3], &[IAC, WILL, NAWS]);
+}
@@ -0,0 +1,415 @@
+use std::ops::Range;
+
+use crate::color::truecolor;
+use crate::ColorTheme;
+
+/// Columns between tab stops
+const TAB_WIDTH: usize = 8;
+
+/// Max length of the parameters of a CSI sequence, longer parameters are ignored past it
+const MAX_CSI_PARAMS: usize = 64;
+
+/// Color of a cell in a terminal grid, as set by SGR escape sequences
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CellColor {
+    /// Theme's foreground or background color
+    #[default]
+    Default,
+    /// Color of the 256-color palette, 0-15 are the ansi colors of the theme
+    Indexed(u8),
+    /// 24-bit sRGB color
+    Rgb(u8, u8, u8),
+}
+
+impl CellColor {
+    /// Returns the color from theme, default is returned for CellColor::Default
+    pub fn resolve(&self, theme: &dyn ColorTheme, default: [f32; 4]) -> [f32; 4] {
+        match self {
+            CellColor::Default => default,
+            CellColor::Indexed(index) => theme.color(*index),
+            CellColor::Rgb(r, g, b) => truecolor(*r, *g, *b),
+        }
+    }
+}
+
+/// Style of a cell in a terminal grid
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CellStyle {
+    /// Color of the cell's character
+    pub foreground: CellColor,
+    /// Color behind the cell's character
+    pub background: CellColor,
+    /// If true, the character is bold
+    pub bold: bool,
+    /// If true, foreground and background are swapped
+    pub inverse: bool,
+}
+
+impl CellStyle {
+    /// Returns the foreground and background colors of the style from theme, w/ inverse applied
+    pub fn colors(&self, theme: &dyn ColorTheme) -> ([f32; 4], Option<[f32; 4]>) {
+        let foreground = self.foreground.resolve(theme, theme.foreground());
+        let background = match self.background {
+            CellColor::Default => None,
+            color => Some(color.resolve(theme, theme.background())),
+        };
+
+        if self.inverse {
+            (background.unwrap_or_else(|| theme.background()), Some(foreground))
+        } else {
+            (foreground, background)
+        }
+    }
+}
+
+/// Character and style of a cell in a terminal grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    /// Character shown in the cell
+    pub ch: char,
+    /// Style the character is shown in
+    pub style: CellStyle,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            style: CellStyle::default(),
+        }
+    }
+}
+
+/// State of the escape sequence parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParseState {
+    /// Printing characters
+    Ground,
+    /// After ESC
+    Escape,
+    /// After ESC [, w/ the parameters read so far
+    Csi(String),
+}
+
+/// Fixed grid of rows x cols cells, written to like a terminal
+///
+/// Unlike the free-form buffer of a char_device, escape sequences can position the cursor absolutely, so remote
+/// applications that redraw the screen, i.e. `top` or `vim`, render accurately. Supports cursor movement, erasing, and SGR colors
+#[derive(Debug, Clone)]
+pub struct TerminalGrid {
+    rows: usize,
+    cols: usize,
+    cells: Vec<Cell>,
+    /// Row and column of the cursor, the column is cols after the last column is written until the next character wraps
+    cursor: (usize, usize),
+    saved_cursor: (usize, usize),
+    cursor_visible: bool,
+    style: CellStyle,
+    state: ParseState,
+    /// Bytes of a utf-8 character that hasn't been completed yet
+    utf8: Vec<u8>,
+}
+
+impl TerminalGrid {
+    /// Returns an empty grid of rows x cols cells
+    pub fn new(rows: usize, cols: usize) -> Self {
+        let (rows, cols) = (rows.max(1), cols.max(1));
+        Self {
+            rows,
+            cols,
+            cells: vec![Cell::default(); rows * cols],
+            cursor: (0, 0),
+            saved_cursor: (0, 0),
+            cursor_visible: true,
+            style: CellStyle::default(),
+            state: ParseState::Ground,
+            utf8: vec![],
+        }
+    }
+
+    /// Returns the number of rows and columns of the grid
+    pub fn size(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    /// Resizes the grid, cells that are still inside of the grid are kept and the cursor is clamped to it
+    pub fn resize(&mut self, rows: usize, cols: usize) {
+        let (rows, cols) = (rows.max(1), cols.max(1));
+        if (rows, cols) == (self.rows, self.cols) {
+            return;
+        }
+
+        let mut cells = vec![Cell::default(); rows * cols];
+        for row in 0..rows.min(self.rows) {
+            for col in 0..cols.min(self.cols) {
+                cells[row * cols + col] = self.cells[row * self.cols + col];
+            }
+        }
+
+        self.rows = rows;
+        self.cols = cols;
+        self.cells = cells;
+        self.cursor = self.clamped(self.cursor);
+        self.saved_cursor = self.clamped(self.saved_cursor);
+    }
+
+    /// Returns position moved inside of the grid
+    fn clamped(&self, (row, col): (usize, usize)) -> (usize, usize) {
+        (row.min(self.rows - 1), col.min(self.cols - 1))
+    }
+
+    /// Returns the row and column of the cursor
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.cursor.0, self.cursor.1.min(self.cols - 1))
+    }
+
+    /// Returns false if the application hid the cursor
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /// Returns the cell at row and col, or None if it's outside of the grid
+    pub fn cell(&self, row: usize, col: usize) -> Option<&Cell> {
+        (row < self.rows && col < self.cols).then(|| &self.cells[row * self.cols + col])
+    }
+
+    /// Returns the characters of row
+    pub fn row_text(&self, row: usize) -> String {
+        self.row(row).iter().map(|c| c.ch).collect()
+    }
+
+    /// Returns the rows of the grid, w/ trailing spaces trimmed, separated by `\r`
+    pub fn text(&self) -> String {
+        (0..self.rows)
+            .map(|row| self.row_text(row).trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\r")
+    }
+
+    /// Returns the columns of row split into runs of cells that share a style
+    pub fn runs(&self, row: usize) -> Vec<(Range<usize>, CellStyle)> {
+        let mut runs: Vec<(Range<usize>, CellStyle)> = vec![];
+        for (col, cell) in self.row(row).iter().enumerate() {
+            match runs.last_mut() {
+                Some((range, style)) if *style == cell.style => range.end = col + 1,
+                _ => runs.push((col..col + 1, cell.style)),
+            }
+        }
+        runs
+    }
+
+    /// Writes bytes to the grid, interpreting control characters and escape sequences
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.write_byte(*b);
+        }
+    }
+
+    fn row(&self, row: usize) -> &[Cell] {
+        if row < self.rows {
+            &self.cells[row * self.cols..(row + 1) * self.cols]
+        } else {
+            &[]
+        }
+    }
+
+    fn write_byte(&mut self, b: u8) {
+        match std::mem::replace(&mut self.state, ParseState::Ground) {
+            ParseState::Ground => match b {
+                0x1b => self.state = ParseState::Escape,
+                b'\r' => self.cursor.1 = 0,
+                b'\n' => self.line_feed(),
+                0x08 => self.cursor.1 = self.cursor.1.min(self.cols - 1).saturating_sub(1),
+                b'\t' => self.cursor.1 = ((self.cursor.1 / TAB_WIDTH + 1) * TAB_WIDTH).min(self.cols - 1),
+                0x00..=0x1f | 0x7f => {}
+                _ => {
+                    self.utf8.push(b);
+                    match std::str::from_utf8(&self.utf8) {
+                        Ok(s) => {
+                            let chars = s.chars().collect::<Vec<_>>();
+                            self.utf8.clear();
+                            chars.into_iter().for_each(|c| self.print(c));
+                        }
+                        Err(err) if err.error_len().is_some() || self.utf8.len() >= 4 => {
+                            self.utf8.clear();
+                            self.print(char::REPLACEMENT_CHARACTER);
+                        }
+                        Err(_) => {}
+                    }
+                }
+            },
+            ParseState::Escape => match b {
+                b'[' => self.state = ParseState::Csi(String::new()),
+                b'7' => self.saved_cursor = self.cursor,
+                b'8' => self.cursor = self.clamped(self.saved_cursor),
+                b'c' => *self = Self::new(self.rows, self.cols),
+                _ => {}
+            },
+            ParseState::Csi(mut params) => match b {
+                0x40..=0x7e => self.csi(&params, b as char),
+                _ => {
+                    if params.len() < MAX_CSI_PARAMS {
+                        params.push(b as char);
+                    }
+                    self.state = ParseState::Csi(params);
+                }
+            },
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        if self.cursor.1 >= self.cols {
+            self.cursor.1 = 0;
+            self.line_feed();
+        }
+
+        let (row, col) = self.cursor;
+        self.cells[row * self.cols + col] = Cell { ch: c, style: self.style };
+        self.cursor.1 += 1;
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor.0 + 1 < self.rows {
+            self.cursor.0 += 1;
+        } else {
+            self.cells.drain(..self.cols);
+            self.cells.extend(std::iter::repeat(Cell::default()).take(self.cols));
+        }
+    }
+
+    /// Resets cells in range of the grid's cells to blanks
+    fn erase(&mut self, range: Range<usize>) {
+        let end = range.end.min(self.cells.len());
+        for cell in &mut self.cells[range.start.min(end)..end] {
+            *cell = Cell::default();
+        }
+    }
+
+    fn csi(&mut self, params: &str, action: char) {
+        let private = params.starts_with('?');
+        let args = params
+            .trim_start_matches('?')
+            .split(';')
+            .map(|p| p.parse::<usize>().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let arg = |idx: usize| args.get(idx).copied().unwrap_or_default();
+        let count = arg(0).max(1);
+
+        let (row, col) = (self.cursor.0, self.cursor.1.min(self.cols - 1));
+        let at = row * self.cols + col;
+        match action {
+            'A' => self.cursor.0 = row.saturating_sub(count),
+            'B' => self.cursor.0 = (row + count).min(self.rows - 1),
+            'C' => self.cursor.1 = (col + count).min(self.cols - 1),
+            'D' => self.cursor.1 = col.saturating_sub(count),
+            'G' => self.cursor.1 = (count - 1).min(self.cols - 1),
+            'd' => self.cursor.0 = (count - 1).min(self.rows - 1),
+            'H' | 'f' => {
+                self.cursor = (
+                    (arg(0).max(1) - 1).min(self.rows - 1),
+                    (arg(1).max(1) - 1).min(self.cols - 1),
+                )
+            }
+            'J' => match arg(0) {
+                0 => self.erase(at..self.cells.len()),
+                1 => self.erase(0..at + 1),
+                _ => self.erase(0..self.cells.len()),
+            },
+            'K' => match arg(0) {
+                0 => self.erase(at..(row + 1) * self.cols),
+                1 => self.erase(row * self.cols..at + 1),
+                _ => self.erase(row * self.cols..(row + 1) * self.cols),
+            },
+            'm' => self.sgr(&args),
+            'h' | 'l' if private && arg(0) == 25 => self.cursor_visible = action == 'h',
+            's' => self.saved_cursor = self.cursor,
+            'u' => self.cursor = self.clamped(self.saved_cursor),
+            _ => {}
+        }
+    }
+
+    fn sgr(&mut self, args: &[usize]) {
+        let mut args = args.iter().copied();
+        while let Some(code) = args.next() {
+            match code {
+                0 => self.style = CellStyle::default(),
+                1 => self.style.bold = true,
+                22 => self.style.bold = false,
+                7 => self.style.inverse = true,
+                27 => self.style.inverse = false,
+                30..=37 => self.style.foreground = CellColor::Indexed((code - 30) as u8),
+                90..=97 => self.style.foreground = CellColor::Indexed((code - 90 + 8) as u8),
+                39 => self.style.foreground = CellColor::Default,
+                40..=47 => self.style.background = CellColor::Indexed((code - 40) as u8),
+                100..=107 => self.style.background = CellColor::Indexed((code - 100 + 8) as u8),
+                49 => self.style.background = CellColor::Default,
+                38 | 48 => {
+                    let color = match args.next() {
+                        Some(5) => args.next().map(|i| CellColor::Indexed(i.min(255) as u8)),
+                        Some(2) => match (args.next(), args.next(), args.next()) {
+                            (Some(r), Some(g), Some(b)) => {
+                                Some(CellColor::Rgb(r.min(255) as u8, g.min(255) as u8, b.min(255) as u8))
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+
+                    match (code, color) {
+                        (38, Some(color)) => self.style.foreground = color,
+                        (48, Some(color)) => self.style.background = color,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn test_terminal_grid() {
+    let mut grid = TerminalGrid::new(3, 5);
+    grid.write_bytes(b"hello world");
+    assert_eq!(grid.text(), "hello\r worl\rd");
+    assert_eq!(grid.cursor(), (2, 1));
+
+    // Writing past the last row scrolls the grid up
+    grid.write_bytes(b"\r\nnext");
+    assert_eq!(grid.text(), " worl\rd\rnext");
+
+    // Cursor positioning is 1-based, and erasing clears to the end of the line
+    grid.write_bytes(b"\x1b[1;2HX\x1b[2;1H\x1b[2K\x1b[3;3H\x1b[K");
+    assert_eq!(grid.text(), " Xorl\r\rne");
+
+    grid.write_bytes("\x1b[2J\x1b[H\x1b[31;1mé\x1b[0mb".as_bytes());
+    assert_eq!(grid.row_text(0), "éb   ");
+    let red = CellStyle {
+        foreground: CellColor::Indexed(1),
+        bold: true,
+        ..Default::default()
+    };
+    assert_eq!(grid.runs(0), vec![(0..1, red), (1..5, CellStyle::default())]);
+
+    grid.write_bytes(b"\x1b[?25l\x1b[48;2;0;128;255m ");
+    assert!(!grid.cursor_visible());
+    assert_eq!(grid.cell(0, 2).map(|c| c.style.background), Some(CellColor::Rgb(0, 128, 255)));
+
+    grid.resize(2, 2);
+    assert_eq!(grid.size(), (2, 2));
+    assert_eq!(grid.text(), "éb\r");
+
+    // A cursor saved before the grid shrinks is restored inside of it
+    let mut grid = TerminalGrid::new(10, 10);
+    grid.write_bytes(b"\x1b[9;9H\x1b7\x1b[8;8H\x1b[s");
+    grid.resize(3, 3);
+    grid.write_bytes(b"\x1b8a\x1b[ub");
+    assert_eq!(grid.row_text(2), "  b");
+
+    // Overlong parameters don't grow without bound
+    let mut long = b"\x1b[".to_vec();
+    long.extend(std::iter::repeat(b'1').take(10_000));
+    long.extend(b"m");
+    grid.write_bytes(&long);
+    assert_eq!(grid.cursor().0, 2);
+}
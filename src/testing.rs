@@ -0,0 +1,158 @@
+//! Fixtures for writing deterministic tests against the shell, enabled by the `test-support` feature
+//!
+use async_trait::async_trait;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
+
+use crate::{CharDevice, Clock, Transport};
+
+/// Transport that records everything written to it in memory
+///
+/// Clones share the same state, so a test can keep a handle after the transport is given to the shell
+#[derive(Clone, Default)]
+pub struct MemoryTransport {
+    /// Bytes written to this transport
+    written: Arc<Mutex<Vec<u8>>>,
+    /// If true, writes fail as if the connection closed
+    closed: Arc<AtomicBool>,
+}
+
+impl MemoryTransport {
+    /// Returns everything written to the transport so far
+    pub fn written(&self) -> Vec<u8> {
+        self.written.lock().map(|w| w.clone()).unwrap_or_default()
+    }
+
+    /// Returns everything written to the transport, split into lines
+    pub fn written_lines(&self) -> Vec<String> {
+        String::from_utf8_lossy(&self.written())
+            .lines()
+            .map(|l| l.to_string())
+            .collect()
+    }
+
+    /// Closes the transport, the next write will fail
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Transport for MemoryTransport {
+    async fn write(&mut self, message: &[u8]) -> std::io::Result<usize> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe));
+        }
+
+        if let Ok(mut written) = self.written.lock() {
+            written.extend_from_slice(message);
+        }
+        Ok(message.len())
+    }
+
+    fn peer(&self) -> String {
+        String::from("memory")
+    }
+}
+
+/// Clock that only moves when advanced
+///
+/// Clones share the same time
+#[derive(Clone)]
+pub struct FakeClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+}
+
+impl FakeClock {
+    /// Moves the clock forward by duration
+    pub fn advance(&self, duration: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            *now += duration;
+        }
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.now.lock().map(|n| *n).unwrap_or_else(|_| Instant::now())
+    }
+}
+
+/// Builder for a pre-populated char device
+#[derive(Default)]
+pub struct CharDeviceBuilder {
+    text: String,
+    cursor: Option<usize>,
+    scroll: i32,
+}
+
+impl CharDeviceBuilder {
+    /// Sets the text of the device, line endings are converted to `\r`
+    pub fn with_text(mut self, text: impl AsRef<str>) -> Self {
+        self.text = text.as_ref().to_string();
+        self
+    }
+
+    /// Sets the text of the device from lines
+    pub fn with_lines<'a>(mut self, lines: impl IntoIterator<Item = &'a str>) -> Self {
+        self.text = lines.into_iter().collect::<Vec<_>>().join("\r");
+        self
+    }
+
+    /// Sets the cursor offset, by default the cursor is at the end of the text
+    pub fn with_cursor(mut self, cursor: usize) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Sets the number of lines the output is scrolled by
+    pub fn with_scroll(mut self, scroll: i32) -> Self {
+        self.scroll = scroll;
+        self
+    }
+
+    /// Returns the char device
+    pub fn build(self) -> CharDevice {
+        let mut device = CharDevice::default();
+        device.set_text(self.text);
+        if let Some(cursor) = self.cursor {
+            device.set_cursor(cursor);
+        }
+        device.scroll_by(self.scroll);
+        device
+    }
+}
+
+#[test]
+fn test_fixtures() {
+    let clock = FakeClock::default();
+    let start = clock.now();
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(clock.now() - start, Duration::from_secs(5));
+
+    let device = CharDeviceBuilder::default()
+        .with_lines(["``` test", "add a .text b", "```"])
+        .with_cursor(3)
+        .build();
+    assert_eq!(device.line_count(), 3);
+    assert_eq!(device.before_cursor().as_ref(), "```");
+    assert_eq!(device.get_line(1), Some("add a .text b".to_string()));
+
+    let transport = MemoryTransport::default();
+    let mut sender = transport.clone();
+    tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(async move { sender.write(b"hello\r\n").await.ok() });
+    assert_eq!(transport.written_lines(), vec!["hello".to_string()]);
+}
@@ -0,0 +1,101 @@
+use std::ops::Range;
+use wgpu_glyph::Text;
+
+/// Max number of lines queued in a single section, when a buffer is queued in chunks
+///
+/// The glyph brush only lays out sections that changed since the last frame, so appending output only lays out its last chunk
+pub const CHUNK_LINES: usize = 64;
+
+/// Pool of text vectors reused between sections and frames, so that queuing a section doesn't allocate
+#[derive(Default)]
+pub struct TextBatch {
+    recycled: Vec<Vec<Text<'static>>>,
+}
+
+impl TextBatch {
+    /// Returns an empty vector for the texts of a section
+    pub fn take<'a>(&mut self) -> Vec<Text<'a>> {
+        self.recycled.pop().map(recycle).unwrap_or_default()
+    }
+
+    /// Returns the texts of a section that was queued to the pool, so the next section can reuse their allocation
+    pub fn recycle(&mut self, texts: Vec<Text<'_>>) {
+        self.recycled.push(recycle(texts));
+    }
+}
+
+/// Empties texts, and returns their allocation w/ a new lifetime
+fn recycle<'a, 'b>(mut texts: Vec<Text<'a>>) -> Vec<Text<'b>> {
+    texts.clear();
+    // Collecting an empty vector into a vector of the same layout reuses its allocation
+    texts.into_iter().map(|_| unreachable!()).collect()
+}
+
+/// Returns the spans inside range, cut to its edges, w/ adjacent spans of the same color joined into one
+///
+/// Spans are expected to be sorted, joining them means each run of a color is queued as a single text
+pub fn coalesced_spans<'a>(
+    spans: &'a [(Range<usize>, [f32; 4])],
+    range: Range<usize>,
+) -> impl Iterator<Item = (Range<usize>, [f32; 4])> + 'a {
+    let first = spans.partition_point(|(span, _)| span.end <= range.start);
+    let mut spans = spans[first..]
+        .iter()
+        .take_while(move |(span, _)| span.start < range.end)
+        .map(move |(span, color)| (span.start.max(range.start)..span.end.min(range.end), *color))
+        .peekable();
+
+    std::iter::from_fn(move || {
+        let (mut span, color) = spans.next()?;
+        while let Some((next, _)) = spans.next_if(|(next, next_color)| next.start == span.end && *next_color == color) {
+            span.end = next.end;
+        }
+        Some((span, color))
+    })
+}
+
+/// Splits source into chunks of up to CHUNK_LINES lines, returns the range of each chunk and the row it starts on
+///
+/// Lines are expected to be separated by `\r`, rows account for lines longer than columns wrapping like the gutter
+pub fn line_chunks(source: &str, columns: usize) -> Vec<(Range<usize>, usize)> {
+    let columns = columns.max(1);
+    let mut chunks = vec![];
+    let (mut start, mut start_row, mut row) = (0, 0, 0);
+    let mut offset = 0;
+    for (idx, line) in source.split('\r').enumerate() {
+        if idx > 0 && idx % CHUNK_LINES == 0 {
+            chunks.push((start..offset, start_row));
+            start = offset;
+            start_row = row;
+        }
+        row += 1 + line.chars().count().saturating_sub(1) / columns;
+        offset += line.len() + 1;
+    }
+    chunks.push((start..source.len(), start_row));
+    chunks
+}
+
+#[test]
+fn test_text_batch() {
+    let (red, blue) = ([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]);
+    let spans = [(0..2, red), (2..4, red), (4..6, blue), (7..9, blue), (9..12, blue)];
+    assert_eq!(coalesced_spans(&spans, 0..12).collect::<Vec<_>>(), vec![(0..4, red), (4..6, blue), (7..12, blue)]);
+    assert_eq!(coalesced_spans(&spans, 3..8).collect::<Vec<_>>(), vec![(3..4, red), (4..6, blue), (7..8, blue)]);
+
+    let source = (0..CHUNK_LINES + 2).map(|_| "abcdef").collect::<Vec<_>>().join("\r");
+    let chunks = line_chunks(&source, 4);
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0], (0..CHUNK_LINES * 7, 0));
+    assert_eq!(chunks[1], (CHUNK_LINES * 7..source.len(), CHUNK_LINES * 2));
+    assert_eq!(line_chunks("", 4), vec![(0..0, 0)]);
+
+    let mut batch = TextBatch::default();
+    let source = String::from("hello");
+    let mut texts = batch.take();
+    texts.push(Text::new(&source));
+    let capacity = texts.capacity();
+    batch.recycle(texts);
+    let texts = batch.take();
+    assert!(texts.is_empty());
+    assert_eq!(texts.capacity(), capacity);
+}
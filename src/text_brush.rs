@@ -0,0 +1,87 @@
+use std::borrow::Cow;
+use wgpu::{DepthStencilState, TextureFormat};
+use wgpu_glyph::{ab_glyph::FontArc, GlyphBrush, GlyphBrushBuilder, Section};
+
+/// Glyph brush the shell queues text to, w/ or without a depth buffer
+///
+/// Without a depth buffer the z of text is ignored, sections are drawn in the order they're queued w/ alpha blending,
+/// so overlays like the cursor and squiggles have to be queued after the text they cover
+pub enum TextBrush {
+    /// Layers text by z, draws w/ the host's depth view
+    Depth(GlyphBrush<DepthStencilState>),
+    /// Layers text by the order it was queued
+    Flat(GlyphBrush<()>),
+}
+
+impl TextBrush {
+    /// Builds a brush for font, w/ a depth stencil state if the host provides a depth view
+    pub fn build(font: FontArc, device: &wgpu::Device, format: TextureFormat, depth: bool) -> Self {
+        let builder = GlyphBrushBuilder::using_font(font);
+        if depth {
+            TextBrush::Depth(
+                builder
+                    .depth_stencil_state(DepthStencilState {
+                        format: TextureFormat::Depth32Float,
+                        depth_write_enabled: true,
+                        depth_compare: wgpu::CompareFunction::LessEqual,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState::default(),
+                    })
+                    .build(device, format),
+            )
+        } else {
+            TextBrush::Flat(builder.build(device, format))
+        }
+    }
+
+    /// Returns true if this brush draws w/ a depth view
+    pub fn has_depth(&self) -> bool {
+        matches!(self, TextBrush::Depth(_))
+    }
+
+    /// Queues a section of text to be drawn
+    pub fn queue<'a>(&mut self, section: impl Into<Cow<'a, Section<'a>>>) {
+        match self {
+            TextBrush::Depth(brush) => brush.queue(section),
+            TextBrush::Flat(brush) => brush.queue(section),
+        }
+    }
+
+    /// Draws the queued text to target
+    ///
+    /// A brush built w/ depth needs depth_view, otherwise depth_view is ignored
+    pub fn draw_queued(
+        &mut self,
+        device: &wgpu::Device,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        depth_view: Option<&wgpu::TextureView>,
+        width: u32,
+        height: u32,
+    ) -> Result<(), String> {
+        match (self, depth_view) {
+            (TextBrush::Depth(brush), Some(depth_view)) => brush.draw_queued(
+                device,
+                staging_belt,
+                encoder,
+                target,
+                wgpu::RenderPassDepthStencilAttachment {
+                    view: depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(-1.0),
+                        store: true,
+                    }),
+                    stencil_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0),
+                        store: true,
+                    }),
+                },
+                width,
+                height,
+            ),
+            (TextBrush::Depth(_), None) => Err("Brush was built w/ depth, but there is no depth view".to_string()),
+            (TextBrush::Flat(brush), _) => brush.draw_queued(device, staging_belt, encoder, target, width, height),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+/// Counts of a text value, shown while composing values w/ length constraints
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextCounts {
+    /// Number of chars
+    pub chars: usize,
+    /// Number of whitespace separated words
+    pub words: usize,
+    /// Number of bytes when encoded as utf-8, i.e. what a remote protocol receives
+    pub bytes: usize,
+}
+
+impl TextCounts {
+    /// Returns the counts of text
+    pub fn of(text: &str) -> Self {
+        Self {
+            chars: text.chars().count(),
+            words: text.split_whitespace().count(),
+            bytes: text.len(),
+        }
+    }
+}
+
+/// Returns the `.text` value of a runmd line, if offset is inside of it
+///
+/// Offset is in bytes from the start of the line, the value starts after the space following `.text`
+pub fn text_value_at(line: &str, offset: usize) -> Option<&str> {
+    let start = line.find(".text").map(|idx| idx + ".text".len())?;
+    let value = match line[start..].strip_prefix(' ') {
+        Some(_) => start + 1,
+        None if start == line.len() => start,
+        None => return None,
+    };
+
+    if offset >= value && offset <= line.len() {
+        Some(&line[value..])
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_text_counts() {
+    let line = "add greeting .text héllo world";
+    assert_eq!(text_value_at(line, 0), None);
+    assert_eq!(text_value_at(line, 20), Some("héllo world"));
+    assert_eq!(text_value_at(line, line.len()), Some("héllo world"));
+    assert_eq!(text_value_at("add greeting .textual", 20), None);
+
+    assert_eq!(
+        TextCounts::of("héllo world"),
+        TextCounts {
+            chars: 11,
+            words: 2,
+            bytes: 12
+        }
+    );
+}
@@ -1,9 +1,19 @@
 use lifec::plugins::ThunkContext;
 use logos::{Logos, Span};
-use std::{collections::BTreeMap, ops::Range};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tracing::{event, Level};
 use wgpu_glyph::Text;
 
-use crate::{ColorTheme, DefaultTheme};
+use crate::brackets::pairs;
+use crate::selection::token_at;
+use crate::text_batch::coalesced_spans;
+use crate::color::{color_to_linear, color_to_srgb};
+use crate::{ColorTheme, CommandGrammer, DefaultTheme, PaneBackground, Runmd};
 
 /// Generic tokens that can be used to support colorization directly
 /// from a Logos lexer
@@ -18,96 +28,311 @@ pub enum Token {
     Comment,
     Whitespace,
     Newline,
+    Error,
+    Misspelled,
+    Match,
+    MatchedBracket,
     Custom(String),
 }
 
+impl Token {
+    /// Returns the token for a name in a theme block, names that aren't built-in are custom tokens
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "bracket" => Token::Bracket,
+            "operator" => Token::Operator,
+            "modifier" => Token::Modifier,
+            "identifier" => Token::Identifier,
+            "literal" => Token::Literal,
+            "comment" => Token::Comment,
+            "whitespace" => Token::Whitespace,
+            "newline" => Token::Newline,
+            "keyword" => Token::Keyword,
+            "error" => Token::Error,
+            "misspelled" => Token::Misspelled,
+            "match" => Token::Match,
+            "matched_bracket" => Token::MatchedBracket,
+            custom => Token::Custom(custom.to_string()),
+        }
+    }
+}
+
 /// Type alias for a theme token
 pub type ThemeToken = (Token, Option<Range<usize>>);
 
-#[derive(Default)]
+/// Scale text is rendered at, unless the theme sets a font size
+pub const DEFAULT_FONT_SIZE: f32 = 40.0;
+
+/// Glyph drawn for the cursor
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CursorStyle {
+    #[default]
+    Underscore,
+    Block,
+    Bar,
+}
+
+impl CursorStyle {
+    /// Returns the cursor style by name, i.e. from the `cursor` attribute of a theme block
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim() {
+            "underscore" => Some(CursorStyle::Underscore),
+            "block" => Some(CursorStyle::Block),
+            "bar" => Some(CursorStyle::Bar),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of this cursor style
+    pub fn name(&self) -> &'static str {
+        match self {
+            CursorStyle::Underscore => "underscore",
+            CursorStyle::Block => "block",
+            CursorStyle::Bar => "bar",
+        }
+    }
+
+    /// Returns the glyph drawn for the cursor
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            CursorStyle::Underscore => "_",
+            CursorStyle::Block => "█",
+            CursorStyle::Bar => "|",
+        }
+    }
+}
+
 /// Parser that can convert a source into theming tokens
-pub struct Theme<Style = DefaultTheme>
-where
-    Style: ColorTheme + Default,
-{
+pub struct Theme {
     /// Thunk context
     context: ThunkContext,
 
     /// Mapping between token and color -- color values should be linear sRGB
     color_map: BTreeMap<Token, [f32; 4]>,
 
-    /// Style
-    _style: Style,
+    /// Lines starting with this prefix are parsed w/ the CommandGrammer
+    command_prefix: Option<char>,
+
+    /// Backgrounds drawn behind panes, by pane index
+    pane_backgrounds: BTreeMap<usize, PaneBackground>,
+
+    /// Incremented whenever a change would affect rendered output
+    revision: u64,
+
+    /// Scale text is rendered at, DEFAULT_FONT_SIZE if not set
+    font_size: Option<f32>,
+
+    /// Scale factor of the window, font_size is multiplied by it so that text stays readable on hiDPI monitors
+    scale_factor: f32,
+
+    /// Text of the prompt, the style's prompt if not set
+    prompt: Option<String>,
+
+    /// Glyph drawn for the cursor
+    cursor_style: CursorStyle,
+
+    /// Colors named in the context resolve to, and the prompt is rendered w/
+    style: Arc<dyn ColorTheme>,
+
+    /// Names of grammers that produced spans that had to be fixed, so that each is only reported once
+    misbehaving: Mutex<BTreeSet<&'static str>>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new()
+    }
 }
 
 impl Theme {
     pub fn new() -> Self {
         Theme::new_with(ThunkContext::default())
     }
-}
 
+    /// Returns an instance of this theme for a given source, w/ the DefaultTheme style
+    pub fn new_with(tc: ThunkContext) -> Self {
+        Theme::new_with_style(tc, Arc::new(DefaultTheme))
+    }
 
-impl<Style> Theme<Style>
-where
-    Style: ColorTheme + Default,
-{
     /// Returns an instance of this theme for a given source, and passes the thunk_context to the lexer
     ///
-    /// Parses color symbols to build the color map
-    pub fn new_with(tc: ThunkContext) -> Self {
+    /// Parses color symbols to build the color map, and reads the `font_size`, `prompt`, and `cursor` attributes
+    pub fn new_with_style(tc: ThunkContext, style: Arc<dyn ColorTheme>) -> Self {
         let mut color_map = BTreeMap::new();
         for (name, value) in tc.as_ref().find_symbol_values("color") {
             let name = name.trim_end_matches("::color");
             color_map.insert(
-                match name {
-                    "bracket" => Token::Bracket,
-                    "operator" => Token::Operator,
-                    "modifier" => Token::Modifier,
-                    "identifier" => Token::Identifier,
-                    "literal" => Token::Literal,
-                    "comment" => Token::Comment,
-                    "whitespace" => Token::Whitespace,
-                    "keyword" => Token::Keyword,
-                    custom => Token::Custom(custom.to_string()),
-                },
+                Token::from_name(name),
                 match value {
-                    lifec::Value::FloatRange(r, g, b) => [r, g, b, 1.0],
-                    lifec::Value::TextBuffer(color_name) => match color_name.as_str() {
-                        "red" => Style::red(),
-                        "green" => Style::green(),
-                        "blue" => Style::blue(),
-                        "purple" => Style::purple(),
-                        "yellow" => Style::yellow(),
-                        "orange" => Style::orange(),
-                        _ => Style::green(),
-                    },
+                    lifec::Value::FloatRange(r, g, b) => color_to_linear([r, g, b, 1.0]),
+                    lifec::Value::TextBuffer(color_name) => style.named_color(&color_name).unwrap_or(style.green()),
                     _ => [1.0, 1.0, 1.0, 1.0],
                 },
             );
         }
 
-        color_map.insert(Token::Custom("background".to_string()), Style::background());
-        color_map.insert(Token::Custom("red".to_string()), Style::red());
-        color_map.insert(Token::Custom("green".to_string()), Style::green());
-        color_map.insert(Token::Custom("blue".to_string()), Style::blue());
-        color_map.insert(Token::Custom("purple".to_string()), Style::purple());
-        color_map.insert(Token::Custom("yellow".to_string()), Style::yellow());
-        color_map.insert(Token::Custom("orange".to_string()), Style::orange());
+        color_map.entry(Token::Error).or_insert(style.critical());
+        color_map.entry(Token::Misspelled).or_insert(style.blue());
+        color_map.entry(Token::Match).or_insert(style.yellow());
+        color_map.entry(Token::MatchedBracket).or_insert(style.orange());
+        color_map.entry(Token::Custom("log_trace".to_string())).or_insert(style.purple());
+        color_map.entry(Token::Custom("log_debug".to_string())).or_insert(style.blue());
+        color_map.entry(Token::Custom("log_info".to_string())).or_insert(style.green());
+        color_map.entry(Token::Custom("log_warn".to_string())).or_insert(style.yellow());
+        color_map.entry(Token::Custom("log_error".to_string())).or_insert(style.critical());
+        color_map.insert(Token::Custom("background".to_string()), style.background());
+        color_map.insert(Token::Custom("red".to_string()), style.red());
+        color_map.insert(Token::Custom("green".to_string()), style.green());
+        color_map.insert(Token::Custom("blue".to_string()), style.blue());
+        color_map.insert(Token::Custom("purple".to_string()), style.purple());
+        color_map.insert(Token::Custom("yellow".to_string()), style.yellow());
+        color_map.insert(Token::Custom("cyan".to_string()), style.cyan());
+        color_map.insert(Token::Custom("orange".to_string()), style.orange());
+
+        let font_size = tc
+            .as_ref()
+            .find_int("font_size")
+            .filter(|size| *size > 0)
+            .map(|size| size as f32);
+        let prompt = tc.as_ref().find_text("prompt");
+        let cursor_style = tc
+            .as_ref()
+            .find_text("cursor")
+            .and_then(|name| CursorStyle::from_name(&name))
+            .unwrap_or_default();
 
         Self {
             context: tc,
             color_map,
-            _style: Style::default(),
+            command_prefix: Some(':'),
+            pane_backgrounds: BTreeMap::new(),
+            revision: 0,
+            font_size,
+            scale_factor: 1.0,
+            prompt,
+            cursor_style,
+            style,
+            misbehaving: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Returns true if other would render the same as this theme
+    pub fn same_settings(&self, other: &Self) -> bool {
+        self.color_map == other.color_map
+            && self.font_size == other.font_size
+            && self.prompt == other.prompt
+            && self.cursor_style == other.cursor_style
+    }
+
+    /// Replaces the context, style, colors, font size, prompt, and cursor style w/ those of other
+    ///
+    /// Pane backgrounds and the command prefix are kept
+    pub fn apply(&mut self, other: Self) {
+        self.context = other.context;
+        self.style = other.style;
+        self.color_map = other.color_map;
+        self.font_size = other.font_size;
+        self.prompt = other.prompt;
+        self.cursor_style = other.cursor_style;
+        self.revision += 1;
+    }
+
+    /// Replaces the style, and rebuilds the color map from the current context w/ its colors
+    pub fn set_style(&mut self, style: Arc<dyn ColorTheme>) {
+        let rebuilt = Theme::new_with_style(self.context.clone(), style);
+        self.color_map = rebuilt.color_map;
+        self.style = rebuilt.style;
+        self.revision += 1;
+    }
+
+    /// Returns the style colors named in the context resolve to
+    pub fn style(&self) -> &Arc<dyn ColorTheme> {
+        &self.style
+    }
+
+    /// Returns the scale text is rendered at, w/ the window's scale factor applied
+    pub fn font_size(&self) -> f32 {
+        self.font_size.unwrap_or(DEFAULT_FONT_SIZE) * self.scale_factor
+    }
+
+    /// Sets the scale text is rendered at
+    pub fn set_font_size(&mut self, font_size: f32) {
+        self.font_size = Some(font_size);
+        self.revision += 1;
+    }
+
+    /// Sets the scale factor of the window, font_size is multiplied by it
+    ///
+    /// The scale factor isn't saved w/ the theme, since it depends on the monitor the window is on
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        if scale_factor > 0.0 && scale_factor != self.scale_factor {
+            self.scale_factor = scale_factor;
+            self.revision += 1;
+        }
+    }
+
+    /// Returns the glyph drawn for the cursor
+    pub fn cursor_style(&self) -> CursorStyle {
+        self.cursor_style
+    }
+
+    /// Returns the prompt, w/ the theme's prompt text and font size
+    fn prompt<'a>(&'a self) -> Text<'a> {
+        let mut prompt: Text<'a> = self.style.prompt();
+        if let Some(text) = self.prompt.as_deref() {
+            prompt.text = text;
+        }
+        prompt.with_scale(self.font_size())
+    }
+
+    /// Sets the prefix of lines that are parsed as shell commands, None disables command highlighting
+    pub fn set_command_prefix(&mut self, prefix: Option<char>) {
+        self.command_prefix = prefix;
+        self.revision += 1;
+    }
+
+    /// Returns the current revision, which changes whenever a change would affect rendered output
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Sets the background drawn behind the pane at index, None removes the background
+    pub fn set_pane_background(&mut self, index: usize, background: Option<PaneBackground>) {
+        match background {
+            Some(background) => {
+                self.pane_backgrounds.insert(index, background);
+            }
+            None => {
+                self.pane_backgrounds.remove(&index);
+            }
         }
     }
 
+    /// Returns the background drawn behind the pane at index
+    pub fn pane_background(&self, index: usize) -> Option<&PaneBackground> {
+        self.pane_backgrounds.get(&index)
+    }
+
+    /// Returns the background drawn behind the pane at index, for editing
+    pub fn pane_background_mut(&mut self, index: usize) -> Option<&mut PaneBackground> {
+        self.pane_backgrounds.get_mut(&index)
+    }
+
     /// Set's the color value (linear sRGB) for the token
     pub fn set_color(&mut self, token: Token, color: [f32; 4]) {
         self.color_map.insert(token, color);
+        self.revision += 1;
+    }
+
+    /// Iterate over current colors
+    pub fn colors(&self) -> impl Iterator<Item = (&Token, &[f32; 4])> {
+        self.color_map.iter()
     }
 
     /// Iterate over current colors for editing
     pub fn colors_mut(&mut self) -> impl Iterator<Item = (&Token, &mut [f32; 4])> {
+        // Colors could be edited through the iterator, so this is treated as a change
+        self.revision += 1;
         self.color_map.iter_mut()
     }
 
@@ -118,6 +343,7 @@ where
 
     /// Resets the colors to the values set in the current context
     pub fn reset_colors(&mut self) {
+        self.revision += 1;
         for (name, value) in self.context.as_ref().find_symbol_values("color") {
             let name = name.trim_end_matches("::color");
             self.color_map.insert(
@@ -130,35 +356,66 @@ where
                     "comment" => Token::Comment,
                     "whitespace" => Token::Whitespace,
                     "keyword" => Token::Keyword,
+                    "error" => Token::Error,
+                    "misspelled" => Token::Misspelled,
+                    "match" => Token::Match,
+                    "matched_bracket" => Token::MatchedBracket,
                     custom => Token::Custom(custom.to_string()),
                 },
                 match value {
-                    lifec::Value::FloatRange(r, g, b) => [r, g, b, 1.0],
-                    lifec::Value::TextBuffer(color_name) => match color_name.as_str() {
-                        "red" => Style::red(),
-                        "green" => Style::green(),
-                        "blue" => Style::blue(),
-                        "purple" => Style::purple(),
-                        "yellow" => Style::yellow(),
-                        "orange" => Style::orange(),
-                        _ => Style::green(),
-                    },
+                    lifec::Value::FloatRange(r, g, b) => color_to_linear([r, g, b, 1.0]),
+                    lifec::Value::TextBuffer(color_name) => self.style.named_color(&color_name).unwrap_or(self.style.green()),
                     _ => [1.0, 1.0, 1.0, 1.0],
                 },
             );
         }
     }
 
+    /// Returns the colors, font size, prompt, and cursor style as a runmd `theme` block
+    ///
+    /// Colors are written as sRGB, like they're authored in a theme block, alpha is not saved
+    pub fn to_runmd(&self) -> String {
+        let mut block = String::from("``` theme shell\n");
+        for (token, color) in self.color_map.iter() {
+            let [r, g, b, _] = color_to_srgb(*color);
+            block.push_str(&format!("define {} color .float3 {r}, {g}, {b}\n", token_name(token)));
+        }
+
+        if let Some(font_size) = self.font_size {
+            block.push_str(&format!("add font_size .int {}\n", font_size as i32));
+        }
+
+        if let Some(prompt) = self.prompt.as_ref() {
+            block.push_str(&format!("add prompt .text {prompt}\n"));
+        }
+
+        block.push_str(&format!("add cursor .text {}\n```\n", self.cursor_style.name()));
+        block
+    }
+
+    /// Saves the theme to a runmd file at path, so it can be shared
+    pub fn save_to(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_runmd())
+    }
+
+    /// Loads a theme from a runmd file at path, i.e. one saved w/ Theme::save_to
+    pub fn load_from(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        let mut lexer = Runmd::lexer_with_extras(&source, ThunkContext::default());
+        while lexer.next().is_some() {}
+
+        Ok(Self::new_with(lexer.extras))
+    }
+
     /// Parses tokens produced by the lexer into tokens used for theming
     ///
     /// If this theme has already been parsed, this is a no op
     pub fn parse<'a, Grammer>(&self, source: &'a str) -> (Vec<(Token, Range<usize>)>, ThunkContext)
     where
-        Grammer: Logos<'a, Source = str, Extras = ThunkContext> + Into<Vec<ThemeToken>>,
+        Grammer: crate::Grammer,
     {
         let mut lexer = Grammer::lexer_with_extras(source, self.context.clone());
         let mut parsed = vec![];
-        let mut cursor = 0;
         while let Some(token) = lexer.next() {
             let tokens: Vec<(Token, Option<Range<usize>>)> = token.into();
 
@@ -167,11 +424,27 @@ where
                     Some(span) => span,
                     None => lexer.span(),
                 };
-                cursor = span.end;
                 parsed.push((token, span));
             }
         }
 
+        let fixed = normalize_spans(source, &mut parsed);
+        // Parsing runs every frame, so a misbehaving grammer is only reported the first time
+        let first_report = fixed > 0
+            && self
+                .misbehaving
+                .lock()
+                .map(|mut misbehaving| misbehaving.insert(Grammer::name()))
+                .unwrap_or_default();
+        if first_report {
+            event!(
+                Level::WARN,
+                "{} produced {fixed} out of order, overlapping, or out of bounds spans",
+                std::any::type_name::<Grammer>()
+            );
+        }
+        let cursor = parsed.last().map(|(_, span)| span.end).unwrap_or_default();
+
         // Appending this to the end ensures that text currently being typed shows up
         parsed.push((
             Token::Whitespace,
@@ -180,85 +453,266 @@ where
                 end: source.len(),
             },
         ));
+
+        if let Some(prefix) = self.command_prefix.filter(|_| Grammer::parses_commands()) {
+            self.parse_commands(source, prefix, &mut parsed);
+        }
+
         (parsed.to_vec(), lexer.extras.clone())
     }
 
+    /// Replaces the tokens of lines starting with prefix, with tokens from the CommandGrammer
+    fn parse_commands(&self, source: &str, prefix: char, parsed: &mut Vec<(Token, Range<usize>)>) {
+        let mut line_start = 0;
+        let mut found = false;
+        for line in source.split(|c| c == '\r' || c == '\n') {
+            let line_span = line_start..line_start + line.len();
+            line_start = line_span.end + 1;
+
+            if !line.starts_with(prefix) {
+                continue;
+            }
+
+            found = true;
+            parsed.retain(|(_, span)| span.end <= line_span.start || span.start >= line_span.end);
+
+            let mut lexer = CommandGrammer::lexer_with_extras(line, self.context.clone());
+            while let Some(token) = lexer.next() {
+                let tokens: Vec<ThemeToken> = token.into();
+                for (token, span) in tokens {
+                    let Range { start, end } = span.unwrap_or(lexer.span());
+                    parsed.push((token, line_span.start + start..line_span.start + end));
+                }
+            }
+        }
+
+        if found {
+            parsed.sort_by_key(|(_, span)| span.start);
+        }
+    }
+
     /// Renders a vector of texts to render/layout
-    pub fn render<'a, Grammer>(&self, source: &'a str, prompt_enabled: bool) -> Vec<Text<'a>>
+    pub fn render<'a, Grammer>(&'a self, source: &'a str, prompt_enabled: bool) -> Vec<Text<'a>>
+    where
+        Grammer: crate::Grammer,
+    {
+        self.render_spans(source, &self.styled_spans::<Grammer>(source), prompt_enabled)
+    }
+
+    /// Parses the source, and returns each span w/ the color it should be rendered with
+    ///
+    /// The result only depends on the source and the revision of the theme, so it can be cached between frames
+    pub fn styled_spans<'a, Grammer>(&self, source: &'a str) -> Vec<(Range<usize>, [f32; 4])>
     where
-        Grammer: Logos<'a, Source = str, Extras = ThunkContext> + Into<Vec<ThemeToken>>,
+        Grammer: crate::Grammer,
     {
         let mut cursor = 0;
-        let mut texts = vec![];
+        let mut spans = vec![];
         let (tokens, _) = self.parse::<Grammer>(&source);
 
-        if prompt_enabled {
-            texts.push(Style::prompt());
-        }
-
         for (token, span) in tokens {
-            // Render everything between the cursor and the start of this span
-            texts.push(
-                Text::new(&source[cursor..span.start])
-                    .with_color([1.0, 1.0, 1.0, 0.8])
-                    .with_scale(40.0)
-                    .with_z(0.8),
-            );
+            // Everything between the cursor and the start of this span
+            if cursor < span.start {
+                spans.push((cursor..span.start, [1.0, 1.0, 1.0, 0.8]));
+            }
             cursor = span.end;
 
             if span.start < span.end {
-                let mut text = Text::new(&source[span]).with_scale(40.0).with_z(0.8);
-                if let Some(color) = self.color_map.get(&token) {
-                    text = text.with_color(*color);
-                } else {
-                    text = text.with_color(DefaultTheme::green());
-                }
-                texts.push(text);
+                let color = self
+                    .color_map
+                    .get(&token)
+                    .cloned()
+                    .unwrap_or(self.style.green());
+                spans.push((span, color));
             }
         }
 
+        spans
+    }
+
+    /// Renders a vector of texts from styled spans of source
+    pub fn render_spans<'a>(
+        &'a self,
+        source: &'a str,
+        spans: &[(Range<usize>, [f32; 4])],
+        prompt_enabled: bool,
+    ) -> Vec<Text<'a>> {
+        let mut texts = vec![];
+        self.render_spans_into(&mut texts, source, spans, 0..source.len(), prompt_enabled);
         texts
     }
 
-    pub fn render_cursor<'a>(&self, prompt_enabled: bool) -> impl FnOnce(&'a str, &'a str) -> Vec<Text<'a>> { 
+    /// Appends the texts of the styled spans of source inside range to texts, i.e. a vector reused from a TextBatch
+    ///
+    /// Adjacent spans of the same color are rendered as a single text
+    pub fn render_spans_into<'a>(
+        &'a self,
+        texts: &mut Vec<Text<'a>>,
+        source: &'a str,
+        spans: &[(Range<usize>, [f32; 4])],
+        range: Range<usize>,
+        prompt_enabled: bool,
+    ) {
         if prompt_enabled {
-           |before, after| {  vec![
-                Style::prompt(),
-                Text::new(before)
-                    .with_color([0.0, 0.0, 0.0, 0.0])
-                    .with_scale(40.0)
-                    .with_z(0.2),
-                Text::new("_")
-                    .with_color([0.4, 0.8, 0.8, 1.0])
-                    .with_scale(40.0)
-                    .with_z(0.2),
-                Text::new(after)
-                    .with_color([0.0, 0.0, 0.0, 0.0])
-                    .with_scale(40.0)
-                    .with_z(0.2),
-            ]
-           }
-        } else {
-            |before, after| {  vec![
+            texts.push(self.prompt());
+        }
+
+        for (span, color) in coalesced_spans(spans, range) {
+            if let Some(text) = source.get(span) {
+                texts.push(
+                    Text::new(text)
+                        .with_color(color)
+                        .with_scale(self.font_size())
+                        .with_z(0.8),
+                );
+            }
+        }
+    }
+
+    /// Renders squiggles under the spans of diagnostics, meant to be queued on top of the rendered source
+    pub fn render_squiggles<'a>(&self, overlay: &'a str) -> Vec<Text<'a>> {
+        self.render_squiggles_as(Token::Error, overlay)
+    }
+
+    /// Renders squiggles w/ the color of token, i.e. Token::Misspelled for spelling diagnostics
+    pub fn render_squiggles_as<'a>(&self, token: Token, overlay: &'a str) -> Vec<Text<'a>> {
+        vec![Text::new(overlay)
+            .with_color(
+                self.color_map
+                    .get(&token)
+                    .cloned()
+                    .unwrap_or(self.style.critical()),
+            )
+            .with_scale(self.font_size())
+            .with_z(0.6)]
+    }
+
+    /// Renders the matched text of a search overlay, meant to be queued on top of the rendered source
+    pub fn render_matches<'a>(&self, overlay: &'a str) -> Vec<Text<'a>> {
+        self.render_highlight(Token::Match, overlay)
+    }
+
+    /// Renders the text of an overlay w/ the color of token, meant to be queued on top of the rendered source
+    pub fn render_highlight<'a>(&self, token: Token, overlay: &'a str) -> Vec<Text<'a>> {
+        vec![Text::new(overlay)
+            .with_color(
+                self.color_map
+                    .get(&token)
+                    .cloned()
+                    .unwrap_or(self.style.yellow()),
+            )
+            .with_scale(self.font_size())
+            .with_z(0.7)]
+    }
+
+    /// Returns every pair of brackets in source, using the spans produced by the grammer
+    pub fn bracket_pairs<Grammer>(&self, source: &str) -> Vec<(Range<usize>, Range<usize>)>
+    where
+        Grammer: crate::Grammer,
+    {
+        let (tokens, _) = self.parse::<Grammer>(source);
+        pairs(source, &tokens)
+    }
+
+    /// Returns the span of the token under offset, using the spans produced by the grammer
+    pub fn token_at<'a, Grammer>(&self, source: &'a str, offset: usize) -> Option<Range<usize>>
+    where
+        Grammer: crate::Grammer,
+    {
+        let (tokens, _) = self.parse::<Grammer>(source);
+        token_at(source, &tokens, offset)
+    }
+
+    pub fn render_cursor<'a>(&'a self, prompt_enabled: bool) -> impl FnOnce(&'a str, &'a str) -> Vec<Text<'a>> {
+        let font_size = self.font_size();
+        let prompt = prompt_enabled.then(|| self.prompt());
+        let glyph = self.cursor_style.glyph();
+
+        move |before, after| {
+            let mut texts = vec![];
+            texts.extend(prompt);
+            texts.push(
                 Text::new(before)
                     .with_color([0.0, 0.0, 0.0, 0.0])
-                    .with_scale(40.0)
+                    .with_scale(font_size)
                     .with_z(0.2),
-                Text::new("_")
+            );
+            texts.push(
+                Text::new(glyph)
                     .with_color([0.4, 0.8, 0.8, 1.0])
-                    .with_scale(40.0)
+                    .with_scale(font_size)
                     .with_z(0.2),
+            );
+            texts.push(
                 Text::new(after)
                     .with_color([0.0, 0.0, 0.0, 0.0])
-                    .with_scale(40.0)
+                    .with_scale(font_size)
                     .with_z(0.2),
-            ]
-           }
+            );
+            texts
         }
-     
     }
 }
 
+/// Returns the name of token in a theme block, the reverse of the names read by Theme::new_with
+pub(crate) fn token_name(token: &Token) -> String {
+    match token {
+        Token::Keyword => "keyword",
+        Token::Bracket => "bracket",
+        Token::Operator => "operator",
+        Token::Modifier => "modifier",
+        Token::Identifier => "identifier",
+        Token::Literal => "literal",
+        Token::Comment => "comment",
+        Token::Whitespace => "whitespace",
+        Token::Newline => "newline",
+        Token::Error => "error",
+        Token::Misspelled => "misspelled",
+        Token::Match => "match",
+        Token::MatchedBracket => "matched_bracket",
+        Token::Custom(name) => name,
+    }
+    .to_string()
+}
+
+/// Sorts spans, clamps them to char boundaries within source, and trims overlaps w/ earlier spans
+///
+/// Grammers are expected to produce ordered, non-overlapping spans, but a misbehaving grammer shouldn't be able to
+/// garble or crash rendering. Returns the number of spans that had to be fixed
+fn normalize_spans(source: &str, tokens: &mut Vec<(Token, Range<usize>)>) -> usize {
+    let mut fixed = tokens
+        .windows(2)
+        .filter(|pair| pair[1].1.start < pair[0].1.start)
+        .count();
+    tokens.sort_by_key(|(_, span)| span.start);
+
+    let floor = |idx: usize| {
+        let mut idx = idx.min(source.len());
+        while !source.is_char_boundary(idx) {
+            idx -= 1;
+        }
+        idx
+    };
+
+    let mut end = 0;
+    let mut normalized = Vec::with_capacity(tokens.len());
+    for (token, span) in tokens.drain(..) {
+        let start = floor(span.start).max(end);
+        let clamped_end = floor(span.end).max(start);
+        if span.start < span.end && (start != span.start || clamped_end != span.end) {
+            fixed += 1;
+        }
+
+        if start < clamped_end {
+            end = clamped_end;
+            normalized.push((token, start..clamped_end));
+        }
+    }
+    *tokens = normalized;
+
+    fixed
+}
+
 mod test {
     use std::ops::Range;
 
@@ -288,6 +742,70 @@ test      abc
         }
     }
 
+    #[test]
+    fn test_theme_runmd() {
+        let mut lexer = crate::Runmd::lexer_with_extras(
+            "``` theme shell\nadd font_size .int 32\nadd cursor .text bar\n```\n",
+            ThunkContext::default(),
+        );
+        while lexer.next().is_some() {}
+
+        let mut theme = crate::Theme::new_with(lexer.extras);
+        let saved = theme.to_runmd();
+        assert!(saved.starts_with("``` theme shell"));
+        assert!(saved.contains("add font_size .int 32"));
+        assert!(saved.contains("add cursor .text bar"));
+        assert!(saved.contains("define background color .float3"));
+
+        // The scale factor applies to rendering, but isn't saved
+        theme.set_scale_factor(2.0);
+        assert_eq!(theme.font_size(), 64.0);
+        assert!(theme.to_runmd().contains("add font_size .int 32"));
+    }
+
+    #[test]
+    fn test_set_style() {
+        use crate::ColorTheme;
+
+        let mut theme = crate::Theme::new();
+        let revision = theme.revision();
+        theme.set_style(std::sync::Arc::new(crate::LightTheme));
+
+        assert!(theme.revision() > revision);
+        assert_eq!(
+            theme.get_color(Token::Custom("background".to_string())),
+            Some(&crate::LightTheme.background())
+        );
+    }
+
+    #[test]
+    fn test_normalize_spans() {
+        let source = "ab é cd";
+        let mut tokens = vec![
+            (Token::Keyword, 5..7),
+            (Token::Literal, 0..4),
+            (Token::Operator, 2..6),
+            (Token::Comment, 7..20),
+            (Token::Bracket, 1..1),
+        ];
+
+        let fixed = super::normalize_spans(source, &mut tokens);
+        assert_eq!(
+            tokens,
+            vec![
+                (Token::Literal, 0..3),
+                (Token::Operator, 3..6),
+                (Token::Keyword, 6..7),
+                (Token::Comment, 7..8),
+            ]
+        );
+        assert_eq!(fixed, 6);
+
+        for (_, span) in tokens {
+            assert!(source.get(span).is_some());
+        }
+    }
+
     #[derive(Logos, PartialEq, Eq)]
     #[logos(extras = ThunkContext)]
     enum TestGrammer {
@@ -307,6 +825,12 @@ test      abc
         Error,
     }
 
+    impl crate::Grammer for TestGrammer {
+        fn name() -> &'static str {
+            "test"
+        }
+    }
+
     impl Into<Vec<(Token, Option<Range<usize>>)>> for TestGrammer {
         fn into(self) -> Vec<(Token, Option<Range<usize>>)> {
             match self {
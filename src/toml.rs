@@ -0,0 +1,190 @@
+use lifec::plugins::ThunkContext;
+use logos::Lexer;
+use logos::Logos;
+use logos::Span;
+
+use crate::grammer::preceding;
+use crate::{theme::ThemeToken, Grammer, Token};
+
+/// Grammer for toml, i.e. config files loaded into a channel
+#[derive(Logos, PartialEq, Eq, Debug, Clone)]
+#[logos(extras = ThunkContext)]
+pub enum Toml {
+    /// Opening bracket, if it starts a line this is a table header and the spans are the
+    /// opening bracket, the table name, and the closing bracket
+    #[token("[", on_open_bracket)]
+    #[token("[[", on_open_bracket)]
+    OpenBracket(Option<(Span, Span, Span)>),
+    /// Closing brackets of arrays, and braces of inline tables
+    #[token("]")]
+    #[token("]]")]
+    #[token("{")]
+    #[token("}")]
+    Bracket,
+    /// Key, and the `=` that follows it
+    #[regex(r#"([A-Za-z0-9_\-]+|"([^"\\\r\n]|\\.)*"|'[^'\r\n]*')([ \t]*\.[ \t]*([A-Za-z0-9_\-]+|"([^"\\\r\n]|\\.)*"|'[^'\r\n]*'))*[ \t]*="#, on_key)]
+    Key((Span, Span)),
+    /// Basic, literal, and multi-line strings
+    #[regex(r#""([^"\\\r\n]|\\.)*""#)]
+    #[regex(r#"'[^'\r\n]*'"#)]
+    #[regex(r#""""([^"\\]|\\.|"[^"\\]|""[^"\\])*""""#)]
+    #[regex(r#"'''([^']|'[^']|''[^'])*'''"#)]
+    String,
+    /// Integers and floats
+    #[regex(r"[+-]?[0-9][0-9_]*(\.[0-9][0-9_]*)?([eE][+-]?[0-9][0-9_]*)?")]
+    #[regex(r"0x[0-9A-Fa-f][0-9A-Fa-f_]*")]
+    #[regex(r"0o[0-7][0-7_]*")]
+    #[regex(r"0b[01][01_]*")]
+    #[regex(r"[+-]?(inf|nan)")]
+    Number,
+    /// Dates, times, and date-times
+    #[regex(r"[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]([Tt ][0-9][0-9]:[0-9][0-9]:[0-9][0-9](\.[0-9]+)?([Zz]|[+-][0-9][0-9]:[0-9][0-9])?)?")]
+    #[regex(r"[0-9][0-9]:[0-9][0-9]:[0-9][0-9](\.[0-9]+)?")]
+    DateTime,
+    /// true or false
+    #[token("true")]
+    #[token("false")]
+    Boolean,
+    /// Separates array values, and inline table entries
+    #[token(",")]
+    Comma,
+    /// Comments
+    #[regex(r"#[^\r\n]*")]
+    Comment,
+    #[regex(r"[ \t\r\n\f]+", logos::skip)]
+    #[error]
+    Error,
+}
+
+impl Grammer for Toml {
+    fn name() -> &'static str {
+        "toml"
+    }
+}
+
+impl Into<Vec<ThemeToken>> for Toml {
+    fn into(self) -> Vec<ThemeToken> {
+        match self {
+            Toml::OpenBracket(Some((open, name, close))) => vec![
+                (Token::Bracket, Some(open)),
+                (Token::Identifier, Some(name)),
+                (Token::Bracket, Some(close)),
+            ],
+            Toml::OpenBracket(None) | Toml::Bracket => vec![(Token::Bracket, None)],
+            Toml::Key((key, equals)) => vec![
+                (Token::Identifier, Some(key)),
+                (Token::Operator, Some(equals)),
+            ],
+            Toml::String | Toml::DateTime => vec![(Token::Literal, None)],
+            Toml::Number => vec![(Token::Modifier, None)],
+            Toml::Boolean => vec![(Token::Keyword, None)],
+            Toml::Comma => vec![(Token::Operator, None)],
+            Toml::Comment => vec![(Token::Comment, None)],
+            Toml::Error => vec![(Token::Error, None)],
+        }
+    }
+}
+
+/// If the bracket starts a line outside of an array, consumes the rest of the table header
+///
+/// Otherwise the bracket opens an array
+fn on_open_bracket(lexer: &mut Lexer<Toml>) -> Option<(Span, Span, Span)> {
+    let open = lexer.span();
+    if !preceding(lexer).trim().is_empty() || inside_array(&lexer.source()[..open.start]) {
+        return None;
+    }
+
+    let close = if lexer.slice() == "[[" { "]]" } else { "]" };
+    let remaining = lexer.remainder();
+    let line = &remaining[..remaining
+        .find(|c| c == '\r' || c == '\n')
+        .unwrap_or(remaining.len())];
+    let name_len = line.find(close)?;
+
+    lexer.bump(name_len + close.len());
+    Some((
+        open.clone(),
+        open.end..open.end + name_len,
+        open.end + name_len..open.end + name_len + close.len(),
+    ))
+}
+
+/// Returns true if source ends inside of an array, i.e. a bracket starting the next line is a value of a multi-line array
+fn inside_array(source: &str) -> bool {
+    let mut depth = 0usize;
+    for line in source.split(['\r', '\n']) {
+        // Table headers don't open arrays
+        if depth == 0 && line.trim_start().starts_with('[') {
+            continue;
+        }
+
+        let mut quote = None;
+        let mut escaped = false;
+        for c in line.chars() {
+            match (quote, c) {
+                (Some('"'), '\\') if !escaped => {
+                    escaped = true;
+                    continue;
+                }
+                (Some(q), _) if c == q && !escaped => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '#') => break,
+                (None, '[') => depth += 1,
+                (None, ']') => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            escaped = false;
+        }
+    }
+    depth > 0
+}
+
+/// Splits the key from the `=` that follows it
+fn on_key(lexer: &mut Lexer<Toml>) -> (Span, Span) {
+    let span = lexer.span();
+    let key_len = lexer.slice()[..lexer.slice().len() - 1].trim_end().len();
+
+    (span.start..span.start + key_len, span.end - 1..span.end)
+}
+
+#[test]
+fn test_toml() {
+    let source = "# config\r[shell.theme]\rname = \"dark\"\rsize = [12, 0x10]\r[[plugins]]\renabled = true";
+    let mut lexer = Toml::lexer_with_extras(source, ThunkContext::default());
+
+    assert_eq!(lexer.next(), Some(Toml::Comment));
+    assert_eq!(lexer.next(), Some(Toml::OpenBracket(Some((9..10, 10..21, 21..22)))));
+    assert_eq!(lexer.next(), Some(Toml::Key((23..27, 28..29))));
+    assert_eq!(lexer.next(), Some(Toml::String));
+    assert!(matches!(lexer.next(), Some(Toml::Key(_))));
+    assert_eq!(lexer.next(), Some(Toml::OpenBracket(None)));
+    assert_eq!(lexer.next(), Some(Toml::Number));
+    assert_eq!(lexer.next(), Some(Toml::Comma));
+    assert_eq!(lexer.next(), Some(Toml::Number));
+    assert_eq!(lexer.slice(), "0x10");
+    assert_eq!(lexer.next(), Some(Toml::Bracket));
+    assert!(matches!(lexer.next(), Some(Toml::OpenBracket(Some(_)))));
+    assert_eq!(lexer.slice(), "[[plugins]]");
+    assert!(matches!(lexer.next(), Some(Toml::Key(_))));
+    assert_eq!(lexer.next(), Some(Toml::Boolean));
+    assert_eq!(lexer.next(), None);
+    // Brackets starting a line inside of a multi-line array aren't table headers
+    let source = "matrix = [\r  [1, \"]\"],\r  [2],\r]\r[table]";
+    let mut lexer = Toml::lexer_with_extras(source, ThunkContext::default());
+    assert!(matches!(lexer.next(), Some(Toml::Key(_))));
+    assert_eq!(lexer.next(), Some(Toml::OpenBracket(None)));
+    assert_eq!(lexer.next(), Some(Toml::OpenBracket(None)));
+    assert_eq!(lexer.next(), Some(Toml::Number));
+    assert_eq!(lexer.next(), Some(Toml::Comma));
+    assert_eq!(lexer.next(), Some(Toml::String));
+    assert_eq!(lexer.next(), Some(Toml::Bracket));
+    assert_eq!(lexer.next(), Some(Toml::Comma));
+    assert_eq!(lexer.next(), Some(Toml::OpenBracket(None)));
+    assert_eq!(lexer.next(), Some(Toml::Number));
+    assert_eq!(lexer.next(), Some(Toml::Bracket));
+    assert_eq!(lexer.next(), Some(Toml::Comma));
+    assert_eq!(lexer.next(), Some(Toml::Bracket));
+    assert!(matches!(lexer.next(), Some(Toml::OpenBracket(Some(_)))));
+    assert_eq!(lexer.next(), None);
+}
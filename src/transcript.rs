@@ -0,0 +1,183 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{event, Level};
+
+use crate::civil_time::date;
+
+/// Maximum size of a transcript file before a new part is started
+pub const MAX_TRANSCRIPT_BYTES: u64 = 1024 * 1024;
+
+/// Number of transcript files kept per profile, older files are removed
+pub const MAX_TRANSCRIPT_FILES: usize = 20;
+
+/// Rolling transcript of a remote session, written under `<data_dir>/transcripts/<profile>/`
+///
+/// Files are named by date and part, i.e. `2022-08-01-000.log`, a new part is started once a file
+/// reaches MAX_TRANSCRIPT_BYTES or the date changes, and only the newest MAX_TRANSCRIPT_FILES files of a profile are kept.
+/// Writes are buffered, call flush to write them to the file
+#[derive(Debug)]
+pub struct TranscriptBackup {
+    /// Directory of the profile's transcripts
+    dir: PathBuf,
+    /// File currently being written to
+    file: PathBuf,
+    /// Open handle to the current file
+    writer: Option<BufWriter<File>>,
+    /// Date of the current file
+    date: String,
+    /// Bytes written to the current file
+    written: u64,
+}
+
+impl TranscriptBackup {
+    /// Starts a transcript for profile under data_dir, returns None if the directory can't be created
+    ///
+    /// Characters of profile that aren't letters, digits, `-` or `_` are replaced w/ `_`, so it can't name another directory
+    pub fn start(data_dir: impl AsRef<Path>, profile: &str) -> Option<Self> {
+        let dir = data_dir.as_ref().join("transcripts").join(profile_dir(profile));
+        if let Err(err) = std::fs::create_dir_all(&dir) {
+            event!(Level::ERROR, "Could not create transcript directory {:?}, {err}", dir);
+            return None;
+        }
+
+        let mut backup = Self {
+            file: dir.clone(),
+            dir,
+            writer: None,
+            date: String::new(),
+            written: 0,
+        };
+        backup.next_file();
+        Some(backup)
+    }
+
+    /// Returns the file currently being written to
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// Appends a line to the transcript, i.e. a line sent to the remote
+    pub fn append(&mut self, line: impl AsRef<str>) {
+        self.write(format!("{}\n", line.as_ref()).as_bytes());
+    }
+
+    /// Appends output received from the remote, `\r` line breaks are written as `\n`
+    pub fn append_output(&mut self, bytes: &[u8]) {
+        let output = bytes.iter().map(|b| if *b == b'\r' { b'\n' } else { *b }).collect::<Vec<_>>();
+        self.write(&output);
+    }
+
+    /// Writes buffered data to the current file
+    pub fn flush(&mut self) {
+        if let Some(Err(err)) = self.writer.as_mut().map(|w| w.flush()) {
+            event!(Level::ERROR, "Could not write transcript {:?}, {err}", self.file);
+        }
+    }
+
+    /// Writes bytes to the current file, starting a new file if the current one is full or from an earlier date
+    fn write(&mut self, bytes: &[u8]) {
+        if self.written >= MAX_TRANSCRIPT_BYTES || self.date != date(SystemTime::now()) {
+            self.next_file();
+        }
+
+        let written = match self.writer.as_mut() {
+            Some(writer) => writer.write_all(bytes),
+            None => return,
+        };
+
+        match written {
+            Ok(_) => self.written += bytes.len() as u64,
+            Err(err) => event!(Level::ERROR, "Could not write transcript {:?}, {err}", self.file),
+        }
+    }
+
+    /// Moves on to the next unused part for today, and removes transcripts past the retention limit
+    fn next_file(&mut self) {
+        self.flush();
+        self.date = date(SystemTime::now());
+        let mut part = 0;
+        loop {
+            self.file = self.dir.join(format!("{}-{part:03}.log", self.date));
+            match std::fs::metadata(&self.file) {
+                Ok(metadata) if metadata.len() >= MAX_TRANSCRIPT_BYTES => part += 1,
+                Ok(metadata) => {
+                    self.written = metadata.len();
+                    break;
+                }
+                Err(_) => {
+                    self.written = 0;
+                    break;
+                }
+            }
+        }
+
+        self.writer = match OpenOptions::new().create(true).append(true).open(&self.file) {
+            Ok(file) => Some(BufWriter::new(file)),
+            Err(err) => {
+                event!(Level::ERROR, "Could not open transcript {:?}, {err}", self.file);
+                None
+            }
+        };
+        self.prune();
+    }
+
+    /// Removes the oldest transcripts of the profile, keeping MAX_TRANSCRIPT_FILES including the current file
+    fn prune(&self) {
+        let mut files = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().map(|e| e == "log").unwrap_or_default() && *p != self.file)
+                .collect::<Vec<_>>(),
+            Err(_) => return,
+        };
+
+        // Names start w/ the date and a padded part, so they sort oldest first
+        files.sort();
+        let excess = (files.len() + 1).saturating_sub(MAX_TRANSCRIPT_FILES);
+        for file in files.iter().take(excess) {
+            if let Err(err) = std::fs::remove_file(file) {
+                event!(Level::WARN, "Could not remove old transcript {:?}, {err}", file);
+            }
+        }
+    }
+}
+
+/// Returns the name of the directory of profile's transcripts
+fn profile_dir(profile: &str) -> String {
+    let dir = profile
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect::<String>();
+
+    if dir.is_empty() {
+        "default".to_string()
+    } else {
+        dir
+    }
+}
+
+#[test]
+fn test_transcript_backup() {
+    assert_eq!(profile_dir("../staging"), "___staging");
+    assert_eq!(profile_dir(""), "default");
+
+    let data_dir = std::env::temp_dir().join(format!("lifec_shell_transcripts_{}", std::process::id()));
+    for idx in 0..MAX_TRANSCRIPT_FILES + 5 {
+        let old = data_dir.join("transcripts").join("staging").join(format!("2000-01-01-{idx:03}.log"));
+        std::fs::create_dir_all(old.parent().unwrap()).unwrap();
+        std::fs::write(old, "").unwrap();
+    }
+
+    let mut backup = TranscriptBackup::start(&data_dir, "staging").expect("should start");
+    backup.append("> ls");
+    backup.append_output(b"a.txt\rb.txt\r");
+    backup.flush();
+
+    let kept = std::fs::read_dir(backup.file().parent().unwrap()).unwrap().count();
+    assert_eq!(kept, MAX_TRANSCRIPT_FILES);
+    assert_eq!(std::fs::read_to_string(backup.file()).unwrap(), "> ls\na.txt\nb.txt\n");
+
+    std::fs::remove_dir_all(data_dir).unwrap();
+}
@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+use tokio::net::TcpStream;
+use tracing::{event, Level};
+
+use crate::{Datagram, Ipc, IpcAddress, ShellChannel, Telnet};
+
+/// Trait for the connection the shell sends lines to
+///
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Writes the message to the transport, returns the number of bytes written
+    ///
+    /// A `WouldBlock` error means the transport is not ready, any other error means the transport has closed
+    async fn write(&mut self, message: &[u8]) -> std::io::Result<usize>;
+
+    /// Writes all of the message to the transport, continuing after short writes, returns the number of bytes written
+    ///
+    /// A `WouldBlock` error before any bytes are written means the transport is not ready, once part of the message was
+    /// written the rest is retried
+    async fn write_all(&mut self, message: &[u8]) -> std::io::Result<usize> {
+        let mut written = 0;
+        while written < message.len() {
+            match self.write(&message[written..]).await {
+                Ok(0) => return Err(std::io::Error::from(std::io::ErrorKind::WriteZero)),
+                Ok(bytes) => written += bytes,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock && written > 0 => {
+                    event!(Level::TRACE, "Transport is not ready, retrying after {written} bytes");
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(written)
+    }
+
+    /// Returns a description of the other end of this transport
+    fn peer(&self) -> String;
+
+    /// Reports the size of the shell's window in columns and rows, transports that don't negotiate a window size ignore it
+    async fn resize(&mut self, _cols: u16, _rows: u16) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Transport for TcpStream {
+    async fn write(&mut self, message: &[u8]) -> std::io::Result<usize> {
+        event!(Level::TRACE, "Waiting for connection to be writeable");
+        self.writable().await?;
+        self.try_write(message)
+    }
+
+    fn peer(&self) -> String {
+        self.peer_addr()
+            .ok()
+            .map(|a| a.to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Opens a transport to address, output received from the other end is written to output
+///
+/// Addresses w/ a `ws://` scheme open a websocket if the `websocket` feature is enabled, `udp://` addresses send datagrams,
+/// `unix://` and `pipe://` addresses connect to local ipc endpoints, and addresses w/ an `ssh://` scheme open an ssh session
+/// if the `ssh` feature is enabled.
+/// Other addresses are connected to over tcp, `telnet://` addresses w/ a telnet protocol layer. For addresses w/o a scheme,
+/// the telnet layer is only activated once the endpoint sends a telnet command
+pub async fn open(address: &str, output: ShellChannel) -> std::io::Result<Box<dyn Transport>> {
+    #[cfg(feature = "websocket")]
+    if crate::WebSocket::is_websocket(address) {
+        return Ok(Box::new(crate::WebSocket::connect(address, output).await?));
+    }
+
+    #[cfg(not(feature = "websocket"))]
+    if address.starts_with("ws://") || address.starts_with("wss://") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Connecting to a websocket requires the websocket feature",
+        ));
+    }
+
+    if let Some(peer) = Datagram::parse(address) {
+        return Ok(Box::new(Datagram::bind(None, peer, output).await?));
+    }
+
+    if let Some(address) = IpcAddress::parse(address) {
+        return Ok(Box::new(Ipc::connect(address, output).await?));
+    }
+
+    #[cfg(feature = "ssh")]
+    if let Some(address) = crate::ssh::SshAddress::parse(address) {
+        return Ok(Box::new(crate::Ssh::connect(address, output).await?));
+    }
+
+    #[cfg(not(feature = "ssh"))]
+    if address.starts_with("ssh://") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Connecting over ssh requires the ssh feature",
+        ));
+    }
+
+    if let Some(address) = address.strip_prefix("telnet://") {
+        let stream = crate::resolve::connect(address).await?;
+        return Ok(Box::new(Telnet::start(stream, output, true)));
+    }
+
+    let stream = crate::resolve::connect(address).await?;
+    Ok(Box::new(Telnet::start(stream, output, false)))
+}
@@ -0,0 +1,234 @@
+use lifec::plugins::ThunkContext;
+use lifec::Value;
+use logos::Logos;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::{event, Level};
+
+use crate::{CharDevice, Runmd};
+
+/// Action taken when a trigger's pattern matches a line of output
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerAction {
+    /// Sends the command to the channel that matched, like a line routed to its plugin
+    Command(String),
+    /// Dispatches an event to the plugin that owns the channel
+    Event(String),
+    /// Highlights the matching line
+    Highlight,
+    /// Shows a notification
+    Notify(String),
+}
+
+/// Rule that runs an action when a completed line of output matches a pattern
+///
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    /// Name the trigger was defined with
+    pub name: String,
+    /// Pattern completed lines are matched against
+    pub pattern: Regex,
+    /// Action to take when a line matches, arguments can refer to capture groups, i.e. `$1`
+    pub action: TriggerAction,
+}
+
+/// Rules engine that evaluates triggers against the completed lines of each channel
+///
+#[derive(Debug, Default, Clone)]
+pub struct Triggers {
+    /// Rules, in the order they were defined
+    rules: Vec<Trigger>,
+    /// Number of completed lines already evaluated, per channel, including lines trimmed from the device
+    evaluated: BTreeMap<u32, usize>,
+    /// Lines highlighted by a trigger, per channel, numbered from the first line the channel received
+    highlights: BTreeMap<u32, BTreeSet<usize>>,
+}
+
+impl Triggers {
+    /// Returns triggers defined in a runmd `triggers` block
+    ///
+    /// ex:
+    /// ``` triggers
+    /// define build_failed trigger .text error\[(E\d+)\]
+    /// define build_failed trigger_action .text notify
+    /// define build_failed trigger_argument .text build failed with $1
+    /// ```
+    ///
+    /// Actions are `command`, `event`, `highlight`, and `notify`
+    pub fn from_runmd(source: &str) -> Self {
+        let mut lexer = Runmd::lexer_with_extras(source, ThunkContext::default());
+        while lexer.next().is_some() {}
+
+        Self::from_context(&lexer.extras)
+    }
+
+    /// Returns triggers defined in the thunk context of a `triggers` block, see from_runmd
+    pub fn from_context(tc: &ThunkContext) -> Self {
+        let graph = tc.as_ref();
+        let find = |symbol: &str, name: &str| {
+            graph
+                .find_symbol_values(symbol)
+                .into_iter()
+                .find_map(|(n, value)| match value {
+                    Value::TextBuffer(text) if n.trim_end_matches(&format!("::{symbol}")) == name => Some(text),
+                    _ => None,
+                })
+        };
+
+        let mut triggers = Triggers::default();
+        for (name, value) in graph.find_symbol_values("trigger") {
+            let name = name.trim_end_matches("::trigger").to_string();
+            let pattern = match value {
+                Value::TextBuffer(pattern) => pattern,
+                _ => continue,
+            };
+
+            let pattern = match Regex::new(&pattern) {
+                Ok(pattern) => pattern,
+                Err(err) => {
+                    event!(Level::WARN, "Invalid pattern for trigger {name}, {err}");
+                    continue;
+                }
+            };
+
+            let argument = find("trigger_argument", &name).unwrap_or_default();
+            let action = match find("trigger_action", &name).as_deref() {
+                Some("command") => TriggerAction::Command(argument),
+                Some("event") => TriggerAction::Event(argument),
+                Some("notify") => TriggerAction::Notify(argument),
+                Some("highlight") | None => TriggerAction::Highlight,
+                Some(unknown) => {
+                    event!(Level::WARN, "Unknown action {unknown} for trigger {name}");
+                    continue;
+                }
+            };
+
+            triggers.add(Trigger {
+                name,
+                pattern,
+                action,
+            });
+        }
+
+        triggers
+    }
+
+    /// Adds a trigger
+    pub fn add(&mut self, trigger: Trigger) {
+        self.rules.push(trigger);
+    }
+
+    /// Replaces the rules w/ those of triggers, lines that were already evaluated aren't evaluated again
+    pub fn replace_rules(&mut self, triggers: Triggers) {
+        self.rules = triggers.rules;
+    }
+
+    /// Returns true if there are no triggers
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Evaluates triggers against lines of the device completed since the last evaluation
+    ///
+    /// Returns the actions to take, w/ capture groups expanded. Highlights are recorded so they can be rendered
+    ///
+    /// Lines are numbered from the first line the channel received, so that lines trimmed from the device still count
+    pub fn evaluate(&mut self, channel: u32, device: &CharDevice) -> Vec<TriggerAction> {
+        // The last line is still being written
+        let trimmed = device.trimmed_lines();
+        let completed = trimmed + device.line_count().saturating_sub(1);
+        let evaluated = self.evaluated.entry(channel).or_default();
+        if completed < *evaluated {
+            // The buffer was replaced
+            *evaluated = 0;
+            self.highlights.remove(&channel);
+        }
+
+        // Highlights of trimmed lines can't be shown anymore
+        if let Some(highlights) = self.highlights.get_mut(&channel) {
+            *highlights = highlights.split_off(&trimmed);
+        }
+
+        let mut actions = vec![];
+        for line_no in (*evaluated).max(trimmed)..completed {
+            let line = device.get_line(line_no - trimmed).unwrap_or_default();
+            for trigger in self.rules.iter() {
+                let captures = match trigger.pattern.captures(&line) {
+                    Some(captures) => captures,
+                    None => continue,
+                };
+
+                let expand = |argument: &String| {
+                    let mut expanded = String::new();
+                    captures.expand(argument, &mut expanded);
+                    expanded
+                };
+
+                event!(Level::TRACE, "Trigger {} matched line {line_no} of channel {channel}", trigger.name);
+                actions.push(match &trigger.action {
+                    TriggerAction::Command(command) => TriggerAction::Command(expand(command)),
+                    TriggerAction::Event(name) => TriggerAction::Event(expand(name)),
+                    TriggerAction::Notify(message) => TriggerAction::Notify(expand(message)),
+                    TriggerAction::Highlight => {
+                        self.highlights.entry(channel).or_default().insert(line_no);
+                        TriggerAction::Highlight
+                    }
+                });
+            }
+        }
+        *evaluated = completed;
+
+        actions
+    }
+
+    /// Returns the lines of channel highlighted by a trigger, numbered from the first line the channel received
+    ///
+    /// Subtract the device's trimmed_lines to get the line in its buffer
+    pub fn highlighted_lines(&self, channel: u32) -> Option<&BTreeSet<usize>> {
+        self.highlights.get(&channel)
+    }
+}
+
+#[test]
+fn test_triggers() {
+    let mut triggers = Triggers::default();
+    triggers.add(Trigger {
+        name: "error".to_string(),
+        pattern: Regex::new(r"error\[(E\d+)\]").unwrap(),
+        action: TriggerAction::Notify("build failed with $1".to_string()),
+    });
+    triggers.add(Trigger {
+        name: "warning".to_string(),
+        pattern: Regex::new("warning").unwrap(),
+        action: TriggerAction::Highlight,
+    });
+
+    let mut device = CharDevice::default();
+    device.set_buffer("compiling\rwarning: unused\rerror[E0308]: mismatched types\rerror[E0");
+
+    assert_eq!(
+        triggers.evaluate(1, &device),
+        vec![
+            TriggerAction::Highlight,
+            TriggerAction::Notify("build failed with E0308".to_string())
+        ]
+    );
+    assert_eq!(triggers.highlighted_lines(1).map(|l| l.len()), Some(1));
+
+    // Lines are only evaluated once
+    assert!(triggers.evaluate(1, &device).is_empty());
+
+    // Lines keep being evaluated once the device starts trimming its oldest lines
+    let mut device = CharDevice::default();
+    device.set_max_lines(Some(3));
+    device.write_bytes(b"compiling\rwarning: a\r");
+    assert_eq!(triggers.evaluate(2, &device), vec![TriggerAction::Highlight]);
+    device.write_bytes(b"warning: b\rwarning: c\r");
+    assert_eq!(device.trimmed_lines(), 2);
+    assert_eq!(triggers.evaluate(2, &device), vec![TriggerAction::Highlight, TriggerAction::Highlight]);
+    assert_eq!(
+        triggers.highlighted_lines(2).map(|l| l.iter().copied().collect::<Vec<_>>()),
+        Some(vec![2, 3])
+    );
+}
@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+/// Default max number of chars rendered of a single line, i.e. so minified json or base64 dumps don't bloat the glyph queue
+pub const DEFAULT_MAX_LINE_LEN: usize = 1000;
+
+/// Output w/ long lines cut off, and its styled spans clipped to match
+#[derive(Debug, Clone, PartialEq)]
+pub struct Truncated {
+    pub source: String,
+    pub spans: Vec<(Range<usize>, [f32; 4])>,
+}
+
+/// Returns source w/ each line longer than max_len chars cut off, followed by a `… (+N chars)` marker colored marker_color
+///
+/// Returns None if no line is longer than max_len, so the source can be rendered as is
+pub fn truncate_lines(
+    source: &str,
+    spans: &[(Range<usize>, [f32; 4])],
+    max_len: usize,
+    marker_color: [f32; 4],
+) -> Option<Truncated> {
+    truncated_lines(source, max_len).next()?;
+
+    let mut truncated = Truncated {
+        source: String::with_capacity(source.len().min(max_len * 4)),
+        spans: vec![],
+    };
+
+    let mut next_span = 0;
+    let mut keep = |truncated: &mut Truncated, kept: Range<usize>| {
+        let offset = truncated.source.len();
+        truncated.source.push_str(&source[kept.clone()]);
+
+        while let Some((span, color)) = spans.get(next_span).filter(|(span, _)| span.start < kept.end) {
+            let (start, end) = (span.start.max(kept.start), span.end.min(kept.end));
+            if start < end {
+                truncated.spans.push((start - kept.start + offset..end - kept.start + offset, *color));
+            }
+
+            if span.end <= kept.end {
+                next_span += 1;
+            } else {
+                break;
+            }
+        }
+    };
+
+    let mut start = 0;
+    for line in source.split('\r') {
+        let end = start + line.len();
+        // Includes the line break, if the line has one
+        let line_break = (end + 1).min(source.len());
+        match line.char_indices().nth(max_len) {
+            Some((cut, _)) => {
+                keep(&mut truncated, start..start + cut);
+
+                let offset = truncated.source.len();
+                truncated.source.push_str(&marker(line[cut..].chars().count()));
+                truncated.spans.push((offset..truncated.source.len(), marker_color));
+
+                keep(&mut truncated, end..line_break);
+            }
+            None => keep(&mut truncated, start..line_break),
+        }
+        start = end + 1;
+    }
+
+    Some(truncated)
+}
+
+/// Returns the line numbers of lines in source longer than max_len chars
+pub fn truncated_lines(source: &str, max_len: usize) -> impl Iterator<Item = usize> + '_ {
+    source
+        .split('\r')
+        .enumerate()
+        .filter(move |(_, line)| line.len() > max_len && line.chars().nth(max_len).is_some())
+        .map(|(line_no, _)| line_no)
+}
+
+/// Returns the marker shown in place of the hidden chars of a truncated line
+fn marker(hidden: usize) -> String {
+    format!(" … (+{hidden} chars)")
+}
+
+#[test]
+fn test_truncate_lines() {
+    let (red, blue, grey) = ([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0], [0.5; 4]);
+    let source = "short\rlong line\rend";
+    let spans = vec![(0..6, red), (6..10, blue), (10..16, red), (16..19, blue)];
+
+    assert_eq!(truncate_lines(source, &spans, 9, grey), None);
+
+    let truncated = truncate_lines(source, &spans, 6, grey).expect("should truncate");
+    assert_eq!(truncated.source, "short\rlong l … (+3 chars)\rend");
+    assert_eq!(
+        truncated.spans,
+        vec![
+            (0..6, red),
+            (6..10, blue),
+            (10..12, red),
+            (12..27, grey),
+            (27..28, red),
+            (28..31, blue),
+        ]
+    );
+
+    assert_eq!(truncated_lines("ééé\rab\rabcd", 2).collect::<Vec<_>>(), vec![0, 2]);
+}
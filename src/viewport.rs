@@ -0,0 +1,195 @@
+use std::ops::Range;
+use std::time::Duration;
+
+/// Lines tokenized and queued past the edges of a viewport
+///
+/// Ranges of visible lines snap to multiples of it, so that scrolling a few lines reuses cached spans
+pub const CULL_MARGIN: usize = 32;
+
+/// How quickly smooth scrolling catches up, each second the remaining distance shrinks by a factor of e^SMOOTH_SPEED
+const SMOOTH_SPEED: f32 = 12.0;
+
+/// First line shown by a pane, that can be animated towards the line it's scrolling to
+///
+/// Positions are in lines, so that animated frames always start on a whole line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Line the viewport is at, fractional while animating
+    position: f32,
+    /// Line the viewport is scrolling to
+    target: usize,
+    /// Lines that fit in the pane, as of the last frame
+    rows: usize,
+    /// If true, the viewport follows the end of the buffer as lines are added
+    pinned: bool,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            position: 0.0,
+            target: 0,
+            rows: 0,
+            pinned: true,
+        }
+    }
+}
+
+impl Viewport {
+    /// Returns the first visible line
+    pub fn line(&self) -> usize {
+        self.position.round() as usize
+    }
+
+    /// Returns the line the viewport is scrolling to
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    /// Returns true while the viewport is animating towards its target
+    pub fn is_animating(&self) -> bool {
+        self.position != self.target as f32
+    }
+
+    /// Moves to line w/o animating, i.e. when the buffer is trimmed and its lines shift up
+    pub fn reset(&mut self, line: usize) {
+        self.position = line as f32;
+        self.target = line;
+    }
+
+    /// Moves to a line the user scrolled to, the viewport stays pinned only if the end of the buffer is still visible
+    pub fn jump(&mut self, line: usize, line_count: usize) {
+        self.reset(line);
+        self.pinned = line + self.rows >= line_count;
+    }
+
+    /// Scrolls to line, if smooth is false the viewport moves there immediately
+    pub fn scroll_to(&mut self, line: usize, smooth: bool) {
+        self.target = line;
+        if !smooth {
+            self.position = line as f32;
+        }
+    }
+
+    /// Keeps the last lines of a buffer w/ line_count lines visible, if the viewport is pinned to the end
+    pub fn follow_end(&mut self, line_count: usize, rows: usize, smooth: bool) {
+        self.rows = rows;
+        if self.pinned {
+            self.scroll_to(self.target.max(line_count.saturating_sub(rows)), smooth);
+        }
+    }
+
+    /// Scrolls just enough to keep line visible in rows lines
+    pub fn follow_line(&mut self, line: usize, rows: usize, smooth: bool) {
+        self.rows = rows;
+        let rows = rows.max(1);
+        if line < self.target {
+            self.scroll_to(line, smooth);
+        } else if line >= self.target + rows {
+            self.scroll_to(line + 1 - rows, smooth);
+        }
+    }
+
+    /// Advances the animation by elapsed, returns the first visible line
+    pub fn step(&mut self, elapsed: Duration) -> usize {
+        let target = self.target as f32;
+        self.position += (target - self.position) * (1.0 - (-SMOOTH_SPEED * elapsed.as_secs_f32()).exp());
+        if (target - self.position).abs() < 0.5 {
+            self.position = target;
+        }
+        self.line()
+    }
+}
+
+/// Returns the byte offset line starts at in source, lines are expected to be separated by `\r`
+///
+/// Lines past the end of source start at its end
+pub fn line_start(source: &str, line: usize) -> usize {
+    if line == 0 {
+        return 0;
+    }
+    source
+        .match_indices('\r')
+        .nth(line - 1)
+        .map(|(idx, _)| idx + 1)
+        .unwrap_or(source.len())
+}
+
+/// Returns the line the fenced block enclosing line starts on, or line if it isn't inside of a block
+///
+/// Lexers are stateful around fences, so lexing a range of lines must start at the block the range starts in
+pub fn block_start(source: &str, line: usize) -> usize {
+    let mut start = None;
+    for (idx, text) in source.split('\r').take(line).enumerate() {
+        let trimmed = text.trim();
+        match start {
+            Some(_) if trimmed == "```" => start = None,
+            None if trimmed.starts_with("```") => start = Some(idx),
+            _ => {}
+        }
+    }
+    start.unwrap_or(line)
+}
+
+/// Returns the lines of source in lines, i.e. an overlay of the buffer cut to the lines of a viewport
+pub fn lines_of(source: &str, lines: Range<usize>) -> &str {
+    let start = line_start(source, lines.start);
+    &source[start..line_start(source, lines.end).max(start)]
+}
+
+/// Returns the lines worth rendering for a viewport that shows rows lines from first, w/ a margin on each side
+pub fn visible_lines(first: usize, rows: usize) -> Range<usize> {
+    let start = first.saturating_sub(CULL_MARGIN) / CULL_MARGIN * CULL_MARGIN;
+    let end = (first + rows + CULL_MARGIN + CULL_MARGIN - 1) / CULL_MARGIN * CULL_MARGIN;
+    start..end
+}
+
+#[test]
+fn test_viewport() {
+    let mut viewport = Viewport::default();
+    viewport.follow_end(30, 10, false);
+    assert_eq!(viewport.line(), 20);
+
+    // Smooth scrolling reaches the target over a few frames
+    viewport.follow_end(40, 10, true);
+    assert_eq!(viewport.line(), 20);
+    assert!(viewport.is_animating());
+    let frame = Duration::from_millis(16);
+    let first = viewport.step(frame);
+    assert!(first > 20 && first < 30);
+    while viewport.is_animating() {
+        viewport.step(frame);
+    }
+    assert_eq!(viewport.line(), 30);
+
+    // Scrolling up unpins the viewport, scrolling back to the end pins it again
+    viewport.jump(5, 40);
+    viewport.follow_end(50, 10, false);
+    assert_eq!(viewport.line(), 5);
+    viewport.jump(40, 50);
+    viewport.follow_end(60, 10, false);
+    assert_eq!(viewport.line(), 50);
+
+    let mut editor = Viewport::default();
+    editor.follow_line(12, 10, false);
+    assert_eq!(editor.line(), 3);
+    editor.follow_line(8, 10, false);
+    assert_eq!(editor.line(), 3);
+    editor.follow_line(1, 10, false);
+    assert_eq!(editor.line(), 1);
+
+    let source = "ab\rcd\ref";
+    assert_eq!(line_start(source, 1), 3);
+    assert_eq!(lines_of(source, 1..2), "cd\r");
+    assert_eq!(lines_of(source, 2..usize::MAX), "ef");
+    assert_eq!(lines_of(source, 5..8), "");
+
+    let source = "text\r``` demo process\radd debug .text hello\r```\rtext\r";
+    assert_eq!(block_start(source, 3), 1);
+    assert_eq!(block_start(source, 4), 4);
+    assert_eq!(block_start(source, 1), 1);
+
+    assert_eq!(visible_lines(0, 40), 0..CULL_MARGIN * 3);
+    assert_eq!(visible_lines(100, 40), CULL_MARGIN * 2..CULL_MARGIN * 6);
+    assert_eq!(visible_lines(101, 40), visible_lines(100, 40));
+}
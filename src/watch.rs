@@ -0,0 +1,80 @@
+use notify::{recommended_watcher, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tracing::{event, Level};
+
+/// Watches files bound to channels, and reports the channels whose file changed on disk
+///
+/// The parent directory is watched instead of the file, so that editors that save by replacing the file are still picked up
+pub struct FileWatcher {
+    /// Bound files and their watchers, by channel
+    watching: BTreeMap<u32, (PathBuf, RecommendedWatcher)>,
+    /// Sends channels whose file has changed
+    changed_tx: UnboundedSender<u32>,
+    /// Receives channels whose file has changed
+    changed_rx: UnboundedReceiver<u32>,
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        let (changed_tx, changed_rx) = unbounded_channel();
+        Self {
+            watching: BTreeMap::default(),
+            changed_tx,
+            changed_rx,
+        }
+    }
+}
+
+impl FileWatcher {
+    /// Binds the file at path to channel, replacing any file that was previously bound
+    pub fn watch(&mut self, channel: u32, path: impl AsRef<Path>) -> notify::Result<()> {
+        let path = std::fs::canonicalize(path.as_ref())?;
+        let changed_tx = self.changed_tx.clone();
+        let file = path.clone();
+
+        let mut watcher = recommended_watcher(move |result: notify::Result<Event>| match result {
+            Ok(e) if e.kind.is_create() || e.kind.is_modify() => {
+                if e.paths.iter().any(|p| p == &file) {
+                    changed_tx.send(channel).ok();
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                event!(Level::ERROR, "Error watching file, {err}");
+            }
+        })?;
+
+        let dir = path.parent().unwrap_or(&path);
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+        event!(Level::DEBUG, "Watching {:?} for channel {channel}", path);
+        self.watching.insert(channel, (path, watcher));
+        Ok(())
+    }
+
+    /// Stops watching the file bound to channel
+    pub fn unwatch(&mut self, channel: u32) {
+        if let Some((path, _)) = self.watching.remove(&channel) {
+            event!(Level::DEBUG, "Stopped watching {:?}", path);
+        }
+    }
+
+    /// Returns the file bound to channel
+    pub fn watched_file(&self, channel: u32) -> Option<&Path> {
+        self.watching.get(&channel).map(|(path, _)| path.as_path())
+    }
+
+    /// Returns the channels and files that changed since the last call, each channel is returned once
+    pub fn changed(&mut self) -> Vec<(u32, PathBuf)> {
+        let mut changed = BTreeMap::new();
+        while let Ok(channel) = self.changed_rx.try_recv() {
+            if let Some(path) = self.watched_file(channel) {
+                changed.insert(channel, path.to_path_buf());
+            }
+        }
+
+        changed.into_iter().collect()
+    }
+}
@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use std::io::{Error, ErrorKind};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::{event, Level};
+
+use crate::{ShellChannel, Transport};
+
+/// Max number of lines queued for the socket task
+const MAX_PENDING_INPUT: usize = 64;
+
+/// Transport to a websocket endpoint, i.e. `ws://host:port/path`
+///
+/// Each text frame received is written to a shell channel as a line, and each line sent to this transport is sent as a text frame.
+pub struct WebSocket {
+    /// Queue of lines to the socket task
+    input: Sender<String>,
+    /// Address of the endpoint
+    address: String,
+}
+
+impl WebSocket {
+    /// Returns true if address has a websocket scheme
+    pub fn is_websocket(address: &str) -> bool {
+        address.starts_with("ws://") || address.starts_with("wss://")
+    }
+
+    /// Opens a websocket to address, text frames received are written to output
+    pub async fn connect(address: &str, output: ShellChannel) -> std::io::Result<Self> {
+        let (stream, response) = tokio_tungstenite::connect_async(address)
+            .await
+            .map_err(|err| Error::new(ErrorKind::Other, err.to_string()))?;
+        event!(Level::DEBUG, "Opened websocket to {address}, {}", response.status());
+
+        let (input, rx) = tokio::sync::mpsc::channel(MAX_PENDING_INPUT);
+        tokio::spawn(run(stream, rx, output));
+        Ok(Self {
+            input,
+            address: address.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for WebSocket {
+    async fn write(&mut self, message: &[u8]) -> std::io::Result<usize> {
+        self.input
+            .send(frame_text(message))
+            .await
+            .map_err(|_| Error::new(ErrorKind::BrokenPipe, "Websocket closed"))?;
+        Ok(message.len())
+    }
+
+    fn peer(&self) -> String {
+        self.address.clone()
+    }
+}
+
+/// Returns the text of the frame to send for message, w/o the line ending added by the shell
+fn frame_text(message: &[u8]) -> String {
+    let message = message.strip_suffix(b"\n").unwrap_or(message);
+    let message = message.strip_suffix(b"\r").unwrap_or(message);
+    String::from_utf8_lossy(message).to_string()
+}
+
+/// Writes text frames to the shell channel, and sends queued lines as text frames, until either side closes
+async fn run(stream: WebSocketStream<MaybeTlsStream<TcpStream>>, mut input: Receiver<String>, output: ShellChannel) {
+    let (mut sink, mut frames) = stream.split();
+    loop {
+        tokio::select! {
+            frame = frames.next() => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    if !output.send_line(text).await {
+                        break;
+                    }
+                }
+                Some(Ok(Message::Binary(bytes))) => {
+                    event!(Level::DEBUG, "Skipped binary frame of {} bytes", bytes.len());
+                }
+                Some(Ok(Message::Close(frame))) => {
+                    event!(Level::DEBUG, "Websocket closed by endpoint, {:?}", frame);
+                    break;
+                }
+                // Pings are answered by tungstenite
+                Some(Ok(_)) => {}
+                Some(Err(err)) => {
+                    event!(Level::DEBUG, "Websocket closed, {err}");
+                    break;
+                }
+                None => break,
+            },
+            line = input.recv() => match line {
+                Some(line) => {
+                    if let Err(err) = sink.send(Message::Text(line)).await {
+                        event!(Level::DEBUG, "Could not send frame, {err}");
+                        break;
+                    }
+                }
+                None => {
+                    let _ = sink.close().await;
+                    break;
+                }
+            },
+        }
+    }
+}
+
+#[test]
+fn test_frame_text() {
+    assert_eq!(frame_text(b"hello\r\n"), "hello");
+    assert_eq!(frame_text(b"hello\r\n\r\n"), "hello\r\n");
+    assert_eq!(frame_text(b"hello"), "hello");
+}
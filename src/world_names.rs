@@ -0,0 +1,83 @@
+use std::collections::BTreeSet;
+
+/// Kind of name being typed on a runmd line, that names known to the app world can complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameSlot {
+    /// Name of the attribute of an `add` or `define` line
+    Attribute,
+    /// Name of the block of an opening delimitter
+    Block,
+}
+
+impl NameSlot {
+    /// Returns the kind of name the word at the end of line is, or None if it isn't a name
+    ///
+    /// line is the text of the current line before the cursor, the word being typed is its last word
+    pub fn at(line: &str) -> Option<Self> {
+        let first = line.split_whitespace().next()?;
+        // A trailing space starts the next word
+        let index = line.split_whitespace().count() - 1 + usize::from(line.ends_with(char::is_whitespace));
+
+        match (first, index) {
+            ("add" | "define", 1) => Some(NameSlot::Attribute),
+            ("```", 1) => Some(NameSlot::Block),
+            _ => None,
+        }
+    }
+}
+
+/// Names of the attributes and blocks known to the app world, offered as completions on runmd lines
+#[derive(Debug, Default, Clone)]
+pub struct WorldNames {
+    attributes: BTreeSet<String>,
+    blocks: BTreeSet<String>,
+}
+
+impl WorldNames {
+    /// Adds the name of an attribute
+    pub fn add_attribute(&mut self, name: impl Into<String>) {
+        self.attributes.insert(name.into());
+    }
+
+    /// Adds the name of a block, empty names are ignored
+    pub fn add_block(&mut self, name: impl Into<String>) {
+        let name = name.into();
+        if !name.is_empty() {
+            self.blocks.insert(name);
+        }
+    }
+
+    /// Returns the names of slot that start w/ word, sorted by name
+    ///
+    /// A name equal to word is skipped, since there is nothing left to complete
+    pub fn complete<'a>(&'a self, slot: NameSlot, word: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        let names = match slot {
+            NameSlot::Attribute => &self.attributes,
+            NameSlot::Block => &self.blocks,
+        };
+        names.iter().filter(move |n| !word.is_empty() && n.starts_with(word) && n.as_str() != word)
+    }
+}
+
+#[test]
+fn test_world_names() {
+    assert_eq!(NameSlot::at("add sh"), Some(NameSlot::Attribute));
+    assert_eq!(NameSlot::at("  define "), Some(NameSlot::Attribute));
+    assert_eq!(NameSlot::at("``` de"), Some(NameSlot::Block));
+    assert_eq!(NameSlot::at("ad"), None);
+    assert_eq!(NameSlot::at("add shell_grammer .te"), None);
+    assert_eq!(NameSlot::at("define a sy"), None);
+
+    let mut names = WorldNames::default();
+    names.add_attribute("shell_grammer");
+    names.add_attribute("shell_running");
+    names.add_attribute("font_size");
+    names.add_block("");
+    names.add_block("demo");
+
+    let complete = |slot, word| names.complete(slot, word).cloned().collect::<Vec<_>>();
+    assert_eq!(complete(NameSlot::Attribute, "shell_"), vec!["shell_grammer", "shell_running"]);
+    assert_eq!(complete(NameSlot::Attribute, "font_size"), Vec::<String>::new());
+    assert_eq!(complete(NameSlot::Block, "d"), vec!["demo"]);
+    assert_eq!(complete(NameSlot::Block, ""), Vec::<String>::new());
+}
@@ -0,0 +1,94 @@
+use std::ops::Range;
+
+use crate::GlyphMetrics;
+
+/// Scale the editor is rendered at, and that glyph metrics are measured at
+const DEFAULT_SCALE: f32 = 40.0;
+
+/// Temporary view of the editor, zoomed to fit a single runmd block
+///
+/// The editor's buffer and cursor aren't changed, so clearing the view restores the previous view
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoomView {
+    /// Span of the block in the editor's buffer
+    pub block: Range<usize>,
+    /// Scale the block is rendered at
+    pub scale: f32,
+}
+
+impl ZoomView {
+    /// Returns a view that fits the block around cursor within bounds, or None if the cursor isn't in a block
+    pub fn fit(source: &str, cursor: usize, bounds: (f32, f32), metrics: &GlyphMetrics) -> Option<Self> {
+        let block = block_at(source, cursor)?;
+        let lines = source[block.clone()].split(|c| c == '\r' || c == '\n');
+        let (rows, columns) = lines.fold((0, 1), |(rows, columns), line| {
+            (rows + 1, columns.max(line.chars().count()))
+        });
+
+        let (width, height) = bounds;
+        let fit = (height / (rows as f32 * metrics.line_height))
+            .min(width / (columns as f32 * metrics.advance));
+
+        Some(Self {
+            block,
+            scale: (DEFAULT_SCALE * fit).clamp(8.0, DEFAULT_SCALE * 2.0),
+        })
+    }
+}
+
+/// Returns the span of the runmd block containing cursor, from the opening delimitter to the end of the closing delimitter
+///
+/// A block opened w/ `md` or `runmd` is a comment, and a block that isn't closed runs to the end of source
+pub fn block_at(source: &str, cursor: usize) -> Option<Range<usize>> {
+    let mut start = None;
+    let mut offset = 0;
+    for line in source.split(|c| c == '\r' || c == '\n') {
+        let line_end = offset + line.len();
+        let trimmed = line.trim();
+
+        match start {
+            None if trimmed.starts_with("```") => {
+                let info = trimmed.trim_start_matches('`').trim();
+                if !info.is_empty() && info != "md" && info != "runmd" {
+                    start = Some(offset);
+                }
+            }
+            Some(block_start) if trimmed == "```" => {
+                if (block_start..=line_end).contains(&cursor) {
+                    return Some(block_start..line_end);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+
+        offset = line_end + 1;
+    }
+
+    start
+        .filter(|s| cursor >= *s)
+        .map(|s| s..source.len())
+}
+
+#[test]
+fn test_zoom_view() {
+    let source = "# notes\r``` demo process\radd a .text hello\r``` println\radd b .text world\r```\r";
+
+    assert_eq!(block_at(source, 3), None);
+    assert_eq!(block_at(source, 30), Some(8..75));
+    assert_eq!(&source[8..75], "``` demo process\radd a .text hello\r``` println\radd b .text world\r```");
+
+    let metrics = GlyphMetrics {
+        advance: 20.0,
+        line_height: 40.0,
+    };
+
+    // 5 lines, 17 columns, so the height limits the scale
+    let view = ZoomView::fit(source, 30, (1000.0, 100.0), &metrics).expect("should fit");
+    assert_eq!(view.block, 8..75);
+    assert_eq!(view.scale, 20.0);
+
+    // Small blocks don't zoom in past 2x
+    let view = ZoomView::fit(source, 30, (10000.0, 10000.0), &metrics).expect("should fit");
+    assert_eq!(view.scale, 80.0);
+}